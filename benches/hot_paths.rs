@@ -0,0 +1,193 @@
+use byteorder::{ByteOrder, LittleEndian};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use rust_thanos::mca::entry::{decode_lz4_blocks, lz4_checksum, McaEntry};
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::mca::writer::McaWriter;
+use rust_thanos::patterns::inhabited::{find_inhabited_fast, inhabited_time};
+use rust_thanos::world::regions::copy_file;
+use rust_thanos::world::ProgressMode;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Roughly the size of a decompressed vanilla chunk, with InhabitedTime near the end
+fn synthetic_chunk_nbt(inhabited: i64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(64 * 1024);
+    for i in 0..64 * 1024 - 32 {
+        data.push((i % 251) as u8);
+    }
+    data.extend_from_slice(b"\x04\x00\x0dInhabitedTime");
+    data.extend_from_slice(&inhabited.to_be_bytes());
+    data
+}
+
+fn lz4_block_stream(payload: &[u8]) -> Vec<u8> {
+    let compressed = lz4_flex::block::compress_prepend_size(payload);
+    let mut stream = Vec::new();
+    stream.extend_from_slice(b"LZ4Block");
+    stream.push(0x20);
+    let mut buf = [0u8; 4];
+    LittleEndian::write_u32(&mut buf, (compressed.len() - 4) as u32);
+    stream.extend_from_slice(&buf);
+    LittleEndian::write_u32(&mut buf, payload.len() as u32);
+    stream.extend_from_slice(&buf);
+    LittleEndian::write_u32(&mut buf, lz4_checksum(payload));
+    stream.extend_from_slice(&buf);
+    stream.extend_from_slice(&compressed[4..]);
+    stream
+}
+
+fn write_zlib_chunk(path: &Path, inhabited: i64) {
+    let mut enc = ZlibEncoder::new(Vec::new(), Compression::default());
+    enc.write_all(&synthetic_chunk_nbt(inhabited)).unwrap();
+    let data = enc.finish().unwrap();
+    let mut out = Vec::new();
+    out.extend_from_slice(&((1 + data.len()) as u32).to_be_bytes());
+    out.push(2); // ZLIB
+    out.extend_from_slice(&data);
+    fs::write(path, out).unwrap();
+}
+
+fn synthetic_world() -> PathBuf {
+    let base = std::env::temp_dir().join(format!("rt-bench-{}", uuid::Uuid::new_v4()));
+    let region = base.join("region");
+    fs::create_dir_all(&region).unwrap();
+    let chunk_src = base.join("chunk-src.bin");
+    let mut writer = McaWriter::open(region.join("r.0.0.mca").to_string_lossy().as_ref()).unwrap();
+    for index in 0..1024u32 {
+        // every other chunk falls below the bench threshold
+        write_zlib_chunk(&chunk_src, if index % 2 == 0 { 0 } else { 72_000 });
+        let file = fs::File::open(&chunk_src).unwrap();
+        let len = fs::metadata(&chunk_src).unwrap().len() as usize;
+        let mut entry = McaEntry::new(file, 0, len, index, 0, 0, 0);
+        writer.write_entry(&mut entry).unwrap();
+    }
+    writer.finalize().unwrap();
+    fs::remove_file(&chunk_src).unwrap();
+    base
+}
+
+fn bench_find_inhabited_fast(c: &mut Criterion) {
+    let data = synthetic_chunk_nbt(72_000);
+    c.bench_function("find_inhabited_fast", |b| {
+        b.iter(|| find_inhabited_fast(black_box(&data)))
+    });
+}
+
+fn bench_decode_lz4_blocks(c: &mut Criterion) {
+    let stream = lz4_block_stream(&synthetic_chunk_nbt(72_000));
+    c.bench_function("decode_lz4_blocks", |b| {
+        b.iter(|| decode_lz4_blocks(black_box(&stream)).unwrap())
+    });
+}
+
+fn bench_reader_entries(c: &mut Criterion) {
+    let world = synthetic_world();
+    let region = world.join("region").join("r.0.0.mca");
+    let path = region.to_string_lossy().to_string();
+    c.bench_function("mca_reader_entries", |b| {
+        b.iter(|| {
+            let mut reader = McaReader::open(&path).unwrap();
+            black_box(reader.entries().unwrap().len())
+        })
+    });
+    fs::remove_dir_all(&world).ok();
+}
+
+fn bench_run_single_region(c: &mut Criterion) {
+    let world = synthetic_world();
+    let mut group = c.benchmark_group("run");
+    group.sample_size(10);
+    group.bench_function("single_region", |b| {
+        b.iter_batched(
+            || std::env::temp_dir().join(format!("rt-bench-out-{}", uuid::Uuid::new_v4())),
+            |out| {
+                rust_thanos::world::run(
+                    world.clone(),
+                    Some(out.clone()),
+                    6000,
+                    false,
+                    ProgressMode::Off,
+                )
+                .unwrap();
+                fs::remove_dir_all(&out).ok();
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+    fs::remove_dir_all(&world).ok();
+}
+
+// Keep check plus write of every chunk of a full region, as the processing loop does
+// for kept chunks: read twice from the region, or once into memory
+fn bench_kept_chunks(c: &mut Criterion) {
+    let world = synthetic_world();
+    let path = world
+        .join("region")
+        .join("r.0.0.mca")
+        .to_string_lossy()
+        .to_string();
+    let out = world.join("out.mca").to_string_lossy().to_string();
+    let mut group = c.benchmark_group("kept_chunks");
+    group.sample_size(10);
+    for buffered in [false, true] {
+        let name = if buffered { "buffered" } else { "from_region" };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let mut reader = McaReader::open(&path).unwrap();
+                let mut writer = McaWriter::open(&out).unwrap();
+                for mut entry in reader.entries().unwrap() {
+                    if buffered {
+                        entry.buffer_serialized().unwrap();
+                    }
+                    black_box(inhabited_time(&mut entry).unwrap());
+                    writer.write_entry(&mut entry).unwrap();
+                }
+                writer.finalize().unwrap();
+            })
+        });
+    }
+    group.finish();
+    fs::remove_dir_all(&world).ok();
+}
+
+// A large region file: what verbatim copies and the in-place copy-back move around
+fn bench_copy_file(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("rt-bench-copy-{}", uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    let src = dir.join("r.0.0.mca");
+    fs::write(
+        &src,
+        (0..64u32 << 20)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    let dst = dir.join("copy.mca");
+    let mut group = c.benchmark_group("copy_file_64mib");
+    group.sample_size(10);
+    group.bench_function("fs_copy", |b| {
+        b.iter(|| copy_file(&src, &dst, None).unwrap())
+    });
+    for kb in [64, 1024, 8192] {
+        group.bench_function(format!("buffer_{}kb", kb), |b| {
+            b.iter(|| copy_file(&src, &dst, Some(kb)).unwrap())
+        });
+    }
+    group.finish();
+    fs::remove_dir_all(&dir).ok();
+}
+
+criterion_group!(
+    benches,
+    bench_find_inhabited_fast,
+    bench_decode_lz4_blocks,
+    bench_reader_entries,
+    bench_run_single_region,
+    bench_kept_chunks,
+    bench_copy_file
+);
+criterion_main!(benches);
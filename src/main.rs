@@ -1,11 +1,24 @@
-use crate::world::ProgressMode;
+use crate::mca::entry::{
+    ChunkEncoding, LZ4_DEFAULT_BLOCK_SIZE, LZ4_MAX_BLOCK_SIZE, LZ4_MIN_BLOCK_SIZE,
+};
+use crate::mca::naming::{RegionNaming, VANILLA_REGION_NAMING};
+use crate::world::analyze;
+use crate::world::coords::{self, CoordUnit};
+use crate::world::progress::BarLayout;
+use crate::world::regions::is_junk_file;
+use crate::world::scrub::DEFAULT_SCRUB_FIELDS;
+use crate::world::{
+    AuxKind, OnError, OutputPolicy, ProgressMode, Recompression, RunOptions,
+    DEFAULT_CHUNK_MEMORY_LIMIT, DEFAULT_COPY_IGNORE, DEFAULT_DIMENSION_DEPTH, DEFAULT_TICKET_TYPES,
+};
 use anyhow::Result;
 use chrono::Local;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use console::Term;
 use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::{Cursor, Write};
 use std::path::Path;
 use std::path::PathBuf;
 use walkdir::WalkDir;
@@ -21,11 +34,26 @@ mod world;
     version,
     about = "Optimize Minecraft Java worlds",
     long_about = "Scan MCA region files and remove unused chunks. Keep chunks by InhabitedTime threshold and force-loaded tickets. Supports RAW/ZLIB/GZIP/LZ4. Recursively detects dimension directories. Friendly progress display. Optional zip archive of OUTPUT_DIR.",
-    after_help = "Examples:\n  rust-thanos /world /out\n  rust-thanos /world /out -t 600\n  rust-thanos /world /out --remove-unknown --progress-mode global\n  rust-thanos /world --in-place\n  rust-thanos /world /out --zip-output\n  rust-thanos /world /out -f\n\nNotes:\n  • InhabitedTime threshold unit: seconds (1s = 20 ticks).\n  • Non in-place mode requires OUTPUT_DIR to be empty; if it exists and is not empty, you will be prompted. Use -f/--force to overwrite without prompt.\n  • If OUTPUT_DIR is omitted, a temporary directory is used and replaces the input directory.\n  • On Windows, WSL is recommended for better performance."
+    after_help = "Examples:\n  rust-thanos /world /out\n  rust-thanos /world /out -t 600\n  rust-thanos /world /out -t 1200 --dim-threshold DIM-1=60\n  rust-thanos /world /out --link-dimensions overworld,mining\n  rust-thanos /world /out --region-bounds -2 -2 1 1\n  rust-thanos /world /out --keep-range -120,-80,250,300 --coord-unit block\n  rust-thanos /world /out --keep-polygon claims.geojson --coord-unit block\n  rust-thanos /world /out --no-inhabited --keep-range -100,-100,99,99\n  rust-thanos /world /out --remove-unknown --progress-mode global\n  rust-thanos /world --in-place\n  rust-thanos /world --in-place --strict\n  rust-thanos /world /out --on-error remove\n  rust-thanos /world /out --abort-if-no-dimensions\n  rust-thanos /server /out --server-layout\n  rust-thanos /world -t 600 --report-empty-regions\n  rust-thanos /world -t 600 --explain-chunk -3,7\n  rust-thanos /world --threshold-sweep 60,300,600,1200\n  rust-thanos /world --compression-report --compression-sample 5000\n  rust-thanos /world --dataversion-report --min-upgradable-dataversion 1976\n  rust-thanos /world --in-place --safe-defaults\n  rust-thanos /world --in-place --copy-buffer-kb 4096\n  rust-thanos /world --in-place --max-memory-mb 512\n  rust-thanos /world --in-place --max-removed-percent 80 --min-kept-chunks 1000\n  rust-thanos /world /out --zip-output\n  rust-thanos /world /out --zip-output --zip-threads 4\n  rust-thanos /world --tar-stdout | ssh host tar -xf - -C /srv/world\n  rust-thanos world.zip /out --copy-world-files\n  rust-thanos /world /out -f\n  rust-thanos /world /out --output-policy must-create\n  rust-thanos /world /out --json-summary --progress-mode off\n  rust-thanos /world /out --progress-fd 3 3>progress.jsonl\n  rust-thanos /world /out --stable-order --progress-mode off\n  rust-thanos /world /out --recompress zlib --zlib-level 9\n  rust-thanos /world /out --recompress lz4 --lz4-block-size 262144\n  rust-thanos /world /out --optimize-layout\n  rust-thanos /world /out --region-naming 'region_{x}_{z}.mca'\n  rust-thanos /world /out --build-index --use-index\n  rust-thanos /world /out --copy-world-files --copy-ignore logs,crash-reports,stats\n  rust-thanos /world /out --skip-entities --copy-verbatim poi\n  rust-thanos /world /out --prune-trivial-entities\n  rust-thanos /world /out --scrub\n  rust-thanos /world /out --scrub --scrub-fields Owner,LastOutput\n  rust-thanos /world /out --remove-below-status full\n  rust-thanos /world /out -t 300 --conservative-cull\n  rust-thanos /world /out --remove-empty-chunks\n  rust-thanos /world /out -t 0 --keep-modified-only\n  rust-thanos analyze /world\n  rust-thanos verify /world\n  rust-thanos /world /out --checksum-manifest out.xxh64\n  rust-thanos verify-manifest /out out.xxh64\n  rust-thanos dump /world -3 7 --dim DIM-1 -o chunk.nbt\n  rust-thanos list-dimensions /world\n  rust-thanos /world --detect-duplicate-regions\n  rust-thanos /world --dump-forced forced.json\n  rust-thanos /world /out --keep-list forced.json\n  rust-thanos dump-header r.0.0.mca\n  rust-thanos rebuild-header r.0.0.mca r.0.0.fixed.mca\n  rust-thanos selfcheck\n\nNotes:\n  • InhabitedTime threshold unit: seconds (1s = 20 ticks).\n  • Non in-place mode requires OUTPUT_DIR to be empty; if it exists and is not empty, you will be prompted. Use -f/--force to overwrite without prompt.\n  • If OUTPUT_DIR is omitted, a temporary directory is used and replaces the input directory.\n  • On Windows, WSL is recommended for better performance.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Args {
-    #[arg(value_name = "WORLD_DIR", help = "Minecraft world root")]
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// The bare `rust-thanos WORLD_DIR ...` form, an alias for `run`
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    #[arg(
+        value_name = "WORLD_DIR",
+        required = true,
+        help = "Minecraft world root, or a .zip of one (read without unpacking; needs OUTPUT_DIR)"
+    )]
+    input: Option<PathBuf>,
     #[arg(
         value_name = "OUTPUT_DIR",
         help = "Output directory (must be empty; optional)"
@@ -38,6 +66,20 @@ struct Args {
         help = "InhabitedTime threshold in seconds (1s = 20 ticks)"
     )]
     inhabited_time_seconds: i64,
+    #[arg(
+        long = "dim-threshold",
+        value_name = "NAME=SECONDS",
+        value_parser = parse_dim_threshold,
+        help = "Per-dimension InhabitedTime threshold in seconds (repeatable), e.g. DIM-1=60 or overworld=1200"
+    )]
+    dim_thresholds: Vec<(String, i64)>,
+    #[arg(
+        long = "link-dimensions",
+        value_name = "NAME,NAME[,...]",
+        value_parser = parse_dimension_link,
+        help = "Keep a chunk in every listed dimension when any of them keeps it (repeatable); adds a read-only first pass over these dimensions"
+    )]
+    linked_dimensions: Vec<String>,
     #[arg(
         long,
         default_value_t = false,
@@ -58,6 +100,13 @@ struct Args {
         help = "Zip OUTPUT_DIR to timestamped archive (YYYYMMddHHmmss.zip) and remove it"
     )]
     zip_output: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Threads compressing --zip-output entries; 0 = one per CPU (rayon default)"
+    )]
+    zip_threads: usize,
     #[arg(
         short = 'f',
         long,
@@ -65,18 +114,1071 @@ struct Args {
         help = "Force overwrite OUTPUT_DIR if it exists (no prompt)"
     )]
     force: bool,
+    #[arg(
+        long,
+        value_enum,
+        help = "Re-encode kept region chunks: zlib or lz4 (default: copy chunks verbatim)"
+    )]
+    recompress: Option<Recompression>,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = LZ4_DEFAULT_BLOCK_SIZE as u64,
+        value_parser = clap::value_parser!(u64).range(LZ4_MIN_BLOCK_SIZE as u64..=LZ4_MAX_BLOCK_SIZE as u64),
+        help = "Uncompressed block size for --recompress lz4 (64 B to 32 MiB; Minecraft uses 65536)"
+    )]
+    lz4_block_size: u64,
+    #[arg(
+        long,
+        default_value_t = 6,
+        value_parser = clap::value_parser!(u32).range(0..=9),
+        help = "zlib level for --recompress zlib: 0 = store only, 9 = slowest/smallest"
+    )]
+    zlib_level: u32,
+    #[arg(
+        long,
+        default_value_t = DEFAULT_DIMENSION_DEPTH,
+        help = "Max directory depth below WORLD_DIR searched for dimensions (dimensions/<ns>/<name> is 3)"
+    )]
+    max_dimension_depth: usize,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only report groups of byte-identical region files across dimensions (read-only, nothing is processed)"
+    )]
+    detect_duplicate_regions: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Copy level.dat, playerdata/ and other non-region world files into OUTPUT_DIR"
+    )]
+    copy_world_files: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = DEFAULT_COPY_IGNORE.map(String::from),
+        help = "Comma-separated file/directory names skipped by --copy-world-files"
+    )]
+    copy_ignore: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "After processing, check that every force-loaded chunk is still in the output; fail otherwise"
+    )]
+    verify_forced: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Cross-check the InhabitedTime fast scan against a full NBT parse; fail on mismatch"
+    )]
+    audit_inhabited: bool,
+    #[arg(
+        long,
+        default_value_t = 1.0,
+        help = "Percentage of chunks sampled by --audit-inhabited"
+    )]
+    audit_percent: f64,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = DEFAULT_TICKET_TYPES.map(String::from),
+        help = "Comma-separated chunks.dat ticket types whose chunks are always kept (e.g. forced,start,portal)"
+    )]
+    keep_ticket_types: Vec<String>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also keep chunks of temporary chunks.dat tickets that have not expired (ticks_left > 0)"
+    )]
+    keep_active_tickets: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Only write every dimension's force-loaded chunks (as selected by --keep-ticket-types/--keep-active-tickets) to FILE as JSON, readable by --keep-list; nothing is processed"
+    )]
+    dump_forced: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_delimiter = ',',
+        help = "Only report, for each comma-separated candidate threshold (seconds), how many chunks and bytes InhabitedTime would remove, reading every chunk once; nothing is processed"
+    )]
+    threshold_sweep: Vec<i64>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only estimate the region size and encoding time under --recompress zlib (at --zlib-level and 9) and lz4 from a sample of chunks; nothing is processed"
+    )]
+    compression_report: bool,
+    #[arg(
+        long,
+        value_name = "CHUNKS",
+        default_value_t = 2000,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Number of chunks --compression-report samples, spread evenly over the world; more is slower but more accurate"
+    )]
+    compression_sample: u64,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only report the DataVersion range and distribution of all chunks and the regions holding chunks older than --min-upgradable-dataversion; nothing is processed"
+    )]
+    dataversion_report: bool,
+    #[arg(
+        long,
+        value_name = "DATAVERSION",
+        default_value_t = 1343,
+        help = "Chunks below this DataVersion (or without one) are flagged by --dataversion-report; 1343 is Minecraft 1.12.2, the last version before the 1.13 flattening"
+    )]
+    min_upgradable_dataversion: i32,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Drop entities/ entirely (mobs, item frames, armor stands are lost)"
+    )]
+    skip_entities: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Drop poi/ entirely (the game rebuilds it from blocks)"
+    )]
+    skip_poi: bool,
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "Copy entities/poi as-is instead of matching them against kept chunks"
+    )]
+    copy_verbatim: Vec<AuxKind>,
+    #[arg(
+        long,
+        value_name = "CSV",
+        help = "Export dimension,chunk_x,chunk_z,inhabited_seconds for every chunk"
+    )]
+    export_inhabited: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "STATUS",
+        help = "Remove chunks generated below this status (e.g. full, features); chunks without a status are kept"
+    )]
+    remove_below_status: Option<String>,
+    #[arg(
+        long = "keep-only-biome",
+        value_name = "BIOME[,BIOME...]",
+        value_delimiter = ',',
+        help = "Remove chunks whose dominant biome is not one of these (e.g. plains,minecraft:cherry_grove; repeatable); applies on top of the other rules; chunks without 1.18+ biome palettes are left alone"
+    )]
+    keep_only_biomes: Vec<String>,
+    #[arg(
+        long,
+        value_name = "EPOCH",
+        help = "Write this timestamp (seconds since the epoch, e.g. 0) for every output chunk"
+    )]
+    set_timestamp: Option<u32>,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_CHUNK_MEMORY_LIMIT,
+        help = "Stream chunks larger than this through bounded buffers instead of reading them whole"
+    )]
+    chunk_memory_limit: u64,
+    #[arg(
+        long,
+        value_name = "MB",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Soft cap on chunk data held in memory by all workers at once, estimated from declared chunk sizes; workers wait for room before decoding"
+    )]
+    max_memory_mb: Option<u64>,
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        default_value = VANILLA_REGION_NAMING,
+        value_parser = RegionNaming::parse,
+        help = "File name of region files, with {x} and {z} for the region coordinates, for tools that do not write r.<x>.<z>.mca"
+    )]
+    region_naming: RegionNaming,
+    #[arg(
+        long,
+        num_args = 4,
+        allow_negative_numbers = true,
+        value_names = ["MIN_X", "MIN_Z", "MAX_X", "MAX_Z"],
+        help = "Only process regions inside this box (region coordinates, inclusive); others are copied unchanged"
+    )]
+    region_bounds: Option<Vec<i32>>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Also keep chunks already upgraded to the DataVersion in level.dat (recently loaded)"
+    )]
+    keep_current_dataversion: bool,
+    #[arg(
+        long,
+        value_name = "TICKS",
+        value_parser = clap::value_parser!(i64).range(0..),
+        help = "Also keep chunks whose LastUpdate is within TICKS game ticks of the Time in level.dat"
+    )]
+    last_update_within: Option<i64>,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Also keep PERCENT% of the chunks the other rules remove, sampled pseudo-randomly by chunk coordinate; for small test worlds, not for cleaning up"
+    )]
+    sample_keep: Option<f64>,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "sample_keep",
+        help = "Seed of --sample-keep; the same seed keeps the same chunks"
+    )]
+    sample_seed: u64,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Keep the spawn chunks around the level.dat spawn (radius from the spawnChunkRadius game rule)"
+    )]
+    keep_spawn_chunks: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Enable every keep that protects the game's own chunk loading: spawn chunks, forced and start tickets, active temporary tickets"
+    )]
+    safe_defaults: bool,
+    #[arg(
+        long = "keep-chunk",
+        value_name = "X,Z",
+        allow_hyphen_values = true,
+        value_parser = coords::parse_point,
+        help = "Always keep this chunk (repeatable; unit set by --coord-unit)"
+    )]
+    keep_chunks: Vec<(i32, i32)>,
+    #[arg(
+        long = "keep-range",
+        value_name = "X1,Z1,X2,Z2",
+        allow_hyphen_values = true,
+        value_parser = coords::parse_range,
+        help = "Always keep every chunk in this box, inclusive (repeatable; unit set by --coord-unit)"
+    )]
+    keep_ranges: Vec<(i32, i32, i32, i32)>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "File of X,Z points or X1,Z1,X2,Z2 boxes to always keep, one per line (# comments)"
+    )]
+    keep_list: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "JSON [[x, z], ...] ring or GeoJSON (Multi)Polygon; chunks whose center is inside are always kept (unit set by --coord-unit)"
+    )]
+    keep_polygon: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = CoordUnit::Chunk,
+        help = "Unit of --keep-chunk/--keep-range/--keep-list/--keep-polygon coordinates: block | chunk | region"
+    )]
+    coord_unit: CoordUnit,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Copy every region that holds a force-loaded chunk unchanged"
+    )]
+    keep_forced_regions_whole: bool,
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Abort if more than this percentage of chunks would be removed, leaving the world untouched and OUTPUT_DIR without output"
+    )]
+    max_removed_percent: Option<f64>,
+    #[arg(
+        long,
+        visible_alias = "flush-every",
+        value_name = "REGIONS",
+        default_value_t = 0,
+        help = "fsync output files every N regions so a crash or power loss keeps finished regions (0 = off, fastest)"
+    )]
+    sync_interval: u64,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Remove chunks that look freshly generated (no block entities, block ticks or player-only blocks); heuristic and lossy"
+    )]
+    keep_modified_only: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 0,
+        help = "Abort if fewer than N chunks would be kept world-wide (0 = off), leaving the world untouched and OUTPUT_DIR without output"
+    )]
+    min_kept_chunks: u64,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Drop entities chunks that hold only ambient mobs (bats, fish) or nothing, even for kept chunks"
+    )]
+    prune_trivial_entities: bool,
+    #[arg(
+        long,
+        value_name = "COUNT",
+        help = "List kept chunks whose entities chunk holds more than COUNT entities (passengers included), worst first"
+    )]
+    report_entity_hotspots: Option<usize>,
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "report_entity_hotspots",
+        help = "Also drop the entities chunk of every --report-entity-hotspots match, keeping the region chunk"
+    )]
+    clear_entity_hotspots: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Always keep chunks holding a named entity (a name-tagged pet, villager or other mob with a CustomName)"
+    )]
+    keep_named_entities: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Fail on the first unreadable, unwritable or unevaluable chunk or region instead of warning and skipping it"
+    )]
+    strict: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Lay out the kept chunks of each region in Z-order so neighbouring chunks sit in adjacent sectors"
+    )]
+    optimize_layout: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Print the final stats as one JSON line on stdout instead of the progress and summary lines"
+    )]
+    json_summary: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputPolicy::AllowEmpty,
+        help = "allow-empty: OUTPUT_DIR may be an existing empty directory | must-create: OUTPUT_DIR must not exist yet (no prompt, -f ignored)"
+    )]
+    output_policy: OutputPolicy,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Write an r.<x>.<z>.mca.idx sidecar caching each chunk's InhabitedTime, size and compression next to every input region (needs OUTPUT_DIR)"
+    )]
+    build_index: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Read InhabitedTime from .mca.idx sidecars instead of decoding chunks; stale sidecars (region changed since) are ignored"
+    )]
+    use_index: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Remove chunks with a valid header but an empty payload, even without --remove-unknown"
+    )]
+    remove_empty_chunks: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Keep generated but never visited chunks (InhabitedTime exactly 0) whatever the threshold"
+    )]
+    keep_zero_inhabited: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "keep_zero_inhabited",
+        help = "Ignore InhabitedTime: only --keep-* options, --region-bounds and force-loaded chunks keep chunks (needs one of them, or --max-removed-percent 100)"
+    )]
+    no_inhabited: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "no_inhabited",
+        help = "Only remove fully generated chunks below the threshold; chunks still generating (proto-chunks) are kept"
+    )]
+    conservative_cull: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["no_inhabited", "keep_zero_inhabited", "conservative_cull"],
+        help = "Rhai script deciding keep (true) or remove (false) per chunk instead of the InhabitedTime threshold; needs the `script` build feature"
+    )]
+    chunk_filter_script: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Before processing, print the keep/remove rules of each dimension in precedence order and where keep coordinates overlap"
+    )]
+    explain_rules: bool,
+    #[arg(
+        long,
+        value_name = "X,Z",
+        allow_hyphen_values = true,
+        value_parser = coords::parse_point,
+        conflicts_with_all = ["zip_output", "tar_stdout", "report_empty_regions", "checksum_manifest"],
+        help = "Show how the rules decide on the chunk at these global chunk coordinates in every dimension (values read, each rule's vote), then exit without changes"
+    )]
+    explain_chunk: Option<(i32, i32)>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Remove player-identifying tags (owners, sign text, book authors, ...) from kept chunks; see --scrub-fields"
+    )]
+    scrub: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        requires = "scrub",
+        default_values_t = DEFAULT_SCRUB_FIELDS.map(String::from),
+        help = "Comma-separated NBT tag names removed at any depth by --scrub"
+    )]
+    scrub_fields: Vec<String>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OnError::Keep,
+        help = "Chunks no keep rule could evaluate (every one failed to decode them): keep | remove"
+    )]
+    on_error: OnError,
+    #[arg(
+        long,
+        value_name = "FD",
+        help = "Write JSON-line progress events (start, chunks, region, summary) to this inherited file descriptor (Unix only)"
+    )]
+    progress_fd: Option<u32>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Append a line (time, dimension, region file, chunk x, chunk z) to FILE for every removed chunk as it is removed, e.g. to follow with tail -f"
+    )]
+    removal_log: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "KB",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Copy unchanged region files (verbatim regions, in-place copy-back) through a buffer of this many KiB instead of the platform copy"
+    )]
+    copy_buffer_kb: Option<u64>,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Fail, listing what WORLD_DIR contains, when no dimension (folder with region/) is found; --strict implies it"
+    )]
+    abort_if_no_dimensions: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "WORLD_DIR is a Bukkit/Spigot/Paper server root: process every subfolder with a level.dat (world, world_nether, world_the_end) as its own world, into OUTPUT_DIR/<name>"
+    )]
+    server_layout: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "zip_output",
+        help = "Only list, per dimension, the region files that would keep no chunk; nothing is written"
+    )]
+    report_empty_regions: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["output", "in_place", "zip_output", "report_empty_regions", "verify_forced", "max_removed_percent", "min_kept_chunks"],
+        help = "Stream the optimized world to stdout as a tar archive instead of writing OUTPUT_DIR, each file as soon as it is written; messages go to stderr"
+    )]
+    tar_stdout: bool,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Process dimensions one at a time and regions in name order, so logs, progress lines and --progress-fd events are the same on every run (slower with several dimensions)"
+    )]
+    stable_order: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["tar_stdout", "zip_output", "report_empty_regions"],
+        help = "After the run, write an XXH64 digest of every output region file to FILE as `hash  path` lines (xxhsum -c compatible), for verify-manifest"
+    )]
+    checksum_manifest: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(
+        about = "Remove unused chunks from a world (the default when no subcommand is given)",
+        after_help = "`rust-thanos run WORLD_DIR ...` and `rust-thanos WORLD_DIR ...` are the same; see `rust-thanos --help` for examples."
+    )]
+    Run(Box<RunArgs>),
+    #[command(
+        about = "Report compression methods, InhabitedTime distribution and size of a world without writing anything",
+        long_about = "Decode every region chunk of every dimension and print how the chunks are compressed, how their InhabitedTime is distributed and how large the world is. Nothing is created or modified, which makes it a safe first look at a world before choosing a threshold for `run`."
+    )]
+    Analyze {
+        #[arg(value_name = "WORLD_DIR", help = "Minecraft world root")]
+        input: PathBuf,
+        #[arg(
+            long,
+            default_value_t = DEFAULT_DIMENSION_DEPTH,
+            help = "Max directory depth below WORLD_DIR searched for dimensions (dimensions/<ns>/<name> is 3)"
+        )]
+        max_dimension_depth: usize,
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            default_value = VANILLA_REGION_NAMING,
+            value_parser = RegionNaming::parse,
+            help = "File name of region files, with {x} and {z} for the region coordinates"
+        )]
+        region_naming: RegionNaming,
+    },
+    #[command(
+        about = "Write the uncompressed NBT of one chunk to a file or stdout",
+        allow_negative_numbers = true
+    )]
+    Dump {
+        #[arg(value_name = "WORLD_DIR", help = "Minecraft world root")]
+        input: PathBuf,
+        #[arg(value_name = "X", help = "Chunk x coordinate")]
+        x: i32,
+        #[arg(value_name = "Z", help = "Chunk z coordinate")]
+        z: i32,
+        #[arg(
+            long,
+            default_value = ".",
+            help = "Dimension directory relative to WORLD_DIR, as printed by list-dimensions (e.g. DIM-1)"
+        )]
+        dim: String,
+        #[arg(
+            short,
+            long,
+            value_name = "FILE",
+            help = "Write the NBT here instead of stdout"
+        )]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            default_value = VANILLA_REGION_NAMING,
+            value_parser = RegionNaming::parse,
+            help = "File name of region files, with {x} and {z} for the region coordinates"
+        )]
+        region_naming: RegionNaming,
+    },
+    #[command(
+        about = "Decode every chunk of a world and fail if any region or chunk cannot be read"
+    )]
+    Verify {
+        #[arg(value_name = "WORLD_DIR", help = "Minecraft world root")]
+        input: PathBuf,
+        #[arg(
+            long,
+            default_value_t = DEFAULT_DIMENSION_DEPTH,
+            help = "Max directory depth below WORLD_DIR searched for dimensions (dimensions/<ns>/<name> is 3)"
+        )]
+        max_dimension_depth: usize,
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            default_value = VANILLA_REGION_NAMING,
+            value_parser = RegionNaming::parse,
+            help = "File name of region files, with {x} and {z} for the region coordinates"
+        )]
+        region_naming: RegionNaming,
+    },
+    #[command(about = "List the dimensions that would be processed, with region and chunk counts")]
+    ListDimensions {
+        #[arg(value_name = "WORLD_DIR", help = "Minecraft world root")]
+        input: PathBuf,
+        #[arg(
+            long,
+            default_value_t = DEFAULT_DIMENSION_DEPTH,
+            help = "Max directory depth below WORLD_DIR searched for dimensions (dimensions/<ns>/<name> is 3)"
+        )]
+        max_dimension_depth: usize,
+        #[arg(
+            long,
+            value_name = "TEMPLATE",
+            default_value = VANILLA_REGION_NAMING,
+            value_parser = RegionNaming::parse,
+            help = "File name of region files, with {x} and {z} for the region coordinates"
+        )]
+        region_naming: RegionNaming,
+    },
+    #[command(
+        about = "Print a region's location and timestamp tables, flagging slots that cannot be right",
+        long_about = "Print offset and size (in 4 KiB sectors) and timestamp of every used slot of a region's header, with the global chunk coordinates of the slot. Slots pointing into the header, past the end of the file or into another chunk's sectors, and slots with only one of offset and size set, are marked with the reason. Nothing is decompressed."
+    )]
+    DumpHeader {
+        #[arg(value_name = "REGION", help = "r.<x>.<z>.mca file")]
+        region: PathBuf,
+        #[arg(long, default_value_t = false, help = "Also print the unused slots")]
+        all: bool,
+    },
+    #[command(
+        about = "Rebuild a damaged region header by scanning its data sectors",
+        long_about = "Rebuild the location table of a region whose header is damaged but whose chunk sectors are intact. Every sector boundary is tried as a chunk start; chunks that decompress to NBT are placed by their xPos/zPos. When a chunk appears twice the copy with the highest LastUpdate wins. External (.mcc) and custom-compressed chunks are dropped."
+    )]
+    RebuildHeader {
+        #[arg(value_name = "REGION", help = "Damaged r.<x>.<z>.mca file")]
+        region: PathBuf,
+        #[arg(value_name = "OUTPUT", help = "Where to write the rebuilt region")]
+        output: PathBuf,
+    },
+    #[command(
+        about = "Round-trip synthetic regions through the writer and reader to validate this build",
+        long_about = "Write synthetic regions covering every compression method, a full region, negative coordinates, a 255-sector chunk and an external chunk to a temporary directory, read them back and check every chunk's bytes, payload, InhabitedTime, timestamp and coordinates. Prints one line per case and fails if any case fails; no world is touched."
+    )]
+    Selfcheck,
+    #[command(
+        about = "Check a world's region files against a --checksum-manifest",
+        long_about = "Recompute the XXH64 digest of every file listed in MANIFEST, relative to WORLD_DIR, and fail if any file is missing or differs. Files not listed are ignored, so regions added since the manifest was written are not reported."
+    )]
+    VerifyManifest {
+        #[arg(value_name = "WORLD_DIR", help = "World the manifest was written for")]
+        input: PathBuf,
+        #[arg(value_name = "MANIFEST", help = "File written by --checksum-manifest")]
+        manifest: PathBuf,
+    },
+}
+
+fn parse_dim_threshold(s: &str) -> std::result::Result<(String, i64), String> {
+    let (name, secs) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=SECONDS, got {}", s))?;
+    let secs: i64 = secs
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid seconds in {}", s))?;
+    Ok((name.trim().to_string(), secs))
+}
+
+/// Validates one `--link-dimensions` group; split into names when building `RunOptions`.
+fn parse_dimension_link(s: &str) -> std::result::Result<String, String> {
+    let names = split_dimension_link(s);
+    if names.len() < 2 {
+        return Err(format!("expected at least two dimension names, got {}", s));
+    }
+    Ok(s.to_string())
+}
+
+fn split_dimension_link(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn run_command(command: Command) -> Result<()> {
+    match command {
+        Command::Run(args) => run(*args),
+        Command::Analyze {
+            input,
+            max_dimension_depth,
+            region_naming,
+        } => {
+            let a = analyze::analyze_world(&input, max_dimension_depth, &region_naming)?;
+            println!(
+                "扫描了 {} 个维度、{} 个区域文件、{} 个区块，世界总大小 {}",
+                a.dimensions.len(),
+                a.regions,
+                a.chunks,
+                world::fmt_bytes(a.total_bytes)
+            );
+            let pct = |n: u64| n as f64 * 100.0 / a.chunks.max(1) as f64;
+            println!("压缩方式:");
+            for (method, n) in a.compression_methods() {
+                println!("  {:<14} {:>8}  ({:.1}%)", method.name(), n, pct(n));
+            }
+            println!("InhabitedTime 分布:");
+            let bounds = analyze::INHABITED_BUCKETS;
+            for (i, n) in a.inhabited.iter().enumerate() {
+                let label = match i {
+                    0 => "0 秒".to_string(),
+                    i if i == bounds.len() => format!(">= {} 秒", bounds[i - 1]),
+                    i => format!("{}-{} 秒", bounds[i - 1], bounds[i]),
+                };
+                println!("  {:<14} {:>8}  ({:.1}%)", label, n, pct(*n));
+            }
+            println!(
+                "  {:<14} {:>8}  ({:.1}%)",
+                "未知",
+                a.inhabited_unknown,
+                pct(a.inhabited_unknown)
+            );
+            if !a.errors.is_empty() {
+                println!("读取失败 {} 处，可用 verify 子命令查看详情", a.errors.len());
+            }
+            Ok(())
+        }
+        Command::Dump {
+            input,
+            x,
+            z,
+            dim,
+            output,
+            region_naming,
+        } => {
+            let nbt = world::get_chunk(&input, &dim, x, z, &region_naming)?
+                .ok_or_else(|| anyhow::anyhow!("chunk ({}, {}) not found in {}", x, z, dim))?;
+            match output {
+                Some(path) => std::fs::write(path, nbt)?,
+                None => std::io::stdout().write_all(&nbt)?,
+            }
+            Ok(())
+        }
+        Command::Verify {
+            input,
+            max_dimension_depth,
+            region_naming,
+        } => {
+            let a = analyze::analyze_world(&input, max_dimension_depth, &region_naming)?;
+            for e in &a.errors {
+                println!("  {}", e);
+            }
+            if a.errors.is_empty() {
+                println!(
+                    "校验通过: {} 个区域文件、{} 个区块均可读取",
+                    a.regions, a.chunks
+                );
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} regions or chunks failed to read",
+                    a.errors.len()
+                ))
+            }
+        }
+        Command::VerifyManifest { input, manifest } => {
+            let check = world::manifest::verify_manifest(&input, &manifest)?;
+            for f in &check.failures {
+                match f.actual {
+                    Some(ref actual) => println!("  {}: 校验值不符（现为 {}）", f.path, actual),
+                    None => println!("  {}: 文件缺失或无法读取", f.path),
+                }
+            }
+            if check.failures.is_empty() {
+                println!("校验通过: {} 个文件与清单一致", check.checked);
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} of {} files do not match the manifest",
+                    check.failures.len(),
+                    check.checked
+                ))
+            }
+        }
+        Command::ListDimensions {
+            input,
+            max_dimension_depth,
+            region_naming,
+        } => {
+            let dims = world::summarize_dimensions(&input, max_dimension_depth, &region_naming)?;
+            println!("发现 {} 个维度:", dims.len());
+            for d in &dims {
+                println!(
+                    "  {}  区域文件 {} 个，区块 {} 个  ({})",
+                    d.label,
+                    d.regions,
+                    d.chunks,
+                    d.path.display()
+                );
+            }
+            Ok(())
+        }
+        Command::DumpHeader { region, all } => {
+            let mut reader = mca::reader::McaReader::open(&region.to_string_lossy())?;
+            let header = reader.header()?;
+            let anomalies = header.anomalies();
+            println!(
+                "{}: 已使用 {} 个槽位，文件 {} 字节（{} 个扇区），异常 {} 处",
+                region.display(),
+                header.slots.iter().filter(|s| s.is_used()).count(),
+                header.file_len,
+                header.file_len.div_ceil(4096),
+                anomalies.len()
+            );
+            println!(
+                "{:>5} {:>7} {:>7} {:>7} {:>5}  timestamp (UTC)",
+                "slot", "x", "z", "offset", "size"
+            );
+            for (i, slot) in header.slots.iter().enumerate() {
+                let reasons: Vec<&str> = anomalies
+                    .iter()
+                    .filter(|(j, _)| *j == i)
+                    .map(|(_, r)| r.as_str())
+                    .collect();
+                if !all && !slot.is_used() {
+                    continue;
+                }
+                let time = match slot.timestamp {
+                    0 => "-".to_string(),
+                    t => chrono::DateTime::from_timestamp(t as i64, 0)
+                        .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+                        .unwrap_or_default(),
+                };
+                let flag = if reasons.is_empty() {
+                    String::new()
+                } else {
+                    format!("  ! {}", reasons.join("; "))
+                };
+                println!(
+                    "{:>5} {:>7} {:>7} {:>7} {:>5}  {:<19}{}",
+                    i,
+                    reader.x_pos() * 32 + (i % 32) as i32,
+                    reader.z_pos() * 32 + (i / 32) as i32,
+                    slot.offset,
+                    slot.sectors,
+                    time,
+                    flag
+                );
+            }
+            Ok(())
+        }
+        Command::Selfcheck => {
+            let dir =
+                std::env::temp_dir().join(format!("thanos-selfcheck-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir)?;
+            let reports = mca::selfcheck::run_selfcheck(&dir);
+            let _ = std::fs::remove_dir_all(&dir);
+            for r in &reports {
+                match &r.failure {
+                    None => println!("通过  {}", r.name),
+                    Some(e) => println!("失败  {}: {}", r.name, e),
+                }
+            }
+            let failed = reports.iter().filter(|r| r.failure.is_some()).count();
+            if failed == 0 {
+                println!("自检通过: {} 项", reports.len());
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} of {} self-check cases failed",
+                    failed,
+                    reports.len()
+                ))
+            }
+        }
+        Command::RebuildHeader { region, output } => {
+            if output.exists() && output.canonicalize()? == region.canonicalize()? {
+                return Err(anyhow::anyhow!(
+                    "output must not overwrite the damaged region"
+                ));
+            }
+            let report = mca::repair::rebuild_header(&region, &output)?;
+            println!(
+                "重建完成: 恢复 {} 个区块，丢弃旧副本 {} 个，无法定位 {} 个 → {}",
+                report.recovered,
+                report.duplicates,
+                report.unplaced,
+                output.display()
+            );
+            Ok(())
+        }
+    }
 }
 
 fn main() -> Result<()> {
     env_logger::init();
     let args = Args::parse();
+    match args.command {
+        Some(command) => run_command(command),
+        None => run(args.run),
+    }
+}
+
+fn run(args: RunArgs) -> Result<()> {
+    let input = args.input.expect("WORLD_DIR is required by clap");
+    if args.detect_duplicate_regions {
+        let groups =
+            world::find_duplicate_regions(&input, args.max_dimension_depth, &args.region_naming)?;
+        if groups.is_empty() {
+            println!("未发现内容相同的区域文件");
+        } else {
+            println!("发现 {} 组内容相同的区域文件:", groups.len());
+        }
+        for (i, group) in groups.iter().enumerate() {
+            println!("  第 {} 组:", i + 1);
+            for p in group {
+                println!("    {}", p.display());
+            }
+        }
+        return Ok(());
+    }
+    if let Some(ref path) = args.dump_forced {
+        let mut opts = RunOptions {
+            keep_ticket_types: args.keep_ticket_types.clone(),
+            keep_active_tickets: args.keep_active_tickets,
+            ..RunOptions::default()
+        };
+        if args.safe_defaults {
+            opts = opts.with_safe_defaults();
+        }
+        let dims = world::forced_by_dimension(
+            &input,
+            args.max_dimension_depth,
+            &opts.keep_ticket_types,
+            opts.keep_active_tickets,
+        )?;
+        std::fs::write(path, coords::dimension_chunks_json(&dims))?;
+        let total: usize = dims.iter().map(|(_, chunks)| chunks.len()).sum();
+        println!(
+            "已导出 {} 个维度的 {} 个强加载区块: {}",
+            dims.len(),
+            total,
+            path.display()
+        );
+        return Ok(());
+    }
+    if !args.threshold_sweep.is_empty() {
+        let sweep = analyze::threshold_sweep(
+            &input,
+            args.max_dimension_depth,
+            &args.region_naming,
+            &args.threshold_sweep,
+            &args.keep_ticket_types,
+            args.keep_active_tickets,
+            args.remove_unknown,
+        )?;
+        println!(
+            "扫描了 {} 个区块（{}），强加载区块 {} 个、读取失败 {} 个始终保留",
+            sweep.chunks,
+            world::fmt_bytes(sweep.bytes),
+            sweep.forced,
+            sweep.errors
+        );
+        println!(
+            "  {:>10}  {:>10}  {:>7}  {:>12}",
+            "阈值(秒)", "删除区块", "占比", "释放空间"
+        );
+        for row in &sweep.rows {
+            println!(
+                "  {:>10}  {:>10}  {:>6.1}%  {:>12}",
+                row.threshold_seconds,
+                row.removed_chunks,
+                row.removed_chunks as f64 * 100.0 / sweep.chunks.max(1) as f64,
+                world::fmt_bytes(row.removed_bytes)
+            );
+        }
+        return Ok(());
+    }
+    if args.dataversion_report {
+        let report = analyze::dataversion_report(
+            &input,
+            args.max_dimension_depth,
+            &args.region_naming,
+            args.min_upgradable_dataversion,
+        )?;
+        let (min, max) = (
+            report.versions.keys().next(),
+            report.versions.keys().next_back(),
+        );
+        match (min, max) {
+            (Some(min), Some(max)) => println!(
+                "扫描了 {} 个区块，DataVersion 范围 {} - {}（无 DataVersion {} 个，读取失败 {} 个，过大未读取 {} 个）",
+                report.chunks, min, max, report.unknown, report.errors, report.oversized
+            ),
+            _ => println!(
+                "扫描了 {} 个区块，均无 DataVersion（无 DataVersion {} 个，读取失败 {} 个，过大未读取 {} 个）",
+                report.chunks, report.unknown, report.errors, report.oversized
+            ),
+        }
+        let largest = report.versions.values().copied().max().unwrap_or(0).max(1);
+        let pct = |n: u64| n as f64 * 100.0 / report.chunks.max(1) as f64;
+        for (version, n) in &report.versions {
+            println!(
+                "  {:>6}  {:>8}  ({:>5.1}%)  {}",
+                version,
+                n,
+                pct(*n),
+                "#".repeat(((n * 40).div_ceil(largest)) as usize)
+            );
+        }
+        let at_risk = report.at_risk_chunks();
+        println!(
+            "低于 {} 或无 DataVersion 的区块 {} 个（{:.1}%），分布在 {} 个区域文件{}",
+            args.min_upgradable_dataversion,
+            at_risk,
+            pct(at_risk),
+            report.at_risk.len(),
+            if at_risk > 0 { ":" } else { "" }
+        );
+        for (region, n) in &report.at_risk {
+            println!("  {}  {} 个区块", region.display(), n);
+        }
+        return Ok(());
+    }
+    if args.compression_report {
+        let mut encodings = vec![ChunkEncoding::Zlib {
+            level: args.zlib_level,
+        }];
+        if args.zlib_level != 9 {
+            encodings.push(ChunkEncoding::Zlib { level: 9 });
+        }
+        encodings.push(ChunkEncoding::Lz4 {
+            block_size: args.lz4_block_size as usize,
+        });
+        let report = analyze::compression_report(
+            &input,
+            args.max_dimension_depth,
+            &args.region_naming,
+            args.compression_sample,
+            &encodings,
+        )?;
+        let scale = report.scale();
+        let estimate = |bytes: u64| world::fmt_bytes((bytes as f64 * scale) as u64);
+        println!(
+            "抽样 {}/{} 个区块（{} 个无法重新编码、{} 个读取失败，按原大小计入）",
+            report.sampled, report.chunks, report.skipped, report.errors
+        );
+        println!(
+            "  {:<12}  {:>12}  {:>7}  {:>14}",
+            "编码", "估计大小", "变化", "估计单线程耗时"
+        );
+        println!(
+            "  {:<12}  {:>12}  {:>7}  {:>14}",
+            "当前",
+            estimate(report.current_bytes),
+            "",
+            format!("解码 {:.1?}", report.decode_time.mul_f64(scale))
+        );
+        for trial in &report.trials {
+            let label = match trial.encoding {
+                ChunkEncoding::Zlib { level } => format!("zlib-{}", level),
+                ChunkEncoding::Lz4 { .. } => "lz4".to_string(),
+            };
+            println!(
+                "  {:<12}  {:>12}  {:>+6.1}%  {:>14}",
+                label,
+                estimate(trial.bytes),
+                (trial.bytes as f64 - report.current_bytes as f64) * 100.0
+                    / report.current_bytes.max(1) as f64,
+                format!("{:.1?}", trial.encode_time.mul_f64(scale))
+            );
+        }
+        return Ok(());
+    }
     let ticks = args
         .inhabited_time_seconds
         .checked_mul(20)
         .ok_or_else(|| anyhow::anyhow!("inhabited threshold seconds overflow"))?;
-    if !args.in_place {
+    let dim_thresholds = args
+        .dim_thresholds
+        .iter()
+        .map(|(name, secs)| {
+            secs.checked_mul(20)
+                .map(|ticks| (name.clone(), ticks))
+                .ok_or_else(|| anyhow::anyhow!("inhabited threshold seconds overflow"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // a report writes nothing, so an existing output needs no prompt
+    if !args.in_place && !args.report_empty_regions && args.explain_chunk.is_none() {
         if let Some(ref out_dir) = args.output {
-            if out_dir.exists() {
+            // Before any overwrite prompt, so -f can never delete the world itself
+            world::check_disjoint(&input, out_dir)?;
+            // must-create never deletes anything; run_with_options rejects the path
+            if out_dir.exists() && args.output_policy != OutputPolicy::MustCreate {
                 let non_empty = out_dir.read_dir()?.next().is_some();
                 if non_empty {
                     if args.force {
@@ -102,22 +1204,120 @@ fn main() -> Result<()> {
     } else {
         args.output.clone()
     };
-    world::run(
-        args.input,
-        dest,
-        ticks,
-        args.remove_unknown,
-        args.progress_mode,
-    )?;
+    let mut opts = RunOptions {
+        inhabited_threshold: ticks,
+        dim_thresholds,
+        remove_unknown: args.remove_unknown,
+        progress_mode: args.progress_mode,
+        recompress: args.recompress,
+        zlib_level: args.zlib_level,
+        lz4_block_size: args.lz4_block_size as usize,
+        max_dimension_depth: args.max_dimension_depth,
+        copy_world_files: args.copy_world_files,
+        copy_ignore: args.copy_ignore,
+        audit_inhabited_percent: args.audit_inhabited.then_some(args.audit_percent),
+        verify_forced: args.verify_forced,
+        keep_ticket_types: args.keep_ticket_types,
+        keep_active_tickets: args.keep_active_tickets,
+        skip_entities: args.skip_entities,
+        skip_poi: args.skip_poi,
+        copy_verbatim: args.copy_verbatim,
+        export_inhabited: args.export_inhabited,
+        remove_below_status: args.remove_below_status,
+        keep_only_biomes: args.keep_only_biomes,
+        set_timestamp: args.set_timestamp,
+        chunk_memory_limit: Some(args.chunk_memory_limit),
+        region_bounds: args.region_bounds.map(|b| {
+            (
+                b[0].min(b[2]),
+                b[1].min(b[3]),
+                b[0].max(b[2]),
+                b[1].max(b[3]),
+            )
+        }),
+        keep_current_dataversion: args.keep_current_dataversion,
+        last_update_within: args.last_update_within,
+        sample_keep: args.sample_keep,
+        sample_seed: args.sample_seed,
+        chunk_filter_script: args.chunk_filter_script,
+        explain_rules: args.explain_rules,
+        explain_chunk: args.explain_chunk,
+        keep_chunks: args.keep_chunks,
+        keep_ranges: args.keep_ranges,
+        keep_list: args.keep_list,
+        keep_polygon: args.keep_polygon,
+        coord_unit: args.coord_unit,
+        keep_forced_regions_whole: args.keep_forced_regions_whole,
+        max_removed_percent: args.max_removed_percent,
+        sync_interval: args.sync_interval,
+        keep_modified_only: args.keep_modified_only,
+        min_kept_chunks: args.min_kept_chunks,
+        prune_trivial_entities: args.prune_trivial_entities,
+        entity_hotspot_threshold: args.report_entity_hotspots,
+        clear_entity_hotspots: args.clear_entity_hotspots,
+        keep_named_entities: args.keep_named_entities,
+        max_memory_mb: args.max_memory_mb,
+        region_naming: args.region_naming,
+        strict: args.strict,
+        optimize_layout: args.optimize_layout,
+        json_summary: args.json_summary,
+        output_policy: args.output_policy,
+        build_index: args.build_index,
+        use_index: args.use_index,
+        remove_empty_chunks: args.remove_empty_chunks,
+        keep_zero_inhabited: args.keep_zero_inhabited,
+        no_inhabited: args.no_inhabited,
+        conservative_cull: args.conservative_cull,
+        scrub_fields: args.scrub.then_some(args.scrub_fields),
+        on_error: args.on_error,
+        copy_buffer_kb: args.copy_buffer_kb.map(|kb| kb as usize),
+        progress_events: args.progress_fd.map(progress_fd_path).transpose()?,
+        removal_log: args.removal_log,
+        linked_dimensions: args
+            .linked_dimensions
+            .iter()
+            .map(|s| split_dimension_link(s))
+            .collect(),
+        abort_if_no_dimensions: args.abort_if_no_dimensions,
+        keep_spawn_chunks: args.keep_spawn_chunks,
+        report_empty_regions: args.report_empty_regions,
+        report_to_stderr: args.tar_stdout,
+        tar_stdout: args.tar_stdout,
+        stable_order: args.stable_order,
+    };
+    if args.safe_defaults {
+        opts = opts.with_safe_defaults();
+    }
+    // in place (or without OUTPUT_DIR) the result replaces the input
+    let world_root = match args.output {
+        Some(ref out_dir) if !args.in_place => out_dir.clone(),
+        _ => input.clone(),
+    };
+    let result = if args.server_layout {
+        world::run_server_with_options(input, dest, &opts)
+    } else {
+        world::run_with_options(input, dest, &opts)
+    };
+    result?;
+    if let Some(ref manifest) = args.checksum_manifest {
+        let files = world::manifest::write_manifest(&world_root, manifest)?;
+        if !args.json_summary {
+            println!("校验清单: {} 个文件 → {}", files, manifest.display());
+        }
+    }
     if !args.in_place {
         if let Some(ref out_dir) = args.output {
             if args.zip_output {
                 let ts = Local::now().format("%Y%m%d%H%M%S").to_string();
                 let parent = out_dir.parent().unwrap_or(Path::new("."));
                 let zip_path = parent.join(format!("{}.zip", ts));
-                println!("开始压缩: {} → {}", out_dir.display(), zip_path.display());
-                zip_dir(out_dir, &zip_path)?;
-                println!("zip: {}", zip_path.display());
+                if !args.json_summary {
+                    println!("开始压缩: {} → {}", out_dir.display(), zip_path.display());
+                }
+                zip_dir(out_dir, &zip_path, args.zip_threads)?;
+                if !args.json_summary {
+                    println!("zip: {}", zip_path.display());
+                }
                 std::fs::remove_dir_all(out_dir)?;
             }
         }
@@ -125,49 +1325,79 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn zip_dir(src_dir: &Path, dst_zip: &Path) -> Result<()> {
+/// The path through which an inherited descriptor `fd` can be opened.
+#[cfg(unix)]
+fn progress_fd_path(fd: u32) -> Result<PathBuf> {
+    Ok(PathBuf::from(format!("/dev/fd/{}", fd)))
+}
+
+#[cfg(not(unix))]
+fn progress_fd_path(_fd: u32) -> Result<PathBuf> {
+    Err(anyhow::anyhow!("--progress-fd is only supported on Unix"))
+}
+
+/// Deflates one file into a single-entry in-memory archive, so pool threads can
+/// compress while the merge into the real archive stays in walk order.
+fn deflate_entry(path: &Path, name: &str, options: FileOptions) -> Result<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file(name, options)?;
+    std::io::copy(&mut File::open(path)?, &mut zip)?;
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Zips `src_dir` (junk files left out) into `dst_zip`, deflating files on a pool of
+/// `threads` threads (0 = rayon's default of one per CPU). Entries keep the order of
+/// a sequential walk.
+fn zip_dir(src_dir: &Path, dst_zip: &Path, threads: usize) -> Result<()> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()?;
     let file = File::create(dst_zip)?;
     let mut zip = zip::ZipWriter::new(file);
     let options = FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-    let total_files: u64 = WalkDir::new(src_dir)
+    let entries: Vec<(PathBuf, String, bool)> = WalkDir::new(src_dir)
         .into_iter()
         .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-        .count() as u64;
+        .filter_map(|e| {
+            let path = e.path();
+            let name = path.strip_prefix(src_dir).unwrap();
+            if name.as_os_str().is_empty() || is_junk_file(path) {
+                return None;
+            }
+            let name = name.to_string_lossy().to_string();
+            Some((path.to_path_buf(), name, path.is_file()))
+        })
+        .collect();
+    let total_files = entries.iter().filter(|(_, _, is_file)| *is_file).count() as u64;
     let pb = ProgressBar::new(total_files.max(1));
     let term = Term::stdout();
     let (_, cols) = term.size();
-    let reserve = 30u16;
-    let mut bar_width = if cols > reserve {
-        (cols - reserve) as usize
-    } else {
-        20usize
-    };
-    bar_width = bar_width.min(50);
-    let style = ProgressStyle::with_template(&format!(
-        "{{spinner:.green}} {{bar:{width}.cyan/blue}} {{pos}}/{{len}} 文件 {{percent}}% {{msg}}",
-        width = bar_width
+    let style = ProgressStyle::with_template(&BarLayout::for_columns(cols, 30).template(
+        "{spinner:.green}",
+        "cyan/blue",
+        "{pos}/{len} 文件 {percent}%",
     ))
     .unwrap()
     .progress_chars("=>-");
     pb.set_style(style);
-    for entry in WalkDir::new(src_dir).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        let name = path.strip_prefix(src_dir).unwrap();
-        if name.as_os_str().is_empty() {
-            continue;
-        }
-        if path.is_file() {
-            pb.set_message(name.to_string_lossy().to_string());
-            zip.start_file(name.to_string_lossy(), options)?;
-            let mut f = std::fs::File::open(path)?;
-            let mut buf = Vec::new();
-            f.read_to_end(&mut buf)?;
-            zip.write_all(&buf)?;
-            pb.inc(1);
-        } else {
-            let dir_name = format!("{}/", name.to_string_lossy());
-            zip.add_directory(dir_name, options)?;
+    // a few files per thread at a time bounds the compressed bytes held in memory
+    for batch in entries.chunks(pool.current_num_threads() * 4) {
+        let deflated: Vec<Option<Result<Vec<u8>>>> = pool.install(|| {
+            batch
+                .par_iter()
+                .map(|(path, name, is_file)| is_file.then(|| deflate_entry(path, name, options)))
+                .collect()
+        });
+        for ((_, name, _), data) in batch.iter().zip(deflated) {
+            match data {
+                Some(data) => {
+                    pb.set_message(name.clone());
+                    let mut single = zip::ZipArchive::new(Cursor::new(data?))?;
+                    zip.raw_copy_file(single.by_index(0)?)?;
+                    pb.inc(1);
+                }
+                None => zip.add_directory(format!("{}/", name), options)?,
+            }
         }
     }
     zip.finish()?;
@@ -1,18 +1,27 @@
-use anyhow::{anyhow, Result};
+use crate::mca::error::{McaError, Result};
+use crate::mca::source::RegionSource;
 use byteorder::{ByteOrder, LittleEndian};
 use flate2::read::{GzDecoder, ZlibDecoder};
-use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fs::{self, File};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
 use xxhash_rust::xxh32::xxh32;
 
 pub struct McaEntry {
-    file: File,
+    file: Box<dyn RegionSource>,
     start: u64,
-    _length: usize,
+    length: usize,
     index: u32,
     modified: u32,
     region_x: i32,
     region_z: i32,
+    memory_limit: Option<u64>,
+    external_dir: Option<PathBuf>,
+    /// `Some` once InhabitedTime is known, holding `None` for a chunk without one
+    cached_inhabited: Option<Option<i64>>,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq)]
@@ -28,6 +37,86 @@ pub enum CompressionMethod {
     ExternalLz4,
 }
 
+impl CompressionMethod {
+    pub fn id(&self) -> i8 {
+        match self {
+            CompressionMethod::Gzip => 1,
+            CompressionMethod::Zlib => 2,
+            CompressionMethod::Raw => 3,
+            CompressionMethod::Lz4 => 4,
+            CompressionMethod::Custom => 127,
+            CompressionMethod::ExternalGzip => -127,
+            CompressionMethod::ExternalZlib => -126,
+            CompressionMethod::ExternalRaw => -125,
+            CompressionMethod::ExternalLz4 => -124,
+        }
+    }
+
+    pub fn from_id(id: i8) -> Option<Self> {
+        Some(match id {
+            1 => CompressionMethod::Gzip,
+            2 => CompressionMethod::Zlib,
+            3 => CompressionMethod::Raw,
+            4 => CompressionMethod::Lz4,
+            127 => CompressionMethod::Custom,
+            -127 => CompressionMethod::ExternalGzip,
+            -126 => CompressionMethod::ExternalZlib,
+            -125 => CompressionMethod::ExternalRaw,
+            -124 => CompressionMethod::ExternalLz4,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionMethod::Gzip => "GZIP",
+            CompressionMethod::Zlib => "ZLIB",
+            CompressionMethod::Raw => "RAW",
+            CompressionMethod::Lz4 => "LZ4",
+            CompressionMethod::Custom => "custom",
+            CompressionMethod::ExternalGzip => "external GZIP",
+            CompressionMethod::ExternalZlib => "external ZLIB",
+            CompressionMethod::ExternalRaw => "external RAW",
+            CompressionMethod::ExternalLz4 => "external LZ4",
+        }
+    }
+
+    /// Compression of the payload itself: external methods map to their inline
+    /// counterpart, which is how the `.mcc` file is compressed.
+    pub fn payload_method(&self) -> Self {
+        match self {
+            CompressionMethod::ExternalGzip => CompressionMethod::Gzip,
+            CompressionMethod::ExternalZlib => CompressionMethod::Zlib,
+            CompressionMethod::ExternalRaw => CompressionMethod::Raw,
+            CompressionMethod::ExternalLz4 => CompressionMethod::Lz4,
+            other => *other,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ChunkEncoding {
+    /// zlib at the given level (0 = store only, 9 = slowest/smallest)
+    Zlib { level: u32 },
+    /// LZ4Block framing with blocks of `block_size` uncompressed bytes, see
+    /// `encode_lz4_blocks`
+    Lz4 { block_size: usize },
+}
+
+impl ChunkEncoding {
+    /// Serialized chunk bytes (length + method + data) of an uncompressed payload.
+    pub fn serialize(self, data: &[u8]) -> Result<Vec<u8>> {
+        Ok(match self {
+            ChunkEncoding::Zlib { level } => {
+                serialize_chunk(CompressionMethod::Zlib, &compress_zlib(data, level)?)
+            }
+            ChunkEncoding::Lz4 { block_size } => {
+                serialize_chunk(CompressionMethod::Lz4, &encode_lz4_blocks(data, block_size))
+            }
+        })
+    }
+}
+
 impl McaEntry {
     pub fn new(
         file: File,
@@ -37,17 +126,65 @@ impl McaEntry {
         modified: u32,
         region_x: i32,
         region_z: i32,
+    ) -> Self {
+        Self::from_source(
+            Box::new(file),
+            start,
+            length,
+            index,
+            modified,
+            region_x,
+            region_z,
+        )
+    }
+
+    /// Like `new`, reading from any `RegionSource` (e.g. a region held in memory).
+    pub fn from_source(
+        file: Box<dyn RegionSource>,
+        start: u64,
+        length: usize,
+        index: u32,
+        modified: u32,
+        region_x: i32,
+        region_z: i32,
     ) -> Self {
         Self {
             file,
             start,
-            _length: length,
+            length,
             index,
             modified,
             region_x,
             region_z,
+            memory_limit: None,
+            external_dir: None,
+            cached_inhabited: None,
         }
     }
+
+    /// Chunks whose declared length exceeds `limit` are streamed through bounded
+    /// buffers by the streaming helpers instead of being collected into memory.
+    pub fn with_memory_limit(mut self, limit: Option<u64>) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// Directory holding this chunk's `c.<x>.<z>.mcc` file if it is stored externally,
+    /// normally the region file's own directory. Without it external chunks read as empty.
+    pub fn with_external_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.external_dir = dir;
+        self
+    }
+    /// InhabitedTime known without decoding the chunk (e.g. from a `RegionIndex`),
+    /// `None` when the chunk has none; `patterns::inhabited::inhabited_time` returns
+    /// it as is.
+    pub fn set_cached_inhabited(&mut self, inhabited: Option<i64>) {
+        self.cached_inhabited = Some(inhabited);
+    }
+    /// `None` while nothing is cached, `Some(None)` for a cached missing InhabitedTime.
+    pub fn cached_inhabited(&self) -> Option<Option<i64>> {
+        self.cached_inhabited
+    }
     pub fn region_index(&self) -> u32 {
         self.index
     }
@@ -73,18 +210,7 @@ impl McaEntry {
         self.file.read_exact(&mut buf)?;
         let len = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
         let method = buf[4] as i8;
-        let cm = match method {
-            1 => CompressionMethod::Gzip,
-            2 => CompressionMethod::Zlib,
-            3 => CompressionMethod::Raw,
-            4 => CompressionMethod::Lz4,
-            127 => CompressionMethod::Custom,
-            -127 => CompressionMethod::ExternalGzip,
-            -126 => CompressionMethod::ExternalZlib,
-            -125 => CompressionMethod::ExternalRaw,
-            -124 => CompressionMethod::ExternalLz4,
-            _ => return Err(anyhow!("unknown compression")),
-        };
+        let cm = CompressionMethod::from_id(method).ok_or(McaError::UnknownCompression(method))?;
         let mut custom = None;
         if cm == CompressionMethod::Custom {
             let mut lbuf = [0u8; 2];
@@ -97,41 +223,130 @@ impl McaEntry {
         Ok((len, cm, custom))
     }
 
-    pub fn serialized_bytes(&mut self) -> Result<Vec<u8>> {
+    /// Serialized size on disk: the 4-byte length field plus the length it declares.
+    pub fn declared_length(&mut self) -> Result<u64> {
         let (len, _, _) = self.read_header()?;
-        let total = 4u64 + len as u64;
+        Ok(4 + len as u64)
+    }
+
+    /// Bytes reserved for the chunk by the location table (whole sectors).
+    pub fn allocated_length(&self) -> u64 {
+        self.length as u64
+    }
+
+    /// The chunk header claims more bytes than its sectors hold: a wrapped size byte
+    /// (e.g. a >255-sector chunk written inline) or a corrupt header.
+    pub fn exceeds_allocation(&mut self) -> Result<bool> {
+        Ok(self.declared_length()? > self.allocated_length())
+    }
+
+    /// Compares the `.mcc` file size for external chunks, the declared length otherwise.
+    pub fn exceeds_memory_limit(&mut self) -> Result<bool> {
+        let Some(limit) = self.memory_limit else {
+            return Ok(false);
+        };
+        let len = match self.external_path()? {
+            Some(path) => fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            None => self.declared_length()?,
+        };
+        Ok(len > limit)
+    }
+
+    /// `declared_length`, rejected when it runs past the chunk's sectors: reading it
+    /// would copy the next chunk, or whatever follows, into the output.
+    fn checked_length(&mut self) -> Result<u64> {
+        let declared = self.declared_length()?;
+        if declared > self.allocated_length() {
+            return Err(McaError::ExceedsAllocation {
+                declared,
+                allocated: self.allocated_length(),
+            });
+        }
+        Ok(declared)
+    }
+
+    /// Copies the serialized chunk (length + method + data) to `out` without buffering it
+    /// whole. Fails when the declared length exceeds the allocation (`exceeds_allocation`).
+    pub fn copy_serialized(&mut self, out: &mut dyn Write) -> Result<u64> {
+        let total = self.checked_length()?;
+        self.file.seek(SeekFrom::Start(self.start))?;
+        let copied = std::io::copy(&mut std::io::Read::take(&mut self.file, total), out)?;
+        if copied < total {
+            return Err(McaError::TruncatedChunk {
+                expected: total,
+                actual: copied,
+            });
+        }
+        Ok(copied)
+    }
+
+    /// The serialized chunk (length + method + data); fails like `copy_serialized`.
+    pub fn serialized_bytes(&mut self) -> Result<Vec<u8>> {
+        let total = self.checked_length()?;
         self.file.seek(SeekFrom::Start(self.start))?;
         let mut out = Vec::new();
         std::io::Read::take(&mut self.file, total).read_to_end(&mut out)?;
+        if (out.len() as u64) < total {
+            return Err(McaError::TruncatedChunk {
+                expected: total,
+                actual: out.len() as u64,
+            });
+        }
         Ok(out)
     }
 
+    /// Reads the serialized chunk once and serves every later read (keep rules,
+    /// decoding, `copy_serialized`) from memory, so a kept chunk is not read from the
+    /// region a second time when it is written. Chunks above the memory limit stay on
+    /// disk and yield `false`; a chunk that cannot be read fails like `serialized_bytes`.
+    pub fn buffer_serialized(&mut self) -> Result<bool> {
+        if self.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let bytes = self.serialized_bytes()?;
+        self.file = Box::new(Cursor::new(Arc::<[u8]>::from(bytes)));
+        self.start = 0;
+        Ok(true)
+    }
+
     pub fn data_bytes(&mut self) -> Result<(CompressionMethod, Vec<u8>, Option<String>)> {
         let (len, cm, custom) = self.read_header()?;
-        let mut pos = self.start + 5;
-        if cm == CompressionMethod::Custom {
-            let mut lbuf = [0u8; 2];
-            self.file.read_exact(&mut lbuf)?;
-            let n = u16::from_be_bytes(lbuf) as usize;
-            let mut s = vec![0u8; n];
-            self.file.read_exact(&mut s)?;
-            pos += 2 + n as u64;
-        }
-        let data_len = len as usize
-            - 1
-            - if cm == CompressionMethod::Custom {
-                2 + custom.as_ref().map(|v| v.len()).unwrap_or(0)
-            } else {
-                0
-            };
+        // `read_header` stops at the payload, past a custom compression name; the
+        // length covers the method byte and that name
+        let pos = self.file.stream_position()?;
+        let overhead = (pos - self.start - 4) as usize;
+        if (len as usize) < overhead {
+            return Err(McaError::InvalidChunkLength {
+                declared: len,
+                min: overhead as u64,
+            });
+        }
+        let data_len = len as usize - overhead;
         self.file.seek(SeekFrom::Start(pos))?;
         let mut data = Vec::new();
         std::io::Read::take(&mut self.file, data_len as u64).read_to_end(&mut data)?;
+        if data.len() < data_len {
+            return Err(McaError::TruncatedChunk {
+                expected: data_len as u64,
+                actual: data.len() as u64,
+            });
+        }
         Ok((cm, data, custom))
     }
 
+    /// External chunks are read from their `.mcc` file, which reads as empty when missing.
     pub fn all_data_uncompressed(&mut self) -> Result<Vec<u8>> {
-        let (cm, data, _) = self.data_bytes()?;
+        let (cm, data) = match self.external_path()? {
+            Some(path) if path.is_file() => {
+                let (_, cm, _) = self.read_header()?;
+                (cm.payload_method(), fs::read(path)?)
+            }
+            Some(_) => return Ok(Vec::new()),
+            None => {
+                let (cm, data, _) = self.data_bytes()?;
+                (cm, data)
+            }
+        };
         match cm {
             CompressionMethod::Raw => Ok(data),
             CompressionMethod::Zlib => {
@@ -151,6 +366,85 @@ impl McaEntry {
         }
     }
 
+    /// Streams the decompressed payload; only one LZ4 block is held in memory at a time.
+    /// Custom-compressed chunks and external chunks without their `.mcc` read as empty,
+    /// like `all_data_uncompressed`.
+    pub fn uncompressed_reader(&mut self) -> Result<Box<dyn Read + '_>> {
+        let (len, cm, _) = self.read_header()?;
+        if let Some(path) = self.external_path()? {
+            if !path.is_file() {
+                return Ok(Box::new(std::io::empty()));
+            }
+            let data = File::open(path)?;
+            return Ok(match cm.payload_method() {
+                CompressionMethod::Zlib => Box::new(ZlibDecoder::new(data)),
+                CompressionMethod::Gzip => Box::new(GzDecoder::new(data)),
+                CompressionMethod::Lz4 => Box::new(Lz4BlockReader::new(data)),
+                _ => Box::new(data),
+            });
+        }
+        if !matches!(
+            cm,
+            CompressionMethod::Raw
+                | CompressionMethod::Zlib
+                | CompressionMethod::Gzip
+                | CompressionMethod::Lz4
+        ) {
+            return Ok(Box::new(std::io::empty()));
+        }
+        if len == 0 {
+            return Err(McaError::InvalidChunkLength {
+                declared: len,
+                min: 1,
+            });
+        }
+        self.file.seek(SeekFrom::Start(self.start + 5))?;
+        let data = std::io::Read::take(&mut self.file, len as u64 - 1);
+        Ok(match cm {
+            CompressionMethod::Zlib => Box::new(ZlibDecoder::new(data)),
+            CompressionMethod::Gzip => Box::new(GzDecoder::new(data)),
+            CompressionMethod::Lz4 => Box::new(Lz4BlockReader::new(data)),
+            _ => Box::new(data),
+        })
+    }
+
+    /// The payload decodes to zero bytes: a valid header over no data, which no game
+    /// version writes. `false` when the payload cannot be decoded at all (custom
+    /// compression, external without its `.mcc`), which is unknown rather than empty;
+    /// decompression errors are returned. Only the first decoded byte is read.
+    pub fn has_empty_payload(&mut self) -> Result<bool> {
+        let (_, cm, _) = self.read_header()?;
+        if cm == CompressionMethod::Custom {
+            return Ok(false);
+        }
+        if cm != cm.payload_method() && !self.external_path()?.is_some_and(|p| p.is_file()) {
+            return Ok(false);
+        }
+        let mut byte = [0u8; 1];
+        Ok(self.uncompressed_reader()?.read(&mut byte)? == 0)
+    }
+
+    /// Re-encodes the chunk payload, returning the serialized bytes (length + method + data).
+    /// External and custom-compressed chunks cannot be re-encoded and yield `None`, as do
+    /// chunks above the memory limit, which are left as they are.
+    pub fn recompressed_bytes(&mut self, encoding: ChunkEncoding) -> Result<Option<Vec<u8>>> {
+        let (_, cm, _) = self.read_header()?;
+        if self.exceeds_memory_limit()? {
+            return Ok(None);
+        }
+        if !matches!(
+            cm,
+            CompressionMethod::Raw
+                | CompressionMethod::Zlib
+                | CompressionMethod::Gzip
+                | CompressionMethod::Lz4
+        ) {
+            return Ok(None);
+        }
+        let data = self.all_data_uncompressed()?;
+        Ok(Some(encoding.serialize(&data)?))
+    }
+
     pub fn is_external(&mut self) -> Result<bool> {
         let (_, cm, _) = self.read_header()?;
         Ok(matches!(
@@ -161,37 +455,175 @@ impl McaEntry {
                 | CompressionMethod::ExternalLz4
         ))
     }
+
+    /// `c.<x>.<z>.mcc` path of an external chunk; `None` for inline chunks or when no
+    /// external directory was given.
+    pub fn external_path(&mut self) -> Result<Option<PathBuf>> {
+        let Some(ref dir) = self.external_dir else {
+            return Ok(None);
+        };
+        let name = format!("c.{}.{}.mcc", self.global_x(), self.global_z());
+        let path = dir.join(name);
+        Ok(self.is_external()?.then_some(path))
+    }
+}
+
+pub fn compress_zlib(data: &[u8], level: u32) -> Result<Vec<u8>> {
+    let mut enc = ZlibEncoder::new(Vec::new(), Compression::new(level.min(9)));
+    enc.write_all(data)?;
+    Ok(enc.finish()?)
+}
+
+pub fn serialize_chunk(method: CompressionMethod, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5 + payload.len());
+    out.extend_from_slice(&((1 + payload.len()) as u32).to_be_bytes());
+    out.push(method.id() as u8);
+    out.extend_from_slice(payload);
+    out
 }
 
 const LZ4_MAGIC: &[u8] = b"LZ4Block";
 const LZ4_HEADER_LEN: usize = 8 + 1 + 4 + 4 + 4;
 const LZ4_XXHASH_SEED: u32 = 0x9747b28c;
+const LZ4_METHOD_RAW: u8 = 0x10;
+const LZ4_METHOD_LZ4: u8 = 0x20;
+/// Block size bounds of lz4-java's `LZ4BlockOutputStream`, which Minecraft uses
+/// (with the 64 KiB default)
+pub const LZ4_MIN_BLOCK_SIZE: usize = 64;
+pub const LZ4_MAX_BLOCK_SIZE: usize = 1 << 25;
+pub const LZ4_DEFAULT_BLOCK_SIZE: usize = 1 << 16;
 
 pub fn lz4_checksum(data: &[u8]) -> u32 {
     xxh32(data, LZ4_XXHASH_SEED) & 0x0FFFFFFF
 }
 
+fn invalid_data(e: McaError) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+}
+
+/// Streaming counterpart of `decode_lz4_blocks`: decodes and verifies one block per refill.
+pub struct Lz4BlockReader<R> {
+    inner: R,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> Lz4BlockReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            block: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// Loads the next block; `false` at a clean end of stream.
+    fn next_block(&mut self) -> std::io::Result<bool> {
+        let mut header = [0u8; LZ4_HEADER_LEN];
+        let mut filled = 0;
+        while filled < LZ4_HEADER_LEN {
+            let n = self.inner.read(&mut header[filled..])?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        if filled == 0 {
+            return Ok(false);
+        }
+        if filled < LZ4_HEADER_LEN {
+            return Err(invalid_data(McaError::DanglingLz4Bytes));
+        }
+        if &header[..8] != LZ4_MAGIC {
+            return Err(invalid_data(McaError::InvalidLz4Magic));
+        }
+        let method = header[8] & 0xF0;
+        let comp_len = LittleEndian::read_u32(&header[9..13]) as usize;
+        let decomp_len = LittleEndian::read_u32(&header[13..17]) as usize;
+        let checksum_le = LittleEndian::read_u32(&header[17..21]);
+        check_lz4_block_len(comp_len, decomp_len).map_err(invalid_data)?;
+        let mut block = Vec::new();
+        std::io::Read::take(&mut self.inner, comp_len as u64).read_to_end(&mut block)?;
+        if block.len() < comp_len {
+            return Err(invalid_data(McaError::Lz4BlockTruncated));
+        }
+        if is_lz4_end_mark(comp_len, decomp_len, checksum_le) {
+            return Ok(true);
+        }
+        let decoded = match method {
+            LZ4_METHOD_RAW => block,
+            LZ4_METHOD_LZ4 => lz4_flex::block::decompress(&block, decomp_len)
+                .map_err(|e| invalid_data(McaError::Lz4Decompress(e)))?,
+            _ => return Err(invalid_data(McaError::UnsupportedLz4Method(method))),
+        };
+        if lz4_checksum(&decoded) != checksum_le {
+            return Err(invalid_data(McaError::Lz4ChecksumMismatch));
+        }
+        self.block = decoded;
+        self.pos = 0;
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Lz4BlockReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.block.len() {
+            if !self.next_block()? {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.block.len() - self.pos);
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Rejects block lengths no `LZ4BlockOutputStream` writes, before anything is
+/// allocated for them: lz4_flex zero-fills the declared decoded length up front.
+fn check_lz4_block_len(comp_len: usize, decomp_len: usize) -> Result<()> {
+    if comp_len > LZ4_MAX_BLOCK_SIZE || decomp_len > LZ4_MAX_BLOCK_SIZE {
+        return Err(McaError::Lz4BlockTooLarge {
+            compressed: comp_len,
+            decompressed: decomp_len,
+            max: LZ4_MAX_BLOCK_SIZE,
+        });
+    }
+    Ok(())
+}
+
+/// The empty block lz4-java writes when the stream is closed. Its checksum field is
+/// 0 rather than the checksum of no bytes.
+fn is_lz4_end_mark(comp_len: usize, decomp_len: usize, checksum: u32) -> bool {
+    comp_len == 0 && decomp_len == 0 && checksum == 0
+}
+
 pub fn decode_lz4_blocks(inp: &[u8]) -> Result<Vec<u8>> {
     let mut i = 0usize;
     let mut out = Vec::new();
     while i + LZ4_HEADER_LEN <= inp.len() {
         if &inp[i..i + 8] != LZ4_MAGIC {
-            return Err(anyhow!("invalid LZ4 magic"));
+            return Err(McaError::InvalidLz4Magic);
         }
         let token = inp[i + 8];
         let method = token & 0xF0; // 0x10 RAW, 0x20 LZ4
         let comp_len = LittleEndian::read_u32(&inp[i + 9..i + 13]) as usize;
         let decomp_len = LittleEndian::read_u32(&inp[i + 13..i + 17]) as usize;
         let checksum_le = LittleEndian::read_u32(&inp[i + 17..i + 21]);
+        check_lz4_block_len(comp_len, decomp_len)?;
         let start = i + LZ4_HEADER_LEN;
         if start + comp_len > inp.len() {
-            return Err(anyhow!("LZ4 block truncated"));
+            return Err(McaError::Lz4BlockTruncated);
         }
         let block = &inp[start..start + comp_len];
-        let decoded = if method == 0x10 {
+        if is_lz4_end_mark(comp_len, decomp_len, checksum_le) {
+            i = start;
+            continue;
+        }
+        let decoded = if method == LZ4_METHOD_RAW {
             // RAW
             block.to_vec()
-        } else if method == 0x20 {
+        } else if method == LZ4_METHOD_LZ4 {
             // LZ4
             let mut with_prepended = Vec::with_capacity(4 + block.len());
             let mut size_buf = [0u8; 4];
@@ -200,17 +632,50 @@ pub fn decode_lz4_blocks(inp: &[u8]) -> Result<Vec<u8>> {
             with_prepended.extend_from_slice(block);
             lz4_flex::block::decompress_size_prepended(&with_prepended)?
         } else {
-            return Err(anyhow!("unsupported LZ4 method"));
+            return Err(McaError::UnsupportedLz4Method(method));
         };
         let checksum = lz4_checksum(&decoded);
         if checksum != checksum_le {
-            return Err(anyhow!("LZ4 checksum mismatch"));
+            return Err(McaError::Lz4ChecksumMismatch);
         }
         out.extend_from_slice(&decoded);
         i = start + comp_len;
     }
     if i != inp.len() {
-        return Err(anyhow!("dangling LZ4 bytes"));
+        return Err(McaError::DanglingLz4Bytes);
     }
     Ok(out)
 }
+
+/// Encodes `data` as LZ4Block stream the way lz4-java's `LZ4BlockOutputStream` does:
+/// blocks of up to `block_size` bytes (clamped to `LZ4_MIN_BLOCK_SIZE..=LZ4_MAX_BLOCK_SIZE`),
+/// each stored raw (`0x10`) when LZ4 would not shrink it, a token whose low nibble is
+/// the block size exponent, and a closing empty block.
+pub fn encode_lz4_blocks(data: &[u8], block_size: usize) -> Vec<u8> {
+    let block_size = block_size.clamp(LZ4_MIN_BLOCK_SIZE, LZ4_MAX_BLOCK_SIZE);
+    // log2 of the block size rounded up, minus 10 (64 KiB → 6)
+    let level = ((usize::BITS - (block_size - 1).leading_zeros()) as u8).saturating_sub(10);
+    let mut out = Vec::with_capacity(data.len() + LZ4_HEADER_LEN * 2);
+    let header = |out: &mut Vec<u8>, method: u8, comp: usize, decomp: usize, sum: u32| {
+        out.extend_from_slice(LZ4_MAGIC);
+        out.push(method | level);
+        let mut buf = [0u8; 4];
+        for v in [comp as u32, decomp as u32, sum] {
+            LittleEndian::write_u32(&mut buf, v);
+            out.extend_from_slice(&buf);
+        }
+    };
+    for block in data.chunks(block_size) {
+        let compressed = lz4_flex::block::compress(block);
+        let sum = lz4_checksum(block);
+        if compressed.len() < block.len() {
+            header(&mut out, LZ4_METHOD_LZ4, compressed.len(), block.len(), sum);
+            out.extend_from_slice(&compressed);
+        } else {
+            header(&mut out, LZ4_METHOD_RAW, block.len(), block.len(), sum);
+            out.extend_from_slice(block);
+        }
+    }
+    header(&mut out, LZ4_METHOD_RAW, 0, 0, 0);
+    out
+}
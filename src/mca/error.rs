@@ -0,0 +1,53 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum McaError {
+    #[error("invalid mca filename: {0}")]
+    InvalidFilename(String),
+    #[error("unknown compression: {0}")]
+    UnknownCompression(i8),
+    #[error("chunk truncated: expected {expected} bytes, got {actual}")]
+    TruncatedChunk { expected: u64, actual: u64 },
+    #[error("chunk header declares {declared} bytes, less than the {min} its own header takes")]
+    InvalidChunkLength { declared: u32, min: u64 },
+    #[error("chunk declares {declared} bytes but only {allocated} are allocated to it")]
+    ExceedsAllocation { declared: u64, allocated: u64 },
+    #[error("{path}: region header truncated, {table} table has {got} of 4096 bytes")]
+    TruncatedHeader {
+        path: String,
+        table: &'static str,
+        got: usize,
+    },
+    #[error("slot {index} points to byte {offset}, past the end of the file ({file_len} bytes)")]
+    OffsetOutOfRange {
+        index: usize,
+        offset: u64,
+        file_len: u64,
+    },
+    #[error("invalid LZ4 magic")]
+    InvalidLz4Magic,
+    #[error("LZ4 block truncated")]
+    Lz4BlockTruncated,
+    #[error("LZ4 block of {compressed} bytes declares {decompressed} decoded bytes, above the {max} byte block limit")]
+    Lz4BlockTooLarge {
+        compressed: usize,
+        decompressed: usize,
+        max: usize,
+    },
+    #[error("unsupported LZ4 method: {0:#04x}")]
+    UnsupportedLz4Method(u8),
+    #[error("LZ4 checksum mismatch")]
+    Lz4ChecksumMismatch,
+    #[error("invalid region index: {0}")]
+    InvalidIndex(&'static str),
+    #[error("dangling LZ4 bytes")]
+    DanglingLz4Bytes,
+    #[error("LZ4 decompression failed: {0}")]
+    Lz4Decompress(#[from] lz4_flex::block::DecompressError),
+    #[error(transparent)]
+    Regex(#[from] regex::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, McaError>;
@@ -0,0 +1,131 @@
+use crate::mca::error::{McaError, Result};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+const MAGIC: &[u8; 8] = b"THANOSIX";
+const VERSION: u8 = 1;
+
+/// Cached facts about one chunk of an indexed region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Slot in the region's location table (0..1024)
+    pub index: u16,
+    /// Compression id as stored in the chunk header (`CompressionMethod::id`)
+    pub compression: i8,
+    /// Serialized size: the length field plus the length it declares
+    pub size: u32,
+    /// `None` when the chunk has no InhabitedTime (or is external without its `.mcc`
+    /// file). Chunks that could not be decoded are not indexed at all, so scans using
+    /// the index decode them again and see the error
+    pub inhabited: Option<i64>,
+}
+
+/// Sidecar `r.<x>.<z>.mca.idx` caching per-chunk facts of the region next to it, so
+/// later scans can skip decompressing every chunk. It records the region's length and
+/// modification time and is only trusted while both still match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegionIndex {
+    region_len: u64,
+    region_mtime: (u64, u32),
+    pub entries: Vec<IndexEntry>,
+}
+
+fn region_stamp(region: &Path) -> Result<(u64, (u64, u32))> {
+    let meta = fs::metadata(region)?;
+    let mtime = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok((meta.len(), (mtime.as_secs(), mtime.subsec_nanos())))
+}
+
+impl RegionIndex {
+    pub fn sidecar_path(region: &Path) -> PathBuf {
+        let mut name = region.as_os_str().to_owned();
+        name.push(".idx");
+        PathBuf::from(name)
+    }
+
+    /// An index of `region` as it is on disk now.
+    pub fn new(region: &Path, entries: Vec<IndexEntry>) -> Result<Self> {
+        let (region_len, region_mtime) = region_stamp(region)?;
+        Ok(Self {
+            region_len,
+            region_mtime,
+            entries,
+        })
+    }
+
+    /// The sidecar of `region`, or `None` when it is missing, unreadable or stale.
+    pub fn load_fresh(region: &Path) -> Option<Self> {
+        let index = Self::read(&Self::sidecar_path(region)).ok()?;
+        let (len, mtime) = region_stamp(region).ok()?;
+        (index.region_len == len && index.region_mtime == mtime).then_some(index)
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut r = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(McaError::InvalidIndex("bad magic"));
+        }
+        if r.read_u8()? != VERSION {
+            return Err(McaError::InvalidIndex("unsupported version"));
+        }
+        let region_len = r.read_u64::<BigEndian>()?;
+        let region_mtime = (r.read_u64::<BigEndian>()?, r.read_u32::<BigEndian>()?);
+        let count = r.read_u16::<BigEndian>()?;
+        if count > 1024 {
+            return Err(McaError::InvalidIndex("more than 1024 chunks"));
+        }
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let index = r.read_u16::<BigEndian>()?;
+            let compression = r.read_i8()?;
+            let size = r.read_u32::<BigEndian>()?;
+            let inhabited = match r.read_u8()? {
+                0 => None,
+                _ => Some(r.read_i64::<BigEndian>()?),
+            };
+            entries.push(IndexEntry {
+                index,
+                compression,
+                size,
+                inhabited,
+            });
+        }
+        Ok(Self {
+            region_len,
+            region_mtime,
+            entries,
+        })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        w.write_all(MAGIC)?;
+        w.write_u8(VERSION)?;
+        w.write_u64::<BigEndian>(self.region_len)?;
+        w.write_u64::<BigEndian>(self.region_mtime.0)?;
+        w.write_u32::<BigEndian>(self.region_mtime.1)?;
+        w.write_u16::<BigEndian>(self.entries.len() as u16)?;
+        for e in &self.entries {
+            w.write_u16::<BigEndian>(e.index)?;
+            w.write_i8(e.compression)?;
+            w.write_u32::<BigEndian>(e.size)?;
+            match e.inhabited {
+                Some(t) => {
+                    w.write_u8(1)?;
+                    w.write_i64::<BigEndian>(t)?;
+                }
+                None => w.write_u8(0)?,
+            }
+        }
+        w.flush()?;
+        Ok(())
+    }
+}
@@ -1,3 +1,9 @@
 pub mod entry;
+pub mod error;
+pub mod index;
+pub mod naming;
 pub mod reader;
+pub mod repair;
+pub mod selfcheck;
+pub mod source;
 pub mod writer;
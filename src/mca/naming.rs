@@ -0,0 +1,89 @@
+use regex::Regex;
+use std::fmt;
+use std::path::Path;
+
+/// The file name template of vanilla Anvil regions.
+pub const VANILLA_REGION_NAMING: &str = "r.{x}.{z}.mca";
+
+/// How region files are named: a template such as `r.{x}.{z}.mca` whose `{x}` and
+/// `{z}` stand for the region coordinates. Names are matched at the end of a path,
+/// like the reader always did, so `region/r.0.0.mca` matches the vanilla template.
+#[derive(Clone)]
+pub struct RegionNaming {
+    template: String,
+    pattern: Regex,
+    /// `{x}` comes after `{z}` in the template
+    z_first: bool,
+}
+
+impl RegionNaming {
+    /// Parses a template; it needs exactly one `{x}` and one `{z}`, separated by at
+    /// least one character so the two numbers cannot run together.
+    pub fn parse(template: &str) -> Result<Self, String> {
+        let (xs, zs) = (
+            template.matches("{x}").count(),
+            template.matches("{z}").count(),
+        );
+        if xs != 1 || zs != 1 {
+            return Err(format!(
+                "region naming template needs one {{x}} and one {{z}}, got {}",
+                template
+            ));
+        }
+        let (x, z) = (template.find("{x}").unwrap(), template.find("{z}").unwrap());
+        if x.abs_diff(z) == 3 {
+            return Err(format!(
+                "region naming template needs a separator between {{x}} and {{z}}, got {}",
+                template
+            ));
+        }
+        if template.contains(['/', '\\']) {
+            return Err(format!(
+                "region naming template is a file name, not a path: {}",
+                template
+            ));
+        }
+        let pattern = regex::escape(template)
+            .replace(r"\{x\}", r"(-?\d+)")
+            .replace(r"\{z\}", r"(-?\d+)");
+        Ok(Self {
+            template: template.to_string(),
+            pattern: Regex::new(&format!("{}$", pattern)).map_err(|e| e.to_string())?,
+            z_first: z < x,
+        })
+    }
+
+    /// Region coordinates in `name` (a file name or a path ending in one).
+    pub fn coords(&self, name: &str) -> Option<(i32, i32)> {
+        let caps = self.pattern.captures(name)?;
+        let at = |i: usize| caps.get(i)?.as_str().parse::<i32>().ok();
+        let (a, b) = (at(1)?, at(2)?);
+        Some(if self.z_first { (b, a) } else { (a, b) })
+    }
+
+    /// Whether the file name of `path` follows the template.
+    pub fn matches(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| self.coords(n).is_some())
+    }
+
+    /// The file name of region (`x`, `z`).
+    pub fn file_name(&self, x: i32, z: i32) -> String {
+        self.template
+            .replace("{x}", &x.to_string())
+            .replace("{z}", &z.to_string())
+    }
+}
+
+impl Default for RegionNaming {
+    fn default() -> Self {
+        Self::parse(VANILLA_REGION_NAMING).unwrap()
+    }
+}
+
+impl fmt::Display for RegionNaming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
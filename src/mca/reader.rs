@@ -1,66 +1,226 @@
 use crate::mca::entry::McaEntry;
-use anyhow::{anyhow, Result};
-use regex::Regex;
+use crate::mca::error::{McaError, Result};
+use crate::mca::naming::RegionNaming;
+use crate::mca::source::RegionSource;
+use log::warn;
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 
 pub struct McaReader {
-    file: File,
+    file: Box<dyn RegionSource>,
+    /// File name as given to `open`/`from_source`, for error messages
+    name: String,
     x_pos: i32,
     z_pos: i32,
-    offsets: Option<Vec<u32>>,
-    sizes: Option<Vec<u32>>,
+    /// Byte offset and allocated bytes of every slot, 0 for unused slots
+    offsets: Option<Vec<u64>>,
+    sizes: Option<Vec<u64>>,
+    file_len: u64,
     timestamps: Option<Vec<u32>>,
+    memory_limit: Option<u64>,
+    dir: Option<PathBuf>,
+}
+
+/// One slot of the location and timestamp tables, as stored: offset and size in
+/// 4 KiB sectors, timestamp in epoch seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderSlot {
+    pub offset: u32,
+    pub sectors: u32,
+    pub timestamp: u32,
+}
+
+impl HeaderSlot {
+    /// An offset or a size is set; readers skip the slot unless both are.
+    pub fn is_used(&self) -> bool {
+        self.offset != 0 || self.sectors != 0
+    }
+}
+
+/// The parsed header of a region file, see `McaReader::header`.
+pub struct RegionHeader {
+    /// Indexed by slot (`x + z * 32`)
+    pub slots: Vec<HeaderSlot>,
+    pub file_len: u64,
+}
+
+impl RegionHeader {
+    /// Slots whose entry cannot describe a readable chunk, in slot order, with the reason.
+    /// Overlapping slots are both reported, each naming the other.
+    pub fn anomalies(&self) -> Vec<(usize, String)> {
+        let file_sectors = self.file_len.div_ceil(4096);
+        let mut found: Vec<(usize, String)> = Vec::new();
+        for (i, s) in self.slots.iter().enumerate() {
+            let reason = if !s.is_used() {
+                continue;
+            } else if s.offset == 0 {
+                "sector count without an offset".to_string()
+            } else if s.sectors == 0 {
+                "offset with a sector count of 0".to_string()
+            } else if s.offset < 2 {
+                "offset inside the header".to_string()
+            } else if s.offset as u64 + s.sectors as u64 > file_sectors {
+                format!(
+                    "sectors {}..{} run past the end of the file ({} sectors)",
+                    s.offset,
+                    s.offset + s.sectors,
+                    file_sectors
+                )
+            } else {
+                continue;
+            };
+            found.push((i, reason));
+        }
+        let mut spans: Vec<(u32, u32, usize)> = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.offset >= 2 && s.sectors > 0)
+            .map(|(i, s)| (s.offset, s.offset + s.sectors, i))
+            .collect();
+        spans.sort_unstable();
+        for (a, earlier) in spans.iter().enumerate() {
+            for later in spans[a + 1..].iter().take_while(|l| l.0 < earlier.1) {
+                found.push((earlier.2, format!("overlaps slot {}", later.2)));
+                found.push((later.2, format!("overlaps slot {}", earlier.2)));
+            }
+        }
+        found.sort();
+        found
+    }
+}
+
+fn region_coords(name: &str, naming: &RegionNaming) -> Result<(i32, i32)> {
+    naming
+        .coords(name)
+        .ok_or_else(|| McaError::InvalidFilename(name.to_string()))
 }
 
 impl McaReader {
     pub fn open(path: &str) -> Result<Self> {
-        let re = Regex::new(r#"r\.(-?\d+)\.(-?\d+)\.mca$"#)?;
-        let caps = re
-            .captures(path)
-            .ok_or_else(|| anyhow!("invalid mca filename"))?;
-        let x_pos: i32 = caps.get(1).unwrap().as_str().parse()?;
-        let z_pos: i32 = caps.get(2).unwrap().as_str().parse()?;
-        let file = File::open(path)?;
+        Self::open_named(path, &RegionNaming::default())
+    }
+
+    /// Like `open`, taking the region coordinates from a file named after `naming`.
+    pub fn open_named(path: &str, naming: &RegionNaming) -> Result<Self> {
+        // a bad name is reported before the file is touched
+        region_coords(path, naming)?;
+        let dir = Path::new(path).parent().map(Path::to_path_buf);
+        Ok(Self::from_source_named(path, File::open(path)?, naming)?.with_external_dir(dir))
+    }
+
+    /// Reads a region from `source`; `name` only has to end in `r.<x>.<z>.mca`.
+    /// External chunks stay unreadable unless `with_external_dir` is set.
+    #[allow(dead_code)]
+    pub fn from_source(name: &str, source: impl RegionSource + 'static) -> Result<Self> {
+        Self::from_source_named(name, source, &RegionNaming::default())
+    }
+
+    /// Like `from_source`, `name` ending in a file name that follows `naming`.
+    pub fn from_source_named(
+        name: &str,
+        source: impl RegionSource + 'static,
+        naming: &RegionNaming,
+    ) -> Result<Self> {
+        let (x_pos, z_pos) = region_coords(name, naming)?;
         Ok(Self {
-            file,
+            file: Box::new(source),
+            name: name.to_string(),
             x_pos,
             z_pos,
             offsets: None,
             sizes: None,
+            file_len: 0,
             timestamps: None,
+            memory_limit: None,
+            dir: None,
         })
     }
 
-    fn read_header(&mut self) -> Result<()> {
-        self.file.seek(SeekFrom::Start(0))?;
-        let mut loc = vec![0u8; 4096];
-        self.file.read_exact(&mut loc)?;
-        let mut time = vec![0u8; 4096];
-        self.file.read_exact(&mut time)?;
-        let mut offsets = Vec::with_capacity(1024);
-        let mut sizes = Vec::with_capacity(1024);
-        for i in 0..1024 {
-            let base = i * 4;
-            let v = u32::from_be_bytes([loc[base], loc[base + 1], loc[base + 2], loc[base + 3]]);
-            let off = (v >> 8) * 4096;
-            let size = (v & 0xFF) * 4096;
-            offsets.push(off);
-            sizes.push(size);
+    /// Directory searched for the `.mcc` files of external chunks.
+    pub fn with_external_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.dir = dir;
+        self
+    }
+
+    /// Memory limit handed to every entry (see `McaEntry::with_memory_limit`).
+    pub fn with_memory_limit(mut self, limit: Option<u64>) -> Self {
+        self.memory_limit = limit;
+        self
+    }
+
+    /// The next 4096-byte header table; `TruncatedHeader` when the file ends inside it.
+    fn read_table(&mut self, table: &'static str) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(4096);
+        (&mut self.file).take(4096).read_to_end(&mut buf)?;
+        if buf.len() < 4096 {
+            return Err(McaError::TruncatedHeader {
+                path: self.name.clone(),
+                table,
+                got: buf.len(),
+            });
         }
-        let mut timestamps = Vec::with_capacity(1024);
-        for i in 0..1024 {
+        Ok(buf)
+    }
+
+    /// Both header tables as stored, unscaled.
+    fn read_slots(&mut self) -> Result<Vec<HeaderSlot>> {
+        self.file.seek(SeekFrom::Start(0))?;
+        let loc = self.read_table("location")?;
+        let time = self.read_table("timestamp")?;
+        let word = |table: &[u8], i: usize| {
             let base = i * 4;
-            let v =
-                u32::from_be_bytes([time[base], time[base + 1], time[base + 2], time[base + 3]]);
-            timestamps.push(v);
+            u32::from_be_bytes([
+                table[base],
+                table[base + 1],
+                table[base + 2],
+                table[base + 3],
+            ])
+        };
+        Ok((0..1024)
+            .map(|i| {
+                let v = word(&loc, i);
+                HeaderSlot {
+                    offset: v >> 8,
+                    sectors: v & 0xFF,
+                    timestamp: word(&time, i),
+                }
+            })
+            .collect())
+    }
+
+    fn read_header(&mut self) -> Result<()> {
+        let slots = self.read_slots()?;
+        // in u64 a garbage offset (up to 2^24 sectors, 64 GiB) cannot wrap onto a
+        // real sector; it is checked against the file length when read
+        self.offsets = Some(slots.iter().map(|s| s.offset as u64 * 4096).collect());
+        self.sizes = Some(slots.iter().map(|s| s.sectors as u64 * 4096).collect());
+        self.timestamps = Some(slots.iter().map(|s| s.timestamp).collect());
+        self.file_len = self.file.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// `Err(OffsetOutOfRange)` when slot `index` starts at or past the end of the file.
+    fn check_offset(&self, index: usize) -> Result<()> {
+        let offset = self.offsets.as_ref().unwrap()[index];
+        if offset >= self.file_len {
+            return Err(McaError::OffsetOutOfRange {
+                index,
+                offset,
+                file_len: self.file_len,
+            });
         }
-        self.offsets = Some(offsets);
-        self.sizes = Some(sizes);
-        self.timestamps = Some(timestamps);
         Ok(())
     }
 
+    /// The location and timestamp tables as stored, whatever they contain.
+    pub fn header(&mut self) -> Result<RegionHeader> {
+        let slots = self.read_slots()?;
+        let file_len = self.file.seek(SeekFrom::End(0))?;
+        Ok(RegionHeader { slots, file_len })
+    }
+
     fn ensure(&mut self) -> Result<()> {
         if self.offsets.is_none() {
             self.read_header()?;
@@ -77,6 +237,16 @@ impl McaReader {
         self.z_pos
     }
 
+    /// Number of occupied slots in the location table; reads nothing but the header.
+    pub fn chunk_count(&mut self) -> Result<usize> {
+        self.ensure()?;
+        let offsets = self.offsets.as_ref().unwrap();
+        let sizes = self.sizes.as_ref().unwrap();
+        Ok((0..1024)
+            .filter(|&i| offsets[i] != 0 && sizes[i] != 0)
+            .count())
+    }
+
     pub fn entries(&mut self) -> Result<Vec<McaEntry>> {
         self.ensure()?;
         let offsets = self.offsets.as_ref().unwrap();
@@ -90,15 +260,36 @@ impl McaReader {
             if off == 0 || size == 0 {
                 continue;
             }
-            out.push(McaEntry::new(
-                self.file.try_clone()?,
-                off as u64,
+            if let Err(e) = self.check_offset(i) {
+                warn!(
+                    "Skipping chunk {} in r.{}.{}.mca: {}",
+                    i, self.x_pos, self.z_pos, e
+                );
+                continue;
+            }
+            let mut entry = McaEntry::from_source(
+                self.file.try_clone_source()?,
+                off,
                 size as usize,
                 i as u32,
                 ts,
                 self.x_pos,
                 self.z_pos,
-            ));
+            )
+            .with_memory_limit(self.memory_limit)
+            .with_external_dir(self.dir.clone());
+            if let Ok(true) = entry.exceeds_allocation() {
+                warn!(
+                    "Chunk {} in r.{}.{}.mca declares {} bytes but only {} sectors ({} bytes) are allocated",
+                    i,
+                    self.x_pos,
+                    self.z_pos,
+                    entry.declared_length().unwrap_or(0),
+                    size / 4096,
+                    size
+                );
+            }
+            out.push(entry);
         }
         Ok(out)
     }
@@ -114,14 +305,19 @@ impl McaReader {
         if off == 0 || size == 0 {
             return Ok(None);
         }
-        Ok(Some(McaEntry::new(
-            self.file.try_clone()?,
-            off as u64,
-            size as usize,
-            index as u32,
-            ts,
-            self.x_pos,
-            self.z_pos,
-        )))
+        self.check_offset(index)?;
+        Ok(Some(
+            McaEntry::from_source(
+                self.file.try_clone_source()?,
+                off,
+                size as usize,
+                index as u32,
+                ts,
+                self.x_pos,
+                self.z_pos,
+            )
+            .with_memory_limit(self.memory_limit)
+            .with_external_dir(self.dir.clone()),
+        ))
     }
 }
@@ -0,0 +1,136 @@
+use crate::mca::entry::McaEntry;
+use crate::mca::error::{McaError, Result};
+use crate::mca::reader::McaReader;
+use crate::mca::writer::McaWriter;
+use crate::patterns::nbt;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+const SECTOR: u64 = 4096;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RebuildReport {
+    /// Chunks written to the rebuilt region
+    pub recovered: usize,
+    /// Older copies of a chunk that lost against a newer `LastUpdate`
+    pub duplicates: usize,
+    /// Decodable chunks without usable `xPos`/`zPos`, or belonging to another region
+    pub unplaced: usize,
+}
+
+struct Candidate {
+    start: u64,
+    last_update: i64,
+}
+
+/// Rebuilds the location table of `input` by scanning its data sectors and writes
+/// the recovered chunks to `output`.
+///
+/// Every sector boundary after the header is tried as a chunk start: the 5-byte
+/// length + method header must describe an inline RAW/ZLIB/GZIP/LZ4 chunk that fits
+/// in the file, the payload must decompress to an NBT compound, and its
+/// `xPos`/`zPos` (root or legacy `Level`) must fall inside this region. When the same
+/// chunk is found more than once, the copy with the highest `LastUpdate` wins.
+/// The original timestamp table is reused as-is.
+///
+/// External (`.mcc`) stubs carry no coordinates and custom-compressed chunks cannot
+/// be decoded, so both are dropped. The input filename must be `r.<x>.<z>.mca`.
+pub fn rebuild_header(input: &Path, output: &Path) -> Result<RebuildReport> {
+    let path = input.to_string_lossy().to_string();
+    let reader = McaReader::open(&path)?;
+    let (region_x, region_z) = (reader.x_pos(), reader.z_pos());
+    let mut file = File::open(input)?;
+    let file_len = file.metadata()?.len();
+    if file_len < 2 * SECTOR {
+        return Err(McaError::TruncatedChunk {
+            expected: 2 * SECTOR,
+            actual: file_len,
+        });
+    }
+    let mut time = vec![0u8; SECTOR as usize];
+    file.seek(SeekFrom::Start(SECTOR))?;
+    file.read_exact(&mut time)?;
+
+    let mut report = RebuildReport::default();
+    let mut found: BTreeMap<u32, Candidate> = BTreeMap::new();
+    let mut sector = 2u64;
+    while sector * SECTOR + 5 <= file_len {
+        let start = sector * SECTOR;
+        let mut head = [0u8; 5];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut head)?;
+        let len = u32::from_be_bytes([head[0], head[1], head[2], head[3]]) as u64;
+        if len < 2 || !(1..=4).contains(&head[4]) || start + 4 + len > file_len {
+            sector += 1;
+            continue;
+        }
+        let mut entry = McaEntry::new(
+            file.try_clone()?,
+            start,
+            (4 + len) as usize,
+            0,
+            0,
+            region_x,
+            region_z,
+        );
+        let root = match entry.all_data_uncompressed() {
+            Ok(data) => nbt::parse_root(&data),
+            Err(_) => None,
+        };
+        let Some(root) = root else {
+            sector += 1;
+            continue;
+        };
+        // A decodable chunk: whatever happens next, its sectors are accounted for
+        sector += (4 + len).div_ceil(SECTOR);
+        let fields = nbt::chunk_fields(&root);
+        let pos = (
+            fields.get("xPos").and_then(nbt::as_i64),
+            fields.get("zPos").and_then(nbt::as_i64),
+        );
+        let (Some(x), Some(z)) = pos else {
+            report.unplaced += 1;
+            continue;
+        };
+        if x.div_euclid(32) != region_x as i64 || z.div_euclid(32) != region_z as i64 {
+            report.unplaced += 1;
+            continue;
+        }
+        let index = (x.rem_euclid(32) + z.rem_euclid(32) * 32) as u32;
+        let last_update = fields.get("LastUpdate").and_then(nbt::as_i64).unwrap_or(0);
+        match found.get(&index) {
+            Some(prev) if prev.last_update >= last_update => {
+                report.duplicates += 1;
+            }
+            Some(_) => {
+                report.duplicates += 1;
+                found.insert(index, Candidate { start, last_update });
+            }
+            None => {
+                found.insert(index, Candidate { start, last_update });
+            }
+        }
+    }
+
+    let mut writer = McaWriter::open(output.to_string_lossy().as_ref())?;
+    for (index, candidate) in &found {
+        // no location table to trust: the chunk may use everything up to the end
+        let mut entry = McaEntry::new(
+            file.try_clone()?,
+            candidate.start,
+            (file_len - candidate.start) as usize,
+            *index,
+            0,
+            region_x,
+            region_z,
+        );
+        let i = *index as usize * 4;
+        let timestamp = u32::from_be_bytes([time[i], time[i + 1], time[i + 2], time[i + 3]]);
+        writer.write_serialized(*index, timestamp, &entry.serialized_bytes()?)?;
+        report.recovered += 1;
+    }
+    writer.finalize()?;
+    Ok(report)
+}
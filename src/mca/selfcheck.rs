@@ -0,0 +1,266 @@
+use crate::mca::entry::{
+    compress_zlib, encode_lz4_blocks, serialize_chunk, CompressionMethod, LZ4_DEFAULT_BLOCK_SIZE,
+};
+use crate::mca::reader::McaReader;
+use crate::mca::writer::McaWriter;
+use crate::patterns::inhabited::inhabited_time;
+use anyhow::{anyhow, ensure, Result};
+use fastnbt::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// Largest serialized chunk a location table entry can describe (255 sectors).
+const MAX_INLINE_LEN: usize = 255 * 4096;
+
+/// Outcome of one `run_selfcheck` case; `failure` is `None` when it passed.
+#[derive(Debug)]
+pub struct CaseReport {
+    pub name: &'static str,
+    pub failure: Option<String>,
+}
+
+/// A chunk as written: slot, timestamp, serialized bytes and what reading it back
+/// must give.
+struct Written {
+    index: u32,
+    timestamp: u32,
+    serialized: Vec<u8>,
+    payload: Vec<u8>,
+    inhabited: i64,
+}
+
+/// Chunk NBT with coordinates, `InhabitedTime` and `filler` pseudo-random bytes.
+fn chunk_nbt(x: i32, z: i32, inhabited: i64, filler: usize) -> Vec<u8> {
+    let mut seed = (x as u32).wrapping_mul(31).wrapping_add(z as u32) ^ 0x9e37_79b9;
+    let bytes = (0..filler)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as i8
+        })
+        .collect();
+    let root = HashMap::from([
+        ("xPos".to_string(), Value::Int(x)),
+        ("zPos".to_string(), Value::Int(z)),
+        ("InhabitedTime".to_string(), Value::Long(inhabited)),
+        (
+            "filler".to_string(),
+            Value::ByteArray(fastnbt::ByteArray::new(bytes)),
+        ),
+    ]);
+    fastnbt::to_bytes(&Value::Compound(root)).unwrap()
+}
+
+fn compress(method: CompressionMethod, data: &[u8]) -> Result<Vec<u8>> {
+    Ok(match method {
+        CompressionMethod::Gzip => {
+            let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::new(6));
+            enc.write_all(data)?;
+            enc.finish()?
+        }
+        CompressionMethod::Zlib => compress_zlib(data, 6)?,
+        CompressionMethod::Raw => data.to_vec(),
+        CompressionMethod::Lz4 => encode_lz4_blocks(data, LZ4_DEFAULT_BLOCK_SIZE),
+        other => return Err(anyhow!("no encoder for {}", other.name())),
+    })
+}
+
+fn chunk(
+    region: (i32, i32),
+    index: u32,
+    method: CompressionMethod,
+    inhabited: i64,
+    filler: usize,
+) -> Result<Written> {
+    let x = region.0 * 32 + (index % 32) as i32;
+    let z = region.1 * 32 + (index / 32) as i32;
+    let payload = chunk_nbt(x, z, inhabited, filler);
+    Ok(Written {
+        index,
+        timestamp: 1_600_000_000 + index,
+        serialized: serialize_chunk(method, &compress(method, &payload)?),
+        payload,
+        inhabited,
+    })
+}
+
+/// Writes `chunks` to `dir/r.<x>.<z>.mca`, reads the region back and compares
+/// every chunk.
+fn round_trip(dir: &Path, region: (i32, i32), chunks: &[Written]) -> Result<()> {
+    let path = dir.join(format!("r.{}.{}.mca", region.0, region.1));
+    let name = path.to_string_lossy();
+    let mut writer = McaWriter::open(&name)?;
+    for c in chunks {
+        writer.write_serialized(c.index, c.timestamp, &c.serialized)?;
+    }
+    writer.finalize()?;
+
+    let mut reader = McaReader::open(&name)?;
+    ensure!(
+        reader.header()?.anomalies().is_empty(),
+        "header anomalies: {:?}",
+        reader.header()?.anomalies()
+    );
+    let mut entries = reader.entries()?;
+    ensure!(
+        entries.len() == chunks.len(),
+        "read back {} chunks, wrote {}",
+        entries.len(),
+        chunks.len()
+    );
+    entries.sort_by_key(|e| e.region_index());
+    let mut expected: Vec<&Written> = chunks.iter().collect();
+    expected.sort_by_key(|c| c.index);
+    for (entry, c) in entries.iter_mut().zip(expected) {
+        let at = format!("slot {}", c.index);
+        ensure!(
+            entry.region_index() == c.index,
+            "{at}: found slot {}",
+            entry.region_index()
+        );
+        ensure!(
+            entry.modified_time() == c.timestamp,
+            "{at}: timestamp {} instead of {}",
+            entry.modified_time(),
+            c.timestamp
+        );
+        ensure!(
+            (entry.global_x(), entry.global_z())
+                == (
+                    region.0 * 32 + (c.index % 32) as i32,
+                    region.1 * 32 + (c.index / 32) as i32
+                ),
+            "{at}: wrong global coordinates ({}, {})",
+            entry.global_x(),
+            entry.global_z()
+        );
+        ensure!(
+            entry.serialized_bytes()? == c.serialized,
+            "{at}: serialized bytes differ"
+        );
+        ensure!(
+            entry.all_data_uncompressed()? == c.payload,
+            "{at}: decompressed payload differs"
+        );
+        let inhabited = inhabited_time(entry)?;
+        ensure!(
+            inhabited == Some(c.inhabited),
+            "{at}: InhabitedTime {:?} instead of {}",
+            inhabited,
+            c.inhabited
+        );
+    }
+    Ok(())
+}
+
+/// Like `round_trip` for one external chunk: an in-region stub plus its `.mcc` file.
+fn external_round_trip(dir: &Path) -> Result<()> {
+    let payload = chunk_nbt(3, 4, 777, 64);
+    let path = dir.join("r.0.0.mca");
+    let name = path.to_string_lossy();
+    let mut writer = McaWriter::open(&name)?;
+    writer.write_serialized(
+        4 * 32 + 3,
+        0,
+        &serialize_chunk(CompressionMethod::ExternalZlib, &[]),
+    )?;
+    writer.finalize()?;
+    fs::write(dir.join("c.3.4.mcc"), compress_zlib(&payload, 6)?)?;
+
+    let mut reader = McaReader::open(&name)?;
+    let mut entry = reader
+        .get(4 * 32 + 3)?
+        .ok_or_else(|| anyhow!("slot {} is empty", 4 * 32 + 3))?;
+    ensure!(entry.is_external()?, "stub not recognized as external");
+    ensure!(
+        entry.all_data_uncompressed()? == payload,
+        "decompressed payload differs"
+    );
+    ensure!(
+        inhabited_time(&mut entry)? == Some(777),
+        "InhabitedTime not read from the .mcc file"
+    );
+    Ok(())
+}
+
+/// A bare, a small and a multi-block chunk in `method`.
+fn method_case(method: CompressionMethod) -> Result<Vec<Written>> {
+    Ok(vec![
+        chunk((0, 0), 0, method, 0, 0)?,
+        chunk((0, 0), 33, method, 42, 10_000)?,
+        // several LZ4 blocks, several gzip/zlib windows
+        chunk((0, 0), 1023, method, i64::MAX, 300_000)?,
+    ])
+}
+
+/// Largest raw chunk that still fits in-region: exactly 255 sectors.
+fn max_inline_chunk() -> Written {
+    let base = chunk_nbt(5, 6, 1, 0).len();
+    let payload = chunk_nbt(5, 6, 1, MAX_INLINE_LEN - 5 - base);
+    let serialized = serialize_chunk(CompressionMethod::Raw, &payload);
+    debug_assert_eq!(serialized.len(), MAX_INLINE_LEN);
+    Written {
+        index: 6 * 32 + 5,
+        timestamp: u32::MAX,
+        serialized,
+        payload,
+        inhabited: 1,
+    }
+}
+
+/// Writes synthetic regions under `dir` (which must exist) with `McaWriter`, reads
+/// them back with `McaReader` and checks every chunk's serialized bytes, payload,
+/// InhabitedTime, timestamp and coordinates. One report per case, in a fixed order;
+/// a failing case does not stop the others.
+pub fn run_selfcheck(dir: &Path) -> Vec<CaseReport> {
+    type Case = fn(&Path) -> Result<()>;
+    let cases: [(&'static str, Case); 8] = [
+        ("gzip", |d| {
+            round_trip(d, (0, 0), &method_case(CompressionMethod::Gzip)?)
+        }),
+        ("zlib", |d| {
+            round_trip(d, (0, 0), &method_case(CompressionMethod::Zlib)?)
+        }),
+        ("raw", |d| {
+            round_trip(d, (0, 0), &method_case(CompressionMethod::Raw)?)
+        }),
+        ("lz4", |d| {
+            round_trip(d, (0, 0), &method_case(CompressionMethod::Lz4)?)
+        }),
+        ("full region", |d| {
+            let chunks = (0..1024)
+                .map(|i| chunk((2, -3), i, CompressionMethod::Zlib, i as i64 * 20, 16))
+                .collect::<Result<Vec<_>>>()?;
+            round_trip(d, (2, -3), &chunks)
+        }),
+        ("negative coordinates", |d| {
+            round_trip(
+                d,
+                (-1, -1),
+                &[chunk((-1, -1), 31 * 32 + 31, CompressionMethod::Raw, 5, 0)?],
+            )
+        }),
+        ("max in-region size", |d| {
+            round_trip(d, (0, 0), &[max_inline_chunk()])
+        }),
+        ("external chunk", external_round_trip),
+    ];
+    cases
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, case))| {
+            // each case in its own folder, so region names may repeat
+            let case_dir = dir.join(format!("case-{}", i));
+            let result = fs::create_dir_all(&case_dir)
+                .map_err(anyhow::Error::from)
+                .and_then(|_| case(&case_dir));
+            CaseReport {
+                name,
+                failure: result.err().map(|e| format!("{:#}", e)),
+            }
+        })
+        .collect()
+}
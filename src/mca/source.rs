@@ -0,0 +1,22 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::sync::Arc;
+
+/// Bytes of a region file: a file on disk, or an in-memory copy such as a zip entry.
+pub trait RegionSource: Read + Seek + Send {
+    /// An independent handle on the same bytes with its own position.
+    fn try_clone_source(&self) -> std::io::Result<Box<dyn RegionSource>>;
+}
+
+impl RegionSource for File {
+    fn try_clone_source(&self) -> std::io::Result<Box<dyn RegionSource>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+}
+
+/// Shared in-memory region; clones only bump the reference count.
+impl RegionSource for Cursor<Arc<[u8]>> {
+    fn try_clone_source(&self) -> std::io::Result<Box<dyn RegionSource>> {
+        Ok(Box::new(Cursor::new(self.get_ref().clone())))
+    }
+}
@@ -1,7 +1,40 @@
 use crate::mca::entry::McaEntry;
-use anyhow::Result;
-use std::fs::File;
-use std::io::{Seek, SeekFrom, Write};
+use crate::mca::error::Result;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Position of chunk `index` on a Z-order (Morton) curve over the region's 32x32 grid.
+/// Index order lays each row of 32 chunks out in turn, so the square of neighbours the
+/// game loads around a player spans several rows of sectors; in Z-order every aligned
+/// 2x2, 4x4, ... block of chunks is contiguous.
+pub fn morton_rank(index: u32) -> u32 {
+    let spread = |mut v: u32| {
+        v = (v | (v << 4)) & 0x0f0f;
+        v = (v | (v << 2)) & 0x3333;
+        (v | (v << 1)) & 0x5555
+    };
+    spread(index % 32) | (spread(index / 32) << 1)
+}
+
+/// Rewrites the timestamp table of the region at `path` the way `McaWriter` with
+/// `with_timestamp(Some(timestamp))` would have written it: `timestamp` for every
+/// used slot, 0 for the others. The location table and the chunks are left as they
+/// are.
+pub fn restamp(path: &Path, timestamp: u32) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut loc = vec![0u8; 4096];
+    file.read_exact(&mut loc)?;
+    let time: Vec<u8> = loc
+        .chunks_exact(4)
+        .flat_map(|slot| {
+            let used = slot.iter().any(|&b| b != 0);
+            if used { timestamp } else { 0 }.to_be_bytes()
+        })
+        .collect();
+    file.write_all(&time)?;
+    Ok(())
+}
 
 pub struct McaWriter {
     file: File,
@@ -9,6 +42,7 @@ pub struct McaWriter {
     offsets: Vec<u32>,
     sizes: Vec<u32>,
     timestamps: Vec<u32>,
+    timestamp_override: Option<u32>,
 }
 
 impl McaWriter {
@@ -21,24 +55,48 @@ impl McaWriter {
             offsets: vec![0; 1024],
             sizes: vec![0; 1024],
             timestamps: vec![0; 1024],
+            timestamp_override: None,
         })
     }
 
-    pub fn write_entry(&mut self, entry: &mut McaEntry) -> Result<()> {
-        let serialized = entry.serialized_bytes()?;
+    /// Records `timestamp` for every chunk instead of the one passed to the write calls.
+    pub fn with_timestamp(mut self, timestamp: Option<u32>) -> Self {
+        self.timestamp_override = timestamp;
+        self
+    }
+
+    /// Streams the entry's serialized bytes into the region; returns the bytes written.
+    pub fn write_entry(&mut self, entry: &mut McaEntry) -> Result<u64> {
         let start = self.data_offset;
         self.file.seek(SeekFrom::Start(start))?;
-        self.file.write_all(&serialized)?;
-        let written = serialized.len() as u64;
+        let written = entry.copy_serialized(&mut self.file)?;
+        self.finish_chunk(entry.region_index(), entry.modified_time(), start, written)?;
+        Ok(written)
+    }
+
+    pub fn write_serialized(
+        &mut self,
+        index: u32,
+        timestamp: u32,
+        serialized: &[u8],
+    ) -> Result<()> {
+        let start = self.data_offset;
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.write_all(serialized)?;
+        self.finish_chunk(index, timestamp, start, serialized.len() as u64)
+    }
+
+    /// Pads the chunk written at `start` to a sector boundary and records it.
+    fn finish_chunk(&mut self, index: u32, timestamp: u32, start: u64, written: u64) -> Result<()> {
         let pad = (4096 - (written % 4096)) % 4096;
         if pad > 0 {
             self.file.write_all(vec![0u8; pad as usize].as_slice())?;
         }
         self.data_offset += written + pad;
-        let idx = entry.region_index() as usize;
+        let idx = index as usize;
         self.offsets[idx] = start as u32;
         self.sizes[idx] = (written + pad) as u32;
-        self.timestamps[idx] = entry.modified_time();
+        self.timestamps[idx] = self.timestamp_override.unwrap_or(timestamp);
         Ok(())
     }
 
@@ -57,6 +115,8 @@ impl McaWriter {
         self.file.seek(SeekFrom::Start(0))?;
         self.file.write_all(&loc)?;
         self.file.write_all(&time)?;
+        // no trailing slack: the location table bounds every read
+        self.file.set_len(self.data_offset)?;
         Ok(())
     }
 }
@@ -0,0 +1,118 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::{nbt, ChunkPattern};
+use anyhow::{anyhow, Result};
+use fastnbt::Value;
+use std::collections::{BTreeMap, HashSet};
+
+/// Biome cells of a section: a 4×4×4 grid.
+const SECTION_CELLS: usize = 64;
+
+/// `minecraft:`-prefixed form of a biome id; ids with a namespace are kept as given.
+pub fn normalize_biome(id: &str) -> String {
+    if id.contains(':') {
+        id.to_string()
+    } else {
+        format!("minecraft:{}", id)
+    }
+}
+
+/// Palette indices of one section's `biomes` compound, or `None` when it is malformed.
+/// Indices are packed without spanning longs, like block states since 1.16.
+fn section_cells(biomes: &Value) -> Option<Vec<String>> {
+    let Value::Compound(biomes) = biomes else {
+        return None;
+    };
+    let Some(Value::List(palette)) = biomes.get("palette") else {
+        return None;
+    };
+    let palette: Vec<&str> = palette
+        .iter()
+        .map(|v| match v {
+            Value::String(s) => Some(s.as_str()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    match palette.len() {
+        0 => return None,
+        1 => return Some(vec![palette[0].to_string(); SECTION_CELLS]),
+        _ => {}
+    }
+    let Some(Value::LongArray(data)) = biomes.get("data") else {
+        return None;
+    };
+    let bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as usize;
+    let per_long = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+    (0..SECTION_CELLS)
+        .map(|i| {
+            let long = *data.get(i / per_long)? as u64;
+            let index = (long >> ((i % per_long) * bits)) & mask;
+            palette.get(index as usize).map(|s| s.to_string())
+        })
+        .collect()
+}
+
+/// How many biome cells of an uncompressed chunk hold each biome, summed over the
+/// `sections` palettes (1.18+). `None` for chunks without any readable palette,
+/// such as pre-1.18 chunks with their numeric `Biomes` array.
+pub fn biome_counts(data: &[u8]) -> Option<BTreeMap<String, usize>> {
+    let root = nbt::parse_root(data)?;
+    let Some(Value::List(sections)) = nbt::chunk_fields(&root).get("sections") else {
+        return None;
+    };
+    let mut counts = BTreeMap::new();
+    for section in sections {
+        let Value::Compound(section) = section else {
+            continue;
+        };
+        let Some(cells) = section.get("biomes").and_then(section_cells) else {
+            continue;
+        };
+        for biome in cells {
+            *counts.entry(biome).or_insert(0) += 1;
+        }
+    }
+    (!counts.is_empty()).then_some(counts)
+}
+
+/// Matches chunks whose dominant biome (most cells in `biome_counts`) is not one of
+/// `biomes`: the `--keep-only-biome` veto. At a transition where several biomes
+/// tie for the most cells, the chunk counts as inside when any of them is listed.
+/// Chunks without readable biomes never match, nor do chunks above the memory
+/// limit, since the palettes need a full NBT parse.
+pub struct BiomePattern {
+    biomes: HashSet<String>,
+}
+
+impl BiomePattern {
+    pub fn outside(biomes: &[String]) -> Result<Self> {
+        if biomes.iter().any(|b| b.trim().is_empty()) {
+            return Err(anyhow!("biome ids cannot be empty"));
+        }
+        Ok(Self {
+            biomes: biomes.iter().map(|b| normalize_biome(b.trim())).collect(),
+        })
+    }
+
+    /// Whether the dominant biome of an uncompressed chunk is listed; `None` when
+    /// the chunk has no readable biomes.
+    pub fn dominant_listed(&self, data: &[u8]) -> Option<bool> {
+        let counts = biome_counts(data)?;
+        let top = counts.values().copied().max()?;
+        Some(
+            counts
+                .iter()
+                .any(|(biome, &n)| n == top && self.biomes.contains(biome)),
+        )
+    }
+}
+
+impl ChunkPattern for BiomePattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(self.dominant_listed(&de) == Some(false))
+    }
+}
@@ -0,0 +1,38 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::{BoxedPattern, ChunkPattern};
+use anyhow::Result;
+
+/// Boolean composition of other patterns. A failing inner pattern fails the whole
+/// composition, so `KeepRules` still reports it and applies `--on-error`.
+pub enum CombinedPattern {
+    /// Matches when every pattern matches; stops at the first one that does not.
+    All(Vec<BoxedPattern>),
+    Not(BoxedPattern),
+}
+
+impl CombinedPattern {
+    /// Keep pattern of `--conservative-cull`: removes a chunk only if it is fully
+    /// generated and `inhabited` would not keep it.
+    pub fn conservative_cull(inhabited: BoxedPattern) -> Result<Self> {
+        Ok(Self::Not(Box::new(Self::All(vec![
+            Box::new(super::status::StatusPattern::reached("full")?),
+            Box::new(Self::Not(inhabited)),
+        ]))))
+    }
+}
+
+impl ChunkPattern for CombinedPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        match self {
+            Self::All(patterns) => {
+                for p in patterns {
+                    if !p.matches(entry)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Self::Not(p) => Ok(!p.matches(entry)?),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::ChunkPattern;
+use anyhow::Result;
+use byteorder::{BigEndian, ByteOrder};
+
+const INT_TAG: u8 = 3;
+
+/// Byte-scans for the chunk's `DataVersion` int, like `find_inhabited_fast`.
+/// The tag sits at the root in every chunk format since 1.9.
+pub fn find_data_version_fast(data: &[u8]) -> Option<i32> {
+    let name = b"DataVersion";
+    let mut prefix = Vec::with_capacity(1 + 2 + name.len());
+    prefix.push(INT_TAG);
+    prefix.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    prefix.extend_from_slice(name);
+    let plen = prefix.len();
+    data.windows(plen + 4)
+        .find(|w| w[..plen] == prefix[..])
+        .map(|w| BigEndian::read_i32(&w[plen..]))
+}
+
+/// Keeps chunks already upgraded to `current` (the world's `level.dat` DataVersion):
+/// the running game rewrote them, so they were loaded recently.
+pub struct DataVersionPattern {
+    current: i32,
+}
+
+impl DataVersionPattern {
+    pub fn new(current: i32) -> Self {
+        Self { current }
+    }
+}
+
+impl ChunkPattern for DataVersionPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(find_data_version_fast(&de) == Some(self.current))
+    }
+}
@@ -0,0 +1,14 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::ChunkPattern;
+use anyhow::Result;
+
+/// Veto pattern: matches chunks whose payload decodes to nothing
+/// (`McaEntry::has_empty_payload`). Undecodable chunks do not match; their
+/// InhabitedTime stays unknown and `--remove-unknown` decides.
+pub struct EmptyPayloadPattern;
+
+impl ChunkPattern for EmptyPayloadPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        Ok(entry.has_empty_payload()?)
+    }
+}
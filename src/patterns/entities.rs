@@ -0,0 +1,166 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::{nbt, ChunkPattern};
+use anyhow::Result;
+use fastnbt::Value;
+use std::collections::HashMap;
+
+/// Mobs of the ambient and water-ambient spawn groups: the game spawns and despawns
+/// them on its own, so losing them loses nothing.
+pub const TRIVIAL_ENTITIES: [&str; 5] = [
+    "minecraft:bat",
+    "minecraft:cod",
+    "minecraft:pufferfish",
+    "minecraft:salmon",
+    "minecraft:tropical_fish",
+];
+
+fn flag(entity: &HashMap<String, Value>, name: &str) -> bool {
+    entity
+        .get(name)
+        .and_then(nbt::as_i64)
+        .is_some_and(|v| v != 0)
+}
+
+/// Named, persistent or bucket-released mobs matter even if their type is trivial.
+fn is_trivial(entity: &Value) -> bool {
+    let Value::Compound(entity) = entity else {
+        return false;
+    };
+    let trivial_type = matches!(
+        entity.get("id"),
+        Some(Value::String(id)) if TRIVIAL_ENTITIES.contains(&id.as_str())
+    );
+    trivial_type
+        && !entity.contains_key("CustomName")
+        && !flag(entity, "PersistenceRequired")
+        && !flag(entity, "FromBucket")
+}
+
+/// Whether an uncompressed `entities/` chunk holds nothing but `TRIVIAL_ENTITIES`
+/// (or nothing at all). `None` when it is not parseable NBT.
+pub fn only_trivial_entities(data: &[u8]) -> Option<bool> {
+    let root = nbt::parse_root(data)?;
+    Some(match root.get("Entities") {
+        Some(Value::List(list)) => list.iter().all(is_trivial),
+        _ => true,
+    })
+}
+
+/// Matches entities chunks that `only_trivial_entities` considers disposable.
+/// Unparseable chunks and chunks above the memory limit never match.
+pub struct TrivialEntitiesPattern;
+
+impl ChunkPattern for TrivialEntitiesPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(only_trivial_entities(&de) == Some(true))
+    }
+}
+
+/// Whether a JSON text component shows any text.
+fn json_text_visible(v: &serde_json::Value) -> bool {
+    match v {
+        serde_json::Value::String(s) => !s.trim().is_empty(),
+        serde_json::Value::Array(parts) => parts.iter().any(json_text_visible),
+        serde_json::Value::Object(m) => {
+            ["text", "translate", "keybind", "selector"]
+                .iter()
+                .any(|k| m.get(*k).is_some_and(json_text_visible))
+                || m.get("extra").is_some_and(json_text_visible)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a `CustomName` shows any text. It is a plain string before 1.13, a JSON
+/// text component (`{"text":"Rex"}`, `"Rex"`, `[...]`) in a string since then, and
+/// the same component stored as NBT since 1.21.5.
+fn has_visible_name(name: &Value) -> bool {
+    match name {
+        Value::String(s) => match serde_json::from_str::<serde_json::Value>(s) {
+            Ok(component) if s.trim_start().starts_with(['{', '[', '"']) => {
+                json_text_visible(&component)
+            }
+            _ => !s.trim().is_empty(),
+        },
+        Value::List(parts) => parts.iter().any(has_visible_name),
+        Value::Compound(m) => {
+            ["text", "translate", "keybind", "selector"]
+                .iter()
+                .any(|k| m.get(*k).is_some_and(has_visible_name))
+                || m.get("extra").is_some_and(has_visible_name)
+        }
+        _ => false,
+    }
+}
+
+/// An entity with a visible `CustomName`, or one riding it.
+fn is_named(entity: &Value) -> bool {
+    let Value::Compound(entity) = entity else {
+        return false;
+    };
+    entity.get("CustomName").is_some_and(has_visible_name)
+        || matches!(
+            entity.get("Passengers"),
+            Some(Value::List(riders)) if riders.iter().any(is_named)
+        )
+}
+
+/// Whether an uncompressed `entities/` chunk holds an entity whose `CustomName`
+/// shows any text; empty names do not count. `None` when it is not parseable NBT.
+pub fn has_named_entities(data: &[u8]) -> Option<bool> {
+    let root = nbt::parse_root(data)?;
+    Some(matches!(
+        root.get("Entities"),
+        Some(Value::List(list)) if list.iter().any(is_named)
+    ))
+}
+
+/// An entity plus everything riding it.
+fn count_with_passengers(entity: &Value) -> usize {
+    let riders = match entity {
+        Value::Compound(e) => match e.get("Passengers") {
+            Some(Value::List(riders)) => riders.iter().map(count_with_passengers).sum(),
+            _ => 0,
+        },
+        _ => 0,
+    };
+    1 + riders
+}
+
+/// Entities in an uncompressed `entities/` chunk, passengers included. `None` when
+/// it is not parseable NBT.
+pub fn entity_count(data: &[u8]) -> Option<usize> {
+    let root = nbt::parse_root(data)?;
+    Some(match root.get("Entities") {
+        Some(Value::List(list)) => list.iter().map(count_with_passengers).sum(),
+        _ => 0,
+    })
+}
+
+/// Matches entities chunks holding more than `max` entities (`entity_count`), the
+/// stacked-mob farms and item piles that lag a server. Unparseable chunks and chunks
+/// above the memory limit never match.
+pub struct EntityHotspotPattern {
+    pub max: usize,
+}
+
+impl EntityHotspotPattern {
+    /// The chunk's entity count when it is above `max`.
+    pub fn excess(&self, entry: &mut McaEntry) -> Result<Option<usize>> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(None);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(entity_count(&de).filter(|&n| n > self.max))
+    }
+}
+
+impl ChunkPattern for EntityHotspotPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        Ok(self.excess(entry)?.is_some())
+    }
+}
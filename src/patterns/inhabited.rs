@@ -1,11 +1,14 @@
 use crate::mca::entry::McaEntry;
-use crate::patterns::ChunkPattern;
+use crate::patterns::{nbt, ChunkPattern};
 use anyhow::Result;
 use byteorder::{BigEndian, ByteOrder};
+use log::debug;
+use std::io::Read;
 
 pub struct InhabitedTimePattern {
     threshold: i64,
     remove_unknown: bool,
+    keep_zero: bool,
 }
 
 impl InhabitedTimePattern {
@@ -13,12 +16,19 @@ impl InhabitedTimePattern {
         Self {
             threshold,
             remove_unknown,
+            keep_zero: false,
         }
     }
+
+    /// Also keep chunks with an InhabitedTime of exactly 0: generated, never visited.
+    pub fn with_keep_zero(mut self, keep_zero: bool) -> Self {
+        self.keep_zero = keep_zero;
+        self
+    }
 }
 
 const LONG_TAG: u8 = 4;
-fn find_inhabited_fast(data: &[u8]) -> Option<i64> {
+pub fn find_inhabited_fast(data: &[u8]) -> Option<i64> {
     let name = b"InhabitedTime";
     let mut prefix = Vec::with_capacity(1 + 2 + name.len());
     prefix.push(LONG_TAG);
@@ -41,18 +51,79 @@ fn find_inhabited_fast(data: &[u8]) -> Option<i64> {
     None
 }
 
-impl ChunkPattern for InhabitedTimePattern {
-    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
-        if entry.is_external()? {
-            return Ok(!self.remove_unknown);
+/// `find_inhabited_fast` over a stream, keeping only a bounded window in memory.
+pub fn find_inhabited_stream(mut reader: impl Read) -> std::io::Result<Option<i64>> {
+    // a match straddling two reads fits in the retained tail
+    let overlap = 1 + 2 + "InhabitedTime".len() + 8 - 1;
+    let mut window = Vec::with_capacity(64 * 1024 + overlap);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return Ok(None);
         }
-        let de = entry.all_data_uncompressed()?;
-        if de.is_empty() {
-            return Ok(!self.remove_unknown);
+        window.extend_from_slice(&buf[..n]);
+        if let Some(v) = find_inhabited_fast(&window) {
+            return Ok(Some(v));
         }
-        if let Some(t) = find_inhabited_fast(&de) {
-            return Ok(t >= self.threshold);
+        let keep = window.len().min(overlap);
+        window.drain(..window.len() - keep);
+    }
+}
+
+/// Reads InhabitedTime through a full NBT parse (root or legacy `Level` compound).
+/// Slow, but immune to the byte scan false-matching a nested tag.
+pub fn find_inhabited_nbt(data: &[u8]) -> Option<i64> {
+    let root = nbt::parse_root(data)?;
+    nbt::chunk_fields(&root)
+        .get("InhabitedTime")
+        .and_then(nbt::as_i64)
+}
+
+/// InhabitedTime of a chunk in ticks; `None` when the chunk is undecodable, external
+/// without its `.mcc` file, or carries no InhabitedTime tag. A cached value
+/// (`McaEntry::set_cached_inhabited`) is returned without decoding.
+///
+/// When the byte scan finds no Long tag, the chunk is parsed as NBT before giving
+/// up, which also accepts an InhabitedTime stored as another integer type. Chunks
+/// above the memory limit are only scanned.
+pub fn inhabited_time(entry: &mut McaEntry) -> Result<Option<i64>> {
+    if let Some(t) = entry.cached_inhabited() {
+        return Ok(t);
+    }
+    if entry.exceeds_memory_limit()? {
+        return Ok(find_inhabited_stream(entry.uncompressed_reader()?)?);
+    }
+    let de = entry.all_data_uncompressed()?;
+    if de.is_empty() {
+        return Ok(None);
+    }
+    if let Some(t) = find_inhabited_fast(&de) {
+        return Ok(Some(t));
+    }
+    let full = find_inhabited_nbt(&de);
+    if full.is_some() {
+        debug!(
+            "InhabitedTime of chunk ({}, {}) found only by the NBT fallback",
+            entry.global_x(),
+            entry.global_z()
+        );
+    }
+    Ok(full)
+}
+
+impl InhabitedTimePattern {
+    pub fn decide(&self, inhabited: Option<i64>) -> bool {
+        match inhabited {
+            Some(0) if self.keep_zero => true,
+            Some(t) => t >= self.threshold,
+            None => !self.remove_unknown,
         }
-        Ok(!self.remove_unknown)
+    }
+}
+
+impl ChunkPattern for InhabitedTimePattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        Ok(self.decide(inhabited_time(entry)?))
     }
 }
@@ -0,0 +1,37 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::{nbt, ChunkPattern};
+use anyhow::Result;
+
+/// Reads the `LastUpdate` game tick of an uncompressed chunk (root or legacy `Level`
+/// compound).
+pub fn find_last_update(data: &[u8]) -> Option<i64> {
+    let root = nbt::parse_root(data)?;
+    nbt::chunk_fields(&root)
+        .get("LastUpdate")
+        .and_then(nbt::as_i64)
+}
+
+/// Keeps chunks whose `LastUpdate` is at most `within` ticks before `now` (the
+/// world's `Time` from `level.dat`), or after it. Chunks without the field, or above
+/// the memory limit, never match and are left to the other rules.
+pub struct LastUpdatePattern {
+    since: i64,
+}
+
+impl LastUpdatePattern {
+    pub fn new(now: i64, within: i64) -> Self {
+        Self {
+            since: now.saturating_sub(within),
+        }
+    }
+}
+
+impl ChunkPattern for LastUpdatePattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(find_last_update(&de).is_some_and(|t| t >= self.since))
+    }
+}
@@ -1,22 +1,22 @@
 use crate::mca::entry::McaEntry;
 use crate::patterns::ChunkPattern;
 use anyhow::Result;
+use std::collections::HashSet;
 
 pub struct ListPattern {
-    coords: Vec<(i32, i32)>,
+    coords: HashSet<(i32, i32)>,
 }
 
 impl ListPattern {
     pub fn new(coords: Vec<(i32, i32)>) -> Self {
-        Self { coords }
+        Self {
+            coords: coords.into_iter().collect(),
+        }
     }
 }
 
 impl ChunkPattern for ListPattern {
     fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
-        Ok(self
-            .coords
-            .iter()
-            .any(|(x, z)| *x == entry.global_x() && *z == entry.global_z()))
+        Ok(self.coords.contains(&(entry.global_x(), entry.global_z())))
     }
 }
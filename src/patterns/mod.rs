@@ -1,9 +1,166 @@
+pub mod biome;
+pub mod combined;
+pub mod dataversion;
+pub mod empty;
+pub mod entities;
 pub mod inhabited;
+pub mod lastupdate;
 pub mod list;
+pub mod modified;
+pub mod nbt;
+pub mod polygon;
 pub mod range;
+pub mod sample;
+#[cfg(feature = "script")]
+pub mod script;
+pub mod status;
 
 use crate::mca::entry::McaEntry;
 
 pub trait ChunkPattern {
     fn matches(&self, entry: &mut McaEntry) -> anyhow::Result<bool>;
+
+    /// How traces (`KeepRules::trace`) refer to the pattern; the type name unless
+    /// it is wrapped by `named`.
+    fn name(&self) -> String {
+        let full = std::any::type_name::<Self>();
+        full.rsplit("::").next().unwrap_or(full).to_string()
+    }
+}
+
+pub type BoxedPattern = Box<dyn ChunkPattern + Send>;
+
+struct Named {
+    name: String,
+    inner: BoxedPattern,
+}
+
+impl ChunkPattern for Named {
+    fn matches(&self, entry: &mut McaEntry) -> anyhow::Result<bool> {
+        self.inner.matches(entry)
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// `pattern` under the name of the option that added it, e.g. `--keep-chunk`.
+pub fn named(name: impl Into<String>, pattern: impl ChunkPattern + Send + 'static) -> BoxedPattern {
+    Box::new(Named {
+        name: name.into(),
+        inner: Box::new(pattern),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleStage {
+    Pin,
+    Veto,
+    Keep,
+}
+
+/// One pattern's answer for a chunk, see `KeepRules::trace`.
+#[derive(Debug)]
+pub struct PatternVote {
+    pub stage: RuleStage,
+    pub name: String,
+    /// Whether it matched; the error text when it failed
+    pub result: Result<bool, String>,
+}
+
+/// The keep decision for one chunk: any pin keeps it, otherwise any veto removes it,
+/// otherwise any keep pattern keeps it.
+#[derive(Default)]
+pub struct KeepRules {
+    pub pins: Vec<BoxedPattern>,
+    pub vetoes: Vec<BoxedPattern>,
+    pub keeps: Vec<BoxedPattern>,
+}
+
+impl KeepRules {
+    /// The standard rules: `forced` chunks are pinned, the rest are kept by
+    /// InhabitedTime (`threshold` in ticks).
+    pub fn defaults(threshold: i64, remove_unknown: bool, forced: Vec<(i32, i32)>) -> Self {
+        Self {
+            pins: vec![named("force-loaded", list::ListPattern::new(forced))],
+            vetoes: Vec::new(),
+            keeps: vec![named(
+                format!("InhabitedTime >= {}", threshold),
+                inhabited::InhabitedTimePattern::new(threshold, remove_unknown),
+            )],
+        }
+    }
+
+    /// A pattern that fails counts as not matching; its error goes to `on_error`.
+    pub fn evaluate(&self, entry: &mut McaEntry, on_error: &mut dyn FnMut(anyhow::Error)) -> bool {
+        self.try_evaluate(entry, on_error).unwrap_or(false)
+    }
+
+    /// Every pattern evaluated on `entry`, pins first, without stopping at the first
+    /// match like `try_evaluate` does.
+    pub fn trace(&self, entry: &mut McaEntry) -> Vec<PatternVote> {
+        [
+            (RuleStage::Pin, &self.pins),
+            (RuleStage::Veto, &self.vetoes),
+            (RuleStage::Keep, &self.keeps),
+        ]
+        .into_iter()
+        .flat_map(|(stage, patterns)| patterns.iter().map(move |p| (stage, p)))
+        .map(|(stage, p)| PatternVote {
+            stage,
+            name: p.name(),
+            result: p.matches(entry).map_err(|e| e.to_string()),
+        })
+        .collect()
+    }
+
+    /// Like `evaluate`, but `None` when the chunk could not be evaluated at all: no
+    /// pin or veto matched and every keep pattern failed.
+    pub fn try_evaluate(
+        &self,
+        entry: &mut McaEntry,
+        on_error: &mut dyn FnMut(anyhow::Error),
+    ) -> Option<bool> {
+        let mut any = |patterns: &[BoxedPattern], entry: &mut McaEntry, failed: &mut usize| {
+            patterns.iter().any(|p| {
+                p.matches(entry).unwrap_or_else(|e| {
+                    on_error(e);
+                    *failed += 1;
+                    false
+                })
+            })
+        };
+        if any(&self.pins, entry, &mut 0) {
+            return Some(true);
+        }
+        if any(&self.vetoes, entry, &mut 0) {
+            return Some(false);
+        }
+        let mut failed = 0;
+        if any(&self.keeps, entry, &mut failed) {
+            return Some(true);
+        }
+        (self.keeps.is_empty() || failed < self.keeps.len()).then_some(false)
+    }
+}
+
+/// Keep decision of `KeepRules::defaults` for a single chunk; fails on the first
+/// pattern error instead of treating it as a non-match.
+#[allow(dead_code)]
+pub fn evaluate_default(
+    entry: &mut McaEntry,
+    threshold: i64,
+    remove_unknown: bool,
+    forced_coords: &[(i32, i32)],
+) -> anyhow::Result<bool> {
+    let rules = KeepRules::defaults(threshold, remove_unknown, forced_coords.to_vec());
+    let mut error = None;
+    let keep = rules.evaluate(entry, &mut |e| {
+        error.get_or_insert(e);
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(keep),
+    }
 }
@@ -0,0 +1,77 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::{nbt, ChunkPattern};
+use anyhow::Result;
+use fastnbt::Value;
+use std::collections::HashMap;
+
+/// Blocks world generation does not place, so their presence means a player did.
+const MARKER_BLOCKS: [&str; 8] = [
+    "minecraft:beacon",
+    "minecraft:comparator",
+    "minecraft:dropper",
+    "minecraft:ender_chest",
+    "minecraft:hopper",
+    "minecraft:nether_portal",
+    "minecraft:observer",
+    "minecraft:shulker_box",
+];
+
+fn non_empty_list(fields: &HashMap<String, Value>, names: &[&str]) -> bool {
+    names
+        .iter()
+        .any(|n| matches!(fields.get(*n), Some(Value::List(l)) if !l.is_empty()))
+}
+
+fn has_marker_block(fields: &HashMap<String, Value>) -> bool {
+    let Some(Value::List(sections)) = fields.get("sections").or_else(|| fields.get("Sections"))
+    else {
+        return false;
+    };
+    sections.iter().any(|section| {
+        let Value::Compound(section) = section else {
+            return false;
+        };
+        // 1.18+ nests the palette in `block_states`, 1.13-1.17 keep it on the section
+        let palette = match section.get("block_states") {
+            Some(Value::Compound(states)) => states.get("palette"),
+            _ => section.get("Palette"),
+        };
+        let Some(Value::List(palette)) = palette else {
+            return false;
+        };
+        palette.iter().any(|block| match block {
+            Value::Compound(b) => matches!(
+                b.get("Name"),
+                Some(Value::String(name)) if MARKER_BLOCKS.contains(&name.as_str())
+            ),
+            _ => false,
+        })
+    })
+}
+
+/// Heuristic for player activity in an uncompressed chunk: any block entity, any
+/// pending block tick, or a block from `MARKER_BLOCKS` in a section palette.
+/// `None` when the chunk is not parseable NBT.
+pub fn looks_modified(data: &[u8]) -> Option<bool> {
+    let root = nbt::parse_root(data)?;
+    let fields = nbt::chunk_fields(&root);
+    Some(
+        non_empty_list(fields, &["block_entities", "TileEntities"])
+            || non_empty_list(fields, &["block_ticks", "TileTicks"])
+            || has_marker_block(fields),
+    )
+}
+
+/// Veto pattern: matches chunks that look freshly generated (see `looks_modified`).
+/// Unparseable chunks and chunks above the memory limit never match.
+pub struct PristinePattern;
+
+impl ChunkPattern for PristinePattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(looks_modified(&de) == Some(false))
+    }
+}
@@ -0,0 +1,28 @@
+use fastnbt::Value;
+use std::collections::HashMap;
+
+/// Fully parses an uncompressed chunk, returning its root compound.
+pub fn parse_root(data: &[u8]) -> Option<HashMap<String, Value>> {
+    match fastnbt::from_bytes::<Value>(data) {
+        Ok(Value::Compound(m)) => Some(m),
+        _ => None,
+    }
+}
+
+/// Chunk fields live at the root since 1.18 and under `Level` before that.
+pub fn chunk_fields(root: &HashMap<String, Value>) -> &HashMap<String, Value> {
+    match root.get("Level") {
+        Some(Value::Compound(level)) => level,
+        _ => root,
+    }
+}
+
+pub fn as_i64(v: &Value) -> Option<i64> {
+    match v {
+        Value::Byte(n) => Some(*n as i64),
+        Value::Short(n) => Some(*n as i64),
+        Value::Int(n) => Some(*n as i64),
+        Value::Long(n) => Some(*n),
+        _ => None,
+    }
+}
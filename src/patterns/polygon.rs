@@ -0,0 +1,67 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::ChunkPattern;
+use anyhow::{anyhow, Result};
+
+/// Matches chunks whose center lies inside a simple polygon given in block coordinates.
+/// The ring may be open or closed (last vertex repeating the first), in either winding.
+#[derive(Clone, Debug)]
+pub struct PolygonPattern {
+    vertices: Vec<(f64, f64)>,
+}
+
+impl PolygonPattern {
+    /// Rejects rings with fewer than 3 distinct vertices or with no area (all collinear).
+    pub fn new(mut vertices: Vec<(f64, f64)>) -> Result<Self> {
+        vertices.dedup();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        if vertices.len() < 3 {
+            return Err(anyhow!(
+                "polygon needs at least 3 distinct vertices, got {}",
+                vertices.len()
+            ));
+        }
+        let pattern = Self { vertices };
+        if pattern.doubled_area() == 0.0 {
+            return Err(anyhow!(
+                "polygon vertices are collinear, it encloses no area"
+            ));
+        }
+        Ok(pattern)
+    }
+
+    /// Shoelace formula; the sign gives the winding.
+    fn doubled_area(&self) -> f64 {
+        let n = self.vertices.len();
+        (0..n)
+            .map(|i| {
+                let (x1, z1) = self.vertices[i];
+                let (x2, z2) = self.vertices[(i + 1) % n];
+                x1 * z2 - x2 * z1
+            })
+            .sum()
+    }
+
+    /// Even-odd ray casting along +x. Points exactly on an edge may fall either way.
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        let n = self.vertices.len();
+        let mut inside = false;
+        for i in 0..n {
+            let (x1, z1) = self.vertices[i];
+            let (x2, z2) = self.vertices[(i + n - 1) % n];
+            if (z1 > z) != (z2 > z) && x < x1 + (z - z1) * (x2 - x1) / (z2 - z1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+impl ChunkPattern for PolygonPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        let cx = entry.global_x() as f64 * 16.0 + 8.0;
+        let cz = entry.global_z() as f64 * 16.0 + 8.0;
+        Ok(self.contains(cx, cz))
+    }
+}
@@ -2,7 +2,6 @@ use crate::mca::entry::McaEntry;
 use crate::patterns::ChunkPattern;
 use anyhow::Result;
 
-#[allow(dead_code)]
 pub struct RangePattern {
     sx: i32,
     sz: i32,
@@ -10,7 +9,6 @@ pub struct RangePattern {
     ez: i32,
 }
 
-#[allow(dead_code)]
 impl RangePattern {
     pub fn new(a: i32, b: i32, c: i32, d: i32) -> Self {
         let sx = a.min(c);
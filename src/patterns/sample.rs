@@ -0,0 +1,46 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::ChunkPattern;
+use anyhow::{anyhow, Result};
+
+/// SplitMix64 finalizer: a well-spread 64-bit hash of `v`.
+fn mix(mut v: u64) -> u64 {
+    v = (v ^ (v >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    v = (v ^ (v >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    v ^ (v >> 31)
+}
+
+/// Matches a deterministic pseudo-random `percent` of chunks (the `--sample-keep` keep):
+/// each chunk draws from a hash of `seed` and its coordinates, so the same seed
+/// samples the same chunks on every run whatever the processing order.
+pub struct SamplePattern {
+    /// Chunks whose draw is below this are kept; `u64::MAX` keeps all
+    cutoff: u64,
+    seed: u64,
+}
+
+impl SamplePattern {
+    pub fn new(percent: f64, seed: u64) -> Result<Self> {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err(anyhow!("--sample-keep must be between 0 and 100"));
+        }
+        let cutoff = if percent >= 100.0 {
+            u64::MAX
+        } else {
+            (percent / 100.0 * u64::MAX as f64) as u64
+        };
+        Ok(Self { cutoff, seed })
+    }
+
+    /// Whether chunk (`x`, `z`) is in the sample.
+    pub fn sampled(&self, x: i32, z: i32) -> bool {
+        let coords = ((x as u32 as u64) << 32) | z as u32 as u64;
+        let draw = mix(mix(self.seed) ^ coords);
+        self.cutoff == u64::MAX || draw < self.cutoff
+    }
+}
+
+impl ChunkPattern for SamplePattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        Ok(self.sampled(entry.global_x(), entry.global_z()))
+    }
+}
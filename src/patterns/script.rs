@@ -0,0 +1,127 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::dataversion::find_data_version_fast;
+use crate::patterns::inhabited::inhabited_time;
+use crate::patterns::ChunkPattern;
+use anyhow::{anyhow, Result};
+use log::warn;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Operations one script run may take before it is stopped (and the chunk kept).
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// A compiled `--chunk-filter-script`, shared by the `ScriptPattern` of every
+/// dimension. Only its first failure is logged; later ones are counted.
+pub struct ChunkScript {
+    engine: Engine,
+    ast: AST,
+    name: String,
+    failures: AtomicU64,
+}
+
+impl ChunkScript {
+    pub fn load(path: &Path) -> Result<Arc<Self>> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("cannot read chunk filter script {}: {}", path.display(), e))?;
+        Self::compile(&path.display().to_string(), &source)
+    }
+
+    /// Compiles `source`; `name` labels its errors.
+    pub fn compile(name: &str, source: &str) -> Result<Arc<Self>> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow!("cannot compile chunk filter script {}: {}", name, e))?;
+        Ok(Arc::new(Self {
+            engine,
+            ast,
+            name: name.to_string(),
+            failures: AtomicU64::new(0),
+        }))
+    }
+
+    /// Chunks the script failed on (and which were kept) so far.
+    pub fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    fn fail(&self, reason: impl Display) -> Option<bool> {
+        if self.failures.fetch_add(1, Ordering::Relaxed) == 0 {
+            warn!(
+                "{} {}; the chunk is kept, further failures are only counted",
+                self.name, reason
+            );
+        }
+        None
+    }
+
+    /// Runs the script with `chunk` in scope; `None` when it fails or does not
+    /// return a bool.
+    pub fn decide(&self, chunk: Map) -> Option<bool> {
+        let mut scope = Scope::new();
+        scope.push("chunk", chunk);
+        match self
+            .engine
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+        {
+            Ok(v) => match v.as_bool() {
+                Ok(keep) => Some(keep),
+                Err(t) => self.fail(format_args!("returned {} instead of a bool", t)),
+            },
+            Err(e) => self.fail(format_args!("failed: {}", e)),
+        }
+    }
+}
+
+/// Keeps the chunks the script returns `true` for; a failing script keeps the chunk.
+/// The script sees a `chunk` map of `x`, `z` (chunk coordinates), `dimension`,
+/// `inhabited` (ticks), `data_version`, `size` (bytes on disk), `compression` and
+/// `timestamp`; unknown values are `()`.
+pub struct ScriptPattern {
+    script: Arc<ChunkScript>,
+    dimension: String,
+}
+
+impl ScriptPattern {
+    pub fn new(script: Arc<ChunkScript>, dimension: &str) -> Self {
+        Self {
+            script,
+            dimension: dimension.to_string(),
+        }
+    }
+
+    fn metadata(&self, entry: &mut McaEntry) -> Result<Map> {
+        let (_, method, _) = entry.read_header()?;
+        let data_version = if entry.exceeds_memory_limit()? {
+            None
+        } else {
+            entry
+                .all_data_uncompressed()
+                .ok()
+                .and_then(|de| find_data_version_fast(&de))
+        };
+        let inhabited = inhabited_time(entry).ok().flatten();
+        let opt = |v: Option<i64>| v.map(Dynamic::from).unwrap_or(Dynamic::UNIT);
+        let mut chunk = Map::new();
+        chunk.insert("x".into(), (entry.global_x() as i64).into());
+        chunk.insert("z".into(), (entry.global_z() as i64).into());
+        chunk.insert("dimension".into(), self.dimension.clone().into());
+        chunk.insert("inhabited".into(), opt(inhabited));
+        chunk.insert("data_version".into(), opt(data_version.map(i64::from)));
+        chunk.insert("size".into(), (entry.declared_length()? as i64).into());
+        chunk.insert("compression".into(), method.name().into());
+        chunk.insert("timestamp".into(), (entry.modified_time() as i64).into());
+        Ok(chunk)
+    }
+}
+
+impl ChunkPattern for ScriptPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        let chunk = self.metadata(entry)?;
+        Ok(self.script.decide(chunk).unwrap_or(true))
+    }
+}
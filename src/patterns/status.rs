@@ -0,0 +1,97 @@
+use crate::mca::entry::McaEntry;
+use crate::patterns::{nbt, ChunkPattern};
+use anyhow::{anyhow, Result};
+use fastnbt::Value;
+
+/// Generation steps in order, using the 1.14+ names. `initialize_light` exists since 1.20,
+/// `liquid_carvers` and `heightmaps` only before 1.18; ranks stay comparable across versions.
+const STATUS_ORDER: [&str; 14] = [
+    "empty",
+    "structure_starts",
+    "structure_references",
+    "biomes",
+    "noise",
+    "surface",
+    "carvers",
+    "liquid_carvers",
+    "features",
+    "initialize_light",
+    "light",
+    "spawn",
+    "heightmaps",
+    "full",
+];
+
+/// 1.13 status names mapped onto their 1.14+ equivalent.
+const LEGACY_STATUS: [(&str, &str); 9] = [
+    ("base", "noise"),
+    ("carved", "carvers"),
+    ("liquid_carved", "liquid_carvers"),
+    ("decorated", "features"),
+    ("lighted", "light"),
+    ("mobs_spawned", "spawn"),
+    ("finalized", "heightmaps"),
+    ("fullchunk", "full"),
+    ("postprocessed", "full"),
+];
+
+/// Position of a status in the generation order; accepts `minecraft:` prefixed
+/// and 1.13 names. `None` for statuses this tool does not know (e.g. modded).
+pub fn status_rank(status: &str) -> Option<usize> {
+    let bare = status.strip_prefix("minecraft:").unwrap_or(status);
+    let name = LEGACY_STATUS
+        .iter()
+        .find(|(legacy, _)| *legacy == bare)
+        .map(|(_, modern)| *modern)
+        .unwrap_or(bare);
+    STATUS_ORDER.iter().position(|s| *s == name)
+}
+
+/// Reads the `Status` string of an uncompressed chunk (root or legacy `Level` compound).
+pub fn find_status(data: &[u8]) -> Option<String> {
+    let root = nbt::parse_root(data)?;
+    match nbt::chunk_fields(&root).get("Status") {
+        Some(Value::String(s)) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Matches chunks whose generation status is below `min` (the `--remove-below-status`
+/// veto), or with `reached` at or above it.
+/// Chunks without a readable or known status never match, nor do chunks above the
+/// memory limit, since the status needs a full NBT parse.
+pub struct StatusPattern {
+    min_rank: usize,
+    reached: bool,
+}
+
+impl StatusPattern {
+    pub fn new(min_status: &str) -> Result<Self> {
+        let min_rank = status_rank(min_status)
+            .ok_or_else(|| anyhow!("unknown chunk status: {}", min_status))?;
+        Ok(Self {
+            min_rank,
+            reached: false,
+        })
+    }
+
+    /// Matches chunks generated up to `status` or further instead.
+    pub fn reached(status: &str) -> Result<Self> {
+        Ok(Self {
+            reached: true,
+            ..Self::new(status)?
+        })
+    }
+}
+
+impl ChunkPattern for StatusPattern {
+    fn matches(&self, entry: &mut McaEntry) -> Result<bool> {
+        if entry.exceeds_memory_limit()? {
+            return Ok(false);
+        }
+        let de = entry.all_data_uncompressed()?;
+        Ok(find_status(&de)
+            .and_then(|s| status_rank(&s))
+            .is_some_and(|rank| (rank >= self.min_rank) == self.reached))
+    }
+}
@@ -0,0 +1,550 @@
+use super::{
+    dimension_label, dir_size, discover_dimensions, export_forced, RegionFiles,
+    DEFAULT_CHUNK_MEMORY_LIMIT,
+};
+use crate::mca::entry::{ChunkEncoding, CompressionMethod};
+use crate::mca::naming::RegionNaming;
+use crate::patterns::dataversion::find_data_version_fast;
+use crate::patterns::inhabited::{inhabited_time, InhabitedTimePattern};
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Lower bounds in seconds of the InhabitedTime buckets after the first (0 or less):
+/// bucket `i + 1` holds chunks from `INHABITED_BUCKETS[i]` seconds (one tick for `0`)
+/// up to the next bound.
+pub const INHABITED_BUCKETS: [i64; 5] = [0, 60, 300, 1800, 7200];
+
+/// Read-only statistics over every region chunk of a world, see `analyze_world`.
+#[derive(Default)]
+pub struct WorldAnalysis {
+    /// Labels of the scanned dimensions, as printed by `dimension_label`
+    pub dimensions: Vec<String>,
+    pub regions: u64,
+    pub chunks: u64,
+    /// Bytes of the whole world directory
+    pub total_bytes: u64,
+    /// Chunk count per compression id
+    pub compression: BTreeMap<i8, u64>,
+    /// Chunk count per InhabitedTime bucket, see `INHABITED_BUCKETS` and `inhabited_bucket`
+    pub inhabited: [u64; INHABITED_BUCKETS.len() + 1],
+    /// Decodable chunks without an InhabitedTime
+    pub inhabited_unknown: u64,
+    /// One line per region or chunk that could not be read or decoded
+    pub errors: Vec<String>,
+}
+
+impl WorldAnalysis {
+    fn merge(mut self, other: Self) -> Self {
+        self.regions += other.regions;
+        self.chunks += other.chunks;
+        for (id, n) in other.compression {
+            *self.compression.entry(id).or_default() += n;
+        }
+        for (a, b) in self.inhabited.iter_mut().zip(other.inhabited) {
+            *a += b;
+        }
+        self.inhabited_unknown += other.inhabited_unknown;
+        self.errors.extend(other.errors);
+        self
+    }
+
+    /// Chunk count per compression method, in id order.
+    pub fn compression_methods(&self) -> Vec<(CompressionMethod, u64)> {
+        self.compression
+            .iter()
+            .filter_map(|(id, n)| CompressionMethod::from_id(*id).map(|m| (m, *n)))
+            .collect()
+    }
+}
+
+/// Index into `WorldAnalysis::inhabited` for an InhabitedTime in ticks.
+pub fn inhabited_bucket(ticks: i64) -> usize {
+    if ticks <= 0 {
+        return 0;
+    }
+    INHABITED_BUCKETS
+        .iter()
+        .filter(|&&secs| ticks >= secs * 20)
+        .count()
+}
+
+/// Every dimension (see `discover_dimensions`) with its region files named after
+/// `naming`, those too small to hold a header left out.
+fn region_files(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+) -> Result<Vec<(PathBuf, Vec<PathBuf>)>> {
+    let mut dims = Vec::new();
+    for dim in discover_dimensions(input, max_depth)? {
+        let mut files = Vec::new();
+        for p in RegionFiles::Disk.files(&dim.join("region"))? {
+            if naming.matches(&p) && RegionFiles::Disk.is_valid_mca(&p) {
+                files.push(p);
+            }
+        }
+        dims.push((dim, files));
+    }
+    Ok(dims)
+}
+
+fn analyze_region(path: &Path, naming: &RegionNaming) -> WorldAnalysis {
+    let mut out = WorldAnalysis {
+        regions: 1,
+        ..WorldAnalysis::default()
+    };
+    let entries = RegionFiles::Disk.open_named(path, naming).and_then(|r| {
+        Ok(r.with_memory_limit(Some(DEFAULT_CHUNK_MEMORY_LIMIT))
+            .entries()?)
+    });
+    let mut entries = match entries {
+        Ok(e) => e,
+        Err(e) => {
+            out.errors.push(format!("{}: {}", path.display(), e));
+            return out;
+        }
+    };
+    for entry in &mut entries {
+        out.chunks += 1;
+        let index = entry.region_index();
+        let failed =
+            |e: &dyn std::fmt::Display| format!("{} chunk {}: {}", path.display(), index, e);
+        let cm = match entry.read_header() {
+            Ok((_, cm, _)) => cm,
+            Err(e) => {
+                out.errors.push(failed(&e));
+                continue;
+            }
+        };
+        *out.compression.entry(cm.id()).or_default() += 1;
+        match inhabited_time(entry) {
+            Ok(Some(t)) => out.inhabited[inhabited_bucket(t)] += 1,
+            Ok(None) => out.inhabited_unknown += 1,
+            Err(e) => out.errors.push(failed(&e)),
+        }
+    }
+    out
+}
+
+/// Scans every region named after `naming` of every dimension (see `region_files`)
+/// without writing anything: compression methods, InhabitedTime distribution and read
+/// errors. Regions are scanned in parallel; `errors` ends up sorted.
+pub fn analyze_world(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+) -> Result<WorldAnalysis> {
+    let dims = region_files(input, max_depth, naming)?;
+    let mut analysis = dims
+        .par_iter()
+        .flat_map(|(_, files)| files)
+        .map(|p| analyze_region(p, naming))
+        .reduce(WorldAnalysis::default, WorldAnalysis::merge);
+    analysis.errors.sort();
+    analysis.dimensions = dims
+        .iter()
+        .map(|(d, _)| dimension_label(d.strip_prefix(input).unwrap_or(d)))
+        .collect();
+    analysis.total_bytes = dir_size(input);
+    Ok(analysis)
+}
+
+/// Chunks and bytes the InhabitedTime rule would remove at one candidate threshold.
+pub struct SweepRow {
+    pub threshold_seconds: i64,
+    pub removed_chunks: u64,
+    /// Sectors the removed chunks occupy in their region files
+    pub removed_bytes: u64,
+}
+
+/// Result of `threshold_sweep`.
+#[derive(Default)]
+pub struct ThresholdSweep {
+    pub chunks: u64,
+    pub bytes: u64,
+    /// Force-loaded chunks, kept at every threshold
+    pub forced: u64,
+    /// Chunks that could not be read, kept at every threshold as `--on-error keep` does
+    pub errors: u64,
+    /// In the order of the candidate thresholds
+    pub rows: Vec<SweepRow>,
+}
+
+/// One region's share of a `ThresholdSweep`.
+#[derive(Default)]
+struct RegionSweep {
+    /// InhabitedTime (`None`: unknown) and allocated size of every chunk the
+    /// threshold decides
+    samples: Vec<(Option<i64>, u64)>,
+    forced: u64,
+    errors: u64,
+    /// Bytes of the forced and unreadable chunks
+    kept_bytes: u64,
+}
+
+fn sweep_region(path: &Path, naming: &RegionNaming, forced: &HashSet<(i32, i32)>) -> RegionSweep {
+    let mut out = RegionSweep::default();
+    let entries = RegionFiles::Disk.open_named(path, naming).and_then(|r| {
+        Ok(r.with_memory_limit(Some(DEFAULT_CHUNK_MEMORY_LIMIT))
+            .entries()?)
+    });
+    let Ok(mut entries) = entries else {
+        return out;
+    };
+    for entry in &mut entries {
+        let bytes = entry.allocated_length();
+        if forced.contains(&(entry.global_x(), entry.global_z())) {
+            out.forced += 1;
+            out.kept_bytes += bytes;
+            continue;
+        }
+        match inhabited_time(entry) {
+            Ok(t) => out.samples.push((t, bytes)),
+            Err(_) => {
+                out.errors += 1;
+                out.kept_bytes += bytes;
+            }
+        }
+    }
+    out
+}
+
+/// Reads every chunk's InhabitedTime once and evaluates each of `thresholds_seconds`
+/// against it, as the default InhabitedTime rule with `remove_unknown` would. Chunks
+/// force-loaded by `ticket_types` are never removed; other keep options and vetoes
+/// are not applied. Read-only; regions are scanned in parallel, those that cannot
+/// be opened are skipped (see `analyze_world` for the errors). Fails before reading
+/// anything when a threshold overflows in ticks.
+pub fn threshold_sweep(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+    thresholds_seconds: &[i64],
+    ticket_types: &[String],
+    keep_active: bool,
+    remove_unknown: bool,
+) -> Result<ThresholdSweep> {
+    let rules = thresholds_seconds
+        .iter()
+        .map(|&secs| {
+            secs.checked_mul(20)
+                .map(|ticks| InhabitedTimePattern::new(ticks, remove_unknown))
+                .ok_or_else(|| anyhow!("inhabited threshold seconds overflow: {}", secs))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut files = Vec::new();
+    for (dim, dim_files) in region_files(input, max_depth, naming)? {
+        let forced: HashSet<(i32, i32)> = export_forced(&dim, ticket_types, keep_active)
+            .into_iter()
+            .collect();
+        files.extend(dim_files.into_iter().map(|p| (p, forced.clone())));
+    }
+    let regions: Vec<_> = files
+        .par_iter()
+        .map(|(p, forced)| sweep_region(p, naming, forced))
+        .collect();
+    let mut sweep = ThresholdSweep::default();
+    for r in &regions {
+        sweep.chunks += r.samples.len() as u64 + r.forced + r.errors;
+        sweep.bytes += r.samples.iter().map(|(_, b)| b).sum::<u64>() + r.kept_bytes;
+        sweep.forced += r.forced;
+        sweep.errors += r.errors;
+    }
+    sweep.rows = thresholds_seconds
+        .iter()
+        .zip(&rules)
+        .map(|(&secs, rule)| {
+            let mut row = SweepRow {
+                threshold_seconds: secs,
+                removed_chunks: 0,
+                removed_bytes: 0,
+            };
+            for (t, bytes) in regions.iter().flat_map(|r| &r.samples) {
+                if !rule.decide(*t) {
+                    row.removed_chunks += 1;
+                    row.removed_bytes += bytes;
+                }
+            }
+            row
+        })
+        .collect();
+    Ok(sweep)
+}
+
+/// One candidate encoding of a `CompressionReport`, over the sampled chunks.
+pub struct CompressionTrial {
+    pub encoding: ChunkEncoding,
+    /// Whole sectors the sampled chunks would occupy re-encoded; chunks that cannot
+    /// be re-encoded count with their current size
+    pub bytes: u64,
+    /// Time spent encoding, summed over all threads
+    pub encode_time: Duration,
+}
+
+/// Result of `compression_report`.
+#[derive(Default)]
+pub struct CompressionReport {
+    /// Every chunk of the world, from the region headers
+    pub chunks: u64,
+    pub sampled: u64,
+    /// Sampled chunks `--recompress` leaves as they are (external, custom-compressed
+    /// or above the memory limit)
+    pub skipped: u64,
+    /// Sampled chunks that could not be read, counted like `skipped`
+    pub errors: u64,
+    /// Sectors the sampled chunks occupy now
+    pub current_bytes: u64,
+    /// Time spent decoding the sampled chunks, summed over all threads
+    pub decode_time: Duration,
+    /// In the order of the candidate encodings
+    pub trials: Vec<CompressionTrial>,
+}
+
+impl CompressionReport {
+    /// Factor from the sample to the whole world.
+    pub fn scale(&self) -> f64 {
+        self.chunks as f64 / self.sampled.max(1) as f64
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        if self.trials.is_empty() {
+            return other;
+        }
+        self.sampled += other.sampled;
+        self.skipped += other.skipped;
+        self.errors += other.errors;
+        self.current_bytes += other.current_bytes;
+        self.decode_time += other.decode_time;
+        for (a, b) in self.trials.iter_mut().zip(other.trials) {
+            a.bytes += b.bytes;
+            a.encode_time += b.encode_time;
+        }
+        self
+    }
+}
+
+/// Bytes of the whole sectors `len` serialized bytes occupy.
+fn sectors(len: u64) -> u64 {
+    len.div_ceil(4096) * 4096
+}
+
+/// Trial-encodes the chunks of `path` at the given positions (in entry order).
+fn sample_region(
+    path: &Path,
+    naming: &RegionNaming,
+    picks: &[usize],
+    encodings: &[ChunkEncoding],
+) -> CompressionReport {
+    let mut out = CompressionReport {
+        trials: encodings
+            .iter()
+            .map(|&encoding| CompressionTrial {
+                encoding,
+                bytes: 0,
+                encode_time: Duration::ZERO,
+            })
+            .collect(),
+        ..CompressionReport::default()
+    };
+    let entries = RegionFiles::Disk.open_named(path, naming).and_then(|r| {
+        Ok(r.with_memory_limit(Some(DEFAULT_CHUNK_MEMORY_LIMIT))
+            .entries()?)
+    });
+    let mut entries = entries.unwrap_or_default();
+    for &i in picks {
+        out.sampled += 1;
+        let Some(entry) = entries.get_mut(i) else {
+            out.errors += 1;
+            continue;
+        };
+        let current = entry.allocated_length();
+        out.current_bytes += current;
+        let eligible = entry.read_header().and_then(|(_, cm, _)| {
+            Ok(matches!(
+                cm,
+                CompressionMethod::Raw
+                    | CompressionMethod::Zlib
+                    | CompressionMethod::Gzip
+                    | CompressionMethod::Lz4
+            ) && !entry.exceeds_memory_limit()?)
+        });
+        let start = Instant::now();
+        let data = match eligible {
+            Ok(true) => entry.all_data_uncompressed(),
+            Ok(false) => {
+                out.skipped += 1;
+                out.trials.iter_mut().for_each(|t| t.bytes += current);
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+        out.decode_time += start.elapsed();
+        let Ok(data) = data else {
+            out.errors += 1;
+            out.trials.iter_mut().for_each(|t| t.bytes += current);
+            continue;
+        };
+        for trial in &mut out.trials {
+            let start = Instant::now();
+            let encoded = trial.encoding.serialize(&data);
+            trial.encode_time += start.elapsed();
+            trial.bytes += encoded.map_or(current, |e| sectors(e.len() as u64));
+        }
+    }
+    out
+}
+
+/// Estimates what `--recompress` would save: every `stride`-th region chunk of the
+/// world (`stride` chosen so about `sample` chunks are picked, spread evenly over the
+/// regions in name order) is decoded and re-encoded with each of `encodings`, and
+/// the sizes are compared in whole sectors. Chunks are counted from the region
+/// headers first, so only the sampled ones are read. Read-only; in parallel.
+pub fn compression_report(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+    sample: u64,
+    encodings: &[ChunkEncoding],
+) -> Result<CompressionReport> {
+    let mut files: Vec<PathBuf> = region_files(input, max_depth, naming)?
+        .into_iter()
+        .flat_map(|(_, files)| files)
+        .collect();
+    files.sort();
+    let counts: Vec<u64> = files
+        .par_iter()
+        .map(|p| RegionFiles::Disk.chunk_count_named(p, naming).unwrap_or(0) as u64)
+        .collect();
+    let total: u64 = counts.iter().sum();
+    let stride = total.div_ceil(sample.max(1)).max(1);
+    let mut first = 0u64;
+    let mut jobs = Vec::new();
+    for (p, &n) in files.iter().zip(&counts) {
+        // global chunk numbers first..first + n, of which the multiples of stride
+        let picks: Vec<usize> = (first.div_ceil(stride) * stride..first + n)
+            .step_by(stride as usize)
+            .map(|g| (g - first) as usize)
+            .collect();
+        first += n;
+        jobs.push((p, picks));
+    }
+    let report = jobs
+        .par_iter()
+        .filter(|(_, picks)| !picks.is_empty())
+        .map(|(p, picks)| sample_region(p, naming, picks, encodings))
+        .reduce(CompressionReport::default, CompressionReport::merge);
+    Ok(CompressionReport {
+        chunks: total,
+        ..report
+    })
+}
+
+/// Result of `dataversion_report`.
+#[derive(Default)]
+pub struct DataVersionReport {
+    pub chunks: u64,
+    /// Chunk count per DataVersion
+    pub versions: BTreeMap<i32, u64>,
+    /// Decodable chunks without a DataVersion, written before 1.9
+    pub unknown: u64,
+    /// Chunks that could not be read or decoded, external chunks without their
+    /// `.mcc` file included
+    pub errors: u64,
+    /// Chunks above `DEFAULT_CHUNK_MEMORY_LIMIT`, not decoded
+    pub oversized: u64,
+    /// Chunks below the minimum version or without one, per region path relative
+    /// to the world
+    pub at_risk: BTreeMap<PathBuf, u64>,
+}
+
+impl DataVersionReport {
+    fn merge(mut self, other: Self) -> Self {
+        self.chunks += other.chunks;
+        for (v, n) in other.versions {
+            *self.versions.entry(v).or_default() += n;
+        }
+        self.unknown += other.unknown;
+        self.errors += other.errors;
+        self.oversized += other.oversized;
+        self.at_risk.extend(other.at_risk);
+        self
+    }
+
+    /// Chunks below the minimum version or without one.
+    pub fn at_risk_chunks(&self) -> u64 {
+        self.at_risk.values().sum()
+    }
+}
+
+fn dataversion_region(
+    path: &Path,
+    naming: &RegionNaming,
+    rel: PathBuf,
+    min_version: i32,
+) -> DataVersionReport {
+    let mut out = DataVersionReport::default();
+    let Ok(mut entries) = RegionFiles::Disk.open_named(path, naming).and_then(|r| {
+        Ok(r.with_memory_limit(Some(DEFAULT_CHUNK_MEMORY_LIMIT))
+            .entries()?)
+    }) else {
+        return out;
+    };
+    let mut at_risk = 0;
+    for entry in &mut entries {
+        out.chunks += 1;
+        match entry.exceeds_memory_limit() {
+            Ok(true) => {
+                out.oversized += 1;
+                continue;
+            }
+            Ok(false) => {}
+            Err(_) => {
+                out.errors += 1;
+                continue;
+            }
+        }
+        match entry.all_data_uncompressed() {
+            // an external chunk whose `.mcc` file is missing
+            Ok(data) if data.is_empty() => out.errors += 1,
+            Ok(data) => match find_data_version_fast(&data) {
+                Some(v) => {
+                    *out.versions.entry(v).or_default() += 1;
+                    at_risk += (v < min_version) as u64;
+                }
+                None => {
+                    out.unknown += 1;
+                    at_risk += 1;
+                }
+            },
+            Err(_) => out.errors += 1,
+        }
+    }
+    if at_risk > 0 {
+        out.at_risk.insert(rel, at_risk);
+    }
+    out
+}
+
+/// Reads the `DataVersion` of every region chunk (see `find_data_version_fast`) and
+/// tallies them, listing per region the chunks below `min_version` or without a
+/// DataVersion at all. Chunks above `DEFAULT_CHUNK_MEMORY_LIMIT` are counted but not
+/// decoded. Read-only; regions are scanned in parallel, those that cannot be opened
+/// are skipped (see `analyze_world` for the errors).
+pub fn dataversion_report(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+    min_version: i32,
+) -> Result<DataVersionReport> {
+    Ok(region_files(input, max_depth, naming)?
+        .par_iter()
+        .flat_map(|(_, files)| files)
+        .map(|p| {
+            let rel = p.strip_prefix(input).unwrap_or(p).to_path_buf();
+            dataversion_region(p, naming, rel, min_version)
+        })
+        .reduce(DataVersionReport::default, DataVersionReport::merge))
+}
@@ -0,0 +1,57 @@
+use std::sync::{Condvar, Mutex};
+
+/// Decoded chunk NBT is assumed to be this many times its declared (compressed)
+/// size when sizing a `MemoryBudget` permit; typical zlib ratios are 4 to 10.
+pub const DECODED_SIZE_FACTOR: u64 = 8;
+
+/// Soft cap on the bytes of chunk data held by all workers at once
+/// (`RunOptions::max_memory_mb`). Permits are estimates taken before decoding, so
+/// actual use can exceed the cap; a permit larger than the whole cap is granted
+/// once nothing else is held, so a huge chunk waits instead of deadlocking.
+pub struct MemoryBudget {
+    limit: u64,
+    used: Mutex<u64>,
+    released: Condvar,
+}
+
+/// Bytes held from a `MemoryBudget`, given back on drop.
+pub struct MemoryPermit<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl MemoryBudget {
+    pub fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Blocks until `bytes` fit below the cap (or nothing else is held).
+    pub fn acquire(&self, bytes: u64) -> MemoryPermit<'_> {
+        let mut used = self.used.lock().unwrap();
+        while *used > 0 && used.saturating_add(bytes) > self.limit {
+            used = self.released.wait(used).unwrap();
+        }
+        *used += bytes;
+        MemoryPermit {
+            budget: self,
+            bytes,
+        }
+    }
+
+    /// Bytes currently held.
+    #[allow(dead_code)]
+    pub fn used(&self) -> u64 {
+        *self.used.lock().unwrap()
+    }
+}
+
+impl Drop for MemoryPermit<'_> {
+    fn drop(&mut self) {
+        *self.budget.used.lock().unwrap() -= self.bytes;
+        self.budget.released.notify_all();
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// Unit of the coordinates given to `--keep-chunk`/`--keep-range`/`--keep-list`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+pub enum CoordUnit {
+    /// Block coordinates (`F3` screen): chunk = block >> 4
+    Block,
+    Chunk,
+    /// Region coordinates (`r.<x>.<z>.mca`): all 32x32 chunks of the region
+    Region,
+}
+
+/// `(x1, z1, x2, z2)`, inclusive on both corners
+pub type CoordBox = (i32, i32, i32, i32);
+
+/// The world border sits at ±30,000,000 blocks; nothing outside can hold a chunk.
+const MAX_BLOCK: i64 = 30_000_000;
+
+impl CoordUnit {
+    fn limit(self) -> i64 {
+        match self {
+            CoordUnit::Block => MAX_BLOCK,
+            CoordUnit::Chunk => MAX_BLOCK >> 4,
+            CoordUnit::Region => MAX_BLOCK >> 9,
+        }
+    }
+
+    /// Inclusive chunk range covered by one coordinate on one axis.
+    fn chunk_span(self, v: i32) -> (i32, i32) {
+        match self {
+            CoordUnit::Block => (v >> 4, v >> 4),
+            CoordUnit::Chunk => (v, v),
+            CoordUnit::Region => (v * 32, v * 32 + 31),
+        }
+    }
+}
+
+fn check(unit: CoordUnit, v: i32) -> Result<()> {
+    let limit = unit.limit();
+    if (v as i64).abs() > limit {
+        return Err(anyhow!(
+            "{} coordinate {} is outside the world border (±{})",
+            format!("{:?}", unit).to_lowercase(),
+            v,
+            limit
+        ));
+    }
+    Ok(())
+}
+
+/// Converts points and ranges in `unit` into inclusive chunk ranges
+/// `(min_x, min_z, max_x, max_z)`, rejecting coordinates beyond the world border.
+pub fn chunk_ranges(
+    unit: CoordUnit,
+    points: &[(i32, i32)],
+    ranges: &[CoordBox],
+) -> Result<Vec<CoordBox>> {
+    let mut out = Vec::with_capacity(points.len() + ranges.len());
+    let boxes = points
+        .iter()
+        .map(|&(x, z)| (x, z, x, z))
+        .chain(ranges.iter().copied());
+    for (x1, z1, x2, z2) in boxes {
+        for v in [x1, z1, x2, z2] {
+            check(unit, v)?;
+        }
+        let (min_x, _) = unit.chunk_span(x1.min(x2));
+        let (_, max_x) = unit.chunk_span(x1.max(x2));
+        let (min_z, _) = unit.chunk_span(z1.min(z2));
+        let (_, max_z) = unit.chunk_span(z1.max(z2));
+        out.push((min_x, min_z, max_x, max_z));
+    }
+    Ok(out)
+}
+
+fn parse_ints(s: &str) -> std::result::Result<Vec<i32>, String> {
+    s.split([',', ' ', '\t'])
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            p.parse()
+                .map_err(|_| format!("invalid coordinate {:?} in {:?}", p, s))
+        })
+        .collect()
+}
+
+/// Parses `X,Z`.
+pub fn parse_point(s: &str) -> std::result::Result<(i32, i32), String> {
+    match parse_ints(s)?.as_slice() {
+        [x, z] => Ok((*x, *z)),
+        _ => Err(format!("expected X,Z, got {:?}", s)),
+    }
+}
+
+/// Parses `X1,Z1,X2,Z2`.
+pub fn parse_range(s: &str) -> std::result::Result<CoordBox, String> {
+    match parse_ints(s)?.as_slice() {
+        [x1, z1, x2, z2] => Ok((*x1, *z1, *x2, *z2)),
+        _ => Err(format!("expected X1,Z1,X2,Z2, got {:?}", s)),
+    }
+}
+
+/// Chunk coordinates per dimension label (`dimension_label`)
+pub type DimensionChunks = Vec<(String, Vec<(i32, i32)>)>;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct KeepList {
+    pub points: Vec<(i32, i32)>,
+    pub ranges: Vec<CoordBox>,
+    /// From a `dimension_chunks_json` file: chunk coordinates whatever the unit,
+    /// kept only in the dimension they name
+    pub dimension_chunks: DimensionChunks,
+}
+
+/// The `--dump-forced` file:
+/// `{"dimensions":[{"dimension":"DIM-1","chunks":[[x,z],...]},...]}`.
+pub fn dimension_chunks_json(dims: &[(String, Vec<(i32, i32)>)]) -> String {
+    let dims: Vec<Value> = dims
+        .iter()
+        .map(|(label, chunks)| {
+            serde_json::json!({
+                "dimension": label,
+                "chunks": chunks.iter().map(|(x, z)| [*x, *z]).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    serde_json::json!({ "dimensions": dims }).to_string()
+}
+
+fn parse_dimension_chunks(value: &Value) -> std::result::Result<DimensionChunks, String> {
+    let point = |p: &Value| match p.as_array()?.as_slice() {
+        [x, z] => Some((
+            i32::try_from(x.as_i64()?).ok()?,
+            i32::try_from(z.as_i64()?).ok()?,
+        )),
+        _ => None,
+    };
+    value["dimensions"]
+        .as_array()
+        .ok_or("expected {\"dimensions\": [...]}")?
+        .iter()
+        .map(|d| {
+            let label = d["dimension"].as_str().ok_or("dimension without a name")?;
+            let chunks = d["chunks"]
+                .as_array()
+                .and_then(|c| c.iter().map(point).collect::<Option<Vec<_>>>())
+                .ok_or_else(|| format!("{}: expected chunks as [[x, z], ...]", label))?;
+            Ok((label.to_string(), chunks))
+        })
+        .collect()
+}
+
+/// Reads a keep list: one `X,Z` point or `X1,Z1,X2,Z2` range per line (commas or
+/// whitespace), blank lines and `#` comments ignored; or, for a file starting with
+/// `{`, the JSON of `dimension_chunks_json`.
+pub fn parse_keep_list(path: &Path) -> Result<KeepList> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow!("cannot read keep list {}: {}", path.display(), e))?;
+    let mut list = KeepList::default();
+    if text.trim_start().starts_with('{') {
+        let value: Value = serde_json::from_str(&text)
+            .map_err(|e| anyhow!("{}: invalid JSON: {}", path.display(), e))?;
+        list.dimension_chunks =
+            parse_dimension_chunks(&value).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        return Ok(list);
+    }
+    for (n, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let err = |e: String| anyhow!("{}:{}: {}", path.display(), n + 1, e);
+        match parse_ints(line).map_err(err)?.as_slice() {
+            [x, z] => list.points.push((*x, *z)),
+            [x1, z1, x2, z2] => list.ranges.push((*x1, *z1, *x2, *z2)),
+            _ => return Err(err(format!("expected X,Z or X1,Z1,X2,Z2, got {:?}", line))),
+        }
+    }
+    Ok(list)
+}
+
+fn ring(value: &Value) -> Option<Vec<(f64, f64)>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|p| match p.as_array()?.as_slice() {
+            [x, z, ..] => Some((x.as_f64()?, z.as_f64()?)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Appends the outer rings of a GeoJSON object (or of a bare `[[x, z], ...]` ring).
+fn collect_rings(value: &Value, out: &mut Vec<Vec<(f64, f64)>>) -> std::result::Result<(), String> {
+    if value.is_array() {
+        out.push(ring(value).ok_or("expected an array of [x, z] vertices")?);
+        return Ok(());
+    }
+    let coords = &value["coordinates"];
+    match value["type"].as_str() {
+        Some("FeatureCollection") => value["features"]
+            .as_array()
+            .ok_or("FeatureCollection without features")?
+            .iter()
+            .try_for_each(|f| collect_rings(f, out)),
+        Some("Feature") => collect_rings(&value["geometry"], out),
+        Some("Polygon") => {
+            out.push(ring(&coords[0]).ok_or("Polygon without an outer ring of [x, z]")?);
+            Ok(())
+        }
+        Some("MultiPolygon") => coords
+            .as_array()
+            .ok_or("MultiPolygon without coordinates")?
+            .iter()
+            .try_for_each(|p| {
+                out.push(ring(&p[0]).ok_or("MultiPolygon without an outer ring of [x, z]")?);
+                Ok(())
+            }),
+        Some(other) => Err(format!("unsupported GeoJSON type {:?}", other)),
+        None => Err("expected a GeoJSON object or an array of [x, z] vertices".to_string()),
+    }
+}
+
+/// Reads the polygons of a `--keep-polygon` file in block coordinates: a bare
+/// `[[x, z], ...]` ring, or a GeoJSON Polygon/MultiPolygon/Feature/FeatureCollection
+/// whose `[x, z]` positions use `unit`. Holes are ignored, each outer ring is kept whole.
+pub fn parse_polygons(path: &Path, unit: CoordUnit) -> Result<Vec<Vec<(f64, f64)>>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow!("cannot read polygon file {}: {}", path.display(), e))?;
+    let value: Value = serde_json::from_str(&text)
+        .map_err(|e| anyhow!("{}: invalid JSON: {}", path.display(), e))?;
+    let mut rings = Vec::new();
+    collect_rings(&value, &mut rings).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    let scale = match unit {
+        CoordUnit::Block => 1.0,
+        CoordUnit::Chunk => 16.0,
+        CoordUnit::Region => 512.0,
+    };
+    for r in &mut rings {
+        for (x, z) in r.iter_mut() {
+            (*x, *z) = (*x * scale, *z * scale);
+        }
+    }
+    Ok(rings)
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use chrono::{Local, SecondsFormat};
+use log::warn;
+use serde_json::Value;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// A `chunks` event is sent every this many processed chunks.
+pub const CHUNK_EVENT_INTERVAL: u64 = 1024;
+
+/// JSON-line progress events for frontends (`RunOptions::progress_events`), one
+/// object per line, each with an `event` field:
+///
+/// - `{"event":"start","chunks":N,"regions":N}`
+/// - `{"event":"chunks","done":N,"total":N}`, every `CHUNK_EVENT_INTERVAL` chunks
+/// - `{"event":"region","dimension":S,"region":S,"chunks":N,"removed":N,"verbatim":B,"chunks_done":N,"regions_done":N}`
+/// - `{"event":"summary",...}` with the fields of the `--json-summary` line
+///
+/// Events of different dimensions interleave unless `RunOptions::stable_order` is
+/// set. Fields are only ever added.
+pub struct ProgressEvents {
+    out: Mutex<File>,
+    /// Set once a write failed; a frontend closing its end must not fail the run
+    broken: AtomicBool,
+    regions_done: AtomicU64,
+}
+
+impl ProgressEvents {
+    /// Opens `path` for appending, never truncating, so `/dev/fd/<n>` and FIFOs work.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| anyhow!("cannot open progress events {}: {}", path.display(), e))?;
+        Ok(Self {
+            out: Mutex::new(file),
+            broken: AtomicBool::new(false),
+            regions_done: AtomicU64::new(0),
+        })
+    }
+
+    fn emit(&self, line: String) {
+        if self.broken.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut out = self.out.lock().unwrap();
+        if let Err(e) = writeln!(out, "{}", line) {
+            warn!("Progress events stopped: {}", e);
+            self.broken.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn start(&self, chunks: u64, regions: u64) {
+        self.emit(format!(
+            "{{\"event\":\"start\",\"chunks\":{},\"regions\":{}}}",
+            chunks, regions
+        ));
+    }
+
+    /// Sends a `chunks` event when `done` is a multiple of `CHUNK_EVENT_INTERVAL`.
+    pub fn chunk_done(&self, done: u64, total: u64) {
+        if done.is_multiple_of(CHUNK_EVENT_INTERVAL) {
+            self.emit(format!(
+                "{{\"event\":\"chunks\",\"done\":{},\"total\":{}}}",
+                done, total
+            ));
+        }
+    }
+
+    pub fn region_done(
+        &self,
+        dimension: &str,
+        region: &str,
+        chunks: u64,
+        removed: u64,
+        verbatim: bool,
+        chunks_done: u64,
+    ) {
+        let regions_done = self.regions_done.fetch_add(1, Ordering::Relaxed) + 1;
+        self.emit(format!(
+            "{{\"event\":\"region\",\"dimension\":{},\"region\":{},\"chunks\":{},\"removed\":{},\"verbatim\":{},\"chunks_done\":{},\"regions_done\":{}}}",
+            Value::from(dimension),
+            Value::from(region),
+            chunks,
+            removed,
+            verbatim,
+            chunks_done,
+            regions_done
+        ));
+    }
+
+    /// `fields`: the members of a JSON object, without the braces.
+    pub fn summary(&self, fields: &str) {
+        self.emit(format!("{{\"event\":\"summary\",{}}}", fields));
+    }
+}
+
+/// One line per removed chunk (`RunOptions::removal_log`), written as the chunk is
+/// dropped so the file can be followed with `tail -f` during a long run:
+///
+/// `<local time, RFC 3339>\t<dimension>\t<region file>\t<chunk x>\t<chunk z>`
+///
+/// Dimensions processed in parallel interleave, but each line is written and
+/// flushed whole under the lock.
+pub struct RemovalLog {
+    out: Mutex<BufWriter<File>>,
+    broken: AtomicBool,
+}
+
+impl RemovalLog {
+    /// Opens `path` for appending, so successive runs add to the same log.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| anyhow!("cannot open removal log {}: {}", path.display(), e))?;
+        Ok(Self {
+            out: Mutex::new(BufWriter::new(file)),
+            broken: AtomicBool::new(false),
+        })
+    }
+
+    pub fn removed(&self, dimension: &str, region: &str, x: i32, z: i32) {
+        if self.broken.load(Ordering::Relaxed) {
+            return;
+        }
+        let line = format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            Local::now().to_rfc3339_opts(SecondsFormat::Millis, false),
+            dimension,
+            region,
+            x,
+            z
+        );
+        let mut out = self.out.lock().unwrap();
+        if let Err(e) = out.write_all(line.as_bytes()).and_then(|_| out.flush()) {
+            warn!("Removal log stopped: {}", e);
+            self.broken.store(true, Ordering::Relaxed);
+        }
+    }
+}
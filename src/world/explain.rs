@@ -0,0 +1,153 @@
+use super::coords::CoordBox;
+
+/// Chunks pinned by one keep option in one dimension, as inclusive chunk boxes
+/// `(min_x, min_z, max_x, max_z)`; single chunks are 1x1 boxes.
+pub struct KeepSource {
+    /// The option, e.g. `--keep-chunk`, `force-loaded`
+    pub name: &'static str,
+    pub boxes: Vec<CoordBox>,
+}
+
+impl KeepSource {
+    pub fn new(name: &'static str, boxes: Vec<CoordBox>) -> Self {
+        Self { name, boxes }
+    }
+
+    /// A source of single chunks.
+    pub fn chunks(name: &'static str, chunks: &[(i32, i32)]) -> Self {
+        Self::new(name, chunks.iter().map(|&(x, z)| (x, z, x, z)).collect())
+    }
+
+    /// Chunks covered by the boxes, overlaps between boxes of this source counted
+    /// once per box.
+    pub fn chunk_count(&self) -> u64 {
+        self.boxes.iter().map(|&b| box_area(b)).sum()
+    }
+}
+
+/// Where two keep sources pin the same chunks.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Overlap {
+    pub first: &'static str,
+    pub second: &'static str,
+    /// The intersection, in chunk coordinates
+    pub area: CoordBox,
+}
+
+impl Overlap {
+    pub fn chunks(&self) -> u64 {
+        box_area(self.area)
+    }
+}
+
+fn box_area((x1, z1, x2, z2): CoordBox) -> u64 {
+    (x2 as i64 - x1 as i64 + 1) as u64 * (z2 as i64 - z1 as i64 + 1) as u64
+}
+
+fn intersect(a: CoordBox, b: CoordBox) -> Option<CoordBox> {
+    let area = (a.0.max(b.0), a.1.max(b.1), a.2.min(b.2), a.3.min(b.3));
+    (area.0 <= area.2 && area.1 <= area.3).then_some(area)
+}
+
+/// Every intersection between boxes of two different sources, in source order.
+/// Harmless, since a chunk pinned twice is kept once, but it shows options that
+/// repeat each other.
+pub fn overlaps(sources: &[KeepSource]) -> Vec<Overlap> {
+    let mut out = Vec::new();
+    for (i, first) in sources.iter().enumerate() {
+        for second in &sources[i + 1..] {
+            for &a in &first.boxes {
+                for &b in &second.boxes {
+                    if let Some(area) = intersect(a, b) {
+                        out.push(Overlap {
+                            first: first.name,
+                            second: second.name,
+                            area,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Overlaps listed one by one before the rest are only counted.
+const MAX_LISTED_OVERLAPS: usize = 20;
+
+/// The keep decision of one dimension as `--explain-rules` prints it, highest
+/// precedence first: pinned chunks, vetoes, keep patterns.
+pub struct RuleSummary {
+    pub label: String,
+    pub sources: Vec<KeepSource>,
+    /// `--keep-polygon` polygons, pinned but left out of the overlap check
+    pub polygons: usize,
+    pub vetoes: Vec<String>,
+    pub keeps: Vec<String>,
+}
+
+fn fmt_area(a: CoordBox) -> String {
+    if a.0 == a.2 && a.1 == a.3 {
+        format!("({}, {})", a.0, a.1)
+    } else {
+        format!("({}, {})-({}, {})", a.0, a.1, a.2, a.3)
+    }
+}
+
+fn or_none(items: Vec<String>) -> String {
+    if items.is_empty() {
+        "无".to_string()
+    } else {
+        items.join(" · ")
+    }
+}
+
+impl RuleSummary {
+    /// Report lines, in Chinese like the rest of the run's output.
+    pub fn lines(&self) -> Vec<String> {
+        let mut pins: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|s| !s.boxes.is_empty())
+            .map(|s| format!("{} {} 个区块", s.name, s.chunk_count()))
+            .collect();
+        if self.polygons > 0 {
+            pins.push(format!("--keep-polygon {} 个多边形", self.polygons));
+        }
+        let has_pins = !pins.is_empty();
+        let mut lines = vec![
+            format!("[{}] 保留规则（按优先级从高到低）:", self.label),
+            format!("  1. 始终保留: {}", or_none(pins)),
+            format!("  2. 删除: {}", or_none(self.vetoes.clone())),
+            format!("  3. 保留: {}", or_none(self.keeps.clone())),
+            "  其余区块删除".to_string(),
+        ];
+        if has_pins && !self.vetoes.is_empty() {
+            lines.push("  冲突时第 1 级优先: 始终保留的区块即使符合删除条件也会保留".to_string());
+        }
+        let overlaps = overlaps(&self.sources);
+        if !overlaps.is_empty() {
+            let chunks: u64 = overlaps.iter().map(Overlap::chunks).sum();
+            lines.push(format!(
+                "  重叠 {} 处（共 {} 个区块，重复指定不影响结果）:",
+                overlaps.len(),
+                chunks
+            ));
+            for o in overlaps.iter().take(MAX_LISTED_OVERLAPS) {
+                lines.push(format!(
+                    "    {} 与 {}: {}",
+                    o.first,
+                    o.second,
+                    fmt_area(o.area)
+                ));
+            }
+            if overlaps.len() > MAX_LISTED_OVERLAPS {
+                lines.push(format!(
+                    "    …… 另有 {} 处",
+                    overlaps.len() - MAX_LISTED_OVERLAPS
+                ));
+            }
+        }
+        lines
+    }
+}
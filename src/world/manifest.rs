@@ -0,0 +1,108 @@
+use super::regions::is_junk_file;
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use walkdir::WalkDir;
+use xxhash_rust::xxh64::Xxh64;
+
+/// One checked file of `verify_manifest` that did not match.
+pub struct ManifestFailure {
+    pub path: String,
+    /// `None` when the file is missing or unreadable
+    pub actual: Option<String>,
+}
+
+/// Result of `verify_manifest`.
+pub struct ManifestCheck {
+    pub checked: u64,
+    pub failures: Vec<ManifestFailure>,
+}
+
+/// XXH64 (seed 0) of the file at `path`, as 16 hex digits in canonical order.
+pub fn file_digest(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh64::new(0);
+    let mut buf = vec![0u8; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:016x}", hasher.digest()))
+}
+
+/// Region files (`.mca`) and external chunks (`.mcc`) below `root`, as sorted
+/// `/`-separated relative paths.
+fn region_files(root: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(root).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        let region = matches!(
+            path.extension().and_then(|s| s.to_str()),
+            Some("mca" | "mcc")
+        );
+        if !entry.file_type().is_file() || !region || is_junk_file(path) {
+            continue;
+        }
+        let rel = path.strip_prefix(root).unwrap();
+        files.push(
+            rel.components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+    }
+    Ok(files)
+}
+
+/// Writes one `<xxh64>  <path>` line per region file below `root` (see
+/// `region_files`) to `manifest`, the format `xxhsum -c` reads when run from `root`.
+/// Files are hashed in parallel; returns how many were listed.
+pub fn write_manifest(root: &Path, manifest: &Path) -> Result<u64> {
+    let files = region_files(root)?;
+    let lines = files
+        .par_iter()
+        .map(|rel| Ok(format!("{}  {}\n", file_digest(&root.join(rel))?, rel)))
+        .collect::<Result<Vec<_>>>()?;
+    fs::write(manifest, lines.concat())
+        .map_err(|e| anyhow!("cannot write manifest {}: {}", manifest.display(), e))?;
+    Ok(files.len() as u64)
+}
+
+/// Recomputes every digest listed in `manifest` for the files below `root`. Blank
+/// lines and `#` comments are skipped; a malformed line fails the whole check.
+pub fn verify_manifest(root: &Path, manifest: &Path) -> Result<ManifestCheck> {
+    let text = fs::read_to_string(manifest)
+        .map_err(|e| anyhow!("cannot read manifest {}: {}", manifest.display(), e))?;
+    let mut listed = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (digest, path) = line
+            .split_once("  ")
+            .filter(|(d, p)| d.len() == 16 && !p.is_empty())
+            .ok_or_else(|| anyhow!("malformed manifest line {}: {}", i + 1, line))?;
+        listed.push((digest.to_ascii_lowercase(), path.to_string()));
+    }
+    let failures: Vec<ManifestFailure> = listed
+        .par_iter()
+        .filter_map(|(expected, path)| {
+            let actual = file_digest(&root.join(path)).ok();
+            (actual.as_ref() != Some(expected)).then(|| ManifestFailure {
+                path: path.clone(),
+                actual,
+            })
+        })
+        .collect();
+    Ok(ManifestCheck {
+        checked: listed.len() as u64,
+        failures,
+    })
+}
@@ -1,25 +1,313 @@
+pub mod analyze;
+pub mod budget;
+pub mod coords;
+pub mod events;
+pub mod explain;
+pub mod manifest;
+pub mod progress;
+pub mod regions;
+pub mod scrub;
+pub mod tar;
+
+use crate::mca::entry::{ChunkEncoding, CompressionMethod, McaEntry, LZ4_DEFAULT_BLOCK_SIZE};
+use crate::mca::index::{IndexEntry, RegionIndex};
+use crate::mca::naming::RegionNaming;
 use crate::mca::reader::McaReader;
-use crate::mca::writer::McaWriter;
-use crate::patterns::inhabited::InhabitedTimePattern;
+use crate::mca::writer::{morton_rank, restamp, McaWriter};
+use crate::patterns::biome::BiomePattern;
+use crate::patterns::combined::CombinedPattern;
+use crate::patterns::dataversion::{find_data_version_fast, DataVersionPattern};
+use crate::patterns::empty::EmptyPayloadPattern;
+use crate::patterns::entities::{self, EntityHotspotPattern, TrivialEntitiesPattern};
+use crate::patterns::inhabited::{
+    find_inhabited_fast, find_inhabited_nbt, inhabited_time, InhabitedTimePattern,
+};
+use crate::patterns::lastupdate::{find_last_update, LastUpdatePattern};
 use crate::patterns::list::ListPattern;
-use crate::patterns::ChunkPattern;
+use crate::patterns::modified::PristinePattern;
+use crate::patterns::polygon::PolygonPattern;
+use crate::patterns::range::RangePattern;
+use crate::patterns::sample::SamplePattern;
+#[cfg(feature = "script")]
+use crate::patterns::script::{ChunkScript, ScriptPattern};
+use crate::patterns::status::{find_status, StatusPattern};
+use crate::patterns::{named, nbt, ChunkPattern, KeepRules, RuleStage};
 use anyhow::{anyhow, Result};
+use budget::{MemoryBudget, DECODED_SIZE_FACTOR};
 use clap::ValueEnum;
 use console::Term;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use coords::CoordUnit;
+use events::{ProgressEvents, RemovalLog};
+use explain::{KeepSource, RuleSummary};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use log::{info, warn};
+use progress::BarLayout;
 use rayon::prelude::*;
+use regions::{RegionFiles, ZipWorld};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use walkdir::WalkDir;
 
+use self::tar::TarStream;
+
+/// `println!` for the human-readable report of a run with options `$opts`, moved
+/// to stderr by `RunOptions::report_to_stderr`
+macro_rules! report {
+    ($opts:expr, $($arg:tt)*) => {
+        if $opts.report_to_stderr {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
+/// A folder holding `region/`, or only `entities/` or `poi/` (whose regions are then
+/// copied as-is, see `run_with_options`).
 fn is_dimension_dir(path: &Path) -> bool {
-    path.join("region").is_dir()
+    ["region", "entities", "poi"]
+        .iter()
+        .any(|d| path.join(d).is_dir())
+}
+
+/// Deepest dimension below the world root: `dimensions/<namespace>/<name>`
+pub const DEFAULT_DIMENSION_DEPTH: usize = 3;
+
+/// Junk left next to a world by servers/clients, skipped by `copy_world_files`
+pub const DEFAULT_COPY_IGNORE: [&str; 2] = ["logs", "crash-reports"];
+
+/// Per-dimension data folders; never dimensions themselves
+const DIMENSION_DATA_DIRS: [&str; 4] = ["region", "entities", "poi", "data"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum DimensionLayout {
+    /// The world root itself (overworld)
+    Root,
+    /// `DIM-1` / `DIM1`
+    Vanilla,
+    /// `dimensions/<namespace>/<name>` (datapack dimensions, 1.16+)
+    Custom,
+    Other,
+}
+
+fn dimension_layout(rel: &Path) -> DimensionLayout {
+    let parts: Vec<_> = rel.components().map(|c| c.as_os_str()).collect();
+    match parts.as_slice() {
+        [] => DimensionLayout::Root,
+        [d] if *d == "DIM-1" || *d == "DIM1" => DimensionLayout::Vanilla,
+        [d, _, _] if *d == "dimensions" => DimensionLayout::Custom,
+        _ => DimensionLayout::Other,
+    }
+}
+
+/// Collects every directory up to `max_depth` levels below `input` (the root included)
+/// that contains a `region/` folder.
+pub fn discover_dimensions(input: &Path, max_depth: usize) -> Result<Vec<PathBuf>> {
+    if !input.is_dir() {
+        return Err(anyhow!("input must be directory"));
+    }
+    let mut tasks = Vec::new();
+    let walker = WalkDir::new(input)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !(e.file_type().is_dir()
+                    && DIMENSION_DATA_DIRS
+                        .iter()
+                        .any(|d| e.file_name() == std::ffi::OsStr::new(d)))
+        });
+    for entry in walker.filter_map(|e| e.ok()) {
+        if entry.file_type().is_dir() && is_dimension_dir(entry.path()) {
+            tasks.push(entry.path().to_path_buf());
+        }
+    }
+    warn_mixed_layouts(input, &tasks);
+    Ok(tasks)
+}
+
+fn warn_mixed_layouts(input: &Path, tasks: &[PathBuf]) {
+    let layouts: Vec<(&Path, DimensionLayout)> = tasks
+        .iter()
+        .map(|t| {
+            let rel = t.strip_prefix(input).unwrap_or(t);
+            (rel, dimension_layout(rel))
+        })
+        .collect();
+    let known = layouts
+        .iter()
+        .any(|(_, l)| *l == DimensionLayout::Vanilla || *l == DimensionLayout::Custom);
+    for (rel, layout) in &layouts {
+        if *layout == DimensionLayout::Other {
+            if known {
+                warn!(
+                    "Mixed dimension layouts: {} is neither DIM-1/DIM1 nor dimensions/<namespace>/<name>",
+                    rel.display()
+                );
+            } else {
+                info!("Non-standard dimension directory: {}", rel.display());
+            }
+        }
+    }
+    let custom_root = input.join("dimensions");
+    if custom_root.is_dir() && !layouts.iter().any(|(_, l)| *l == DimensionLayout::Custom) {
+        warn!(
+            "{} exists but no dimensions/<namespace>/<name>/region was found",
+            custom_root.display()
+        );
+    }
+}
+
+/// Dimension path relative to the world root with `/` separators; `.` for the root.
+pub fn dimension_label(rel: &Path) -> String {
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if parts.is_empty() {
+        ".".to_string()
+    } else {
+        parts.join("/")
+    }
+}
+
+pub struct DimensionSummary {
+    /// Relative to the world root, as printed by `dimension_label`
+    pub label: String,
+    pub path: PathBuf,
+    pub regions: u64,
+    pub chunks: u64,
+}
+
+/// Runs only dimension discovery and counts regions/chunks from the location tables,
+/// region files recognized by `naming`.
+pub fn summarize_dimensions(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+) -> Result<Vec<DimensionSummary>> {
+    let tasks = discover_dimensions(input, max_depth)?;
+    Ok(tasks
+        .into_iter()
+        .map(|dim| {
+            let dims = std::slice::from_ref(&dim);
+            DimensionSummary {
+                label: dimension_label(dim.strip_prefix(input).unwrap_or(&dim)),
+                regions: count_total_regions(&RegionFiles::Disk, dims, naming),
+                chunks: count_total_chunks(&RegionFiles::Disk, dims, naming),
+                path: dim,
+            }
+        })
+        .collect())
+}
+
+/// Groups of byte-identical region files (named after `naming`) across the
+/// `region`/`entities`/`poi` folders of every dimension, e.g. a dimension copied over
+/// another. Files holding only a header are ignored; groups and the paths in them are
+/// sorted.
+pub fn find_duplicate_regions(
+    input: &Path,
+    max_depth: usize,
+    naming: &RegionNaming,
+) -> Result<Vec<Vec<PathBuf>>> {
+    let mut files = Vec::new();
+    for dim in discover_dimensions(input, max_depth)? {
+        for sub in ["region", "entities", "poi"] {
+            for p in RegionFiles::Disk.files(&dim.join(sub))? {
+                if naming.matches(&p) && p.metadata().is_ok_and(|m| m.len() > 8192) {
+                    files.push(p);
+                }
+            }
+        }
+    }
+    let hashed = files
+        .into_par_iter()
+        .map(|p| {
+            let data = fs::read(&p)?;
+            Ok(((data.len(), xxhash_rust::xxh32::xxh32(&data, 0)), p))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut by_hash: HashMap<(usize, u32), Vec<PathBuf>> = HashMap::new();
+    for (key, p) in hashed {
+        by_hash.entry(key).or_default().push(p);
+    }
+    let mut groups = Vec::new();
+    for candidates in by_hash.into_values().filter(|g| g.len() > 1) {
+        // a 32-bit hash can collide; split each candidate group by actual content
+        let mut split: Vec<(Vec<u8>, Vec<PathBuf>)> = Vec::new();
+        for p in candidates {
+            let data = fs::read(&p)?;
+            match split.iter_mut().find(|(d, _)| *d == data) {
+                Some((_, paths)) => paths.push(p),
+                None => split.push((data, vec![p])),
+            }
+        }
+        groups.extend(split.into_iter().map(|(_, g)| g).filter(|g| g.len() > 1));
+    }
+    for g in &mut groups {
+        g.sort();
+    }
+    groups.sort();
+    Ok(groups)
 }
 
+/// Vanilla dimension names and the directory each one lives in.
+const DIMENSION_ALIASES: [(&str, &str); 3] = [
+    ("overworld", "."),
+    ("the_nether", "DIM-1"),
+    ("the_end", "DIM1"),
+];
+
+/// Whether a `--dim-threshold` name refers to the dimension labelled `label`: the full
+/// label (`DIM-1`, `world_nether/DIM-1`, `dimensions/ns/name`), its last component,
+/// a vanilla name (`overworld`, `the_nether`, `the_end`, optionally `minecraft:`) or a
+/// `namespace:name` id for `dimensions/<namespace>/<name>`.
+pub fn dimension_name_matches(name: &str, label: &str) -> bool {
+    if name == label {
+        return true;
+    }
+    let leaf = label.rsplit('/').next().unwrap_or(label);
+    if name == leaf && label != "." {
+        return true;
+    }
+    let bare = name.strip_prefix("minecraft:").unwrap_or(name);
+    if DIMENSION_ALIASES
+        .iter()
+        .any(|(alias, dir)| *alias == bare && (*dir == label || (*dir != "." && *dir == leaf)))
+    {
+        return true;
+    }
+    match name.split_once(':') {
+        Some((ns, id)) => label.ends_with(&format!("dimensions/{}/{}", ns, id)),
+        None => false,
+    }
+}
+
+/// Threshold (ticks) for a dimension: an override naming its full label wins over
+/// one matching by alias or last component; otherwise `default`.
+pub fn dimension_threshold(label: &str, overrides: &[(String, i64)], default: i64) -> i64 {
+    overrides
+        .iter()
+        .find(|(name, _)| name == label)
+        .or_else(|| {
+            overrides
+                .iter()
+                .find(|(name, _)| dimension_name_matches(name, label))
+        })
+        .map(|(_, ticks)| *ticks)
+        .unwrap_or(default)
+}
+
+/// Bytes of the files below `path`, junk (`regions::is_junk_file`) excluded.
 fn dir_size(path: &Path) -> u64 {
+    if regions::is_junk_file(path) {
+        return 0;
+    }
     let mut total = 0u64;
     if let Ok(md) = path.metadata() {
         if md.is_file() {
@@ -34,7 +322,7 @@ fn dir_size(path: &Path) -> u64 {
     total
 }
 
-fn fmt_bytes(mut n: u64) -> String {
+pub(crate) fn fmt_bytes(mut n: u64) -> String {
     const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
     let mut i = 0;
     let mut val = n as f64;
@@ -45,7 +333,194 @@ fn fmt_bytes(mut n: u64) -> String {
     }
     format!("{:.2} {}", val, UNITS[i])
 }
-fn parse_force_loaded(dimension: &Path) -> Vec<(i32, i32)> {
+/// Larger than any chunk that fits in-region (255 sectors), so vanilla chunks are read whole.
+pub const DEFAULT_CHUNK_MEMORY_LIMIT: u64 = 4 * 1024 * 1024;
+
+/// Ticket types honored by default: explicit `/forceload` chunks.
+pub const DEFAULT_TICKET_TYPES: [&str; 1] = ["forced"];
+
+/// Ticket types `RunOptions::with_safe_defaults` adds: `/forceload` chunks and the
+/// spawn ticket of versions that save it.
+pub const SAFE_TICKET_TYPES: [&str; 2] = ["forced", "start"];
+
+fn ticket_type_matches(ticket_type: &str, wanted: &[String]) -> bool {
+    let bare = ticket_type
+        .strip_prefix("minecraft:")
+        .unwrap_or(ticket_type);
+    wanted
+        .iter()
+        .any(|w| w.strip_prefix("minecraft:").unwrap_or(w) == bare)
+}
+
+/// The `Data` compound of the world's gzipped `level.dat`.
+fn level_data(world: &Path) -> Result<HashMap<String, fastnbt::Value>> {
+    let path = world.join("level.dat");
+    let data = fs::read(&path).map_err(|e| anyhow!("cannot read {}: {}", path.display(), e))?;
+    let mut out = Vec::new();
+    std::io::copy(&mut flate2::read::GzDecoder::new(&data[..]), &mut out)?;
+    let mut root = match fastnbt::from_bytes::<fastnbt::Value>(&out)? {
+        fastnbt::Value::Compound(m) => m,
+        _ => return Err(anyhow!("{} is not an NBT compound", path.display())),
+    };
+    match root.remove("Data") {
+        Some(fastnbt::Value::Compound(d)) => Ok(d),
+        _ => Err(anyhow!("{} has no Data compound", path.display())),
+    }
+}
+
+/// `Data.DataVersion` from the world's gzipped `level.dat`.
+pub fn level_data_version(world: &Path) -> Result<i32> {
+    match level_data(world)?.get("DataVersion") {
+        Some(fastnbt::Value::Int(v)) => Ok(*v),
+        _ => Err(anyhow!(
+            "{} has no Data.DataVersion",
+            world.join("level.dat").display()
+        )),
+    }
+}
+
+/// `Data.Time`, the world's game clock in ticks, from the world's gzipped `level.dat`.
+pub fn level_time(world: &Path) -> Result<i64> {
+    match level_data(world)?.get("Time").and_then(nbt::as_i64) {
+        Some(t) => Ok(t),
+        None => Err(anyhow!(
+            "{} has no Data.Time",
+            world.join("level.dat").display()
+        )),
+    }
+}
+
+/// Chunk radius kept loaded around the spawn before the `spawnChunkRadius` game rule
+/// (1.20.5) existed.
+pub const LEGACY_SPAWN_CHUNK_RADIUS: i32 = 11;
+
+/// The world spawn as recorded in `level.dat`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorldSpawn {
+    /// Dimension id holding the spawn, `minecraft:overworld` unless `level.dat` says
+    /// otherwise; matched against directories like `--dim-threshold` names
+    pub dimension: String,
+    /// Spawn chunk coordinates
+    pub chunk: (i32, i32),
+    /// The `spawnChunkRadius` game rule, `LEGACY_SPAWN_CHUNK_RADIUS` without one
+    pub radius: i32,
+}
+
+impl WorldSpawn {
+    /// Corners of the square of spawn chunks, for a `RangePattern`.
+    pub fn area(&self) -> (i32, i32, i32, i32) {
+        let (x, z) = self.chunk;
+        let r = self.radius.max(0);
+        (x - r, z - r, x + r, z + r)
+    }
+}
+
+/// Reads the spawn from `Data.SpawnX`/`SpawnZ`, or the `Data.spawn` compound
+/// (`pos` and `dimension`) of newer versions.
+pub fn level_spawn(world: &Path) -> Result<WorldSpawn> {
+    let data = level_data(world)?;
+    let int = |m: &HashMap<String, fastnbt::Value>, k: &str| match m.get(k) {
+        Some(fastnbt::Value::Int(v)) => Some(*v),
+        _ => None,
+    };
+    let (block, dimension) = match data.get("spawn") {
+        Some(fastnbt::Value::Compound(spawn)) => {
+            let pos = match spawn.get("pos") {
+                Some(fastnbt::Value::IntArray(p)) if p.len() == 3 => Some((p[0], p[2])),
+                _ => None,
+            };
+            let dimension = match spawn.get("dimension") {
+                Some(fastnbt::Value::String(d)) => d.clone(),
+                _ => "minecraft:overworld".to_string(),
+            };
+            (pos, dimension)
+        }
+        _ => (
+            int(&data, "SpawnX").zip(int(&data, "SpawnZ")),
+            "minecraft:overworld".to_string(),
+        ),
+    };
+    let (x, z) = block.ok_or_else(|| {
+        anyhow!(
+            "{} records no spawn position",
+            world.join("level.dat").display()
+        )
+    })?;
+    let radius = match data.get("GameRules") {
+        // game rules are stored as strings
+        Some(fastnbt::Value::Compound(rules)) => match rules.get("spawnChunkRadius") {
+            Some(fastnbt::Value::String(v)) => v.trim().parse().ok(),
+            Some(fastnbt::Value::Int(v)) => Some(*v),
+            _ => None,
+        },
+        _ => None,
+    };
+    Ok(WorldSpawn {
+        dimension,
+        chunk: (x >> 4, z >> 4),
+        radius: radius.unwrap_or(LEGACY_SPAWN_CHUNK_RADIUS),
+    })
+}
+
+/// Uncompressed NBT of the chunk at global chunk coordinates `(x, z)` in `dim`, the
+/// dimension directory relative to `input` (`.` for the overworld, as printed by
+/// `list-dimensions`), its region file named after `naming`. `None` when the region
+/// file or the chunk does not exist.
+pub fn get_chunk(
+    input: &Path,
+    dim: &str,
+    x: i32,
+    z: i32,
+    naming: &RegionNaming,
+) -> Result<Option<Vec<u8>>> {
+    let path = input
+        .join(dim)
+        .join("region")
+        .join(naming.file_name(x >> 5, z >> 5));
+    if !path.is_file() || !RegionFiles::Disk.is_valid_mca(&path) {
+        return Ok(None);
+    }
+    let mut reader = McaReader::open_named(path.to_string_lossy().as_ref(), naming)?;
+    let index = ((x & 31) + (z & 31) * 32) as usize;
+    match reader.get(index)? {
+        Some(mut entry) => Ok(Some(entry.all_data_uncompressed()?)),
+        None => Ok(None),
+    }
+}
+
+/// One chunk-load ticket of `data/chunks.dat`. Entries of the legacy `Forced` array
+/// become `forced` tickets without a level or countdown, flagged `legacy`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChunkTicket {
+    pub ticket_type: String,
+    pub level: Option<i32>,
+    /// Ticks until a temporary ticket expires; 0 or absent for permanent ones
+    pub ticks_left: Option<i64>,
+    pub x: i32,
+    pub z: i32,
+    pub legacy: bool,
+}
+
+impl ChunkTicket {
+    /// A temporary ticket that has not run out yet.
+    pub fn is_active(&self) -> bool {
+        self.ticks_left.is_some_and(|t| t > 0)
+    }
+}
+
+fn nbt_int(v: Option<&fastnbt::Value>) -> Option<i64> {
+    match v? {
+        fastnbt::Value::Byte(n) => Some(*n as i64),
+        fastnbt::Value::Short(n) => Some(*n as i64),
+        fastnbt::Value::Int(n) => Some(*n as i64),
+        fastnbt::Value::Long(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Every ticket in `dimension`'s `data/chunks.dat`; empty when the file is missing or
+/// unreadable. Tickets without a type or a two-element `chunk_pos` are skipped.
+pub fn parse_chunk_tickets(dimension: &Path) -> Vec<ChunkTicket> {
     let f = dimension.join("data").join("chunks.dat");
     if !f.is_file() {
         return Vec::new();
@@ -59,219 +534,2219 @@ fn parse_force_loaded(dimension: &Path) -> Vec<(i32, i32)> {
     if std::io::copy(&mut dec, &mut out).is_err() {
         return Vec::new();
     }
-    let v = match fastnbt::from_bytes::<fastnbt::Value>(&out) {
-        Ok(v) => v,
-        Err(_) => return Vec::new(),
+    let Ok(fastnbt::Value::Compound(m)) = fastnbt::from_bytes::<fastnbt::Value>(&out) else {
+        return Vec::new();
     };
-    match v {
-        fastnbt::Value::Compound(m) => {
-            let data_tag = match m.get("data") {
-                Some(v) => v,
-                None => return Vec::new(),
+    let Some(fastnbt::Value::Compound(dm)) = m.get("data") else {
+        return Vec::new();
+    };
+    let mut tickets = Vec::new();
+    // Legacy Forge format: LongArray("Forced"), treat as pairs
+    if let Some(fastnbt::Value::LongArray(arr)) = dm.get("Forced") {
+        let mut it = arr.iter();
+        while let (Some(a), Some(b)) = (it.next(), it.next()) {
+            tickets.push(ChunkTicket {
+                ticket_type: "forced".to_string(),
+                level: None,
+                ticks_left: None,
+                x: *a as i32,
+                z: *b as i32,
+                legacy: true,
+            });
+        }
+    }
+    // Modern tickets list
+    if let Some(fastnbt::Value::List(list)) = dm.get("tickets") {
+        for t in list {
+            let fastnbt::Value::Compound(tm) = t else {
+                continue;
             };
-            let mut coords = Vec::new();
-            if let fastnbt::Value::Compound(dm) = data_tag {
-                // Legacy Forge format: LongArray("Forced"), treat as pairs
-                if let Some(fastnbt::Value::LongArray(arr)) = dm.get("Forced") {
-                    let mut it = arr.iter();
-                    while let (Some(a), Some(b)) = (it.next(), it.next()) {
-                        coords.push((*a as i32, *b as i32));
-                    }
+            let (Some(fastnbt::Value::String(ty)), Some(fastnbt::Value::IntArray(pos))) =
+                (tm.get("type"), tm.get("chunk_pos"))
+            else {
+                continue;
+            };
+            if pos.len() != 2 {
+                continue;
+            }
+            tickets.push(ChunkTicket {
+                ticket_type: ty.clone(),
+                level: nbt_int(tm.get("level")).map(|l| l as i32),
+                ticks_left: nbt_int(tm.get("ticks_left")),
+                x: pos[0],
+                z: pos[1],
+                legacy: false,
+            });
+        }
+    }
+    tickets
+}
+
+/// The chunks of `dimension` pinned by the legacy `Forced` array or by a ticket whose
+/// type is listed in `ticket_types` (e.g. `forced`, `start`, `portal`; the
+/// `minecraft:` prefix is optional), plus with `keep_active` those of any temporary
+/// ticket still counting down.
+fn parse_force_loaded(
+    dimension: &Path,
+    ticket_types: &[String],
+    keep_active: bool,
+) -> Vec<(i32, i32)> {
+    parse_chunk_tickets(dimension)
+        .into_iter()
+        .filter(|t| {
+            t.legacy
+                || ticket_type_matches(&t.ticket_type, ticket_types)
+                || keep_active && t.is_active()
+        })
+        .map(|t| (t.x, t.z))
+        .collect()
+}
+
+/// What `parse_force_loaded` pins in `dimension`, sorted and without duplicates.
+pub fn export_forced(
+    dimension: &Path,
+    ticket_types: &[String],
+    keep_active: bool,
+) -> Vec<(i32, i32)> {
+    let mut chunks = parse_force_loaded(dimension, ticket_types, keep_active);
+    chunks.sort_unstable();
+    chunks.dedup();
+    chunks
+}
+
+/// `export_forced` for every dimension of `input`, labelled by `dimension_label`.
+pub fn forced_by_dimension(
+    input: &Path,
+    max_depth: usize,
+    ticket_types: &[String],
+    keep_active: bool,
+) -> Result<coords::DimensionChunks> {
+    Ok(discover_dimensions(input, max_depth)?
+        .iter()
+        .map(|dim| {
+            (
+                dimension_label(dim.strip_prefix(input).unwrap_or(dim)),
+                export_forced(dim, ticket_types, keep_active),
+            )
+        })
+        .collect())
+}
+
+/// Copies everything under `input` except the processed region/entities/poi folders,
+/// the output directory itself and any entry whose name is in `ignore`.
+fn copy_world_files(input: &Path, out: &Path, tasks: &[PathBuf], ignore: &[String]) -> Result<()> {
+    let processed: Vec<PathBuf> = tasks
+        .iter()
+        .flat_map(|d| ["region", "entities", "poi"].map(|n| d.join(n)))
+        .collect();
+    let walker = WalkDir::new(input).into_iter().filter_entry(|e| {
+        let p = e.path();
+        e.depth() == 0
+            || !(p == out
+                || processed.iter().any(|d| d == p)
+                || ignore
+                    .iter()
+                    .any(|n| e.file_name() == std::ffi::OsStr::new(n)))
+    });
+    let mut copied = 0u64;
+    for entry in walker.filter_map(|e| e.ok()) {
+        let rel = entry.path().strip_prefix(input).unwrap_or(entry.path());
+        if rel.as_os_str().is_empty() {
+            continue;
+        }
+        let target = out.join(rel);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(entry.path(), &target)?;
+            copied += 1;
+        }
+    }
+    info!("Copied {} world files into {}", copied, out.display());
+    Ok(())
+}
+
+/// Deterministic per-coordinate sample so repeated audits check the same chunks.
+fn audit_sampled(global_x: i32, global_z: i32, percent: f64) -> bool {
+    let mut key = [0u8; 8];
+    key[..4].copy_from_slice(&global_x.to_le_bytes());
+    key[4..].copy_from_slice(&global_z.to_le_bytes());
+    (xxhash_rust::xxh32::xxh32(&key, 0) % 10_000) < (percent * 100.0) as u32
+}
+
+/// Resolves `path` through its nearest existing ancestor, since the output may not exist yet.
+fn resolve_path(path: &Path) -> Result<PathBuf> {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+    let mut existing = absolute.as_path();
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing.canonicalize()?;
+    for name in rest.iter().rev() {
+        resolved.push(name);
+    }
+    Ok(resolved)
+}
+
+/// Fails unless a file can be created in `dir`, or in its nearest existing ancestor
+/// when `dir` is yet to be created, so a read-only mount or missing permission is
+/// reported before any counting or processing. `what` names the directory in the error.
+pub fn probe_writable(dir: &Path, what: &str) -> Result<()> {
+    let existing = dir
+        .ancestors()
+        .find(|d| d.is_dir())
+        .ok_or_else(|| anyhow!("{} {} has no existing parent", what, dir.display()))?;
+    let probe = existing.join(format!(".rust-thanos-probe-{}", uuid::Uuid::new_v4()));
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+        .map_err(|e| anyhow!("{} {} is not writable: {}", what, existing.display(), e))?;
+    fs::remove_file(&probe)
+        .map_err(|e| anyhow!("cannot remove write probe {}: {}", probe.display(), e))?;
+    Ok(())
+}
+
+/// Where the staged output `out` of an in-place run does not mirror the processed
+/// dimensions `tasks` of `input`: each `region`, `entities` or `poi` folder of a
+/// processed dimension missing from `out`, and each dimension found in `out` that was
+/// not processed, as `/`-separated relative paths. Empty when the copy-back is safe.
+pub fn output_divergence(
+    input: &Path,
+    out: &Path,
+    tasks: &[PathBuf],
+    max_depth: usize,
+) -> Result<Vec<String>> {
+    let rel_name = |rel: &Path| {
+        rel.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    let processed: HashSet<&Path> = tasks
+        .iter()
+        .map(|dim| dim.strip_prefix(input).unwrap_or(dim))
+        .collect();
+    let mut diverged = Vec::new();
+    for rel in &processed {
+        for name in ["region", "entities", "poi"] {
+            if input.join(rel).join(name).is_dir() && !out.join(rel).join(name).is_dir() {
+                diverged.push(rel_name(&rel.join(name)));
+            }
+        }
+    }
+    for dim in discover_dimensions(out, max_depth)? {
+        let rel = dim.strip_prefix(out).unwrap_or(&dim);
+        if !processed.contains(rel) {
+            diverged.push(rel_name(rel));
+        }
+    }
+    diverged.sort();
+    Ok(diverged)
+}
+
+/// Rejects an output that is, contains, or sits inside the input world.
+pub fn check_disjoint(input: &Path, output: &Path) -> Result<()> {
+    let input = resolve_path(input)?;
+    let output = resolve_path(output)?;
+    if input == output {
+        return Err(anyhow!(
+            "output must not be the same directory as input: {}",
+            output.display()
+        ));
+    }
+    if output.starts_with(&input) {
+        return Err(anyhow!(
+            "output must not be inside input: {} is within {}",
+            output.display(),
+            input.display()
+        ));
+    }
+    if input.starts_with(&output) {
+        return Err(anyhow!(
+            "input must not be inside output: {} is within {}",
+            input.display(),
+            output.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Region containers of other formats: Linear (`.linear`, some Paper forks) and the
+/// pre-1.2 McRegion format (`.mcr`).
+const UNSUPPORTED_REGION_EXTENSIONS: [&str; 2] = ["linear", "mcr"];
+
+/// Reports region files this tool cannot read; they are skipped, not processed.
+fn warn_unsupported_regions(opts: &RunOptions, dim_label: &str, files: &[PathBuf]) {
+    for ext in UNSUPPORTED_REGION_EXTENSIONS {
+        let count = files
+            .iter()
+            .filter(|p| p.extension().and_then(|s| s.to_str()) == Some(ext))
+            .count();
+        if count > 0 {
+            warn!(
+                "[{}] Skipping {} .{} region files: only the Anvil .mca format is supported",
+                dim_label, count, ext
+            );
+            report!(
+                opts,
+                "警告: [{}] 发现 {} 个不支持的 .{} 区域文件，已跳过（仅支持 .mca），这些区块未被处理",
+                dim_label, count, ext
+            );
+        }
+    }
+}
+
+/// Regions named after `naming` and the `.mcc` files of their externally stored chunks.
+fn is_region_file(p: &Path, naming: &RegionNaming) -> bool {
+    (naming.matches(p) || p.extension().and_then(|s| s.to_str()) == Some("mcc"))
+        && !regions::is_junk_file(p)
+}
+
+/// Copies the `.mcc` file of an external chunk into `dst_dir`; inline chunks are a no-op.
+fn copy_external(entry: &mut McaEntry, dst_dir: &Path, buffer_kb: Option<usize>) -> Result<()> {
+    if let Some(src) = entry.external_path()? {
+        if src.is_file() {
+            regions::copy_file(&src, &dst_dir.join(src.file_name().unwrap()), buffer_kb)?;
+        }
+    }
+    Ok(())
+}
+
+/// Explains an empty `discover_dimensions` result: what `input` holds and, where it
+/// can tell, which path was probably meant.
+fn no_dimensions_report(input: &Path, max_depth: usize) -> String {
+    const SHOWN: usize = 10;
+    let mut names: Vec<(String, bool)> = fs::read_dir(input)
+        .map(|rd| {
+            rd.flatten()
+                .map(|e| {
+                    (
+                        e.file_name().to_string_lossy().to_string(),
+                        e.path().is_dir(),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    let mut report = format!(
+        "no dimension found in {}: no folder up to {} levels deep contains region/",
+        input.display(),
+        max_depth
+    );
+    let listed: Vec<String> = names
+        .iter()
+        .take(SHOWN)
+        .map(|(n, dir)| if *dir { format!("{}/", n) } else { n.clone() })
+        .collect();
+    if listed.is_empty() {
+        report.push_str("\n  the directory is empty");
+    } else {
+        report.push_str(&format!("\n  it contains: {}", listed.join(", ")));
+        if names.len() > SHOWN {
+            report.push_str(&format!(" and {} more", names.len() - SHOWN));
+        }
+    }
+    for (name, _) in names.iter().filter(|(_, dir)| *dir) {
+        if input.join(name).join("level.dat").is_file() {
+            report.push_str(&format!(
+                "\n  {} holds a level.dat, pass it as WORLD_DIR instead",
+                input.join(name).display()
+            ));
+        }
+    }
+    if input.join("db").is_dir() {
+        report.push_str("\n  db/ suggests a Bedrock Edition world, which is not supported");
+    } else if input.join("level.dat").is_file() {
+        report
+            .push_str("\n  level.dat is here but no region/, the world may never have been saved");
+    } else if WalkDir::new(input)
+        .min_depth(max_depth + 1)
+        .into_iter()
+        .flatten()
+        .any(|e| e.file_type().is_dir() && e.file_name() == "region")
+    {
+        report.push_str("\n  a region/ folder exists deeper, raise --max-dimension-depth");
+    }
+    report
+}
+
+/// Region and entities/poi chunks are saved separately; timestamps further apart than
+/// this hint at a save that was interrupted between the two files.
+const TIMESTAMP_DRIFT_WARN_SECS: u32 = 24 * 3600;
+
+/// Warns when an auxiliary chunk's timestamp drifts from its region chunk's by more
+/// than `TIMESTAMP_DRIFT_WARN_SECS`; zero (never written) timestamps are ignored.
+fn check_timestamp_drift(
+    dim_label: &str,
+    name: &str,
+    kind: &str,
+    chunk: &McaEntry,
+    aux: &McaEntry,
+) {
+    let (a, b) = (chunk.modified_time(), aux.modified_time());
+    if a != 0 && b != 0 && a.abs_diff(b) > TIMESTAMP_DRIFT_WARN_SECS {
+        warn!(
+            "[{}] Chunk ({}, {}) in {}: region timestamp {} and {} timestamp {} are {}s apart",
+            dim_label,
+            chunk.global_x(),
+            chunk.global_z(),
+            name,
+            a,
+            kind,
+            b,
+            a.abs_diff(b)
+        );
+    }
+}
+
+/// Checks the projected outcome of a run against `--max-removed-percent` and
+/// `--min-kept-chunks`, before anything is committed.
+fn guard_violation(opts: &RunOptions, kept: u64, removed: u64) -> Option<String> {
+    if let Some(limit) = opts.max_removed_percent {
+        let pct = removed as f64 * 100.0 / (kept + removed).max(1) as f64;
+        if pct > limit {
+            return Some(format!(
+                "{:.2}% of chunks would be removed, above --max-removed-percent {}",
+                pct, limit
+            ));
+        }
+    }
+    if kept < opts.min_kept_chunks {
+        return Some(format!(
+            "only {} chunks would be kept, below --min-kept-chunks {}",
+            kept, opts.min_kept_chunks
+        ));
+    }
+    None
+}
+
+/// A chunk or region that could not be read, evaluated or written: logged and
+/// skipped, or under `--strict` the error the whole run fails with.
+fn recoverable(strict: bool, msg: String) -> Result<()> {
+    if strict {
+        return Err(anyhow!(msg));
+    }
+    warn!("{}", msg);
+    Ok(())
+}
+
+/// Flushes finished output files to disk (`fsync`), so a crash cannot lose them.
+fn sync_files(paths: &[PathBuf]) -> Result<()> {
+    for p in paths {
+        fs::OpenOptions::new().write(true).open(p)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Counts `add_progress_bar` calls, for `progress_bars_created`.
+static PROGRESS_BARS: AtomicU64 = AtomicU64::new(0);
+
+/// Progress bars created by runs in this process, hidden ones included.
+#[doc(hidden)]
+#[allow(dead_code)]
+pub fn progress_bars_created() -> u64 {
+    PROGRESS_BARS.load(Ordering::Relaxed)
+}
+
+fn add_progress_bar(mp: &MultiProgress, len: u64) -> ProgressBar {
+    PROGRESS_BARS.fetch_add(1, Ordering::Relaxed);
+    mp.add(ProgressBar::new(len))
+}
+
+/// Entity hotspots listed by `entity_hotspot_threshold`, worst first.
+const MAX_LISTED_HOTSPOTS: usize = 20;
+
+/// Regions copied whole under `keep_forced_regions_whole`.
+fn forced_region_set(opts: &RunOptions, forced: &[(i32, i32)]) -> HashSet<(i32, i32)> {
+    if opts.keep_forced_regions_whole {
+        forced.iter().map(|(x, z)| (x >> 5, z >> 5)).collect()
+    } else {
+        HashSet::new()
+    }
+}
+
+/// Why region `(x, z)` is copied unchanged rather than filtered, if it is.
+fn verbatim_reason(
+    opts: &RunOptions,
+    forced_regions: &HashSet<(i32, i32)>,
+    x: i32,
+    z: i32,
+) -> Option<&'static str> {
+    if opts
+        .region_bounds
+        .is_some_and(|b| !region_in_bounds(x, z, b))
+    {
+        Some("is outside --region-bounds")
+    } else if forced_regions.contains(&(x, z)) {
+        Some("holds a force-loaded chunk")
+    } else {
+        None
+    }
+}
+
+/// Force-loaded chunks of `tasks` (see `parse_force_loaded`) that exist in the input
+/// but not in the output regions below `out`, as `(dimension label, x, z)`. Chunks
+/// pinned by a ticket but never generated are not expected in the output.
+fn dropped_forced_chunks(
+    regions: &RegionFiles,
+    input: &Path,
+    out: &Path,
+    tasks: &[PathBuf],
+    opts: &RunOptions,
+) -> Result<Vec<(String, i32, i32)>> {
+    let present = |reader: Result<McaReader>| -> Result<HashSet<(i32, i32)>> {
+        Ok(reader?
+            .entries()?
+            .iter()
+            .map(|e| (e.global_x(), e.global_z()))
+            .collect())
+    };
+    let mut dropped = Vec::new();
+    for dim in tasks {
+        let rel = dim.strip_prefix(input).unwrap_or(dim);
+        let dim_label = dimension_label(rel);
+        let mut by_region: BTreeMap<(i32, i32), Vec<(i32, i32)>> = BTreeMap::new();
+        for (x, z) in parse_force_loaded(dim, &opts.keep_ticket_types, opts.keep_active_tickets) {
+            by_region.entry((x >> 5, z >> 5)).or_default().push((x, z));
+        }
+        for ((rx, rz), chunks) in by_region {
+            let name = opts.region_naming.file_name(rx, rz);
+            let src = dim.join("region").join(&name);
+            if !regions.is_file(&src) {
+                continue;
+            }
+            let before = present(regions.open_named(&src, &opts.region_naming))?;
+            let dst = out.join(rel).join("region").join(&name);
+            let after = if dst.is_file() {
+                present(
+                    McaReader::open_named(dst.to_string_lossy().as_ref(), &opts.region_naming)
+                        .map_err(Into::into),
+                )
+                .map_err(|e| anyhow!("cannot verify {}: {}", dst.display(), e))?
+            } else {
+                HashSet::new()
+            };
+            for c in chunks {
+                if before.contains(&c) && !after.contains(&c) {
+                    dropped.push((dim_label.clone(), c.0, c.1));
                 }
-                // Modern tickets list
-                if let Some(fastnbt::Value::List(list)) = dm.get("tickets") {
-                    for t in list {
-                        if let fastnbt::Value::Compound(tm) = t {
-                            if let Some(fastnbt::Value::String(s)) = tm.get("type") {
-                                if s == "minecraft:forced" {
-                                    if let Some(fastnbt::Value::IntArray(pos)) = tm.get("chunk_pos")
-                                    {
-                                        if pos.len() == 2 {
-                                            coords.push((pos[0], pos[1]));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+            }
+        }
+    }
+    Ok(dropped)
+}
+
+/// Prints, for `--explain-chunk`, how every dimension of `tasks` holding chunk
+/// `(x, z)` decides on it: the values the rules read and each pattern's vote.
+fn explain_chunk(
+    regions: &RegionFiles,
+    input: &Path,
+    tasks: &[PathBuf],
+    opts: &RunOptions,
+    dimension_rules: &DimensionRules,
+    linked: &HashMap<PathBuf, HashSet<(i32, i32)>>,
+    (x, z): (i32, i32),
+) -> Result<()> {
+    let (rx, rz) = (x >> 5, z >> 5);
+    let name = opts.region_naming.file_name(rx, rz);
+    let index = ((x & 31) + (z & 31) * 32) as usize;
+    for dim in tasks {
+        let dim_label = dimension_label(dim.strip_prefix(input).unwrap_or(dim));
+        let path = dim.join("region").join(&name);
+        let entry = if regions.is_file(&path) {
+            regions.open_named(&path, &opts.region_naming)?.get(index)?
+        } else {
+            None
+        };
+        let Some(mut entry) = entry else {
+            report!(
+                opts,
+                "[{}] 区块 ({}, {}) 不存在（{} 槽位 {}）",
+                dim_label,
+                x,
+                z,
+                name,
+                index
+            );
+            continue;
+        };
+        let entry = &mut entry;
+        report!(
+            opts,
+            "[{}] 区块 ({}, {}) · {} 槽位 {}",
+            dim_label,
+            x,
+            z,
+            name,
+            index
+        );
+        let (method, size) = match (entry.read_header(), entry.declared_length()) {
+            (Ok((_, cm, _)), Ok(len)) => (cm.name().to_string(), fmt_bytes(len)),
+            (Err(e), _) | (_, Err(e)) => {
+                report!(opts, "  区块头无法读取: {}", e);
+                continue;
+            }
+        };
+        let unknown = || "未知".to_string();
+        let inhabited = match inhabited_time(entry) {
+            Ok(Some(t)) => format!("{} 刻（{:.1} 秒）", t, t as f64 / 20.0),
+            Ok(None) => unknown(),
+            Err(e) => format!("读取失败: {}", e),
+        };
+        let (status, data_version, last_update) = match entry.exceeds_memory_limit() {
+            Ok(false) => match entry.all_data_uncompressed() {
+                Ok(de) => (
+                    find_status(&de).unwrap_or_else(unknown),
+                    find_data_version_fast(&de).map_or_else(unknown, |v| v.to_string()),
+                    find_last_update(&de).map_or_else(unknown, |t| t.to_string()),
+                ),
+                Err(e) => {
+                    let failed = format!("解压失败: {}", e);
+                    (failed.clone(), failed.clone(), failed)
+                }
+            },
+            _ => (unknown(), unknown(), unknown()),
+        };
+        report!(
+            opts,
+            "  压缩: {} · 大小: {} · InhabitedTime: {} · Status: {} · DataVersion: {} · LastUpdate: {}",
+            method,
+            size,
+            inhabited,
+            status,
+            data_version,
+            last_update
+        );
+        let forced = parse_force_loaded(dim, &opts.keep_ticket_types, opts.keep_active_tickets);
+        if let Some(reason) = verbatim_reason(opts, &forced_region_set(opts, &forced), rx, rz) {
+            report!(opts, "  结果: 保留（整个区域原样复制: region {}）", reason);
+            continue;
+        }
+        let mut rules = dimension_rules(&dim_label, forced)?;
+        if let Some(coords) = linked.get(dim) {
+            rules.pins.push(named(
+                "--link-dimensions/--keep-named-entities",
+                ListPattern::new(coords.iter().copied().collect()),
+            ));
+        }
+        for vote in rules.trace(entry) {
+            let stage = match vote.stage {
+                RuleStage::Pin => "始终保留",
+                RuleStage::Veto => "删除",
+                RuleStage::Keep => "保留",
+            };
+            let result = match vote.result {
+                Ok(true) => "符合".to_string(),
+                Ok(false) => "不符合".to_string(),
+                Err(e) => format!("出错: {}", e),
+            };
+            report!(opts, "  [{}] {} → {}", stage, vote.name, result);
+        }
+        let decided = rules.try_evaluate(entry, &mut |_| {});
+        let keep = opts.on_error.decide(decided);
+        report!(
+            opts,
+            "  结果: {}{}",
+            if keep { "保留" } else { "删除" },
+            if decided.is_none() {
+                "（所有保留规则都读取失败，按 --on-error 处理）"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+/// What `dimension_rules` builds for `dim`, spelled out for `--explain-rules`.
+#[allow(clippy::too_many_arguments)]
+fn rule_summary(
+    dim: &Path,
+    dim_label: &str,
+    opts: &RunOptions,
+    inhabited_threshold: i64,
+    keep_list: Option<&coords::KeepList>,
+    polygons: usize,
+    spawn: Option<&WorldSpawn>,
+    linked: Option<&HashSet<(i32, i32)>>,
+) -> Result<RuleSummary> {
+    let unit = opts.coord_unit;
+    let forced = parse_force_loaded(dim, &opts.keep_ticket_types, opts.keep_active_tickets);
+    let mut sources = vec![
+        KeepSource::chunks("强加载区块", &forced),
+        KeepSource::new(
+            "--keep-chunk",
+            coords::chunk_ranges(unit, &opts.keep_chunks, &[])?,
+        ),
+        KeepSource::new(
+            "--keep-range",
+            coords::chunk_ranges(unit, &[], &opts.keep_ranges)?,
+        ),
+    ];
+    if let Some(list) = keep_list {
+        let mut boxes = coords::chunk_ranges(unit, &list.points, &list.ranges)?;
+        for (_, chunks) in list
+            .dimension_chunks
+            .iter()
+            .filter(|(name, _)| dimension_name_matches(name, dim_label))
+        {
+            boxes.extend(chunks.iter().map(|&(x, z)| (x, z, x, z)));
+        }
+        sources.push(KeepSource::new("--keep-list", boxes));
+    }
+    if let Some(spawn) = spawn.filter(|s| dimension_name_matches(&s.dimension, dim_label)) {
+        sources.push(KeepSource::new("--keep-spawn-chunks", vec![spawn.area()]));
+    }
+    if let Some(linked) = linked {
+        let mut chunks: Vec<_> = linked.iter().copied().collect();
+        chunks.sort();
+        sources.push(KeepSource::chunks("关联维度与命名实体", &chunks));
+    }
+    let mut vetoes = Vec::new();
+    if let Some(ref status) = opts.remove_below_status {
+        vetoes.push(format!("--remove-below-status {}", status));
+    }
+    if !opts.keep_only_biomes.is_empty() {
+        vetoes.push(format!(
+            "--keep-only-biome（主要生物群系不是 {}）",
+            opts.keep_only_biomes.join("、")
+        ));
+    }
+    if opts.keep_modified_only {
+        vetoes.push("--keep-modified-only（从未改动）".to_string());
+    }
+    if opts.remove_empty_chunks {
+        vetoes.push("--remove-empty-chunks（数据为空）".to_string());
+    }
+    let mut keeps = Vec::new();
+    if opts.chunk_filter_script.is_some() {
+        keeps.push("--chunk-filter-script 返回 true".to_string());
+    } else if !opts.no_inhabited {
+        let threshold = dimension_threshold(dim_label, &opts.dim_thresholds, inhabited_threshold);
+        let mut rule = format!("InhabitedTime ≥ {} 刻", threshold);
+        if opts.keep_zero_inhabited {
+            rule.push_str("或恰为 0");
+        }
+        if opts.conservative_cull {
+            rule.push_str("或尚未完全生成");
+        }
+        keeps.push(rule);
+        if !opts.remove_unknown {
+            keeps.push("无法读取 InhabitedTime".to_string());
+        }
+    }
+    if opts.keep_current_dataversion {
+        keeps.push("--keep-current-dataversion".to_string());
+    }
+    if let Some(within) = opts.last_update_within {
+        keeps.push(format!("--last-update-within {}", within));
+    }
+    if let Some(percent) = opts.sample_keep {
+        keeps.push(format!(
+            "--sample-keep {}%（种子 {}）",
+            percent, opts.sample_seed
+        ));
+    }
+    Ok(RuleSummary {
+        label: dim_label.to_string(),
+        sources,
+        polygons,
+        vetoes,
+        keeps,
+    })
+}
+
+type DimensionRules<'a> = dyn Fn(&str, Vec<(i32, i32)>) -> Result<KeepRules> + Sync + 'a;
+
+/// The keep decision for every chunk `dim` would process, as
+/// `on_chunk(region, (x, z), kept)`; chunks of verbatim regions are all kept and
+/// `linked` coordinates are pinned as in the processing pass. Unreadable regions and
+/// chunks are skipped; the processing pass reports them.
+fn scan_keeps(
+    regions: &RegionFiles,
+    dim: &Path,
+    dim_label: &str,
+    opts: &RunOptions,
+    dimension_rules: &DimensionRules,
+    linked: Option<&HashSet<(i32, i32)>>,
+    mut on_chunk: impl FnMut(&Path, (i32, i32), bool),
+) -> Result<()> {
+    let forced = parse_force_loaded(dim, &opts.keep_ticket_types, opts.keep_active_tickets);
+    let forced_regions = forced_region_set(opts, &forced);
+    let rules = dimension_rules(dim_label, forced)?;
+    for rf in regions.files(&dim.join("region"))? {
+        if !opts.region_naming.matches(&rf) || !regions.is_valid_mca(&rf) {
+            continue;
+        }
+        let Ok(reader) = regions.open_named(&rf, &opts.region_naming) else {
+            continue;
+        };
+        let mut reader = reader.with_memory_limit(opts.chunk_memory_limit);
+        let whole =
+            verbatim_reason(opts, &forced_regions, reader.x_pos(), reader.z_pos()).is_some();
+        for mut entry in reader.entries().unwrap_or_default() {
+            let coords = (entry.global_x(), entry.global_z());
+            let kept = whole
+                || linked.is_some_and(|l| l.contains(&coords))
+                || opts
+                    .on_error
+                    .decide(rules.try_evaluate(&mut entry, &mut |_| {}));
+            on_chunk(&rf, coords, kept);
+        }
+    }
+    Ok(())
+}
+
+/// Chunk coordinates `dim` keeps by its own rules, chunks of verbatim regions included.
+fn own_keeps(
+    regions: &RegionFiles,
+    dim: &Path,
+    dim_label: &str,
+    opts: &RunOptions,
+    dimension_rules: &DimensionRules,
+) -> Result<HashSet<(i32, i32)>> {
+    let mut kept = HashSet::new();
+    scan_keeps(
+        regions,
+        dim,
+        dim_label,
+        opts,
+        dimension_rules,
+        None,
+        |_, coords, keep| {
+            if keep {
+                kept.insert(coords);
+            }
+        },
+    )?;
+    Ok(kept)
+}
+
+/// Regions of `dim` holding chunks none of which would be kept, by path, with their
+/// chunk count; see `RunOptions::report_empty_regions`.
+fn emptied_regions(
+    regions: &RegionFiles,
+    dim: &Path,
+    dim_label: &str,
+    opts: &RunOptions,
+    dimension_rules: &DimensionRules,
+    linked: Option<&HashSet<(i32, i32)>>,
+) -> Result<Vec<(PathBuf, u64)>> {
+    // region -> (kept, total)
+    let mut tally: BTreeMap<PathBuf, (u64, u64)> = BTreeMap::new();
+    scan_keeps(
+        regions,
+        dim,
+        dim_label,
+        opts,
+        dimension_rules,
+        linked,
+        |rf, _, keep| {
+            let t = tally.entry(rf.to_path_buf()).or_default();
+            t.0 += keep as u64;
+            t.1 += 1;
+        },
+    )?;
+    Ok(tally
+        .into_iter()
+        .filter(|(_, (kept, _))| *kept == 0)
+        .map(|(rf, (_, total))| (rf, total))
+        .collect())
+}
+
+/// First pass of `linked_dimensions`: every linked dimension is scanned with its own
+/// rules, and each one maps to the union of the coordinates kept across its groups.
+/// Dimensions in no group are absent. Empty without links, so nothing extra is read.
+fn linked_dimension_keeps(
+    regions: &RegionFiles,
+    input: &Path,
+    tasks: &[PathBuf],
+    opts: &RunOptions,
+    dimension_rules: &DimensionRules,
+) -> Result<HashMap<PathBuf, HashSet<(i32, i32)>>> {
+    let mut linked = HashMap::new();
+    if opts.linked_dimensions.is_empty() {
+        return Ok(linked);
+    }
+    let labels: Vec<String> = tasks
+        .iter()
+        .map(|t| dimension_label(t.strip_prefix(input).unwrap_or(t)))
+        .collect();
+    let groups: Vec<Vec<usize>> = opts
+        .linked_dimensions
+        .iter()
+        .map(|group| {
+            for name in group {
+                if !labels.iter().any(|l| dimension_name_matches(name, l)) {
+                    warn!("--link-dimensions {} matches no discovered dimension", name);
                 }
             }
-            coords
+            (0..tasks.len())
+                .filter(|&i| group.iter().any(|n| dimension_name_matches(n, &labels[i])))
+                .collect()
+        })
+        .collect();
+    let mut members: Vec<usize> = groups.iter().flatten().copied().collect();
+    members.sort_unstable();
+    members.dedup();
+    let own: HashMap<usize, HashSet<(i32, i32)>> = members
+        .into_par_iter()
+        .map(|i| {
+            Ok((
+                i,
+                own_keeps(regions, &tasks[i], &labels[i], opts, dimension_rules)?,
+            ))
+        })
+        .collect::<Result<_>>()?;
+    for group in &groups {
+        let union: HashSet<(i32, i32)> =
+            group.iter().flat_map(|i| own[i].iter().copied()).collect();
+        for &i in group {
+            linked
+                .entry(tasks[i].clone())
+                .or_insert_with(HashSet::new)
+                .extend(union.iter().copied());
         }
-        _ => Vec::new(),
     }
+    for (dim, coords) in &linked {
+        let i = tasks.iter().position(|t| t == dim).unwrap();
+        info!(
+            "[{}] Linked dimensions keep {} chunk coordinates ({} by its own rules)",
+            labels[i],
+            coords.len(),
+            own[&i].len()
+        );
+    }
+    Ok(linked)
 }
 
-fn is_valid_mca(path: &Path) -> bool {
-    match path.metadata() {
-        Ok(meta) => {
-            let ok = meta.len() >= 8192;
-            if !ok {
+/// Coordinates of the `entities/` chunks of `dim` holding a named entity
+/// (`entities::has_named_entities`); see `RunOptions::keep_named_entities`.
+/// Unreadable regions and chunks are warned about and skipped.
+fn named_entity_keeps(
+    regions: &RegionFiles,
+    dim: &Path,
+    dim_label: &str,
+    opts: &RunOptions,
+) -> Result<HashSet<(i32, i32)>> {
+    let mut named = HashSet::new();
+    for ef in regions.files(&dim.join("entities"))? {
+        if !opts.region_naming.matches(&ef) || !regions.is_valid_mca(&ef) {
+            continue;
+        }
+        let reader = match regions.open_named(&ef, &opts.region_naming) {
+            Ok(r) => r,
+            Err(e) => {
                 warn!(
-                    "Skipping tiny MCA file: {} ({} bytes)",
-                    path.display(),
-                    meta.len()
+                    "[{}] Cannot scan {} for named entities: {}",
+                    dim_label,
+                    ef.display(),
+                    e
                 );
+                continue;
+            }
+        };
+        let mut reader = reader.with_memory_limit(opts.chunk_memory_limit);
+        for mut entry in reader.entries().unwrap_or_default() {
+            let data: Result<Vec<u8>> = match entry.exceeds_memory_limit() {
+                Ok(false) => entry.all_data_uncompressed().map_err(Into::into),
+                Ok(true) => Err(anyhow!("chunk exceeds the memory limit")),
+                Err(e) => Err(e.into()),
+            };
+            match data.map(|d| entities::has_named_entities(&d)) {
+                Ok(Some(true)) => {
+                    named.insert((entry.global_x(), entry.global_z()));
+                }
+                Ok(_) => {}
+                Err(e) => warn!(
+                    "[{}] Cannot scan entities chunk {} of {} for named entities: {}",
+                    dim_label,
+                    entry.region_index(),
+                    ef.display(),
+                    e
+                ),
             }
-            ok
-        }
-        Err(e) => {
-            warn!("Failed to stat MCA file {}: {}", path.display(), e);
-            false
         }
     }
+    Ok(named)
+}
+
+/// `bounds` is `(min_x, min_z, max_x, max_z)` in region coordinates, inclusive.
+fn region_in_bounds(x: i32, z: i32, bounds: (i32, i32, i32, i32)) -> bool {
+    let (min_x, min_z, max_x, max_z) = bounds;
+    x >= min_x && x <= max_x && z >= min_z && z <= max_z
 }
 
-fn count_total_regions(dims: &[PathBuf]) -> u64 {
+fn count_total_regions(regions: &RegionFiles, dims: &[PathBuf], naming: &RegionNaming) -> u64 {
     let mut total: u64 = 0;
     for dim in dims {
-        let region_dir = dim.join("region");
-        if !region_dir.is_dir() {
-            continue;
-        }
-        if let Ok(rd) = fs::read_dir(&region_dir) {
-            for ent in rd.flatten() {
-                let p = ent.path();
-                if p.extension().and_then(|s| s.to_str()) == Some("mca") && is_valid_mca(&p) {
-                    total += 1;
-                }
+        for p in regions.files(&dim.join("region")).unwrap_or_default() {
+            if naming.matches(&p) && regions.is_valid_mca(&p) {
+                total += 1;
             }
         }
     }
     total
 }
 
-fn count_total_chunks(dims: &[PathBuf]) -> u64 {
+/// Writes `rewritten` (scrubbed or recompressed serialized bytes) in place of
+/// `entry` when given.
+fn write_rewritten(
+    w: &mut McaWriter,
+    entry: &mut McaEntry,
+    rewritten: Option<Vec<u8>>,
+) -> crate::mca::error::Result<u64> {
+    match rewritten {
+        Some(bytes) => w
+            .write_serialized(entry.region_index(), entry.modified_time(), &bytes)
+            .map(|_| bytes.len() as u64),
+        None => w.write_entry(entry),
+    }
+}
+
+/// Every chunk of the dimension `dim` (its `region/` folder, regions named after
+/// `naming`) together with the region file it comes from, regions in file name order. Regions
+/// are opened one at a time as the iteration reaches them, so only one region's
+/// entries are held at once; nothing is written. A region that cannot be read yields
+/// one error and the iteration moves on to the next; tiny files are skipped like in
+/// a run.
+#[allow(dead_code)]
+pub fn iter_dimension_chunks(
+    dim: &Path,
+    naming: &RegionNaming,
+) -> impl Iterator<Item = Result<(PathBuf, McaEntry)>> {
+    let naming = naming.clone();
+    let (files, listing) = match RegionFiles::Disk.files(&dim.join("region")) {
+        Ok(mut files) => {
+            files.retain(|p| naming.matches(p) && RegionFiles::Disk.is_valid_mca(p));
+            files.sort();
+            (files, None)
+        }
+        Err(e) => (Vec::new(), Some(Err(e))),
+    };
+    listing
+        .into_iter()
+        .chain(files.into_iter().flat_map(move |path| {
+            let entries = RegionFiles::Disk.open_named(&path, &naming).and_then(|r| {
+                r.with_memory_limit(Some(DEFAULT_CHUNK_MEMORY_LIMIT))
+                    .entries()
+                    .map_err(Into::into)
+            });
+            let chunks: Vec<Result<(PathBuf, McaEntry)>> = match entries {
+                Ok(entries) => entries.into_iter().map(|e| Ok((path.clone(), e))).collect(),
+                Err(e) => vec![Err(anyhow!("{}: {}", path.display(), e))],
+            };
+            chunks
+        }))
+}
+
+fn count_total_chunks(regions: &RegionFiles, dims: &[PathBuf], naming: &RegionNaming) -> u64 {
     let mut total: u64 = 0;
     for dim in dims {
-        let region_dir = dim.join("region");
-        if !region_dir.is_dir() {
-            continue;
+        for p in regions.files(&dim.join("region")).unwrap_or_default() {
+            if naming.matches(&p) && regions.is_valid_mca(&p) {
+                if let Ok(n) = regions.chunk_count_named(&p, naming) {
+                    total += n as u64;
+                }
+            }
+        }
+    }
+    total
+}
+
+pub struct RunOptions {
+    /// InhabitedTime threshold in ticks
+    pub inhabited_threshold: i64,
+    /// Per-dimension `(name, ticks)` overrides of `inhabited_threshold`, see `dimension_threshold`
+    pub dim_thresholds: Vec<(String, i64)>,
+    pub remove_unknown: bool,
+    pub progress_mode: ProgressMode,
+    /// Re-encode kept region chunks instead of copying them verbatim
+    pub recompress: Option<Recompression>,
+    /// zlib level used by `Recompression::Zlib` (0-9)
+    pub zlib_level: u32,
+    /// Uncompressed bytes per block for `Recompression::Lz4`, see `encode_lz4_blocks`
+    pub lz4_block_size: usize,
+    /// How many levels below the world root to look for dimensions
+    pub max_dimension_depth: usize,
+    /// Copy non-region world files (level.dat, playerdata/, ...) into the output
+    pub copy_world_files: bool,
+    /// File/directory names skipped by `copy_world_files`
+    pub copy_ignore: Vec<String>,
+    /// Cross-check the InhabitedTime byte scan against a full NBT parse on this
+    /// percentage of chunks; any divergence fails the run
+    pub audit_inhabited_percent: Option<f64>,
+    /// After processing, reopen the output regions and fail the run (before anything
+    /// is committed in place) if a force-loaded chunk of the input is missing
+    pub verify_forced: bool,
+    /// chunks.dat ticket types whose chunks are always kept
+    pub keep_ticket_types: Vec<String>,
+    /// Also keep chunks of temporary tickets whose `ticks_left` has not run out
+    pub keep_active_tickets: bool,
+    /// Drop the entities folder entirely
+    pub skip_entities: bool,
+    /// Drop the poi folder entirely
+    pub skip_poi: bool,
+    /// Auxiliary folders copied as-is instead of being matched against kept chunks
+    pub copy_verbatim: Vec<AuxKind>,
+    /// Write `dimension,chunk_x,chunk_z,inhabited_seconds` for every chunk to this CSV
+    pub export_inhabited: Option<PathBuf>,
+    /// Remove chunks whose generation status is below this one (forced chunks still win)
+    pub remove_below_status: Option<String>,
+    /// Remove chunks whose dominant biome is not one of these ids (`BiomePattern`), a
+    /// veto on top of the keep rules; empty disables it
+    pub keep_only_biomes: Vec<String>,
+    /// Write this timestamp (seconds since the epoch) for every rewritten chunk, and
+    /// into the timestamp table of regions copied unchanged (`region_bounds`, forced
+    /// regions, header-only regions). Folders in `copy_verbatim` keep theirs
+    pub set_timestamp: Option<u32>,
+    /// Chunks declaring more bytes than this are streamed instead of read whole;
+    /// they are copied without recompression and skipped by NBT-based checks
+    pub chunk_memory_limit: Option<u64>,
+    /// Only process regions inside `(min_x, min_z, max_x, max_z)` (region coordinates,
+    /// inclusive); the others are copied unchanged
+    pub region_bounds: Option<(i32, i32, i32, i32)>,
+    /// Also keep chunks whose DataVersion equals the one in `level.dat`
+    pub keep_current_dataversion: bool,
+    /// Only print how the rules decide on this chunk (global chunk coordinates) in
+    /// every dimension, without processing anything
+    pub explain_chunk: Option<(i32, i32)>,
+    /// Print, per dimension before processing, which options keep or remove chunks in
+    /// which order, and where the coordinate keep options overlap
+    pub explain_rules: bool,
+    /// Rhai script deciding keep/remove in place of the InhabitedTime threshold, see
+    /// `patterns::script::ScriptPattern`; needs the `script` feature and excludes
+    /// `no_inhabited`, `keep_zero_inhabited` and `conservative_cull`
+    pub chunk_filter_script: Option<PathBuf>,
+    /// Also keep chunks whose `LastUpdate` is at most this many ticks before the
+    /// `Time` in `level.dat`
+    pub last_update_within: Option<i64>,
+    /// Also keep this percentage of chunks, drawn by `SamplePattern` from
+    /// `sample_seed` and the chunk coordinates; meant for small test worlds
+    pub sample_keep: Option<f64>,
+    pub sample_seed: u64,
+    /// Keep the spawn chunks recorded in `level.dat` (see `level_spawn`), in the
+    /// spawn's dimension only
+    pub keep_spawn_chunks: bool,
+    /// Chunks always kept, in `coord_unit`
+    pub keep_chunks: Vec<(i32, i32)>,
+    /// `(x1, z1, x2, z2)` boxes always kept, in `coord_unit`
+    pub keep_ranges: Vec<(i32, i32, i32, i32)>,
+    /// File of extra keep points/ranges, or of per-dimension chunks as written by
+    /// `coords::dimension_chunks_json`, see `coords::parse_keep_list`
+    pub keep_list: Option<PathBuf>,
+    /// JSON/GeoJSON file of polygons whose chunks are always kept, in `coord_unit`,
+    /// see `coords::parse_polygons`
+    pub keep_polygon: Option<PathBuf>,
+    pub coord_unit: CoordUnit,
+    /// Copy every region holding a force-loaded chunk unchanged instead of filtering it
+    pub keep_forced_regions_whole: bool,
+    /// Fail the run, before anything is committed, if more than this percentage of
+    /// the chunks would be removed: in place the input is not replaced, and an output
+    /// run, staged meanwhile, leaves nothing in the output directory
+    pub max_removed_percent: Option<f64>,
+    /// `fsync` the output files every this many filtered regions (per dimension);
+    /// 0 leaves flushing to the OS
+    pub sync_interval: u64,
+    /// Remove chunks without signs of player activity (see `modified::looks_modified`),
+    /// even above the InhabitedTime threshold
+    pub keep_modified_only: bool,
+    /// Fail the run like `max_removed_percent` if fewer chunks than this would be
+    /// kept world-wide; 0 disables the check
+    pub min_kept_chunks: u64,
+    /// Drop the entities chunk of a kept chunk when it holds only
+    /// `entities::TRIVIAL_ENTITIES`; the region chunk itself is unaffected
+    pub prune_trivial_entities: bool,
+    /// Report kept chunks whose `entities/` chunk holds more than this many entities
+    /// (`entities::entity_count`), worst first
+    pub entity_hotspot_threshold: Option<usize>,
+    /// Drop the entities chunk of those hotspots; the region chunk itself is kept
+    pub clear_entity_hotspots: bool,
+    /// Keep every chunk whose `entities/` chunk holds an entity with a visible
+    /// `CustomName` (`entities::has_named_entities`), like a pin
+    pub keep_named_entities: bool,
+    /// Soft cap in MiB on the chunk data all dimensions hold at once, estimated from
+    /// declared sizes (see `budget::MemoryBudget`); `None` leaves it unbounded
+    pub max_memory_mb: Option<u64>,
+    /// File names of region files, for worlds whose tools do not write
+    /// `r.<x>.<z>.mca`; output regions keep their input names
+    pub region_naming: RegionNaming,
+    /// Fail the run on the first chunk or region error instead of logging and
+    /// skipping it; an in-place run then leaves the world untouched
+    pub strict: bool,
+    /// Write the kept chunks of each filtered region in `writer::morton_rank` order
+    /// instead of index order
+    pub optimize_layout: bool,
+    /// Replace the progress and summary lines on stdout with one `RunSummary` JSON line
+    pub json_summary: bool,
+    /// Whether an existing empty output directory is accepted; ignored in place
+    pub output_policy: OutputPolicy,
+    /// Write an `index::RegionIndex` sidecar next to every filtered input region;
+    /// needs an output directory and a directory (not zipped) world
+    pub build_index: bool,
+    /// Take InhabitedTime from fresh `index::RegionIndex` sidecars instead of
+    /// decoding the chunks
+    pub use_index: bool,
+    /// Remove chunks whose payload decodes to zero bytes, whatever `remove_unknown`
+    /// says (forced and explicitly kept chunks still win)
+    pub remove_empty_chunks: bool,
+    /// Keep chunks whose InhabitedTime is exactly 0 whatever the threshold; vetoes
+    /// still remove them
+    pub keep_zero_inhabited: bool,
+    /// Drop the InhabitedTime keep pattern (threshold, `remove_unknown` and
+    /// `keep_zero_inhabited` then have no effect): only pins and the other keep
+    /// options keep chunks. Refused without any of them, see `has_explicit_keeps`
+    pub no_inhabited: bool,
+    /// Keep chunks not fully generated whatever their InhabitedTime: only `full`
+    /// chunks below the threshold are removed (`CombinedPattern::conservative_cull`).
+    /// No effect with `no_inhabited`
+    pub conservative_cull: bool,
+    /// Remove these tags from kept region, entities and poi chunks, see `scrub::scrub_chunk`
+    pub scrub_fields: Option<Vec<String>>,
+    /// Print the human-readable report (and the JSON summary) to stderr, leaving
+    /// stdout to a data stream such as `--tar-stdout`
+    pub report_to_stderr: bool,
+    /// Stream the output tree to stdout as a tar archive (`tar::TarStream`) instead
+    /// of writing an output directory: each file is archived once finished and then
+    /// deleted from a scratch directory. Excludes an output directory, in-place runs
+    /// and the options reading the output back after processing
+    pub tar_stdout: bool,
+    /// Process dimensions one at a time and regions in file name order, so logs, progress
+    /// lines and events come out the same on every run. Dimensions no longer run in
+    /// parallel, so worlds with several of them take longer
+    pub stable_order: bool,
+    /// Decision for chunks no keep pattern could evaluate, see `KeepRules::try_evaluate`
+    pub on_error: OnError,
+    /// Groups of dimension names (see `dimension_name_matches`): a chunk kept in one
+    /// dimension of a group is kept at the same coordinates in all of them. Adds a
+    /// read-only first pass over the linked dimensions, see `linked_dimension_keeps`
+    pub linked_dimensions: Vec<Vec<String>>,
+    /// Fail instead of warning when no dimension is discovered (also under `strict`)
+    pub abort_if_no_dimensions: bool,
+    /// Only list the regions of which no chunk would be kept, per dimension, and
+    /// return without creating the output or touching the world
+    pub report_empty_regions: bool,
+    /// File receiving `events::ProgressEvents` JSON lines, e.g. `/dev/fd/3` or a FIFO;
+    /// appended to (never truncated) once the run starts processing
+    pub progress_events: Option<PathBuf>,
+    /// File to append a `events::RemovalLog` line to for every removed chunk, as it
+    /// is removed
+    pub removal_log: Option<PathBuf>,
+    /// Buffer size in KiB for copying region files unchanged (verbatim regions and
+    /// the in-place copy-back); `None` uses `fs::copy`
+    pub copy_buffer_kb: Option<usize>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            inhabited_threshold: 300 * 20,
+            dim_thresholds: Vec::new(),
+            remove_unknown: false,
+            progress_mode: ProgressMode::Region,
+            recompress: None,
+            zlib_level: 6,
+            lz4_block_size: LZ4_DEFAULT_BLOCK_SIZE,
+            max_dimension_depth: DEFAULT_DIMENSION_DEPTH,
+            copy_world_files: false,
+            copy_ignore: DEFAULT_COPY_IGNORE.iter().map(|s| s.to_string()).collect(),
+            audit_inhabited_percent: None,
+            verify_forced: false,
+            keep_ticket_types: DEFAULT_TICKET_TYPES.iter().map(|s| s.to_string()).collect(),
+            keep_active_tickets: false,
+            skip_entities: false,
+            skip_poi: false,
+            copy_verbatim: Vec::new(),
+            export_inhabited: None,
+            remove_below_status: None,
+            keep_only_biomes: Vec::new(),
+            set_timestamp: None,
+            chunk_memory_limit: Some(DEFAULT_CHUNK_MEMORY_LIMIT),
+            region_bounds: None,
+            keep_current_dataversion: false,
+            last_update_within: None,
+            sample_keep: None,
+            sample_seed: 0,
+            chunk_filter_script: None,
+            explain_rules: false,
+            explain_chunk: None,
+            keep_spawn_chunks: false,
+            keep_chunks: Vec::new(),
+            keep_ranges: Vec::new(),
+            keep_list: None,
+            keep_polygon: None,
+            coord_unit: CoordUnit::Chunk,
+            keep_forced_regions_whole: false,
+            max_removed_percent: None,
+            sync_interval: 0,
+            keep_modified_only: false,
+            min_kept_chunks: 0,
+            prune_trivial_entities: false,
+            entity_hotspot_threshold: None,
+            clear_entity_hotspots: false,
+            keep_named_entities: false,
+            max_memory_mb: None,
+            region_naming: RegionNaming::default(),
+            strict: false,
+            optimize_layout: false,
+            json_summary: false,
+            output_policy: OutputPolicy::default(),
+            build_index: false,
+            use_index: false,
+            remove_empty_chunks: false,
+            keep_zero_inhabited: false,
+            conservative_cull: false,
+            scrub_fields: None,
+            report_to_stderr: false,
+            tar_stdout: false,
+            stable_order: false,
+            no_inhabited: false,
+            on_error: OnError::default(),
+            linked_dimensions: Vec::new(),
+            abort_if_no_dimensions: false,
+            report_empty_regions: false,
+            copy_buffer_kb: None,
+            progress_events: None,
+            removal_log: None,
+        }
+    }
+}
+
+impl RunOptions {
+    /// Turns on every keep protecting chunks the game itself keeps loaded: the spawn
+    /// chunks, the `SAFE_TICKET_TYPES` tickets next to the configured ones, and
+    /// temporary tickets still counting down.
+    pub fn with_safe_defaults(mut self) -> Self {
+        self.keep_spawn_chunks = true;
+        self.keep_active_tickets = true;
+        for t in SAFE_TICKET_TYPES {
+            if !self
+                .keep_ticket_types
+                .iter()
+                .any(|k| ticket_type_matches(k, &[t.to_string()]))
+            {
+                self.keep_ticket_types.push(t.to_string());
+            }
+        }
+        self
+    }
+
+    /// A keep option other than InhabitedTime and the chunks.dat tickets is set, so
+    /// `no_inhabited` does not leave the world to forced chunks alone.
+    fn has_explicit_keeps(&self) -> bool {
+        !self.keep_chunks.is_empty()
+            || !self.keep_ranges.is_empty()
+            || self.keep_list.is_some()
+            || self.keep_polygon.is_some()
+            || self.keep_spawn_chunks
+            || self.keep_current_dataversion
+            || self.last_update_within.is_some()
+            || self.sample_keep.is_some()
+            || self.region_bounds.is_some()
+    }
+
+    fn aux_mode(&self, kind: AuxKind) -> AuxMode {
+        let skip = match kind {
+            AuxKind::Entities => self.skip_entities,
+            AuxKind::Poi => self.skip_poi,
+        };
+        if skip {
+            AuxMode::Skip
+        } else if self.copy_verbatim.contains(&kind) {
+            AuxMode::Verbatim
+        } else {
+            AuxMode::Filter
+        }
+    }
+
+    /// Encoding of a scrubbed chunk: `recompress` when set, otherwise the chunk's
+    /// own codec (LZ4 stays LZ4, anything else becomes zlib).
+    fn scrub_encoding(&self, method: CompressionMethod) -> ChunkEncoding {
+        self.chunk_encoding().unwrap_or(match method {
+            CompressionMethod::Lz4 => ChunkEncoding::Lz4 {
+                block_size: self.lz4_block_size,
+            },
+            _ => ChunkEncoding::Zlib {
+                level: self.zlib_level,
+            },
+        })
+    }
+
+    fn chunk_encoding(&self) -> Option<ChunkEncoding> {
+        self.recompress.map(|r| match r {
+            Recompression::Zlib => ChunkEncoding::Zlib {
+                level: self.zlib_level,
+            },
+            Recompression::Lz4 => ChunkEncoding::Lz4 {
+                block_size: self.lz4_block_size,
+            },
+        })
+    }
+}
+
+#[allow(dead_code)]
+pub fn run(
+    input: PathBuf,
+    output: Option<PathBuf>,
+    inhabited_threshold: i64,
+    remove_unknown: bool,
+    progress_mode: ProgressMode,
+) -> Result<()> {
+    run_with_options(
+        input,
+        output,
+        &RunOptions {
+            inhabited_threshold,
+            remove_unknown,
+            progress_mode,
+            ..RunOptions::default()
+        },
+    )
+}
+
+pub fn run_with_options(input: PathBuf, output: Option<PathBuf>, opts: &RunOptions) -> Result<()> {
+    let tar = tar_stream(&output, opts)?;
+    let output = match tar {
+        Some(ref t) => Some(t.root().to_path_buf()),
+        None => output,
+    };
+    if let Some(summary) = process_world(input, output, opts, tar.as_ref())? {
+        summary.print(opts);
+    }
+    finish_tar_stream(tar, opts)
+}
+
+/// The `tar_stdout` stream of a run asked to write into `output`.
+fn tar_stream(output: &Option<PathBuf>, opts: &RunOptions) -> Result<Option<TarStream>> {
+    if !opts.tar_stdout {
+        return Ok(None);
+    }
+    if output.is_some() {
+        return Err(anyhow!("--tar-stdout writes no output directory"));
+    }
+    if opts.verify_forced || opts.max_removed_percent.is_some() || opts.min_kept_chunks > 0 {
+        return Err(anyhow!(
+            "--verify-forced, --max-removed-percent and --min-kept-chunks check the output \
+             after processing, when --tar-stdout has already streamed it"
+        ));
+    }
+    Ok(Some(TarStream::stdout()))
+}
+
+fn finish_tar_stream(tar: Option<TarStream>, opts: &RunOptions) -> Result<()> {
+    if let Some(tar) = tar {
+        let files = tar.finish()?;
+        if !opts.json_summary {
+            report!(opts, "tar: 已写入 {} 个文件到标准输出", files);
+        }
+    }
+    Ok(())
+}
+
+/// Immediate subdirectories of a server root that hold a `level.dat`, sorted: the
+/// separate world roots of the Bukkit layout (`world/`, `world_nether/DIM-1/`,
+/// `world_the_end/DIM1/`), each with its own `level.dat`, tickets and settings.
+pub fn server_worlds(input: &Path) -> Result<Vec<PathBuf>> {
+    if !input.is_dir() {
+        return Err(anyhow!("server layout input must be a directory"));
+    }
+    let mut worlds = Vec::new();
+    for e in fs::read_dir(input)? {
+        let p = e?.path();
+        if p.is_dir() && p.join("level.dat").is_file() {
+            worlds.push(p);
+        }
+    }
+    worlds.sort();
+    if worlds.is_empty() {
+        return Err(anyhow!(
+            "no world (folder with level.dat) found directly inside {}",
+            input.display()
+        ));
+    }
+    Ok(worlds)
+}
+
+/// `run_with_options` on every `server_worlds` world of `input`, each into
+/// `output/<world name>` (or in place), followed by totals over all of them.
+/// `--json-summary` prints only the totals, with a `worlds` array of the per-world
+/// summaries, each carrying a `world` name.
+pub fn run_server_with_options(
+    input: PathBuf,
+    output: Option<PathBuf>,
+    opts: &RunOptions,
+) -> Result<()> {
+    let start_time = std::time::Instant::now();
+    let worlds = server_worlds(&input)?;
+    let tar = tar_stream(&output, opts)?;
+    let output = match tar {
+        Some(ref t) => Some(t.root().to_path_buf()),
+        None => output,
+    };
+    info!(
+        "Server layout: {} worlds in {}",
+        worlds.len(),
+        input.display()
+    );
+    let mut done = Vec::new();
+    for world in worlds {
+        let name = world.file_name().unwrap().to_string_lossy().to_string();
+        if !opts.json_summary {
+            report!(opts, "== 世界 {} ==", name);
+        }
+        let out = output.as_ref().map(|o| o.join(&name));
+        if let Some(summary) = process_world(world, out, opts, tar.as_ref())? {
+            if !opts.json_summary {
+                summary.print(opts);
+            }
+            done.push((name, summary));
+        }
+    }
+    if done.is_empty() {
+        return finish_tar_stream(tar, opts);
+    }
+    let mut total = RunSummary {
+        kept: 0,
+        removed: 0,
+        before_bytes: 0,
+        after_bytes: 0,
+        elapsed_secs: start_time.elapsed().as_secs_f64(),
+        largest_chunks: BTreeMap::new(),
+    };
+    for (name, s) in &done {
+        total.kept += s.kept;
+        total.removed += s.removed;
+        total.before_bytes += s.before_bytes;
+        total.after_bytes += s.after_bytes;
+        for (label, l) in &s.largest_chunks {
+            let label = match label.as_str() {
+                "." => name.clone(),
+                _ => format!("{}/{}", name, label),
+            };
+            total.largest_chunks.insert(label, *l);
+        }
+    }
+    if opts.json_summary {
+        let worlds: Vec<String> = done
+            .iter()
+            .map(|(name, s)| {
+                format!(
+                    "{{\"world\":{},{}}}",
+                    serde_json::Value::from(name.as_str()),
+                    s.json_fields()
+                )
+            })
+            .collect();
+        report!(
+            opts,
+            "{{{},\"worlds\":[{}]}}",
+            total.json_fields(),
+            worlds.join(",")
+        );
+    } else {
+        report!(
+            opts,
+            "== 合计 {} 个世界 == 保留区块总数: {} · 删除区块总数: {} · 总耗时: {:.2}s",
+            done.len(),
+            total.kept,
+            total.removed,
+            total.elapsed_secs
+        );
+        total.print(opts);
+    }
+    finish_tar_stream(tar, opts)
+}
+
+/// The whole run over one world; `None` when a report mode returned before processing.
+/// Prints everything but the final `RunSummary::print` line.
+/// With `tar`, `output` is a folder of its scratch directory whose files are streamed
+/// and deleted as they are finished.
+fn process_world(
+    input: PathBuf,
+    output: Option<PathBuf>,
+    opts: &RunOptions,
+    tar: Option<&TarStream>,
+) -> Result<Option<RunSummary>> {
+    let inhabited_threshold = opts.inhabited_threshold;
+    let remove_unknown = opts.remove_unknown;
+    let progress_mode = opts.progress_mode;
+    let encoding = opts.chunk_encoding();
+    if (opts.skip_entities && opts.copy_verbatim.contains(&AuxKind::Entities))
+        || (opts.skip_poi && opts.copy_verbatim.contains(&AuxKind::Poi))
+    {
+        return Err(anyhow!(
+            "the same folder cannot be both skipped and copied verbatim"
+        ));
+    }
+    let zip_input = regions::is_zip(&input);
+    if zip_input && output.is_none() {
+        return Err(anyhow!("a zipped world needs an output directory"));
+    }
+    if !zip_input && !input.is_dir() {
+        return Err(anyhow!("input must be directory or .zip"));
+    }
+    if let Some(ref status) = opts.remove_below_status {
+        StatusPattern::new(status)?;
+    }
+    if !opts.keep_only_biomes.is_empty() {
+        BiomePattern::outside(&opts.keep_only_biomes)?;
+    }
+    if let Some(percent) = opts.sample_keep {
+        SamplePattern::new(percent, opts.sample_seed)?;
+    }
+    if opts
+        .max_removed_percent
+        .is_some_and(|p| !(0.0..=100.0).contains(&p))
+    {
+        return Err(anyhow!("--max-removed-percent must be between 0 and 100"));
+    }
+    #[cfg(not(feature = "script"))]
+    if opts.chunk_filter_script.is_some() {
+        return Err(anyhow!(
+            "--chunk-filter-script needs rust-thanos built with the `script` feature"
+        ));
+    }
+    if opts.chunk_filter_script.is_some()
+        && (opts.no_inhabited || opts.keep_zero_inhabited || opts.conservative_cull)
+    {
+        return Err(anyhow!(
+            "--chunk-filter-script replaces the InhabitedTime rule and cannot be combined \
+             with --no-inhabited, --keep-zero-inhabited or --conservative-cull"
+        ));
+    }
+    if opts.no_inhabited && !opts.has_explicit_keeps() && opts.max_removed_percent.is_none() {
+        return Err(anyhow!(
+            "--no-inhabited without another keep option removes every chunk that is not \
+             force-loaded; add --keep-chunk, --keep-range, --keep-list, --keep-polygon or \
+             --region-bounds, or pass --max-removed-percent 100 to confirm"
+        ));
+    }
+    if opts.build_index && (output.is_none() || zip_input) {
+        return Err(anyhow!(
+            "--build-index needs a directory world and an output directory: \
+             the index describes the input regions, which in-place runs rewrite"
+        ));
+    }
+    if let Some(ref output) = output {
+        check_disjoint(&input, output)?;
+    }
+    if opts.explain_chunk.is_none() && !opts.report_empty_regions {
+        match output {
+            Some(ref output) => probe_writable(output, "output")?,
+            None => {
+                // in place: staged in the temp directory, then copied back over the input
+                probe_writable(&std::env::temp_dir(), "temp directory")?;
+                probe_writable(&input, "input")?;
+            }
+        }
+    }
+    let start_time = std::time::Instant::now();
+    let regions = if zip_input {
+        RegionFiles::Zip(ZipWorld::open(&input)?)
+    } else {
+        RegionFiles::Disk
+    };
+    // From here on a zipped world is read through its staging directory
+    let input = regions.root(&input).to_path_buf();
+    let tasks = discover_dimensions(&input, opts.max_dimension_depth)?;
+    if tasks.is_empty() {
+        let report = no_dimensions_report(&input, opts.max_dimension_depth);
+        if opts.abort_if_no_dimensions || opts.strict {
+            return Err(anyhow!(report));
+        }
+        warn!("{}", report);
+        report!(
+            opts,
+            "警告: 未找到任何维度（含 region 文件夹的目录），请检查世界路径是否正确"
+        );
+    }
+    let before_size = regions.total_size().unwrap_or_else(|| dir_size(&input));
+    let mut keep_points = opts.keep_chunks.clone();
+    let mut keep_ranges = opts.keep_ranges.clone();
+    let mut keep_dimension_chunks = Vec::new();
+    let keep_list = match opts.keep_list {
+        Some(ref path) => Some(coords::parse_keep_list(path)?),
+        None => None,
+    };
+    if let Some(ref list) = keep_list {
+        keep_points.extend(&list.points);
+        keep_ranges.extend(&list.ranges);
+        keep_dimension_chunks = list.dimension_chunks.clone();
+    }
+    let keep_boxes = coords::chunk_ranges(opts.coord_unit, &keep_points, &keep_ranges)?;
+    let keep_polygons = match opts.keep_polygon {
+        Some(ref path) => coords::parse_polygons(path, opts.coord_unit)?
+            .into_iter()
+            .map(|p| PolygonPattern::new(p).map_err(|e| anyhow!("{}: {}", path.display(), e)))
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    let spawn = if opts.keep_spawn_chunks {
+        let spawn = level_spawn(&input)?;
+        info!(
+            "Keeping spawn chunks within {} of chunk ({}, {}) in {}",
+            spawn.radius, spawn.chunk.0, spawn.chunk.1, spawn.dimension
+        );
+        Some(spawn)
+    } else {
+        None
+    };
+    let current_data_version = if opts.keep_current_dataversion {
+        let v = level_data_version(&input)?;
+        info!("Keeping chunks at the world's DataVersion {}", v);
+        Some(v)
+    } else {
+        None
+    };
+    let recent_since = match opts.last_update_within {
+        Some(within) => {
+            let now = level_time(&input)?;
+            info!(
+                "Keeping chunks updated within {} ticks of the world's Time {}",
+                within, now
+            );
+            Some((now, within))
+        }
+        None => None,
+    };
+    for (name, _) in &opts.dim_thresholds {
+        let matched = tasks.iter().any(|t| {
+            dimension_name_matches(name, &dimension_label(t.strip_prefix(&input).unwrap_or(t)))
+        });
+        if !matched {
+            warn!("--dim-threshold {} matches no discovered dimension", name);
+        }
+    }
+    let total_regions = count_total_regions(&regions, &tasks, &opts.region_naming);
+    let total_chunks = count_total_chunks(&regions, &tasks, &opts.region_naming);
+    let processed_regions = Arc::new(AtomicU64::new(0));
+    let processed_chunks = Arc::new(AtomicU64::new(0));
+    let removed_total = Arc::new(AtomicU64::new(0));
+    // Counted independently of removed_total so chunks lost to read/write errors show up
+    let kept_total = Arc::new(AtomicU64::new(0));
+    let kept_bytes = Arc::new(AtomicU64::new(0));
+    let audited = AtomicU64::new(0);
+    let pruned_entities = AtomicU64::new(0);
+    let hotspot_pattern = opts
+        .entity_hotspot_threshold
+        .map(|max| EntityHotspotPattern { max });
+    // (dimension, chunk x, chunk z, entities) of every `entity_hotspot_threshold` match
+    let entity_hotspots: Mutex<Vec<(String, i32, i32, usize)>> = Mutex::new(Vec::new());
+    let audit_mismatches = AtomicU64::new(0);
+    let largest_chunks: Mutex<BTreeMap<String, LargestChunk>> = Mutex::new(BTreeMap::new());
+    let heatmap = match opts.export_inhabited {
+        Some(ref path) => {
+            let mut w = BufWriter::new(fs::File::create(path)?);
+            writeln!(w, "dimension,chunk_x,chunk_z,inhabited_seconds")?;
+            Some(Mutex::new(w))
+        }
+        None => None,
+    };
+    // Dimensions report from several threads; the lock keeps lines whole and ordered
+    let last_pct = Mutex::new(0usize);
+
+    let term = if opts.report_to_stderr {
+        Term::stderr()
+    } else {
+        Term::stdout()
+    };
+    let is_tty = term.is_term();
+    // Off a terminal the bars still exist but draw nothing; progress lines are printed
+    let mp = Arc::new(if is_tty {
+        MultiProgress::new()
+    } else {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    });
+    let global_enabled = progress_mode != ProgressMode::Off;
+    let region_enabled = progress_mode == ProgressMode::Region;
+    let (_, cols) = term.size();
+    // spinner + numbers + percent + msg space
+    let layout = BarLayout::for_columns(cols, 40);
+    let global_pb = if global_enabled {
+        let pb = add_progress_bar(&mp, total_chunks.max(1));
+        let style = ProgressStyle::with_template(&layout.template(
+            "{spinner:.green}",
+            "cyan/blue",
+            "{pos}/{len} 区块 {percent}%",
+        ))
+        .unwrap()
+        .progress_chars("=>-");
+        pb.set_style(style);
+        Some(pb)
+    } else {
+        None
+    };
+
+    #[cfg(feature = "script")]
+    let chunk_script = match opts.chunk_filter_script {
+        Some(ref path) => Some(ChunkScript::load(path)?),
+        None => None,
+    };
+    // Pins always keep, vetoes remove, then any keep pattern keeps
+    let dimension_rules = |dim_label: &str, forced: Vec<(i32, i32)>| -> Result<KeepRules> {
+        let (single, boxes): (Vec<_>, Vec<_>) = keep_boxes
+            .iter()
+            .partition(|(x1, z1, x2, z2)| x1 == x2 && z1 == z2);
+        let threshold = dimension_threshold(dim_label, &opts.dim_thresholds, inhabited_threshold);
+        let mut rules = KeepRules::defaults(threshold, remove_unknown, forced);
+        if opts.no_inhabited {
+            // with no keep pattern only pins keep a chunk
+            rules.keeps.clear();
+        } else if opts.keep_zero_inhabited {
+            // the defaults' InhabitedTime pattern, also keeping never-visited chunks
+            rules.keeps[0] = named(
+                format!(
+                    "InhabitedTime >= {} or 0 (--keep-zero-inhabited)",
+                    threshold
+                ),
+                InhabitedTimePattern::new(threshold, remove_unknown).with_keep_zero(true),
+            );
+        }
+        if opts.conservative_cull && !opts.no_inhabited {
+            let inhabited = rules.keeps.remove(0);
+            let name = format!(
+                "{} or not fully generated (--conservative-cull)",
+                inhabited.name()
+            );
+            rules.keeps.insert(
+                0,
+                named(name, CombinedPattern::conservative_cull(inhabited)?),
+            );
+        }
+        #[cfg(feature = "script")]
+        if let Some(ref script) = chunk_script {
+            // the script decides in place of the InhabitedTime threshold; the options
+            // changing that rule were rejected above
+            rules.keeps[0] = named(
+                "--chunk-filter-script",
+                ScriptPattern::new(Arc::clone(script), dim_label),
+            );
+        }
+        rules.pins.push(named(
+            "--keep-chunk/--keep-list points",
+            ListPattern::new(single.iter().map(|(x, z, _, _)| (*x, *z)).collect()),
+        ));
+        for (x1, z1, x2, z2) in boxes {
+            rules.pins.push(named(
+                format!("--keep-range/--keep-list ({}, {})-({}, {})", x1, z1, x2, z2),
+                RangePattern::new(x1, z1, x2, z2),
+            ));
+        }
+        for (i, polygon) in keep_polygons.iter().enumerate() {
+            rules
+                .pins
+                .push(named(format!("--keep-polygon #{}", i + 1), polygon.clone()));
+        }
+        for (_, chunks) in keep_dimension_chunks
+            .iter()
+            .filter(|(name, _)| dimension_name_matches(name, dim_label))
+        {
+            rules.pins.push(named(
+                "--keep-list chunks",
+                ListPattern::new(chunks.clone()),
+            ));
+        }
+        if let Some(spawn) = spawn
+            .as_ref()
+            .filter(|s| dimension_name_matches(&s.dimension, dim_label))
+        {
+            let (x1, z1, x2, z2) = spawn.area();
+            rules.pins.push(named(
+                "--keep-spawn-chunks",
+                RangePattern::new(x1, z1, x2, z2),
+            ));
+        }
+        if let Some(ref status) = opts.remove_below_status {
+            rules.vetoes.push(named(
+                format!("--remove-below-status {}", status),
+                StatusPattern::new(status)?,
+            ));
+        }
+        if !opts.keep_only_biomes.is_empty() {
+            rules.vetoes.push(named(
+                format!("--keep-only-biome {}", opts.keep_only_biomes.join(",")),
+                BiomePattern::outside(&opts.keep_only_biomes)?,
+            ));
+        }
+        if opts.keep_modified_only {
+            rules
+                .vetoes
+                .push(named("--keep-modified-only", PristinePattern));
+        }
+        if opts.remove_empty_chunks {
+            rules
+                .vetoes
+                .push(named("--remove-empty-chunks", EmptyPayloadPattern));
+        }
+        if let Some(v) = current_data_version {
+            rules.keeps.push(named(
+                format!("--keep-current-dataversion {}", v),
+                DataVersionPattern::new(v),
+            ));
+        }
+        if let Some((now, within)) = recent_since {
+            rules.keeps.push(named(
+                format!("--last-update-within {}", within),
+                LastUpdatePattern::new(now, within),
+            ));
+        }
+        if let Some(percent) = opts.sample_keep {
+            rules.keeps.push(named(
+                format!("--sample-keep {}% (seed {})", percent, opts.sample_seed),
+                SamplePattern::new(percent, opts.sample_seed)?,
+            ));
+        }
+        Ok(rules)
+    };
+    let mut linked_keeps =
+        linked_dimension_keeps(&regions, &input, &tasks, opts, &dimension_rules)?;
+    if opts.keep_named_entities {
+        // pinned like linked coordinates, so every pass that honours those agrees
+        let named: Vec<(&PathBuf, HashSet<(i32, i32)>)> = tasks
+            .par_iter()
+            .map(|dim| {
+                let label = dimension_label(dim.strip_prefix(&input).unwrap_or(dim));
+                Ok((dim, named_entity_keeps(&regions, dim, &label, opts)?))
+            })
+            .collect::<Result<_>>()?;
+        for (dim, coords) in named {
+            info!(
+                "[{}] Keeping {} chunks with named entities",
+                dimension_label(dim.strip_prefix(&input).unwrap_or(dim)),
+                coords.len()
+            );
+            linked_keeps.entry(dim.clone()).or_default().extend(coords);
+        }
+    }
+    if let Some(chunk) = opts.explain_chunk {
+        explain_chunk(
+            &regions,
+            &input,
+            &tasks,
+            opts,
+            &dimension_rules,
+            &linked_keeps,
+            chunk,
+        )?;
+        return Ok(None);
+    }
+    if opts.explain_rules && !opts.json_summary {
+        for dim in &tasks {
+            let label = dimension_label(dim.strip_prefix(&input).unwrap_or(dim));
+            let summary = rule_summary(
+                dim,
+                &label,
+                opts,
+                inhabited_threshold,
+                keep_list.as_ref(),
+                keep_polygons.len(),
+                spawn.as_ref(),
+                linked_keeps.get(dim),
+            )?;
+            for line in summary.lines() {
+                report!(opts, "{}", line);
+            }
         }
-        if let Ok(rd) = fs::read_dir(&region_dir) {
-            for ent in rd.flatten() {
-                let p = ent.path();
-                if p.extension().and_then(|s| s.to_str()) == Some("mca") && is_valid_mca(&p) {
-                    if let Ok(mut r) = McaReader::open(p.to_string_lossy().as_ref()) {
-                        if let Ok(entries) = r.entries() {
-                            total += entries.len() as u64;
-                        }
-                    }
-                }
+    }
+    if opts.report_empty_regions {
+        let mut emptied_total = 0;
+        for dim in &tasks {
+            let label = dimension_label(dim.strip_prefix(&input).unwrap_or(dim));
+            let emptied = emptied_regions(
+                &regions,
+                dim,
+                &label,
+                opts,
+                &dimension_rules,
+                linked_keeps.get(dim),
+            )?;
+            if emptied.is_empty() {
+                continue;
             }
+            report!(
+                opts,
+                "[{}] 将不再保留任何区块的区域文件 {} 个:",
+                label,
+                emptied.len()
+            );
+            for (rf, chunks) in &emptied {
+                report!(
+                    opts,
+                    "  {}（{} 个区块全部删除）",
+                    rf.strip_prefix(&input).unwrap_or(rf).display(),
+                    chunks
+                );
+            }
+            emptied_total += emptied.len();
         }
+        report!(
+            opts,
+            "共 {} 个区域文件将变为空，未写入任何文件",
+            emptied_total
+        );
+        return Ok(None);
     }
-    total
-}
-
-pub fn run(
-    input: PathBuf,
-    output: Option<PathBuf>,
-    inhabited_threshold: i64,
-    remove_unknown: bool,
-    progress_mode: ProgressMode,
-) -> Result<()> {
-    if !input.is_dir() {
-        return Err(anyhow!("input must be directory"));
+    let events = opts
+        .progress_events
+        .as_deref()
+        .map(ProgressEvents::open)
+        .transpose()?;
+    if let Some(ref ev) = events {
+        ev.start(total_chunks, total_regions);
     }
-    let start_time = std::time::Instant::now();
-    let before_size = dir_size(&input);
+    let removal_log = opts
+        .removal_log
+        .as_deref()
+        .map(RemovalLog::open)
+        .transpose()?;
     let out = output
         .clone()
         .unwrap_or_else(|| std::env::temp_dir().join(format!("thanos-{}", uuid::Uuid::new_v4())));
     if out.exists() {
+        if output.is_some() && opts.output_policy == OutputPolicy::MustCreate {
+            return Err(anyhow!(
+                "output {} already exists (--output-policy must-create)",
+                out.display()
+            ));
+        }
         if out.read_dir()?.next().is_some() {
             return Err(anyhow!("output must be empty"));
         }
+        info!("Writing into existing empty output {}", out.display());
     } else {
         fs::create_dir_all(&out)?;
+        info!("Created output {}", out.display());
     }
-    let mut tasks = Vec::new();
-    if is_dimension_dir(&input) {
-        tasks.push(input.clone());
-    }
-    for entry in fs::read_dir(&input)? {
-        let p = entry?.path();
-        if p.is_dir() && is_dimension_dir(&p) {
-            tasks.push(p);
-        }
-    }
-    for entry in WalkDir::new(&input).into_iter().filter_map(|e| e.ok()) {
-        let p = entry.path();
-        if p.is_dir() && is_dimension_dir(p) {
-            let pb = p.to_path_buf();
-            if !tasks.iter().any(|t| t == &pb) {
-                tasks.push(pb);
-            }
+    // With a guard set, an output run is staged in a hidden folder of the output and
+    // moved into place only once the guards pass, so an aborted run leaves no world
+    let staged_into = (output.is_some()
+        && (opts.max_removed_percent.is_some() || opts.min_kept_chunks > 0))
+        .then(|| out.clone());
+    let out = match staged_into {
+        Some(ref dest) => {
+            let staging = dest.join(format!(".thanos-staging-{}", uuid::Uuid::new_v4()));
+            fs::create_dir(&staging)?;
+            staging
         }
-    }
-    let _total_regions = count_total_regions(&tasks);
-    let total_chunks = count_total_chunks(&tasks);
-    let processed_regions = Arc::new(AtomicU64::new(0));
-    let processed_chunks = Arc::new(AtomicU64::new(0));
-    let removed_total = Arc::new(AtomicU64::new(0));
-    let last_pct = Arc::new(AtomicUsize::new(0));
-
-    let mp = Arc::new(MultiProgress::new());
-    let term = Term::stdout();
-    let is_tty = term.is_term();
-    let global_enabled = progress_mode != ProgressMode::Off && is_tty;
-    let _region_enabled = progress_mode == ProgressMode::Region && is_tty;
-    let global_pb = if global_enabled {
-        let (_, cols) = term.size();
-        let reserve = 40u16; // spinner + numbers + percent + msg space
-        let mut bar_width = if cols > reserve {
-            (cols - reserve) as usize
-        } else {
-            20usize
-        };
-        bar_width = bar_width.min(50);
-        let pb = mp.add(ProgressBar::new(total_chunks.max(1)));
-        let style = ProgressStyle::with_template(&format!("{{spinner:.green}} {{bar:{width}.cyan/blue}} {{pos}}/{{len}} 区块 {{percent}}% {{msg}}", width=bar_width))
-            .unwrap()
-            .progress_chars("=>-");
-        pb.set_style(style);
-        Some(pb)
-    } else {
-        None
+        None => out,
     };
-
-    tasks.par_iter().try_for_each(|dim| -> Result<()> {
+    // the temp directory of an in-place run or the staging folder
+    let scratch_out = output.is_none() || staged_into.is_some();
+    // bytes archived by `tar`, standing in for the size of the output
+    let streamed_bytes = AtomicU64::new(0);
+    let budget = opts
+        .max_memory_mb
+        .map(|mb| MemoryBudget::new(mb.saturating_mul(1024 * 1024)));
+    let process_dimension = |dim: &PathBuf| -> Result<()> {
         let rel = dim.strip_prefix(&input).unwrap_or(dim);
+        let dim_label = dimension_label(rel);
         let target_dim = out.join(rel);
         fs::create_dir_all(&target_dim)?;
-        let mut patterns: Vec<Box<dyn ChunkPattern + Send>> = Vec::new();
-        let forced = parse_force_loaded(dim);
-        patterns.push(Box::new(ListPattern::new(forced)));
-        patterns.push(Box::new(InhabitedTimePattern::new(
-            inhabited_threshold,
-            remove_unknown,
-        )));
+        // Region mode: one bar per dimension below the global one, its message tracking
+        // the chunks of the region being processed
+        let dim_pb = if region_enabled {
+            let dim_chunks =
+                count_total_chunks(&regions, std::slice::from_ref(dim), &opts.region_naming);
+            let pb = add_progress_bar(&mp, dim_chunks.max(1));
+            let style = ProgressStyle::with_template(&layout.template(
+                "  {prefix}",
+                "green/white",
+                "{pos}/{len}",
+            ))
+            .unwrap()
+            .progress_chars("=>-");
+            pb.set_style(style);
+            pb.set_prefix(dim_label.clone());
+            Some(pb)
+        } else {
+            None
+        };
+        let forced = parse_force_loaded(dim, &opts.keep_ticket_types, opts.keep_active_tickets);
+        let forced_regions = forced_region_set(opts, &forced);
+        let mut rules = dimension_rules(&dim_label, forced)?;
+        if let Some(coords) = linked_keeps.get(dim) {
+            rules.pins.push(named(
+                "--link-dimensions/--keep-named-entities",
+                ListPattern::new(coords.iter().copied().collect()),
+            ));
+        }
         let region_dir = dim.join("region");
         let entities_dir = dim.join("entities");
         let poi_dir = dim.join("poi");
-        fs::create_dir_all(target_dim.join("region"))?;
-        if entities_dir.is_dir() {
-            fs::create_dir_all(target_dim.join("entities"))?;
+        if region_dir.is_dir() {
+            fs::create_dir_all(target_dim.join("region"))?;
         }
-        if poi_dir.is_dir() {
-            fs::create_dir_all(target_dim.join("poi"))?;
+        // with `tar`, every file written so far into the dimension's folders is finished
+        let stream_finished = || -> Result<()> {
+            if let Some(tar) = tar {
+                for dir in ["region", "entities", "poi"] {
+                    let bytes = tar.drain(&target_dim.join(dir), 1)?;
+                    streamed_bytes.fetch_add(bytes, Ordering::Relaxed);
+                }
+            }
+            Ok(())
+        };
+        let entities_mode = opts.aux_mode(AuxKind::Entities);
+        let poi_mode = opts.aux_mode(AuxKind::Poi);
+        for (src, mode) in [(&entities_dir, entities_mode), (&poi_dir, poi_mode)] {
+            if !src.is_dir() {
+                continue;
+            }
+            let dst = target_dim.join(src.file_name().unwrap());
+            // Skip still creates the (empty) folder so in-place copy-back clears the input
+            fs::create_dir_all(&dst)?;
+            if mode == AuxMode::Verbatim {
+                if opts.scrub_fields.is_some() {
+                    warn!(
+                        "[{}] {} is copied verbatim and not scrubbed",
+                        dim_label,
+                        src.display()
+                    );
+                }
+                for p in regions.files(src)? {
+                    regions.copy(&p, &dst.join(p.file_name().unwrap()), opts.copy_buffer_kb)?;
+                }
+            }
         }
 
-        for entry in fs::read_dir(&region_dir)? {
-            let rf = entry?.path();
-            if rf.extension().and_then(|s| s.to_str()) != Some("mca") || !is_valid_mca(&rf) {
+        // Output files finalized since the last --sync-interval flush
+        let mut unsynced: Vec<PathBuf> = Vec::new();
+        let mut unsynced_regions = 0u64;
+        let mut largest: Option<LargestChunk> = None;
+        let mut region_files = regions.files(&region_dir)?;
+        if opts.stable_order {
+            region_files.sort();
+        }
+        warn_unsupported_regions(opts, &dim_label, &region_files);
+        for rf in region_files {
+            if !opts.region_naming.matches(&rf) || !regions.is_valid_mca(&rf) {
                 continue;
             }
             let name = rf.file_name().unwrap().to_string_lossy().to_string();
 
-            let mut cr = match McaReader::open(rf.to_string_lossy().as_ref()) {
-                Ok(r) => r,
+            let mut cr = match regions.open_named(&rf, &opts.region_naming) {
+                Ok(r) => r.with_memory_limit(opts.chunk_memory_limit),
                 Err(e) => {
-                    warn!("Failed to open region MCA {}: {}", rf.display(), e);
+                    recoverable(
+                        opts.strict,
+                        format!(
+                            "[{}] Failed to open region MCA {}: {}",
+                            dim_label,
+                            rf.display(),
+                            e
+                        ),
+                    )?;
                     continue;
                 }
             };
+            // Header-only regions have nothing to filter: copied as-is from the location
+            // table alone, their same-name entities/poi regions emptied as filtering would
+            if matches!(cr.chunk_count(), Ok(0)) {
+                let dst = target_dim.join("region").join(&name);
+                regions.copy(&rf, &dst, opts.copy_buffer_kb)?;
+                if let Some(t) = opts.set_timestamp {
+                    restamp(&dst, t)?;
+                }
+                for (src_dir, mode) in [(&entities_dir, entities_mode), (&poi_dir, poi_mode)] {
+                    let src = src_dir.join(&name);
+                    if mode == AuxMode::Filter
+                        && regions.is_file(&src)
+                        && regions.is_valid_mca(&src)
+                    {
+                        let dst = target_dim.join(src_dir.file_name().unwrap()).join(&name);
+                        McaWriter::open(dst.to_string_lossy().as_ref())?
+                            .with_timestamp(opts.set_timestamp)
+                            .finalize()?;
+                    }
+                }
+                processed_regions.fetch_add(1, Ordering::Relaxed);
+                if let Some(ref ev) = events {
+                    ev.region_done(
+                        &dim_label,
+                        &name,
+                        0,
+                        0,
+                        true,
+                        processed_chunks.load(Ordering::Relaxed),
+                    );
+                }
+                info!(
+                    "[{}] Region {} has no chunks, copied as-is",
+                    dim_label, name
+                );
+                stream_finished()?;
+                continue;
+            }
+            if let Some(reason) = verbatim_reason(opts, &forced_regions, cr.x_pos(), cr.z_pos()) {
+                // chunks and location table unchanged; only --set-timestamp is applied
+                let dst = target_dim.join("region").join(&name);
+                regions.copy(&rf, &dst, opts.copy_buffer_kb)?;
+                if let Some(t) = opts.set_timestamp {
+                    restamp(&dst, t)?;
+                }
+                for entry in cr.entries().iter_mut().flatten() {
+                    copy_external(entry, &target_dim.join("region"), opts.copy_buffer_kb)?;
+                }
+                for (src_dir, mode) in [(&entities_dir, entities_mode), (&poi_dir, poi_mode)] {
+                    let src = src_dir.join(&name);
+                    if mode == AuxMode::Filter && regions.is_file(&src) {
+                        let dst_dir = target_dim.join(src_dir.file_name().unwrap());
+                        regions.copy(&src, &dst_dir.join(&name), opts.copy_buffer_kb)?;
+                        if let Some(t) = opts.set_timestamp {
+                            restamp(&dst_dir.join(&name), t)?;
+                        }
+                        if let Ok(mut r) = regions.open_named(&src, &opts.region_naming) {
+                            for entry in r.entries().iter_mut().flatten() {
+                                copy_external(entry, &dst_dir, opts.copy_buffer_kb)?;
+                            }
+                        }
+                    }
+                }
+                let chunks = cr.chunk_count().unwrap_or(0) as u64;
+                processed_chunks.fetch_add(chunks, Ordering::Relaxed);
+                kept_total.fetch_add(chunks, Ordering::Relaxed);
+                if let Some(ref pb) = dim_pb {
+                    pb.inc(chunks);
+                    pb.set_message(format!("{} {}/{}", name, chunks, chunks));
+                }
+                processed_regions.fetch_add(1, Ordering::Relaxed);
+                if let Some(ref ev) = events {
+                    ev.region_done(
+                        &dim_label,
+                        &name,
+                        chunks,
+                        0,
+                        true,
+                        processed_chunks.load(Ordering::Relaxed),
+                    );
+                }
+                if opts.scrub_fields.is_some() {
+                    warn!(
+                        "[{}] Region {} {}, copied as-is without scrubbing",
+                        dim_label, name, reason
+                    );
+                } else {
+                    info!("[{}] Region {} {}, copied as-is", dim_label, name, reason);
+                }
+                stream_finished()?;
+                continue;
+            }
             let mut cw = match McaWriter::open(
                 target_dim
                     .join("region")
@@ -279,9 +2754,15 @@ pub fn run(
                     .to_string_lossy()
                     .as_ref(),
             ) {
-                Ok(w) => w,
+                Ok(w) => w.with_timestamp(opts.set_timestamp),
                 Err(e) => {
-                    warn!("Failed to create output region MCA {}: {}", name, e);
+                    recoverable(
+                        opts.strict,
+                        format!(
+                            "[{}] Failed to create output region MCA {}: {}",
+                            dim_label, name, e
+                        ),
+                    )?;
                     continue;
                 }
             };
@@ -290,7 +2771,10 @@ pub fn run(
             let pfile = poi_dir.join(&name);
 
             let mut ew = None;
-            if efile.is_file() && is_valid_mca(&efile) {
+            if entities_mode == AuxMode::Filter
+                && regions.is_file(&efile)
+                && regions.is_valid_mca(&efile)
+            {
                 ew = match McaWriter::open(
                     target_dim
                         .join("entities")
@@ -298,15 +2782,24 @@ pub fn run(
                         .to_string_lossy()
                         .as_ref(),
                 ) {
-                    Ok(w) => Some(w),
+                    Ok(w) => Some(w.with_timestamp(opts.set_timestamp)),
                     Err(e) => {
-                        warn!("Failed to create output entities MCA {}: {}", name, e);
+                        recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to create output entities MCA {}: {}",
+                                dim_label, name, e
+                            ),
+                        )?;
                         None
                     }
                 };
             }
             let mut pw = None;
-            if pfile.is_file() && is_valid_mca(&pfile) {
+            if poi_mode == AuxMode::Filter
+                && regions.is_file(&pfile)
+                && regions.is_valid_mca(&pfile)
+            {
                 pw = match McaWriter::open(
                     target_dim
                         .join("poi")
@@ -314,9 +2807,15 @@ pub fn run(
                         .to_string_lossy()
                         .as_ref(),
                 ) {
-                    Ok(w) => Some(w),
+                    Ok(w) => Some(w.with_timestamp(opts.set_timestamp)),
                     Err(e) => {
-                        warn!("Failed to create output poi MCA {}: {}", name, e);
+                        recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to create output poi MCA {}: {}",
+                                dim_label, name, e
+                            ),
+                        )?;
                         None
                     }
                 };
@@ -325,119 +2824,416 @@ pub fn run(
             let mut region_entries = match cr.entries() {
                 Ok(v) => v,
                 Err(e) => {
-                    warn!("Failed to read chunk entries in {}: {}", name, e);
+                    recoverable(
+                        opts.strict,
+                        format!(
+                            "[{}] Failed to read chunk entries in {}: {}",
+                            dim_label, name, e
+                        ),
+                    )?;
                     Vec::new()
                 }
             };
 
             let mut er = None;
-            if efile.is_file() && is_valid_mca(&efile) {
-                er = match McaReader::open(efile.to_string_lossy().as_ref()) {
+            if entities_mode == AuxMode::Filter
+                && regions.is_file(&efile)
+                && regions.is_valid_mca(&efile)
+            {
+                er = match regions.open_named(&efile, &opts.region_naming) {
                     Ok(r) => Some(r),
                     Err(e) => {
-                        warn!("Failed to open entities MCA {}: {}", efile.display(), e);
+                        recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to open entities MCA {}: {}",
+                                dim_label,
+                                efile.display(),
+                                e
+                            ),
+                        )?;
                         None
                     }
                 };
             }
             let mut pr = None;
-            if pfile.is_file() && is_valid_mca(&pfile) {
-                pr = match McaReader::open(pfile.to_string_lossy().as_ref()) {
+            if poi_mode == AuxMode::Filter
+                && regions.is_file(&pfile)
+                && regions.is_valid_mca(&pfile)
+            {
+                pr = match regions.open_named(&pfile, &opts.region_naming) {
                     Ok(r) => Some(r),
                     Err(e) => {
-                        warn!("Failed to open poi MCA {}: {}", pfile.display(), e);
+                        recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to open poi MCA {}: {}",
+                                dim_label,
+                                pfile.display(),
+                                e
+                            ),
+                        )?;
                         None
                     }
                 };
             }
 
+            if opts.optimize_layout {
+                region_entries.sort_by_key(|e| morton_rank(e.region_index()));
+            }
+            let region_chunks = region_entries.len();
+            let fresh_index = if opts.use_index {
+                RegionIndex::load_fresh(&rf)
+            } else {
+                None
+            };
+            if let Some(ref index) = fresh_index {
+                let cached: HashMap<u32, Option<i64>> = index
+                    .entries
+                    .iter()
+                    .map(|e| (e.index as u32, e.inhabited))
+                    .collect();
+                for entry in region_entries.iter_mut() {
+                    if let Some(&t) = cached.get(&entry.region_index()) {
+                        entry.set_cached_inhabited(t);
+                    }
+                }
+                info!("[{}] Using InhabitedTime index of {}", dim_label, name);
+            } else if opts.use_index && RegionIndex::sidecar_path(&rf).is_file() {
+                info!("[{}] Ignoring stale index of {}", dim_label, name);
+            }
+            // A fresh index is kept as is; a missing or stale one is rebuilt
+            let mut index_entries = (opts.build_index && fresh_index.is_none()).then(Vec::new);
             let mut removed = 0u64;
 
-            for entry in region_entries.iter_mut() {
-                let mut keep = false;
-                for p in patterns.iter_mut() {
-                    if let Ok(m) = p.matches(entry) {
-                        if m {
-                            keep = true;
-                            break;
+            // Scrubbed bytes of a kept chunk; chunks that cannot be scrubbed are kept as
+            // they are after a warning
+            let scrub_kept = |entry: &mut McaEntry, kind: &str| -> Result<Option<Vec<u8>>> {
+                let Some(ref fields) = opts.scrub_fields else {
+                    return Ok(None);
+                };
+                match scrub::scrub_chunk(entry, fields, |cm| opts.scrub_encoding(cm)) {
+                    Ok(v) => Ok(v),
+                    Err(e) => {
+                        recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to scrub {} chunk {} in {}, kept unscrubbed: {}",
+                                dim_label,
+                                kind,
+                                entry.region_index(),
+                                name,
+                                e
+                            ),
+                        )?;
+                        Ok(None)
+                    }
+                }
+            };
+            for (done_in_region, mut entry) in region_entries.into_iter().enumerate() {
+                let entry = &mut entry;
+                // held until the chunk and its entities chunk are written
+                let _permit = budget
+                    .as_ref()
+                    .map(|b| b.acquire(entry.declared_length().unwrap_or(0) * DECODED_SIZE_FACTOR));
+                // the keep rules read the chunk anyway; a kept chunk is then copied from
+                // memory instead of being read again. Read errors resurface below
+                if encoding.is_none() && entry.cached_inhabited().is_none() {
+                    let _ = entry.buffer_serialized();
+                }
+                if let Some(ref pb) = dim_pb {
+                    pb.set_message(format!("{} {}/{}", name, done_in_region, region_chunks));
+                }
+                // decoded once here for the index and the heatmap; the keep rules reuse
+                // the cached value. Read errors are neither cached nor indexed, so the
+                // rules report them now and later runs decode the chunk again
+                let read = (index_entries.is_some() || heatmap.is_some())
+                    .then(|| inhabited_time(entry).ok())
+                    .flatten();
+                if let Some(t) = read {
+                    entry.set_cached_inhabited(t);
+                }
+                let inhabited = read.flatten();
+                if let (Some(ref mut built), Some(_)) = (&mut index_entries, read) {
+                    let (size, compression) = entry
+                        .read_header()
+                        .map(|(len, method, _)| (len.saturating_add(4), method.id()))
+                        .unwrap_or((0, 0));
+                    built.push(IndexEntry {
+                        index: entry.region_index() as u16,
+                        compression,
+                        size,
+                        inhabited,
+                    });
+                }
+                if let Some(pct) = opts.audit_inhabited_percent {
+                    // the NBT side of the audit needs the whole chunk in memory
+                    if audit_sampled(entry.global_x(), entry.global_z(), pct)
+                        && !entry.exceeds_memory_limit().unwrap_or(false)
+                    {
+                        match entry.all_data_uncompressed() {
+                            Ok(de) if !de.is_empty() => {
+                                audited.fetch_add(1, Ordering::Relaxed);
+                                let fast = find_inhabited_fast(&de);
+                                let full = find_inhabited_nbt(&de);
+                                if fast != full {
+                                    audit_mismatches.fetch_add(1, Ordering::Relaxed);
+                                    warn!(
+                                        "[{}] InhabitedTime audit mismatch at chunk ({}, {}) in {}: fast scan {:?}, NBT {:?}",
+                                        dim_label,
+                                        entry.global_x(),
+                                        entry.global_z(),
+                                        name,
+                                        fast,
+                                        full
+                                    );
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => recoverable(
+                                opts.strict,
+                                format!(
+                                    "[{}] InhabitedTime audit could not decode chunk {} in {}: {}",
+                                    dim_label,
+                                    entry.region_index(),
+                                    name,
+                                    e
+                                ),
+                            )?,
                         }
-                    } else {
-                        warn!(
-                            "Pattern evaluation failed on chunk {} in {}",
-                            entry.region_index(),
-                            name
-                        );
                     }
                 }
+                if let Some(ref csv) = heatmap {
+                    let seconds = match inhabited {
+                        Some(t) => format!("{:.2}", t as f64 / 20.0),
+                        None => String::new(),
+                    };
+                    writeln!(
+                        csv.lock().unwrap(),
+                        "{},{},{},{}",
+                        dim_label,
+                        entry.global_x(),
+                        entry.global_z(),
+                        seconds
+                    )?;
+                }
+                let (x, z, index) = (entry.global_x(), entry.global_z(), entry.region_index());
+                if let Ok(bytes) = entry.declared_length() {
+                    if largest.as_ref().is_none_or(|l| bytes > l.bytes) {
+                        largest = Some(LargestChunk { bytes, x, z });
+                    }
+                }
+                let mut failed = None;
+                let keep = rules.try_evaluate(entry, &mut |e| {
+                    let msg = format!(
+                        "[{}] Pattern evaluation failed on chunk {} ({}, {}) in {}: {}",
+                        dim_label, index, x, z, name, e
+                    );
+                    if let Err(e) = recoverable(opts.strict, msg) {
+                        failed.get_or_insert(e);
+                    }
+                });
+                if let Some(e) = failed {
+                    return Err(e);
+                }
+                let keep = opts.on_error.decide(keep);
                 if keep {
-                    if let Err(e) = cw.write_entry(entry) {
-                        warn!(
-                            "Failed to write chunk entry {} in {}: {}",
-                            entry.region_index(),
-                            name,
-                            e
-                        );
+                    let scrubbed = scrub_kept(entry, "region")?;
+                    let recompressed = match encoding {
+                        // already re-encoded with `encoding`
+                        _ if scrubbed.is_some() => scrubbed,
+                        Some(enc) => match entry.recompressed_bytes(enc) {
+                            Ok(v) => v,
+                            Err(e) => {
+                                recoverable(opts.strict, format!(
+                                    "[{}] Failed to recompress chunk {} in {}, copying verbatim: {}",
+                                    dim_label,
+                                    entry.region_index(),
+                                    name,
+                                    e
+                                ))?;
+                                None
+                            }
+                        },
+                        None => None,
+                    };
+                    let written = write_rewritten(&mut cw, entry, recompressed);
+                    match written {
+                        Ok(n) => {
+                            kept_bytes.fetch_add(n, Ordering::Relaxed);
+                            kept_total.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to write chunk entry {} in {}: {}",
+                                dim_label,
+                                entry.region_index(),
+                                name,
+                                e
+                            ),
+                        )?,
+                    }
+                    if let Err(e) =
+                        copy_external(entry, &target_dim.join("region"), opts.copy_buffer_kb)
+                    {
+                        recoverable(
+                            opts.strict,
+                            format!(
+                                "[{}] Failed to copy external chunk {} of {}: {}",
+                                dim_label,
+                                entry.region_index(),
+                                name,
+                                e
+                            ),
+                        )?;
                     }
                     if let Some(ref mut erdr) = er {
                         match erdr.get(entry.region_index() as usize) {
                             Ok(Some(mut eentry)) => {
-                                if let Some(ref mut w) = ew {
-                                    if let Err(e) = w.write_entry(&mut eentry) {
-                                        warn!(
-                                            "Failed to write entities entry {} in {}: {}",
+                                check_timestamp_drift(
+                                    &dim_label, &name, "entities", entry, &eentry,
+                                );
+                                let trivial = opts.prune_trivial_entities
+                                    && TrivialEntitiesPattern.matches(&mut eentry).unwrap_or(false);
+                                let hotspot = hotspot_pattern
+                                    .as_ref()
+                                    .and_then(|p| p.excess(&mut eentry).unwrap_or(None));
+                                if let Some(count) = hotspot {
+                                    entity_hotspots.lock().unwrap().push((
+                                        dim_label.clone(),
+                                        entry.global_x(),
+                                        entry.global_z(),
+                                        count,
+                                    ));
+                                }
+                                if trivial {
+                                    pruned_entities.fetch_add(1, Ordering::Relaxed);
+                                } else if let Some(count) =
+                                    hotspot.filter(|_| opts.clear_entity_hotspots)
+                                {
+                                    info!(
+                                        "[{}] Cleared {} entities of chunk ({}, {}) in {}",
+                                        dim_label,
+                                        count,
+                                        entry.global_x(),
+                                        entry.global_z(),
+                                        name
+                                    );
+                                } else if let Some(ref mut w) = ew {
+                                    let scrubbed = scrub_kept(&mut eentry, "entities")?;
+                                    if let Err(e) = write_rewritten(w, &mut eentry, scrubbed) {
+                                        recoverable(
+                                            opts.strict,
+                                            format!(
+                                                "[{}] Failed to write entities entry {} in {}: {}",
+                                                dim_label,
+                                                entry.region_index(),
+                                                name,
+                                                e
+                                            ),
+                                        )?;
+                                    }
+                                    let dst = target_dim.join("entities");
+                                    if let Err(e) =
+                                        copy_external(&mut eentry, &dst, opts.copy_buffer_kb)
+                                    {
+                                        recoverable(opts.strict, format!(
+                                            "[{}] Failed to copy external entities chunk {} of {}: {}",
+                                            dim_label,
                                             entry.region_index(),
                                             name,
                                             e
-                                        );
+                                        ))?;
                                     }
                                 }
                             }
                             Ok(None) => {}
-                            Err(e) => warn!(
-                                "Failed to read entities entry {} in {}: {}",
-                                entry.region_index(),
-                                name,
-                                e
-                            ),
+                            Err(e) => recoverable(
+                                opts.strict,
+                                format!(
+                                    "[{}] Failed to read entities entry {} in {}: {}",
+                                    dim_label,
+                                    entry.region_index(),
+                                    name,
+                                    e
+                                ),
+                            )?,
                         }
                     }
                     if let Some(ref mut prdr) = pr {
                         match prdr.get(entry.region_index() as usize) {
                             Ok(Some(mut pentry)) => {
+                                check_timestamp_drift(&dim_label, &name, "poi", entry, &pentry);
                                 if let Some(ref mut w) = pw {
-                                    if let Err(e) = w.write_entry(&mut pentry) {
-                                        warn!(
-                                            "Failed to write poi entry {} in {}: {}",
+                                    let scrubbed = scrub_kept(&mut pentry, "poi")?;
+                                    if let Err(e) = write_rewritten(w, &mut pentry, scrubbed) {
+                                        recoverable(
+                                            opts.strict,
+                                            format!(
+                                                "[{}] Failed to write poi entry {} in {}: {}",
+                                                dim_label,
+                                                entry.region_index(),
+                                                name,
+                                                e
+                                            ),
+                                        )?;
+                                    }
+                                    let dst = target_dim.join("poi");
+                                    if let Err(e) =
+                                        copy_external(&mut pentry, &dst, opts.copy_buffer_kb)
+                                    {
+                                        recoverable(
+                                            opts.strict,
+                                            format!(
+                                            "[{}] Failed to copy external poi chunk {} of {}: {}",
+                                            dim_label,
                                             entry.region_index(),
                                             name,
                                             e
-                                        );
+                                        ),
+                                        )?;
                                     }
                                 }
                             }
                             Ok(None) => {}
-                            Err(e) => warn!(
-                                "Failed to read poi entry {} in {}: {}",
-                                entry.region_index(),
-                                name,
-                                e
-                            ),
+                            Err(e) => recoverable(
+                                opts.strict,
+                                format!(
+                                    "[{}] Failed to read poi entry {} in {}: {}",
+                                    dim_label,
+                                    entry.region_index(),
+                                    name,
+                                    e
+                                ),
+                            )?,
                         }
                     }
                 } else {
                     removed += 1;
                     removed_total.fetch_add(1, Ordering::Relaxed);
+                    if let Some(ref log) = removal_log {
+                        log.removed(&dim_label, &name, entry.global_x(), entry.global_z());
+                    }
                 }
                 let new_chunks = processed_chunks.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(ref ev) = events {
+                    ev.chunk_done(new_chunks, total_chunks);
+                }
+                if let Some(ref pb) = dim_pb {
+                    pb.inc(1);
+                }
                 if let Some(ref pb) = global_pb {
                     pb.set_position(new_chunks);
                     pb.set_message("");
-                } else {
-                    let pct = (new_chunks * 100 / total_chunks.max(1)) as usize;
-                    let prev = last_pct.load(Ordering::Relaxed);
-                    if pct > prev {
-                        last_pct.store(pct, Ordering::Relaxed);
-                        println!("进度: {}% ({}/{})", pct, new_chunks, total_chunks);
+                }
+                if (global_pb.is_none() || !is_tty) && !opts.json_summary {
+                    let mut last = last_pct.lock().unwrap();
+                    let done = new_chunks.max(processed_chunks.load(Ordering::Relaxed));
+                    let pct = (done * 100 / total_chunks.max(1)) as usize;
+                    if pct > *last {
+                        *last = pct;
+                        report!(opts, "进度: {}% ({}/{})", pct, done, total_chunks);
                     }
                 }
             }
@@ -449,12 +3245,109 @@ pub fn run(
             if let Some(ref mut w) = pw {
                 w.finalize()?;
             }
-            info!("Region {} processed, removed {} chunks", name, removed);
+            if let Some(built) = index_entries {
+                let sidecar = RegionIndex::sidecar_path(&rf);
+                if let Err(e) = RegionIndex::new(&rf, built).and_then(|i| i.write(&sidecar)) {
+                    recoverable(
+                        opts.strict,
+                        format!(
+                            "[{}] Failed to write index {}: {}",
+                            dim_label,
+                            sidecar.display(),
+                            e
+                        ),
+                    )?;
+                }
+            }
+            info!(
+                "[{}] Region {} processed, removed {} chunks",
+                dim_label, name, removed
+            );
+            stream_finished()?;
             let _new = processed_regions.fetch_add(1, Ordering::Relaxed) + 1;
+            if let Some(ref ev) = events {
+                ev.region_done(
+                    &dim_label,
+                    &name,
+                    region_chunks as u64,
+                    removed,
+                    false,
+                    processed_chunks.load(Ordering::Relaxed),
+                );
+            }
+            if opts.sync_interval > 0 {
+                for dir in ["region", "entities", "poi"] {
+                    let p = target_dim.join(dir).join(&name);
+                    if p.is_file() {
+                        unsynced.push(p);
+                    }
+                }
+                unsynced_regions += 1;
+                if unsynced_regions >= opts.sync_interval {
+                    sync_files(&unsynced)?;
+                    unsynced.clear();
+                    unsynced_regions = 0;
+                }
+            }
+        }
+        // aux regions without a region file of the same name are never reached above;
+        // copied as-is, as the in-place copy-back would otherwise delete them
+        for (src_dir, mode) in [(&entities_dir, entities_mode), (&poi_dir, poi_mode)] {
+            if mode != AuxMode::Filter {
+                continue;
+            }
+            let dst_dir = target_dim.join(src_dir.file_name().unwrap());
+            let mut aux_files = regions.files(src_dir)?;
+            if opts.stable_order {
+                aux_files.sort();
+            }
+            for src in aux_files {
+                let name = src.file_name().unwrap();
+                if !opts.region_naming.matches(&src) || regions.is_file(&region_dir.join(name)) {
+                    continue;
+                }
+                warn!(
+                    "[{}] {} has no matching region file, copied as-is",
+                    dim_label,
+                    src.display()
+                );
+                regions.copy(&src, &dst_dir.join(name), opts.copy_buffer_kb)?;
+                if let Ok(mut r) = regions.open_named(&src, &opts.region_naming) {
+                    for entry in r.entries().iter_mut().flatten() {
+                        copy_external(entry, &dst_dir, opts.copy_buffer_kb)?;
+                    }
+                }
+                if opts.sync_interval > 0 {
+                    unsynced.push(dst_dir.join(name));
+                }
+            }
+        }
+        sync_files(&unsynced)?;
+        stream_finished()?;
+        if let Some(pb) = dim_pb {
+            pb.finish_with_message("已完成");
+        }
+        if let Some(l) = largest {
+            largest_chunks.lock().unwrap().insert(dim_label, l);
         }
 
         Ok(())
-    })?;
+    };
+    // --stable-order: one dimension after the other, in discovery (sorted) order
+    let result = if opts.stable_order {
+        tasks.iter().try_for_each(process_dimension)
+    } else {
+        tasks.par_iter().try_for_each(process_dimension)
+    };
+    if let Err(e) = result {
+        if scratch_out {
+            let _ = fs::remove_dir_all(&out);
+        }
+        return Err(e);
+    }
+    if let Some(csv) = heatmap {
+        csv.into_inner().unwrap().flush()?;
+    }
     if let Some(pb) = global_pb {
         let done = processed_chunks.load(Ordering::Relaxed);
         let total = total_chunks.max(1);
@@ -464,14 +3357,194 @@ pub fn run(
     // Second line: summary
     let done = processed_chunks.load(Ordering::Relaxed);
     let removed = removed_total.load(Ordering::Relaxed);
-    let kept = done.saturating_sub(removed);
-    println!(
-        "保留区块总数: {} · 删除区块总数: {} · 总耗时: {:.2}s",
-        kept,
-        removed,
-        start_time.elapsed().as_secs_f64()
-    );
+    let kept = kept_total.load(Ordering::Relaxed);
+    let avg_chunk = kept_bytes.load(Ordering::Relaxed) / kept.max(1);
+    if !opts.json_summary {
+        report!(
+            opts,
+            "保留区块总数: {} · 删除区块总数: {} · 平均区块大小: {} · 总耗时: {:.2}s",
+            kept,
+            removed,
+            fmt_bytes(avg_chunk),
+            start_time.elapsed().as_secs_f64()
+        );
+    }
+    let largest_chunks = largest_chunks.into_inner().unwrap();
+    if !opts.json_summary {
+        for (label, l) in &largest_chunks {
+            report!(
+                opts,
+                "[{}] 最大区块: ({}, {}) · {}",
+                label,
+                l.x,
+                l.z,
+                fmt_bytes(l.bytes)
+            );
+        }
+    }
+    if opts.prune_trivial_entities && !opts.json_summary {
+        report!(
+            opts,
+            "清理仅含琐碎实体的实体区块: {} 个",
+            pruned_entities.load(Ordering::Relaxed)
+        );
+    }
+    if let Some(max) = opts.entity_hotspot_threshold {
+        let mut hotspots = entity_hotspots.into_inner().unwrap();
+        hotspots.sort_by(|a, b| {
+            b.3.cmp(&a.3)
+                .then_with(|| (&a.0, a.1, a.2).cmp(&(&b.0, b.1, b.2)))
+        });
+        if !opts.json_summary {
+            report!(
+                opts,
+                "实体超过 {} 个的区块: {} 个{}",
+                max,
+                hotspots.len(),
+                if opts.clear_entity_hotspots {
+                    "（实体已清除）"
+                } else {
+                    ""
+                }
+            );
+            for (label, x, z, count) in hotspots.iter().take(MAX_LISTED_HOTSPOTS) {
+                report!(
+                    opts,
+                    "  [{}] 区块 ({}, {}) · 方块 ({}, {}) · {} 个实体",
+                    label,
+                    x,
+                    z,
+                    x * 16,
+                    z * 16,
+                    count
+                );
+            }
+            if hotspots.len() > MAX_LISTED_HOTSPOTS {
+                report!(
+                    opts,
+                    "  …… 另有 {} 个",
+                    hotspots.len() - MAX_LISTED_HOTSPOTS
+                );
+            }
+        }
+    }
+    if kept + removed != total_chunks {
+        warn!(
+            "Chunk count mismatch: {} input chunks, {} processed, {} kept + {} removed; \
+             check the warnings above for unreadable regions or failed writes",
+            total_chunks, done, kept, removed
+        );
+        if !opts.json_summary {
+            report!(
+                opts,
+                "警告: 区块数量不一致 · 输入 {} · 保留 {} + 删除 {} = {} · 差异 {}（详见日志）",
+                total_chunks,
+                kept,
+                removed,
+                kept + removed,
+                total_chunks as i64 - (kept + removed) as i64
+            );
+        }
+    }
+    if opts.audit_inhabited_percent.is_some() {
+        let mismatches = audit_mismatches.load(Ordering::Relaxed);
+        if !opts.json_summary {
+            report!(
+                opts,
+                "InhabitedTime 审计: 抽样 {} 个区块 · 不一致 {} 个",
+                audited.load(Ordering::Relaxed),
+                mismatches
+            );
+        }
+        if mismatches > 0 {
+            if scratch_out {
+                fs::remove_dir_all(&out)?;
+            }
+            return Err(anyhow!(
+                "InhabitedTime audit failed: {} chunks disagree between the fast scan and the NBT parse",
+                mismatches
+            ));
+        }
+    }
+    if opts.verify_forced {
+        let dropped = dropped_forced_chunks(&regions, &input, &out, &tasks, opts)?;
+        for (label, x, z) in &dropped {
+            warn!(
+                "[{}] Force-loaded chunk ({}, {}) is missing from the output",
+                label, x, z
+            );
+        }
+        if !opts.json_summary {
+            report!(opts, "强加载区块校验: 缺失 {} 个", dropped.len());
+        }
+        if !dropped.is_empty() {
+            if scratch_out {
+                fs::remove_dir_all(&out)?;
+            }
+            return Err(anyhow!(
+                "forced chunk check failed: {} force-loaded chunks were dropped",
+                dropped.len()
+            ));
+        }
+    }
+    #[cfg(feature = "script")]
+    if let Some(failures) = chunk_script
+        .as_ref()
+        .map(|s| s.failures())
+        .filter(|&n| n > 1)
+    {
+        warn!(
+            "the chunk filter script failed on {} chunks, all of them kept",
+            failures
+        );
+    }
+    if let Some(reason) = guard_violation(opts, kept, removed) {
+        if scratch_out {
+            fs::remove_dir_all(&out)?;
+        }
+        report!(
+            opts,
+            "已中止: 结果超出安全限制，请检查阈值等参数是否设置有误"
+        );
+        return Err(anyhow!(reason));
+    }
+    let out = match staged_into {
+        Some(dest) => {
+            for e in fs::read_dir(&out)? {
+                let e = e?;
+                fs::rename(e.path(), dest.join(e.file_name()))?;
+            }
+            fs::remove_dir(&out)?;
+            dest
+        }
+        None => out,
+    };
+    if output.is_some() && opts.copy_world_files {
+        copy_world_files(&input, &out, &tasks, &opts.copy_ignore)?;
+    }
+    if let Some(tar) = tar {
+        // the world files, and anything else left of this world
+        streamed_bytes.fetch_add(tar.drain(&out, usize::MAX)?, Ordering::Relaxed);
+    }
     if output.is_none() {
+        // a dimension missing from the staged output would keep its stale input files
+        let diverged = output_divergence(&input, &out, &tasks, opts.max_dimension_depth)?;
+        if !diverged.is_empty() {
+            let msg = format!(
+                "in-place output does not match the processed dimensions: {}",
+                diverged.join(", ")
+            );
+            if opts.strict {
+                fs::remove_dir_all(&out)?;
+                return Err(anyhow!("{}; the input was left untouched", msg));
+            }
+            warn!("{}; those folders of the input are left as they were", msg);
+            report!(
+                opts,
+                "警告: 临时输出缺少部分维度目录（{}），这些目录未被替换",
+                diverged.join("、")
+            );
+        }
         for dim in &tasks {
             let rel = dim.strip_prefix(&input).unwrap_or(dim);
             let out_dim = out.join(rel);
@@ -486,14 +3559,14 @@ pub fn run(
                 let mut keep: std::collections::HashSet<String> = std::collections::HashSet::new();
                 for e in fs::read_dir(&src)? {
                     let p = e?.path();
-                    if p.extension().and_then(|s| s.to_str()) == Some("mca") {
+                    if is_region_file(&p, &opts.region_naming) {
                         keep.insert(p.file_name().unwrap().to_string_lossy().to_string());
                     }
                 }
                 if dst.is_dir() {
                     for e in fs::read_dir(&dst)? {
                         let p = e?.path();
-                        if p.extension().and_then(|s| s.to_str()) == Some("mca") {
+                        if is_region_file(&p, &opts.region_naming) {
                             let fname = p.file_name().unwrap().to_string_lossy().to_string();
                             if !keep.contains(&fname) {
                                 let _ = fs::remove_file(p);
@@ -503,47 +3576,157 @@ pub fn run(
                 }
                 for e in fs::read_dir(&src)? {
                     let p = e?.path();
-                    if p.extension().and_then(|s| s.to_str()) == Some("mca") {
+                    if is_region_file(&p, &opts.region_naming) {
                         let target = dst.join(p.file_name().unwrap());
-                        fs::copy(&p, &target)?;
+                        regions::copy_file(&p, &target, opts.copy_buffer_kb)?;
                     }
                 }
             }
         }
         fs::remove_dir_all(&out)?;
-        let after_size = dir_size(&input);
-        let pct = if before_size > 0 {
-            (1.0 - (after_size as f64 / before_size as f64)) * 100.0
+    }
+    let summary = RunSummary {
+        kept,
+        removed,
+        before_bytes: before_size,
+        after_bytes: match tar {
+            Some(_) => streamed_bytes.load(Ordering::Relaxed),
+            None => dir_size(if output.is_none() { &input } else { &out }),
+        },
+        elapsed_secs: start_time.elapsed().as_secs_f64(),
+        largest_chunks,
+    };
+    if let Some(ref ev) = events {
+        ev.summary(&summary.json_fields());
+    }
+    Ok(Some(summary))
+}
+
+/// Final statistics of a run, printed by `--json-summary` as
+/// `{"kept":N,"removed":N,"before_bytes":N,"after_bytes":N,"reduction_pct":F,"elapsed_secs":F,"largest_chunks":[..]}`.
+/// Fields are only ever added, never renamed or removed.
+struct RunSummary {
+    kept: u64,
+    removed: u64,
+    before_bytes: u64,
+    after_bytes: u64,
+    elapsed_secs: f64,
+    /// Per dimension label, as `{"dimension":S,"x":N,"z":N,"bytes":N}` entries
+    largest_chunks: BTreeMap<String, LargestChunk>,
+}
+
+/// The chunk with the largest serialized size (length field plus declared length) a
+/// dimension's scan saw, kept or not. Chunks of regions copied verbatim are not read.
+#[derive(Clone, Copy)]
+struct LargestChunk {
+    bytes: u64,
+    x: i32,
+    z: i32,
+}
+
+impl RunSummary {
+    fn reduction_pct(&self) -> f64 {
+        if self.before_bytes > 0 {
+            (1.0 - (self.after_bytes as f64 / self.before_bytes as f64)) * 100.0
         } else {
             0.0
-        };
-        println!(
-            "处理前: {} | 处理后: {} | 缩减: {} ({:.2}%)",
-            fmt_bytes(before_size),
-            fmt_bytes(after_size),
-            fmt_bytes(before_size.saturating_sub(after_size)),
-            pct
-        );
-    } else {
-        let after_size = dir_size(&out);
-        let pct = if before_size > 0 {
-            (1.0 - (after_size as f64 / before_size as f64)) * 100.0
+        }
+    }
+
+    fn to_json(&self) -> String {
+        format!("{{{}}}", self.json_fields())
+    }
+
+    /// The JSON line under `opts.json_summary`, or the human before/after line.
+    fn print(&self, opts: &RunOptions) {
+        if opts.json_summary {
+            report!(opts, "{}", self.to_json());
         } else {
-            0.0
-        };
-        println!(
-            "处理前: {} | 处理后: {} | 缩减: {} ({:.2}%)",
-            fmt_bytes(before_size),
-            fmt_bytes(after_size),
-            fmt_bytes(before_size.saturating_sub(after_size)),
-            pct
-        );
+            report!(
+                opts,
+                "处理前: {} | 处理后: {} | 缩减: {} ({:.2}%)",
+                fmt_bytes(self.before_bytes),
+                fmt_bytes(self.after_bytes),
+                fmt_bytes(self.before_bytes.saturating_sub(self.after_bytes)),
+                self.reduction_pct()
+            );
+        }
     }
-    Ok(())
+
+    /// The members of `to_json`, without the braces.
+    fn json_fields(&self) -> String {
+        format!(
+            "\"kept\":{},\"removed\":{},\"before_bytes\":{},\"after_bytes\":{},\"reduction_pct\":{:.2},\"elapsed_secs\":{:.3},\"largest_chunks\":[{}]",
+            self.kept,
+            self.removed,
+            self.before_bytes,
+            self.after_bytes,
+            self.reduction_pct(),
+            self.elapsed_secs,
+            self.largest_chunks
+                .iter()
+                .map(|(label, l)| format!(
+                    "{{\"dimension\":{},\"x\":{},\"z\":{},\"bytes\":{}}}",
+                    serde_json::Value::from(label.as_str()),
+                    l.x,
+                    l.z,
+                    l.bytes
+                ))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    }
+}
+/// What `run_with_options` accepts as the output directory
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum OutputPolicy {
+    /// A path that does not exist yet (it is created) or an existing empty directory
+    #[default]
+    AllowEmpty,
+    /// Only a path that does not exist yet, so the output is guaranteed fresh
+    MustCreate,
+}
+
+/// What happens to a chunk whose keep patterns all failed on it (undecodable data)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, ValueEnum)]
+pub enum OnError {
+    #[default]
+    Keep,
+    Remove,
 }
+
+impl OnError {
+    /// The keep decision for a `KeepRules::try_evaluate` result.
+    pub fn decide(self, evaluated: Option<bool>) -> bool {
+        evaluated.unwrap_or(self == OnError::Keep)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
 pub enum ProgressMode {
     Off,
     Global,
     Region,
 }
+
+/// Per-chunk auxiliary folders mirrored next to `region/`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AuxKind {
+    Entities,
+    Poi,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AuxMode {
+    /// Keep only the chunks whose region chunk is kept
+    Filter,
+    Skip,
+    Verbatim,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Recompression {
+    Zlib,
+    /// LZ4Block, as written by Minecraft 1.20.5+ with `region-file-compression=lz4`
+    Lz4,
+}
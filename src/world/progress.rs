@@ -0,0 +1,43 @@
+/// Narrowest bar worth drawing; below it the numbers alone are shown.
+pub const MIN_BAR_WIDTH: u16 = 10;
+/// Widest bar drawn, however wide the terminal.
+pub const MAX_BAR_WIDTH: u16 = 50;
+/// Columns the compact layout needs: spinner, `pos/len`, unit and percent of a world
+/// with millions of chunks. Narrower terminals get the spinner alone.
+pub const MIN_COMPACT_COLUMNS: u16 = 30;
+
+/// How a progress bar fits a terminal `cols` wide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BarLayout {
+    /// A bar this many columns wide, followed by the numbers and the message
+    Bar(usize),
+    /// The numbers without bar or message
+    Compact,
+    /// Only the leading spinner (or prefix)
+    Spinner,
+}
+
+impl BarLayout {
+    /// The layout for a terminal `cols` wide when the text around the bar takes
+    /// `reserve` columns.
+    pub fn for_columns(cols: u16, reserve: u16) -> Self {
+        match cols.saturating_sub(reserve) {
+            room if room >= MIN_BAR_WIDTH => BarLayout::Bar(room.min(MAX_BAR_WIDTH) as usize),
+            _ if cols >= MIN_COMPACT_COLUMNS => BarLayout::Compact,
+            _ => BarLayout::Spinner,
+        }
+    }
+
+    /// An indicatif template: `lead` (e.g. `{spinner:.green}`), then the bar in
+    /// `colors` (e.g. `cyan/blue`), `counter` (e.g. `{pos}/{len}`) and `{msg}`, with
+    /// the parts the layout has no room for left out.
+    pub fn template(&self, lead: &str, colors: &str, counter: &str) -> String {
+        match self {
+            BarLayout::Bar(width) => {
+                format!("{} {{bar:{}.{}}} {} {{msg}}", lead, width, colors, counter)
+            }
+            BarLayout::Compact => format!("{} {}", lead, counter),
+            BarLayout::Spinner => lead.to_string(),
+        }
+    }
+}
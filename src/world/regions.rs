@@ -0,0 +1,389 @@
+use crate::mca::naming::RegionNaming;
+use crate::mca::reader::McaReader;
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zip::ZipArchive;
+
+/// Region files smaller than the two header tables hold no chunks.
+const MIN_MCA_LEN: u64 = 8192;
+
+/// Where `run_with_options` reads region files from.
+///
+/// A zipped world is never unpacked whole: its small files (level.dat, chunks.dat,
+/// `.mcc`, ...) are extracted to a staging directory that stands in for the world
+/// directory, while `.mca` files stay in the archive and are read into memory one at
+/// a time. Paths handed to these methods are paths inside that staging directory.
+pub enum RegionFiles {
+    Disk,
+    Zip(ZipWorld),
+}
+
+pub struct ZipWorld {
+    archive: Mutex<ZipArchive<fs::File>>,
+    staging: PathBuf,
+    /// Staging path of every `.mca` entry → (archive index, uncompressed size)
+    regions: HashMap<PathBuf, (usize, u64)>,
+    total_size: u64,
+    /// Archives extracted from the outer ones to reach the world, see `open`
+    nested: Vec<PathBuf>,
+}
+
+/// Archive extensions read as zipped worlds; `.mcworld` is a zip as well.
+const ZIP_EXTENSIONS: [&str; 2] = ["zip", "mcworld"];
+
+/// How many archives wrapped in one another `ZipWorld::open` unwraps.
+const MAX_NESTED_ARCHIVES: usize = 3;
+
+pub fn is_zip(path: &Path) -> bool {
+    path.is_file() && has_zip_extension(path)
+}
+
+fn has_zip_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|e| ZIP_EXTENSIONS.iter().any(|z| e.eq_ignore_ascii_case(z)))
+}
+
+/// A Bedrock world keeps its chunks in a LevelDB `db/` folder instead of regions.
+fn is_bedrock_db(name: &Path) -> bool {
+    let in_db = name
+        .parent()
+        .and_then(|p| p.file_name())
+        .is_some_and(|p| p == "db");
+    let file = name.file_name().and_then(|s| s.to_str()).unwrap_or("");
+    in_db && (file == "CURRENT" || file.ends_with(".ldb"))
+}
+
+/// Folder metadata left by file managers and archivers of other platforms
+const JUNK_FILE_NAMES: [&str; 3] = [".DS_Store", "Thumbs.db", "desktop.ini"];
+
+/// Finder/Explorer metadata (`.DS_Store`, `Thumbs.db`, `desktop.ini`) and macOS
+/// AppleDouble `._*` files, which an archive extracted elsewhere may leave next to
+/// real regions, `._r.0.0.mca` even passing the extension check.
+pub fn is_junk_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    name.starts_with("._") || JUNK_FILE_NAMES.iter().any(|j| j.eq_ignore_ascii_case(name))
+}
+
+fn is_mca(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("mca")
+}
+
+impl ZipWorld {
+    /// Opens `path` and stages everything but its `.mca` files; junk (`is_junk_file`) is
+    /// dropped. The world root is the shallowest folder holding `level.dat`, or the
+    /// archive root without one.
+    ///
+    /// An archive holding neither `level.dat` nor regions but a single `.zip`/`.mcworld`
+    /// (a backup of a zipped world) is unwrapped: the inner archive is extracted to the
+    /// temporary directory and opened instead, up to `MAX_NESTED_ARCHIVES` deep. Bedrock
+    /// worlds are rejected.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut nested = Vec::new();
+        let mut current = path.to_path_buf();
+        loop {
+            match Self::open_archive(&current) {
+                Ok(Opened::World(mut world)) => {
+                    world.nested = nested;
+                    return Ok(world);
+                }
+                Ok(Opened::Nested(inner)) if nested.len() < MAX_NESTED_ARCHIVES => {
+                    debug!(
+                        "{} wraps {}, unwrapping it",
+                        current.display(),
+                        inner.display()
+                    );
+                    nested.push(inner.clone());
+                    current = inner;
+                }
+                Ok(Opened::Nested(_)) => {
+                    Self::remove_nested(&nested);
+                    return Err(anyhow!(
+                        "{}: more than {} archives nested in one another",
+                        path.display(),
+                        MAX_NESTED_ARCHIVES
+                    ));
+                }
+                Err(e) => {
+                    Self::remove_nested(&nested);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn remove_nested(nested: &[PathBuf]) {
+        for p in nested {
+            let _ = fs::remove_file(p);
+        }
+    }
+
+    fn open_archive(path: &Path) -> Result<Opened> {
+        let file = fs::File::open(path)?;
+        let mut archive = ZipArchive::new(file)
+            .map_err(|e| anyhow!("cannot read zip {}: {}", path.display(), e))?;
+        let mut names = Vec::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let entry = archive.by_index(i)?;
+            // enclosed_name rejects absolute and `..` paths
+            match entry.enclosed_name() {
+                Some(name) => names.push(Some(name.to_path_buf())),
+                None => {
+                    warn!("Skipping unsafe zip entry {}", entry.name());
+                    names.push(None);
+                }
+            }
+        }
+        let files: Vec<&PathBuf> = names
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| archive.by_index(*i).is_ok_and(|e| !e.is_dir()))
+            .filter_map(|(_, n)| n.as_ref())
+            .filter(|n| !is_junk_file(n))
+            .collect();
+        // a Bedrock world has a level.dat too, but no regions
+        let has_regions = files.iter().any(|n| is_mca(n));
+        if !has_regions && files.iter().any(|n| is_bedrock_db(n)) {
+            return Err(anyhow!(
+                "{} is a Bedrock world (LevelDB db/ folder); only Java Edition region files can be processed",
+                path.display()
+            ));
+        }
+        let has_world = has_regions
+            || files
+                .iter()
+                .any(|n| n.file_name() == Some("level.dat".as_ref()));
+        if !has_world {
+            if let [inner] = files.as_slice() {
+                if has_zip_extension(inner) {
+                    let index = names
+                        .iter()
+                        .position(|n| n.as_ref() == Some(*inner))
+                        .unwrap();
+                    let dst = std::env::temp_dir()
+                        .join(format!("thanos-nested-{}.zip", uuid::Uuid::new_v4()));
+                    let mut entry = archive.by_index(index)?;
+                    std::io::copy(&mut entry, &mut fs::File::create(&dst)?)?;
+                    return Ok(Opened::Nested(dst));
+                }
+            }
+        }
+        let root = names
+            .iter()
+            .flatten()
+            .filter(|n| n.file_name() == Some("level.dat".as_ref()))
+            .filter_map(|n| n.parent())
+            .min_by_key(|p| p.components().count())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        let staging = std::env::temp_dir().join(format!("thanos-zip-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&staging)?;
+        let mut world = Self {
+            archive: Mutex::new(archive),
+            staging,
+            regions: HashMap::new(),
+            total_size: 0,
+            nested: Vec::new(),
+        };
+        let archive = world.archive.get_mut().unwrap();
+        for (i, name) in names.iter().enumerate() {
+            let Some(rel) = name.as_ref().and_then(|n| n.strip_prefix(&root).ok()) else {
+                continue;
+            };
+            let mut entry = archive.by_index(i)?;
+            let dst = world.staging.join(rel);
+            if entry.is_dir() {
+                fs::create_dir_all(&dst)?;
+                continue;
+            }
+            if is_junk_file(&dst) {
+                continue;
+            }
+            fs::create_dir_all(dst.parent().unwrap())?;
+            world.total_size += entry.size();
+            if is_mca(&dst) {
+                world.regions.insert(dst, (i, entry.size()));
+            } else {
+                std::io::copy(&mut entry, &mut fs::File::create(&dst)?)?;
+            }
+        }
+        Ok(Opened::World(world))
+    }
+}
+
+enum Opened {
+    World(ZipWorld),
+    /// The archive only wraps another one, extracted to this temporary file
+    Nested(PathBuf),
+}
+
+impl ZipWorld {
+    /// Decompresses at most `limit` bytes of the `.mca` entry staged at `path`.
+    fn read(&self, path: &Path, limit: u64) -> Result<Arc<[u8]>> {
+        let mut archive = self.archive.lock().unwrap();
+        let entry = archive.by_index(self.regions[path].0)?;
+        let mut data = Vec::with_capacity(entry.size().min(limit) as usize);
+        entry.take(limit).read_to_end(&mut data)?;
+        Ok(data.into())
+    }
+}
+
+impl Drop for ZipWorld {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.staging);
+        Self::remove_nested(&self.nested);
+    }
+}
+
+/// Copies `src` to `dst` like `fs::copy`, or through a buffer of `buffer_kb` KiB
+/// when given, for storage where the platform copy's fixed chunking is slow.
+/// Returns the number of bytes copied.
+pub fn copy_file(src: &Path, dst: &Path, buffer_kb: Option<usize>) -> std::io::Result<u64> {
+    let Some(kb) = buffer_kb else {
+        return fs::copy(src, dst);
+    };
+    let mut input = fs::File::open(src)?;
+    let mut output = fs::File::create(dst)?;
+    let mut buf = vec![0u8; kb.max(1) * 1024];
+    let mut copied = 0u64;
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        output.write_all(&buf[..n])?;
+        copied += n as u64;
+    }
+    output.set_permissions(input.metadata()?.permissions())?;
+    Ok(copied)
+}
+
+impl RegionFiles {
+    /// The directory to treat as the world: `input` itself, or the zip's staging copy.
+    pub fn root<'a>(&'a self, input: &'a Path) -> &'a Path {
+        match self {
+            RegionFiles::Disk => input,
+            RegionFiles::Zip(z) => &z.staging,
+        }
+    }
+
+    /// Bytes of the world before processing, compressed zip entries counted uncompressed.
+    pub fn total_size(&self) -> Option<u64> {
+        match self {
+            RegionFiles::Disk => None,
+            RegionFiles::Zip(z) => Some(z.total_size),
+        }
+    }
+
+    /// Every file directly inside `dir`, junk (`is_junk_file`) excluded.
+    pub fn files(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut out = Vec::new();
+        if dir.is_dir() {
+            for e in fs::read_dir(dir)? {
+                let p = e?.path();
+                if p.is_file() {
+                    out.push(p);
+                }
+            }
+        }
+        if let RegionFiles::Zip(z) = self {
+            out.extend(
+                z.regions
+                    .keys()
+                    .filter(|p| p.parent() == Some(dir))
+                    .cloned(),
+            );
+        }
+        out.retain(|p| {
+            let junk = is_junk_file(p);
+            if junk {
+                debug!("Skipping junk file {}", p.display());
+            }
+            !junk
+        });
+        Ok(out)
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        match self {
+            RegionFiles::Zip(z) if z.regions.contains_key(path) => true,
+            _ => path.is_file(),
+        }
+    }
+
+    fn len(&self, path: &Path) -> std::io::Result<u64> {
+        match self {
+            RegionFiles::Zip(z) if z.regions.contains_key(path) => Ok(z.regions[path].1),
+            _ => path.metadata().map(|m| m.len()),
+        }
+    }
+
+    /// A region file large enough to hold a header; warns about and rejects tiny ones.
+    pub fn is_valid_mca(&self, path: &Path) -> bool {
+        match self.len(path) {
+            Ok(len) if len >= MIN_MCA_LEN => true,
+            Ok(len) => {
+                warn!("Skipping tiny MCA file: {} ({} bytes)", path.display(), len);
+                false
+            }
+            Err(e) => {
+                warn!("Failed to stat MCA file {}: {}", path.display(), e);
+                false
+            }
+        }
+    }
+
+    /// Occupied slots in the location table of a region named after `naming`; only
+    /// the header is read.
+    pub fn chunk_count_named(&self, path: &Path, naming: &RegionNaming) -> Result<usize> {
+        let mut reader = match self {
+            RegionFiles::Zip(z) if z.regions.contains_key(path) => {
+                let header = z.read(path, MIN_MCA_LEN)?;
+                McaReader::from_source_named(&path.to_string_lossy(), Cursor::new(header), naming)?
+            }
+            _ => McaReader::open_named(&path.to_string_lossy(), naming)?,
+        };
+        Ok(reader.chunk_count()?)
+    }
+
+    /// Opens a region named after `naming`, reading a zip member whole.
+    pub fn open_named(&self, path: &Path, naming: &RegionNaming) -> Result<McaReader> {
+        let name = path.to_string_lossy();
+        match self {
+            RegionFiles::Zip(z) if z.regions.contains_key(path) => {
+                let data = z.read(path, u64::MAX)?;
+                let dir = path.parent().map(Path::to_path_buf);
+                Ok(
+                    McaReader::from_source_named(&name, Cursor::new(data), naming)?
+                        .with_external_dir(dir),
+                )
+            }
+            _ => Ok(McaReader::open_named(&name, naming)?),
+        }
+    }
+
+    /// Copies the region at `path` unchanged; see `copy_file` for `buffer_kb`, which
+    /// zipped regions, already decompressed into memory, ignore.
+    pub fn copy(&self, path: &Path, dst: &Path, buffer_kb: Option<usize>) -> Result<()> {
+        match self {
+            RegionFiles::Zip(z) if z.regions.contains_key(path) => {
+                // already in memory, written in one go
+                fs::write(dst, z.read(path, u64::MAX)?)?;
+            }
+            _ => {
+                copy_file(path, dst, buffer_kb)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use crate::mca::entry::{ChunkEncoding, CompressionMethod, McaEntry};
+use anyhow::{anyhow, Result};
+use fastnbt::Value;
+
+/// Tags removed by `--scrub` unless `--scrub-fields` replaces the list. Each one only
+/// carries who did something, never what the chunk is made of:
+///
+/// - `Owner`, `Thrower`: UUID of a tamed animal's owner, of a projectile's shooter and
+///   of whoever dropped an item
+/// - `Trusted`, `LoveCause`, `AngryAt`: players a fox trusts, who bred an animal, and
+///   whom a wolf, bee or golem is angry at
+/// - `Gossips`: a villager's reputation entries, keyed by player UUID
+/// - `LastOutput`: a command block's last message, which often names a player
+/// - `SkullOwner`, `profile`: the player behind a player head (before/since 1.20.5)
+/// - `author`: the author of a written book
+/// - `Text1`..`Text4`, `front_text`, `back_text`: sign text (before/since 1.20)
+pub const DEFAULT_SCRUB_FIELDS: [&str; 16] = [
+    "Owner",
+    "Thrower",
+    "Trusted",
+    "LoveCause",
+    "AngryAt",
+    "Gossips",
+    "LastOutput",
+    "SkullOwner",
+    "profile",
+    "author",
+    "Text1",
+    "Text2",
+    "Text3",
+    "Text4",
+    "front_text",
+    "back_text",
+];
+
+/// Removes every tag named in `fields` from `v`, at any depth; the number removed.
+fn remove_fields(v: &mut Value, fields: &[String]) -> usize {
+    match v {
+        Value::Compound(m) => {
+            let before = m.len();
+            m.retain(|k, _| !fields.contains(k));
+            let nested: usize = m.values_mut().map(|v| remove_fields(v, fields)).sum();
+            before - m.len() + nested
+        }
+        Value::List(items) => items.iter_mut().map(|v| remove_fields(v, fields)).sum(),
+        _ => 0,
+    }
+}
+
+/// Uncompressed NBT with every tag named in `fields` removed at any depth; `None` when
+/// none occurs. Fails on data that is not an NBT compound.
+pub fn scrub_nbt(data: &[u8], fields: &[String]) -> Result<Option<Vec<u8>>> {
+    let mut root: Value =
+        fastnbt::from_bytes(data).map_err(|e| anyhow!("cannot parse chunk NBT: {}", e))?;
+    if !matches!(root, Value::Compound(_)) {
+        return Err(anyhow!("chunk NBT is not a compound"));
+    }
+    if remove_fields(&mut root, fields) == 0 {
+        return Ok(None);
+    }
+    Ok(Some(fastnbt::to_bytes(&root).map_err(|e| {
+        anyhow!("cannot serialize scrubbed chunk NBT: {}", e)
+    })?))
+}
+
+/// Serialized bytes of `entry` scrubbed of `fields` and encoded by `encoding` (given
+/// the chunk's own method); `None` when no field occurs, leaving the chunk as it is.
+/// External, custom-compressed and over-limit chunks cannot be rewritten and fail,
+/// so they are never kept unscrubbed silently. Empty payloads have nothing to scrub.
+pub fn scrub_chunk(
+    entry: &mut McaEntry,
+    fields: &[String],
+    encoding: impl FnOnce(CompressionMethod) -> ChunkEncoding,
+) -> Result<Option<Vec<u8>>> {
+    let (_, cm, _) = entry.read_header()?;
+    if !matches!(
+        cm,
+        CompressionMethod::Raw
+            | CompressionMethod::Zlib
+            | CompressionMethod::Gzip
+            | CompressionMethod::Lz4
+    ) {
+        return Err(anyhow!("{} chunks cannot be scrubbed", cm.name()));
+    }
+    if entry.exceeds_memory_limit()? {
+        return Err(anyhow!(
+            "chunk exceeds the memory limit and cannot be scrubbed"
+        ));
+    }
+    let data = entry.all_data_uncompressed()?;
+    if data.is_empty() {
+        return Ok(None);
+    }
+    match scrub_nbt(&data, fields)? {
+        Some(scrubbed) => Ok(Some(encoding(cm).serialize(&scrubbed)?)),
+        None => Ok(None),
+    }
+}
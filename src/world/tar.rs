@@ -0,0 +1,78 @@
+use super::regions::is_junk_file;
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+/// The archive `RunOptions::tar_stdout` streams to stdout. Runs write into the
+/// scratch directory `root`, and every file is appended as soon as it is finished and
+/// then deleted, so the scratch never holds more than the regions in progress.
+/// Dimensions processed in parallel share one stream; each append is whole.
+pub struct TarStream {
+    root: PathBuf,
+    builder: Mutex<::tar::Builder<BufWriter<Stdout>>>,
+    files: AtomicU64,
+}
+
+impl TarStream {
+    /// A stream to stdout, its scratch directory under the system temp directory (not
+    /// created yet: runs create their output themselves).
+    pub fn stdout() -> Self {
+        let root = std::env::temp_dir().join(format!("thanos-tar-{}", uuid::Uuid::new_v4()));
+        TarStream {
+            root,
+            builder: Mutex::new(::tar::Builder::new(BufWriter::new(io::stdout()))),
+            files: AtomicU64::new(0),
+        }
+    }
+
+    /// The scratch directory; entries are named after paths relative to it.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Moves the files below `dir`, down to `max_depth` levels (1: only its own),
+    /// into the archive in name order, junk (`is_junk_file`) left out. Returns the
+    /// bytes archived.
+    pub fn drain(&self, dir: &Path, max_depth: usize) -> Result<u64> {
+        if !dir.is_dir() {
+            return Ok(0);
+        }
+        let files: Vec<PathBuf> = WalkDir::new(dir)
+            .max_depth(max_depth)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && !is_junk_file(e.path()))
+            .map(|e| e.into_path())
+            .collect();
+        let mut bytes = 0;
+        let mut builder = self.builder.lock().unwrap();
+        for path in files {
+            let name = path.strip_prefix(&self.root).unwrap_or(&path);
+            let mut file = File::open(&path)?;
+            bytes += file.metadata()?.len();
+            builder.append_file(name, &mut file)?;
+            fs::remove_file(&path)?;
+            self.files.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(bytes)
+    }
+
+    /// Ends the archive and flushes stdout. Returns the number of files archived.
+    pub fn finish(&self) -> Result<u64> {
+        let mut builder = self.builder.lock().unwrap();
+        builder.finish()?;
+        builder.get_mut().flush()?;
+        Ok(self.files.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for TarStream {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use rust_thanos::mca::error::McaError;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn wrapped_size_byte_is_detected() {
+    let dir = common::temp_dir("rt-alloc");
+    let path = dir.join("r.0.0.mca");
+    // two sectors of payload, followed by a one-sector chunk
+    let big = common::chunk_bytes(3, &vec![0u8; 5000]);
+    let small = common::chunk_bytes(3, &common::inhabited_payload(1));
+    common::write_region(&path, &[(0, big), (1, small)]);
+
+    let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let mut entries = r.entries().unwrap();
+    assert!(!entries[0].exceeds_allocation().unwrap());
+    assert!(!entries[1].exceeds_allocation().unwrap());
+
+    // size byte of chunk 0 claims a single sector
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[3] = 1;
+    fs::write(&path, bytes).unwrap();
+
+    let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let mut entries = r.entries().unwrap();
+    assert_eq!(entries[0].allocated_length(), 4096);
+    assert_eq!(entries[0].declared_length().unwrap(), 5005);
+    assert!(entries[0].exceeds_allocation().unwrap());
+    assert!(!entries[1].exceeds_allocation().unwrap());
+}
+
+#[test]
+fn overlong_chunks_are_not_copied_past_their_sectors() {
+    let world = common::temp_dir("rt-alloc-world");
+    let path = world.join("region").join("r.0.0.mca");
+    let mut padded = vec![0u8; 5000];
+    padded.extend(common::inhabited_payload(100_000));
+    let big = common::chunk_bytes(3, &padded);
+    let small = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    common::write_region(&path, &[(0, big), (1, small.clone())]);
+    let mut bytes = fs::read(&path).unwrap();
+    bytes[3] = 1;
+    fs::write(&path, bytes).unwrap();
+
+    let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let mut entries = r.entries().unwrap();
+    let err = entries[0].serialized_bytes().unwrap_err();
+    assert!(
+        matches!(
+            err,
+            McaError::ExceedsAllocation {
+                allocated: 4096,
+                ..
+            }
+        ),
+        "{}",
+        err
+    );
+    assert_eq!(entries[1].serialized_bytes().unwrap(), small);
+
+    let out = common::temp_dir("rt-alloc-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let mut r = McaReader::open(
+        out.join("region")
+            .join("r.0.0.mca")
+            .to_string_lossy()
+            .as_ref(),
+    )
+    .unwrap();
+    let mut entries = r.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].region_index(), 1);
+    assert_eq!(entries[0].serialized_bytes().unwrap(), small);
+}
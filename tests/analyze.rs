@@ -0,0 +1,148 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::analyze::{analyze_world, inhabited_bucket};
+use std::process::Command;
+
+fn thanos() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+}
+
+#[test]
+fn buckets_split_at_the_second_bounds() {
+    assert_eq!(inhabited_bucket(0), 0);
+    assert_eq!(inhabited_bucket(1), 1);
+    assert_eq!(inhabited_bucket(60 * 20 - 1), 1);
+    assert_eq!(inhabited_bucket(60 * 20), 2);
+    assert_eq!(inhabited_bucket(300 * 20), 3);
+    assert_eq!(inhabited_bucket(7200 * 20), 5);
+    assert_eq!(inhabited_bucket(i64::MAX), 5);
+}
+
+#[test]
+fn analyzes_compression_inhabited_and_errors() {
+    let world = common::temp_dir("rt-analyze");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(0))),
+            (
+                1,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (2, common::chunk_bytes(3, &common::nbt_compound(vec![]))),
+            (3, common::chunk_bytes(2, b"not zlib")),
+        ],
+    );
+    common::write_dimension(&world.join("DIM-1"), 2_000);
+
+    let a = analyze_world(&world, 3, &RegionNaming::default()).unwrap();
+    assert_eq!(a.dimensions, vec![".", "DIM-1"]);
+    assert_eq!((a.regions, a.chunks), (2, 5));
+    let methods: Vec<(&str, u64)> = a
+        .compression_methods()
+        .into_iter()
+        .map(|(m, n)| (m.name(), n))
+        .collect();
+    assert_eq!(methods, vec![("ZLIB", 1), ("RAW", 4)]);
+    assert_eq!(a.inhabited, [1, 0, 1, 0, 1, 0]);
+    assert_eq!(a.inhabited_unknown, 1);
+    assert_eq!(a.errors.len(), 1);
+    assert!(a.errors[0].contains("chunk 3"), "{}", a.errors[0]);
+    assert!(a.total_bytes > 2 * 8192);
+}
+
+#[test]
+fn analyze_and_verify_write_nothing() {
+    let world = common::temp_dir("rt-analyze-ro");
+    common::write_dimension(&world, 100_000);
+    let before: Vec<_> = walkdir::WalkDir::new(&world)
+        .into_iter()
+        .map(|e| e.unwrap().into_path())
+        .collect();
+
+    let output = thanos().arg("analyze").arg(&world).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("1 个区块"), "{}", stdout);
+    assert!(thanos()
+        .arg("verify")
+        .arg(&world)
+        .status()
+        .unwrap()
+        .success());
+
+    let after: Vec<_> = walkdir::WalkDir::new(&world)
+        .into_iter()
+        .map(|e| e.unwrap().into_path())
+        .collect();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn verify_fails_on_undecodable_chunks() {
+    let world = common::temp_dir("rt-verify");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(5, common::chunk_bytes(2, b"not zlib"))],
+    );
+    let output = thanos().arg("verify").arg(&world).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("chunk 5"));
+}
+
+#[test]
+fn run_subcommand_is_the_bare_form() {
+    let world = common::temp_dir("rt-run-alias");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    let mut kept = Vec::new();
+    for prefix in [&["run"][..], &[][..]] {
+        let out = common::temp_dir("rt-run-alias-out");
+        let status = thanos()
+            .args(prefix)
+            .arg(&world)
+            .arg(&out)
+            .args(["--progress-mode", "off", "-t", "1"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        kept.push(std::fs::read(out.join("region").join("r.0.0.mca")).unwrap());
+    }
+    assert_eq!(kept[0], kept[1]);
+}
+
+#[test]
+fn dump_writes_the_chunk_nbt() {
+    let world = common::temp_dir("rt-dump");
+    let nbt = common::inhabited_payload(42);
+    common::write_region(
+        &world.join("DIM-1").join("region").join("r.-1.0.mca"),
+        &[(31 + 32 * 2, common::chunk_bytes(3, &nbt))],
+    );
+    let file = world.join("chunk.nbt");
+    let status = thanos()
+        .arg("dump")
+        .arg(&world)
+        .args(["-1", "2", "--dim", "DIM-1", "-o"])
+        .arg(&file)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(std::fs::read(&file).unwrap(), nbt);
+    assert!(!thanos()
+        .arg("dump")
+        .arg(&world)
+        .args(["0", "0"])
+        .status()
+        .unwrap()
+        .success());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use rust_thanos::world::{AuxKind, ProgressMode, RunOptions};
+use std::path::PathBuf;
+
+/// Chunk 0 is kept (inhabited), chunk 1 is removed; entities/poi hold both.
+fn world_with_aux() -> PathBuf {
+    let world = common::temp_dir("rt-aux");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(100))),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    let aux = common::chunk_bytes(3, b"aux");
+    for dir in ["entities", "poi"] {
+        common::write_region(
+            &world.join(dir).join("r.0.0.mca"),
+            &[(0, aux.clone()), (1, aux.clone())],
+        );
+    }
+    world
+}
+
+fn opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn aux_chunks_follow_kept_region_chunks_by_default() {
+    let world = world_with_aux();
+    let out = common::temp_dir("rt-aux-out");
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts()).unwrap();
+    assert_eq!(
+        common::chunk_count(&out.join("entities").join("r.0.0.mca")),
+        1
+    );
+    assert_eq!(common::chunk_count(&out.join("poi").join("r.0.0.mca")), 1);
+}
+
+#[test]
+fn skip_entities_and_copy_poi_verbatim() {
+    let world = world_with_aux();
+    let out = common::temp_dir("rt-aux-out");
+    let opts = RunOptions {
+        skip_entities: true,
+        copy_verbatim: vec![AuxKind::Poi],
+        ..opts()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+    assert_eq!(
+        common::chunk_count(&out.join("region").join("r.0.0.mca")),
+        1
+    );
+    assert!(!out.join("entities").join("r.0.0.mca").exists());
+    assert_eq!(common::chunk_count(&out.join("poi").join("r.0.0.mca")), 2);
+}
+
+#[test]
+fn skip_entities_in_place_clears_input_entities() {
+    let world = world_with_aux();
+    let opts = RunOptions {
+        skip_entities: true,
+        ..opts()
+    };
+    rust_thanos::world::run_with_options(world.clone(), None, &opts).unwrap();
+    assert!(!world.join("entities").join("r.0.0.mca").exists());
+    assert_eq!(common::chunk_count(&world.join("poi").join("r.0.0.mca")), 1);
+}
+
+#[test]
+fn skip_and_verbatim_conflict_is_rejected() {
+    let world = world_with_aux();
+    let opts = RunOptions {
+        skip_poi: true,
+        copy_verbatim: vec![AuxKind::Poi],
+        ..opts()
+    };
+    assert!(rust_thanos::world::run_with_options(
+        world.clone(),
+        Some(common::temp_dir("rt-aux-out")),
+        &opts
+    )
+    .is_err());
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::mca::writer::McaWriter;
+use rust_thanos::patterns::inhabited::inhabited_time;
+use std::fs;
+
+#[test]
+fn buffered_chunk_is_served_from_memory() {
+    let dir = common::temp_dir("rt-buffered");
+    let path = dir.join("r.0.0.mca");
+    common::write_region(
+        &path,
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(1234))),
+            (1, common::chunk_bytes(3, &vec![7u8; 9000])),
+        ],
+    );
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref())
+        .unwrap()
+        .with_memory_limit(Some(8192));
+    let mut entries = reader.entries().unwrap();
+    let original = entries[0].serialized_bytes().unwrap();
+    assert!(entries[0].buffer_serialized().unwrap());
+    // over the memory limit: left on disk
+    assert!(!entries[1].buffer_serialized().unwrap());
+
+    // whatever the region holds now, the buffered chunk reads as it was
+    fs::write(
+        &path,
+        vec![0u8; fs::metadata(&path).unwrap().len() as usize],
+    )
+    .unwrap();
+    let entry = &mut entries[0];
+    assert_eq!(inhabited_time(entry).unwrap(), Some(1234));
+    assert_eq!(entry.serialized_bytes().unwrap(), original);
+
+    let copy = dir.join("copy").join("r.0.0.mca");
+    fs::create_dir_all(copy.parent().unwrap()).unwrap();
+    let mut writer = McaWriter::open(copy.to_string_lossy().as_ref()).unwrap();
+    writer.write_entry(entry).unwrap();
+    writer.finalize().unwrap();
+    let mut copied = McaReader::open(copy.to_string_lossy().as_ref()).unwrap();
+    assert_eq!(
+        copied.entries().unwrap()[0].serialized_bytes().unwrap(),
+        original
+    );
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use rust_thanos::world::manifest::{file_digest, verify_manifest};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn thanos(args: &[&std::ffi::OsStr]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs the world through the CLI with `--checksum-manifest`; (output, manifest).
+fn optimized() -> (PathBuf, PathBuf) {
+    let world = common::temp_dir("rt-manifest");
+    common::write_dimension(&world, 100_000);
+    common::write_region(
+        &world.join("DIM-1").join("region").join("r.-1.0.mca"),
+        &[(
+            0,
+            common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+        )],
+    );
+    let out = common::temp_dir("rt-manifest-out");
+    let manifest = common::temp_dir("rt-manifest-file").join("out.xxh64");
+    let output = thanos(&[
+        world.as_os_str(),
+        out.as_os_str(),
+        "--checksum-manifest".as_ref(),
+        manifest.as_os_str(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    (out, manifest)
+}
+
+#[test]
+fn digests_are_xxh64() {
+    let dir = common::temp_dir("rt-manifest-digest");
+    fs::write(dir.join("empty"), b"").unwrap();
+    assert_eq!(file_digest(&dir.join("empty")).unwrap(), "ef46db3751d8e999");
+}
+
+#[test]
+fn manifest_lists_every_output_region() {
+    let (out, manifest) = optimized();
+    let text = fs::read_to_string(&manifest).unwrap();
+    let paths: Vec<&str> = text.lines().map(|l| &l[18..]).collect();
+    assert_eq!(paths, ["DIM-1/region/r.-1.0.mca", "region/r.0.0.mca"]);
+    for line in text.lines() {
+        let (digest, path) = line.split_once("  ").unwrap();
+        assert_eq!(file_digest(&out.join(path)).unwrap(), digest);
+    }
+    let check = verify_manifest(&out, &manifest).unwrap();
+    assert_eq!((check.checked, check.failures.len()), (2, 0));
+}
+
+#[test]
+fn verify_manifest_reports_changed_and_missing_files() {
+    let (out, manifest) = optimized();
+    let ok = thanos(&[
+        "verify-manifest".as_ref(),
+        out.as_os_str(),
+        manifest.as_os_str(),
+    ]);
+    assert!(ok.status.success());
+
+    let region = out.join("region").join("r.0.0.mca");
+    let mut data = fs::read(&region).unwrap();
+    let last = data.len() - 1;
+    data[last] ^= 0xFF;
+    fs::write(&region, data).unwrap();
+    fs::remove_file(out.join("DIM-1").join("region").join("r.-1.0.mca")).unwrap();
+
+    let check = verify_manifest(&out, &manifest).unwrap();
+    let mut failures: Vec<_> = check
+        .failures
+        .iter()
+        .map(|f| (f.path.as_str(), f.actual.is_some()))
+        .collect();
+    failures.sort();
+    assert_eq!(
+        failures,
+        [
+            ("DIM-1/region/r.-1.0.mca", false),
+            ("region/r.0.0.mca", true)
+        ]
+    );
+    let bad = thanos(&[
+        "verify-manifest".as_ref(),
+        out.as_os_str(),
+        manifest.as_os_str(),
+    ]);
+    assert!(!bad.status.success());
+    let stdout = String::from_utf8_lossy(&bad.stdout);
+    assert!(stdout.contains("region/r.0.0.mca"), "{}", stdout);
+}
+
+#[test]
+fn malformed_manifest_is_rejected() {
+    let dir = common::temp_dir("rt-manifest-malformed");
+    fs::write(dir.join("m.txt"), "# comment\n\nnot a digest line\n").unwrap();
+    let err = verify_manifest(&dir, &dir.join("m.txt")).err().unwrap();
+    assert!(err.to_string().contains("line 3"), "{}", err);
+}
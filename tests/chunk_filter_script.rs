@@ -0,0 +1,137 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Region (0, 0) with chunks inhabited 0, 100, 1000 and 5000 ticks in slots 0..4.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-filter-script");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[0, 100, 1000, 5000]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                (
+                    i as u32,
+                    common::chunk_bytes(3, &common::inhabited_payload(*t)),
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+    world
+}
+
+fn run(script: &str, out: &Path) -> anyhow::Result<()> {
+    let path = common::temp_dir("rt-filter-script-src").join("filter.rhai");
+    fs::write(&path, script).unwrap();
+    let opts = RunOptions {
+        inhabited_threshold: 10_000,
+        progress_mode: ProgressMode::Off,
+        chunk_filter_script: Some(path),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.to_path_buf()), &opts)
+}
+
+#[cfg(feature = "script")]
+mod enabled {
+    use super::*;
+    use rust_thanos::mca::reader::McaReader;
+
+    fn kept(script: &str) -> Vec<u32> {
+        let out = common::temp_dir("rt-filter-script-out");
+        run(script, &out).unwrap();
+        let region = out.join("region").join("r.0.0.mca");
+        let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+        r.entries()
+            .unwrap()
+            .iter()
+            .map(|e| e.region_index())
+            .collect()
+    }
+
+    #[test]
+    fn script_replaces_the_threshold() {
+        // the threshold of 10000 ticks would remove every chunk
+        assert_eq!(kept("chunk.inhabited >= 1000"), vec![2, 3]);
+        assert_eq!(
+            kept("chunk.x % 2 == 0 && chunk.dimension == \".\""),
+            vec![0, 2]
+        );
+        assert_eq!(
+            kept("chunk.compression == \"RAW\" && chunk.size > 0 && chunk.data_version == ()"),
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn failing_scripts_keep_the_chunk() {
+        // a runtime error, a non-bool result and an endless loop
+        assert_eq!(
+            kept("if chunk.z == 0 { throw \"no\" } false"),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(kept("chunk.inhabited"), vec![0, 1, 2, 3]);
+        assert_eq!(kept("loop {}"), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn failures_are_counted() {
+        let script =
+            rust_thanos::patterns::script::ChunkScript::compile("t", "chunk.inhabited").unwrap();
+        for _ in 0..3 {
+            assert_eq!(script.decide(rhai::Map::new()), None);
+        }
+        assert_eq!(script.failures(), 3);
+    }
+
+    #[test]
+    fn options_changing_the_threshold_rule_are_rejected() {
+        let path = common::temp_dir("rt-filter-script-conflict").join("filter.rhai");
+        fs::write(&path, "true").unwrap();
+        for opts in [
+            RunOptions {
+                no_inhabited: true,
+                max_removed_percent: Some(100.0),
+                ..RunOptions::default()
+            },
+            RunOptions {
+                keep_zero_inhabited: true,
+                ..RunOptions::default()
+            },
+            RunOptions {
+                conservative_cull: true,
+                ..RunOptions::default()
+            },
+        ] {
+            let opts = RunOptions {
+                progress_mode: ProgressMode::Off,
+                chunk_filter_script: Some(path.clone()),
+                ..opts
+            };
+            let out = common::temp_dir("rt-filter-script-conflict-out");
+            let err = rust_thanos::world::run_with_options(world(), Some(out), &opts).unwrap_err();
+            assert!(err.to_string().contains("cannot be combined"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn syntax_errors_fail_the_run() {
+        let err = run("chunk.x ==", &common::temp_dir("rt-filter-script-bad")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("cannot compile chunk filter script"),
+            "{}",
+            err
+        );
+    }
+}
+
+#[cfg(not(feature = "script"))]
+#[test]
+fn script_needs_the_feature() {
+    let err = run("true", &common::temp_dir("rt-filter-script-off")).unwrap_err();
+    assert!(err.to_string().contains("`script` feature"), "{}", err);
+}
@@ -0,0 +1,134 @@
+#![allow(dead_code)]
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::mca::writer::McaWriter;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn temp_dir(prefix: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", prefix, uuid::Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Long tag (type=4), name length=13, name="InhabitedTime", value (BE i64)
+pub fn inhabited_payload(inhabited: i64) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(b"\x04\x00\x0dInhabitedTime");
+    data.extend_from_slice(&inhabited.to_be_bytes());
+    data
+}
+
+/// Serialized chunk: BE length (including the method byte), method, payload
+pub fn chunk_bytes(method: u8, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&((1 + payload.len()) as u32).to_be_bytes());
+    out.push(method);
+    out.extend_from_slice(payload);
+    out
+}
+
+/// Writes a region file holding the given `(index, serialized chunk)` pairs.
+pub fn write_region(path: &Path, chunks: &[(u32, Vec<u8>)]) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut writer = McaWriter::open(path.to_string_lossy().as_ref()).unwrap();
+    for (index, bytes) in chunks {
+        writer.write_serialized(*index, 0, bytes).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+/// Slot indices of the chunks present in `region`, in slot order.
+pub fn kept_indices(region: &Path) -> Vec<u32> {
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    r.entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect()
+}
+
+/// Number of chunks in `region`, read from its location table.
+pub fn chunk_count(region: &Path) -> usize {
+    chunk_count_named(region, &RegionNaming::default())
+}
+
+/// Like `chunk_count` for a region named after `naming`.
+pub fn chunk_count_named(region: &Path, naming: &RegionNaming) -> usize {
+    McaReader::open_named(region.to_string_lossy().as_ref(), naming)
+        .unwrap()
+        .chunk_count()
+        .unwrap()
+}
+
+/// Writes a region file of exactly 8192 bytes: both header tables, all zero.
+pub fn write_header_only_region(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, [0u8; 8192]).unwrap();
+}
+
+/// Writes `<dim>/region/r.0.0.mca` with a single RAW chunk at index 0.
+pub fn write_dimension(dim: &Path, inhabited: i64) -> PathBuf {
+    let path = dim.join("region").join("r.0.0.mca");
+    write_region(&path, &[(0, chunk_bytes(3, &inhabited_payload(inhabited)))]);
+    path
+}
+
+/// Serializes a root NBT compound built from `(name, value)` pairs.
+pub fn nbt_compound(fields: Vec<(&str, fastnbt::Value)>) -> Vec<u8> {
+    let map = fields
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect::<std::collections::HashMap<_, _>>();
+    fastnbt::to_bytes(&fastnbt::Value::Compound(map)).unwrap()
+}
+
+/// Writes `<dim>/data/chunks.dat` with modern `tickets` entries of `(type, x, z)`.
+pub fn write_tickets(dim: &Path, tickets: &[(&str, i32, i32)]) {
+    let full: Vec<_> = tickets.iter().map(|&(t, x, z)| (t, 31, 0, x, z)).collect();
+    write_ticket_entries(dim, &full);
+}
+
+/// Like `write_tickets`, with entries of `(type, level, ticks_left, x, z)`.
+pub fn write_ticket_entries(dim: &Path, tickets: &[(&str, i32, i64, i32, i32)]) {
+    use fastnbt::Value;
+    use std::collections::HashMap;
+    use std::io::Write;
+    let list = tickets
+        .iter()
+        .map(|(t, level, ticks_left, x, z)| {
+            Value::Compound(HashMap::from([
+                ("type".to_string(), Value::String(t.to_string())),
+                ("level".to_string(), Value::Int(*level)),
+                ("ticks_left".to_string(), Value::Long(*ticks_left)),
+                (
+                    "chunk_pos".to_string(),
+                    Value::IntArray(fastnbt::IntArray::new(vec![*x, *z])),
+                ),
+            ]))
+        })
+        .collect();
+    let data = HashMap::from([("tickets".to_string(), Value::List(list))]);
+    let nbt = nbt_compound(vec![
+        ("DataVersion", Value::Int(4325)),
+        ("data", Value::Compound(data)),
+    ]);
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(&nbt).unwrap();
+    fs::create_dir_all(dim.join("data")).unwrap();
+    fs::write(dim.join("data").join("chunks.dat"), enc.finish().unwrap()).unwrap();
+}
+
+/// Writes a gzipped `<world>/level.dat` whose `Data` compound holds `DataVersion`.
+pub fn write_level_dat(world: &Path, data_version: i32) {
+    use fastnbt::Value;
+    use std::collections::HashMap;
+    use std::io::Write;
+    let data = HashMap::from([("DataVersion".to_string(), Value::Int(data_version))]);
+    let nbt = nbt_compound(vec![("Data", Value::Compound(data))]);
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(&nbt).unwrap();
+    fs::create_dir_all(world).unwrap();
+    fs::write(world.join("level.dat"), enc.finish().unwrap()).unwrap();
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use rust_thanos::mca::entry::ChunkEncoding;
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::analyze::compression_report;
+use std::process::Command;
+
+const ENCODINGS: [ChunkEncoding; 2] = [
+    ChunkEncoding::Zlib { level: 9 },
+    ChunkEncoding::Lz4 { block_size: 65536 },
+];
+
+/// Ten raw chunks of 20000 zero bytes each: five sectors that compress to one.
+fn world() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-compression-report");
+    let chunks: Vec<_> = (0..10)
+        .map(|i| (i, common::chunk_bytes(3, &vec![0u8; 20_000])))
+        .collect();
+    common::write_region(&world.join("region").join("r.0.0.mca"), &chunks);
+    world
+}
+
+#[test]
+fn sample_is_measured_and_extrapolated() {
+    let report = compression_report(&world(), 2, &RegionNaming::default(), 3, &ENCODINGS).unwrap();
+    assert_eq!(report.chunks, 10);
+    // every 4th chunk: 0, 4 and 8
+    assert_eq!(report.sampled, 3);
+    assert_eq!((report.skipped, report.errors), (0, 0));
+    assert_eq!(report.current_bytes, 3 * 5 * 4096);
+    assert_eq!(report.trials.len(), 2);
+    for trial in &report.trials {
+        assert_eq!(trial.bytes, 3 * 4096);
+    }
+    assert!((report.scale() - 10.0 / 3.0).abs() < 1e-9);
+
+    let all = compression_report(&world(), 2, &RegionNaming::default(), 100, &ENCODINGS).unwrap();
+    assert_eq!(all.sampled, 10);
+}
+
+#[test]
+fn compression_report_writes_nothing() {
+    let world = world();
+    let before = std::fs::read(world.join("region").join("r.0.0.mca")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args(["--compression-report", "--compression-sample", "5"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("抽样 5/10"), "{}", stdout);
+    assert!(stdout.contains("zlib-6") && stdout.contains("zlib-9") && stdout.contains("lz4"));
+    assert!(stdout.contains("-80.0%"), "{}", stdout);
+    assert_eq!(
+        std::fs::read(world.join("region").join("r.0.0.mca")).unwrap(),
+        before
+    );
+}
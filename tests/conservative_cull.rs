@@ -0,0 +1,92 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::path::PathBuf;
+
+const PLAYED: i64 = 100_000;
+const FRESH: i64 = 20;
+
+fn chunk(status: Option<&str>, inhabited: i64) -> Vec<u8> {
+    let mut fields = vec![("InhabitedTime", Value::Long(inhabited))];
+    if let Some(s) = status {
+        fields.push(("Status", Value::String(s.to_string())));
+    }
+    common::chunk_bytes(3, &common::nbt_compound(fields))
+}
+
+/// One chunk per status/inhabited quadrant, plus chunks without or with an unknown status.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-conservative");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, chunk(Some("minecraft:full"), PLAYED)),
+            (1, chunk(Some("minecraft:full"), FRESH)),
+            (2, chunk(Some("minecraft:features"), PLAYED)),
+            (3, chunk(Some("minecraft:features"), FRESH)),
+            (4, chunk(Some("fullchunk"), FRESH)),
+            (5, chunk(None, FRESH)),
+            (6, chunk(Some("mymod:custom"), FRESH)),
+        ],
+    );
+    world
+}
+
+fn kept(opts: RunOptions) -> Vec<u32> {
+    let out = common::temp_dir("rt-conservative-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        ..opts
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    r.entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect()
+}
+
+#[test]
+fn only_full_chunks_below_the_threshold_are_removed() {
+    let kept = kept(RunOptions {
+        conservative_cull: true,
+        ..RunOptions::default()
+    });
+    // full+played kept, full+fresh removed (also the 1.13 name), proto chunks
+    // kept either way, chunks of unknown status kept
+    assert_eq!(kept, vec![0, 2, 3, 5, 6]);
+}
+
+#[test]
+fn without_the_preset_fresh_proto_chunks_are_removed() {
+    assert_eq!(kept(RunOptions::default()), vec![0, 2]);
+}
+
+#[test]
+fn forced_chunks_and_vetoes_still_apply() {
+    let world = world();
+    common::write_tickets(&world, &[("minecraft:forced", 1, 0)]);
+    let out = common::temp_dir("rt-conservative-forced");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        conservative_cull: true,
+        remove_below_status: Some("full".to_string()),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = r
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0, 1, 5, 6]);
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use rust_thanos::world::regions::copy_file;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn buffered_copy_matches_source() {
+    let dir = common::temp_dir("rt-copybuf");
+    let src = dir.join("r.0.0.mca");
+    // not a multiple of the buffer, so the last read is partial
+    let data: Vec<u8> = (0..3 * 1024 * 1024 + 123)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    fs::write(&src, &data).unwrap();
+    for buffer_kb in [None, Some(1), Some(64), Some(8192)] {
+        let dst = dir.join("copy.mca");
+        assert_eq!(copy_file(&src, &dst, buffer_kb).unwrap(), data.len() as u64);
+        assert_eq!(fs::read(&dst).unwrap(), data, "buffer {:?}", buffer_kb);
+    }
+}
+
+fn filter_in_place(copy_buffer_kb: Option<usize>) -> Vec<u8> {
+    let world = common::temp_dir("rt-copybuf-world");
+    let region = world.join("region").join("r.0.0.mca");
+    common::write_region(
+        &region,
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+            (
+                2,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+        ],
+    );
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        copy_buffer_kb,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, None, &opts).unwrap();
+    fs::read(region).unwrap()
+}
+
+#[test]
+fn in_place_copy_back_is_the_same_with_a_buffer() {
+    assert_eq!(filter_in_place(Some(4)), filter_in_place(None));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn copy_world_files_makes_output_a_complete_world() {
+    let world = common::temp_dir("rt-copy-world");
+    common::write_dimension(&world, 100);
+    common::write_dimension(&world.join("DIM-1"), 100);
+    fs::write(world.join("level.dat"), b"level").unwrap();
+    fs::create_dir_all(world.join("playerdata")).unwrap();
+    fs::write(world.join("playerdata").join("player.dat"), b"player").unwrap();
+    fs::create_dir_all(world.join("DIM-1").join("data")).unwrap();
+    fs::write(world.join("DIM-1").join("data").join("raids.dat"), b"raids").unwrap();
+    fs::create_dir_all(world.join("logs")).unwrap();
+    fs::write(world.join("logs").join("latest.log"), b"log").unwrap();
+
+    let out = common::temp_dir("rt-copy-world-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        copy_world_files: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+
+    assert_eq!(fs::read(out.join("level.dat")).unwrap(), b"level");
+    assert!(out.join("playerdata").join("player.dat").is_file());
+    assert!(out.join("DIM-1").join("data").join("raids.dat").is_file());
+    assert!(!out.join("logs").exists());
+    assert!(out.join("DIM-1").join("region").join("r.0.0.mca").is_file());
+}
+
+#[test]
+fn world_files_are_not_copied_by_default() {
+    let world = common::temp_dir("rt-copy-world-off");
+    common::write_dimension(&world, 100);
+    fs::write(world.join("level.dat"), b"level").unwrap();
+    let out = common::temp_dir("rt-copy-world-off-out");
+    rust_thanos::world::run(world, Some(out.clone()), 10, false, ProgressMode::Off).unwrap();
+    assert!(!out.join("level.dat").exists());
+}
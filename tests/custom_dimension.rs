@@ -0,0 +1,66 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, DEFAULT_DIMENSION_DEPTH};
+
+#[test]
+fn custom_datapack_dimension_is_discovered_and_processed() {
+    let world = common::temp_dir("rt-custom-dim");
+    common::write_dimension(&world, 100);
+    common::write_dimension(&world.join("DIM-1"), 100);
+    let custom = world.join("dimensions").join("mypack").join("sky");
+    common::write_dimension(&custom, 100);
+
+    let dims = rust_thanos::world::discover_dimensions(&world, DEFAULT_DIMENSION_DEPTH).unwrap();
+    assert!(dims.contains(&custom));
+    assert_eq!(dims.len(), 3);
+
+    let out = common::temp_dir("rt-custom-dim-out");
+    rust_thanos::world::run(
+        world.clone(),
+        Some(out.clone()),
+        10,
+        false,
+        ProgressMode::Off,
+    )
+    .unwrap();
+    assert!(out
+        .join("dimensions")
+        .join("mypack")
+        .join("sky")
+        .join("region")
+        .join("r.0.0.mca")
+        .is_file());
+}
+
+#[test]
+fn unrelated_deep_trees_are_not_scanned() {
+    let world = common::temp_dir("rt-deep-dim");
+    common::write_dimension(&world, 100);
+    let deep = world.join("backups").join("a").join("b").join("old");
+    common::write_dimension(&deep, 100);
+    let dims = rust_thanos::world::discover_dimensions(&world, DEFAULT_DIMENSION_DEPTH).unwrap();
+    assert!(!dims.contains(&deep));
+}
+
+#[test]
+fn depth_limit_bounds_discovery() {
+    let world = common::temp_dir("rt-depth-dim");
+    common::write_dimension(&world, 100);
+    let custom = world.join("dimensions").join("ns").join("name");
+    common::write_dimension(&custom, 100);
+
+    let dims = rust_thanos::world::discover_dimensions(&world, 3).unwrap();
+    assert_eq!(dims, vec![world.clone(), custom.clone()]);
+    let shallow = rust_thanos::world::discover_dimensions(&world, 2).unwrap();
+    assert_eq!(shallow, vec![world.clone()]);
+}
+
+#[test]
+fn data_folders_are_not_descended_into() {
+    let world = common::temp_dir("rt-data-dirs");
+    common::write_dimension(&world, 100);
+    // a stray dimension-like tree inside region/ must not be picked up
+    common::write_dimension(&world.join("region").join("nested"), 100);
+    let dims = rust_thanos::world::discover_dimensions(&world, DEFAULT_DIMENSION_DEPTH).unwrap();
+    assert_eq!(dims, vec![world.clone()]);
+}
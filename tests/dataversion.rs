@@ -0,0 +1,70 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::dataversion::find_data_version_fast;
+use rust_thanos::world::{level_data_version, ProgressMode, RunOptions};
+
+fn chunk(data_version: i32) -> Vec<u8> {
+    common::chunk_bytes(
+        3,
+        &common::nbt_compound(vec![
+            ("DataVersion", Value::Int(data_version)),
+            ("InhabitedTime", Value::Long(0)),
+        ]),
+    )
+}
+
+fn opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        keep_current_dataversion: true,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn data_version_is_read_from_chunks_and_level_dat() {
+    let payload = common::nbt_compound(vec![("DataVersion", Value::Int(3465))]);
+    assert_eq!(find_data_version_fast(&payload), Some(3465));
+    assert_eq!(find_data_version_fast(&common::inhabited_payload(5)), None);
+
+    let world = common::temp_dir("rt-dataversion-level");
+    common::write_level_dat(&world, 3700);
+    assert_eq!(level_data_version(&world).unwrap(), 3700);
+}
+
+#[test]
+fn chunks_at_current_data_version_are_kept() {
+    let world = common::temp_dir("rt-dataversion");
+    common::write_level_dat(&world, 3465);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, chunk(3465)), (1, chunk(3337))],
+    );
+    let out = common::temp_dir("rt-dataversion-out");
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts()).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = r
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0]);
+}
+
+#[test]
+fn missing_level_dat_is_an_error() {
+    let world = common::temp_dir("rt-dataversion-missing");
+    common::write_dimension(&world, 0);
+    let err = rust_thanos::world::run_with_options(
+        world,
+        Some(common::temp_dir("rt-dataversion-missing-out")),
+        &opts(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("level.dat"));
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::analyze::dataversion_report;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn chunk(data_version: Option<i32>) -> Vec<u8> {
+    let mut fields = vec![("InhabitedTime", Value::Long(0))];
+    if let Some(v) = data_version {
+        fields.push(("DataVersion", Value::Int(v)));
+    }
+    common::chunk_bytes(3, &common::nbt_compound(fields))
+}
+
+/// r.0.0: three 1.20.4 chunks; r.1.0: one 1.12.2, one 1.8 (no DataVersion) and one
+/// 1.20.4 chunk; DIM-1 r.0.0: one 1.16.5 chunk.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-dataversion-report");
+    let region = |dim: &Path, name: &str, chunks: Vec<Option<i32>>| {
+        common::write_region(
+            &dim.join("region").join(name),
+            &chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (i as u32, chunk(v)))
+                .collect::<Vec<_>>(),
+        );
+    };
+    region(&world, "r.0.0.mca", vec![Some(3700); 3]);
+    region(&world, "r.1.0.mca", vec![Some(1343), None, Some(3700)]);
+    region(&world.join("DIM-1"), "r.0.0.mca", vec![Some(2586)]);
+    world
+}
+
+#[test]
+fn versions_are_tallied_and_old_chunks_flagged() {
+    let report = dataversion_report(&world(), 2, &RegionNaming::default(), 2000).unwrap();
+    assert_eq!(report.chunks, 7);
+    assert_eq!(
+        report
+            .versions
+            .iter()
+            .map(|(v, n)| (*v, *n))
+            .collect::<Vec<_>>(),
+        [(1343, 1), (2586, 1), (3700, 4)]
+    );
+    assert_eq!((report.unknown, report.errors), (1, 0));
+    assert_eq!(report.at_risk_chunks(), 2);
+    assert_eq!(
+        report.at_risk.into_iter().collect::<Vec<_>>(),
+        [(Path::new("region").join("r.1.0.mca"), 2)]
+    );
+
+    let strict = dataversion_report(&world(), 2, &RegionNaming::default(), 3000).unwrap();
+    assert_eq!(strict.at_risk_chunks(), 3);
+    assert_eq!(strict.at_risk.len(), 2);
+}
+
+#[test]
+fn missing_mcc_and_oversized_chunks_are_not_flagged() {
+    let world = common::temp_dir("rt-dataversion-unread");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, chunk(Some(3700))),
+            // external zlib chunk whose c.0.0.mcc is missing
+            (1, common::chunk_bytes(0x82, &[])),
+            // above the 4 MiB chunk memory limit
+            (2, common::chunk_bytes(3, &vec![0u8; 5 * 1024 * 1024])),
+        ],
+    );
+    let report = dataversion_report(&world, 2, &RegionNaming::default(), 2000).unwrap();
+    assert_eq!(report.chunks, 3);
+    assert_eq!((report.unknown, report.errors, report.oversized), (0, 1, 1));
+    assert_eq!(report.at_risk_chunks(), 0);
+}
+
+#[test]
+fn dataversion_report_prints_the_range() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(world())
+        .args([
+            "--dataversion-report",
+            "--min-upgradable-dataversion",
+            "2000",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("DataVersion 范围 1343 - 3700"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("区块 2 个"), "{}", stdout);
+    assert!(stdout.contains("r.1.0.mca  2 个区块"), "{}", stdout);
+}
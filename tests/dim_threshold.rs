@@ -0,0 +1,48 @@
+mod common;
+
+use rust_thanos::world::{dimension_name_matches, dimension_threshold, ProgressMode, RunOptions};
+
+#[test]
+fn names_match_labels_aliases_and_ids() {
+    assert!(dimension_name_matches("DIM-1", "DIM-1"));
+    assert!(dimension_name_matches("DIM-1", "world_nether/DIM-1"));
+    assert!(dimension_name_matches("the_nether", "DIM-1"));
+    assert!(dimension_name_matches("minecraft:the_end", "DIM1"));
+    assert!(dimension_name_matches("overworld", "."));
+    assert!(dimension_name_matches("ns:mine", "dimensions/ns/mine"));
+    assert!(dimension_name_matches("mine", "dimensions/ns/mine"));
+    assert!(!dimension_name_matches("DIM1", "DIM-1"));
+    assert!(!dimension_name_matches("overworld", "DIM-1"));
+
+    let overrides = vec![
+        ("DIM-1".to_string(), 5),
+        ("world_nether/DIM-1".to_string(), 7),
+    ];
+    assert_eq!(dimension_threshold("world_nether/DIM-1", &overrides, 99), 7);
+    assert_eq!(dimension_threshold("DIM-1", &overrides, 99), 5);
+    assert_eq!(dimension_threshold(".", &overrides, 99), 99);
+}
+
+#[test]
+fn nether_uses_its_own_threshold() {
+    let world = common::temp_dir("rt-dim-threshold");
+    // 100 ticks everywhere: below the global threshold, above the nether one
+    common::write_dimension(&world, 100);
+    common::write_dimension(&world.join("DIM-1"), 100);
+    let out = common::temp_dir("rt-dim-threshold-out");
+    let opts = RunOptions {
+        inhabited_threshold: 1000,
+        dim_thresholds: vec![("the_nether".to_string(), 50)],
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    assert_eq!(
+        common::chunk_count(&out.join("region").join("r.0.0.mca")),
+        0
+    );
+    assert_eq!(
+        common::chunk_count(&out.join("DIM-1").join("region").join("r.0.0.mca")),
+        1
+    );
+}
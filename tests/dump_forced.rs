@@ -0,0 +1,136 @@
+mod common;
+
+use rust_thanos::world::coords::{self, CoordUnit};
+use rust_thanos::world::{ProgressMode, RunOptions, DEFAULT_TICKET_TYPES};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn default_types() -> Vec<String> {
+    DEFAULT_TICKET_TYPES.iter().map(|s| s.to_string()).collect()
+}
+
+/// Overworld and nether with four fresh chunks each (slots 0..4); the nether
+/// force-loads chunks 2 and 1 (twice) and has a portal ticket on chunk 3.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-dump-forced");
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for dim in [world.clone(), world.join("DIM-1")] {
+        common::write_region(
+            &dim.join("region").join("r.0.0.mca"),
+            &(0..4).map(|i| (i, fresh.clone())).collect::<Vec<_>>(),
+        );
+    }
+    common::write_tickets(
+        &world.join("DIM-1"),
+        &[
+            ("minecraft:forced", 2, 0),
+            ("minecraft:forced", 1, 0),
+            ("minecraft:forced", 1, 0),
+            ("minecraft:portal", 3, 0),
+        ],
+    );
+    world
+}
+
+#[test]
+fn export_forced_is_sorted_and_deduplicated() {
+    let world = world();
+    let nether = world.join("DIM-1");
+    assert_eq!(
+        rust_thanos::world::export_forced(&nether, &default_types(), false),
+        vec![(1, 0), (2, 0)]
+    );
+    let with_portal = vec!["forced".to_string(), "portal".to_string()];
+    assert_eq!(
+        rust_thanos::world::export_forced(&nether, &with_portal, false),
+        vec![(1, 0), (2, 0), (3, 0)]
+    );
+    assert!(rust_thanos::world::export_forced(&world, &default_types(), false).is_empty());
+}
+
+#[test]
+fn dump_forced_round_trips_through_keep_list() {
+    let world = world();
+    let file = common::temp_dir("rt-dump-forced-file").join("forced.json");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg("--dump-forced")
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("已导出 2 个维度的 2 个强加载区块"),
+        "{}",
+        stdout
+    );
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"dimensions": [
+            {"dimension": ".", "chunks": []},
+            {"dimension": "DIM-1", "chunks": [[1, 0], [2, 0]]},
+        ]})
+    );
+    let list = coords::parse_keep_list(&file).unwrap();
+    assert!(list.points.is_empty() && list.ranges.is_empty());
+    assert_eq!(
+        list.dimension_chunks,
+        vec![
+            (".".to_string(), vec![]),
+            ("DIM-1".to_string(), vec![(1, 0), (2, 0)]),
+        ]
+    );
+}
+
+#[test]
+fn imported_chunks_are_kept_in_their_dimension_only() {
+    // the snapshot outlives the tickets: another world without any chunks.dat
+    let source = world();
+    let snapshot = common::temp_dir("rt-dump-forced-snap").join("forced.json");
+    let dims =
+        rust_thanos::world::forced_by_dimension(&source, 3, &default_types(), false).unwrap();
+    fs::write(&snapshot, coords::dimension_chunks_json(&dims)).unwrap();
+    let target = world();
+    fs::remove_file(target.join("DIM-1").join("data").join("chunks.dat")).unwrap();
+
+    let out = common::temp_dir("rt-dump-forced-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        keep_list: Some(snapshot),
+        // chunk coordinates whatever the unit
+        coord_unit: CoordUnit::Region,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(target, Some(out.clone()), &opts).unwrap();
+    assert_eq!(
+        common::kept_indices(&out.join("DIM-1").join("region").join("r.0.0.mca")),
+        vec![1, 2]
+    );
+    assert!(common::kept_indices(&out.join("region").join("r.0.0.mca")).is_empty());
+}
+
+#[test]
+fn malformed_json_keep_list_is_rejected() {
+    let file = common::temp_dir("rt-dump-forced-bad").join("forced.json");
+    fs::write(
+        &file,
+        r#"{"dimensions": [{"dimension": "DIM-1", "chunks": [[1]]}]}"#,
+    )
+    .unwrap();
+    let err = coords::parse_keep_list(&file).unwrap_err().to_string();
+    assert!(
+        err.contains("DIM-1: expected chunks as [[x, z], ...]"),
+        "{}",
+        err
+    );
+}
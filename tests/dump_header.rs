@@ -0,0 +1,97 @@
+mod common;
+
+use rust_thanos::mca::reader::{HeaderSlot, McaReader};
+use std::fs;
+use std::process::Command;
+
+/// A four-sector region whose location table holds `(slot, offset, sectors)`.
+fn region_with_slots(slots: &[(usize, u32, u32)]) -> std::path::PathBuf {
+    let path = common::temp_dir("rt-dump-header").join("r.1.-1.mca");
+    let mut data = vec![0u8; 4 * 4096];
+    for &(i, offset, sectors) in slots {
+        data[i * 4..i * 4 + 4].copy_from_slice(&((offset << 8) | sectors).to_be_bytes());
+        data[4096 + i * 4..4096 + i * 4 + 4].copy_from_slice(&1_700_000_000u32.to_be_bytes());
+    }
+    fs::write(&path, data).unwrap();
+    path
+}
+
+#[test]
+fn header_exposes_raw_slots() {
+    let path = region_with_slots(&[(0, 2, 1), (33, 3, 1)]);
+    let header = McaReader::open(path.to_string_lossy().as_ref())
+        .unwrap()
+        .header()
+        .unwrap();
+    assert_eq!(header.file_len, 4 * 4096);
+    assert_eq!(header.slots.len(), 1024);
+    assert_eq!(
+        header.slots[33],
+        HeaderSlot {
+            offset: 3,
+            sectors: 1,
+            timestamp: 1_700_000_000
+        }
+    );
+    assert!(!header.slots[1].is_used());
+    assert!(header.anomalies().is_empty());
+}
+
+#[test]
+fn anomalies_name_each_broken_slot() {
+    let path = region_with_slots(&[
+        (0, 2, 1),
+        (1, 1, 1),
+        (2, 5, 0),
+        (3, 0, 2),
+        (4, 3, 2),
+        (5, 2, 1),
+        (6, 0xFF_FFFF, 1),
+    ]);
+    let header = McaReader::open(path.to_string_lossy().as_ref())
+        .unwrap()
+        .header()
+        .unwrap();
+    let anomalies = header.anomalies();
+    let slots: Vec<usize> = anomalies.iter().map(|(i, _)| *i).collect();
+    assert_eq!(slots, vec![0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(anomalies[0].1, "overlaps slot 5");
+    assert_eq!(anomalies[1].1, "offset inside the header");
+    assert_eq!(anomalies[2].1, "offset with a sector count of 0");
+    assert_eq!(anomalies[3].1, "sector count without an offset");
+    assert!(anomalies[4].1.contains("past the end of the file"));
+    assert_eq!(anomalies[5].1, "overlaps slot 0");
+    assert!(anomalies[6].1.contains("past the end of the file"));
+}
+
+#[test]
+fn dump_header_prints_used_slots_and_flags() {
+    let path = region_with_slots(&[(0, 2, 1), (33, 1, 1)]);
+    let out = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg("dump-header")
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let rows: Vec<&str> = stdout.lines().skip(2).collect();
+    assert_eq!(rows.len(), 2, "{stdout}");
+    // slot 33 of r.1.-1 is chunk (33, -31)
+    assert!(
+        rows[1].contains("33") && rows[1].contains("-31"),
+        "{stdout}"
+    );
+    assert!(rows[1].contains("! offset inside the header"), "{stdout}");
+    assert!(!rows[0].contains('!'), "{stdout}");
+    assert!(rows[0].contains("2023-11-14 22:13:20"), "{stdout}");
+
+    let all = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .args(["dump-header", "--all"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&all.stdout).lines().count(),
+        2 + 1024
+    );
+}
@@ -0,0 +1,23 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::find_duplicate_regions;
+use std::fs;
+
+#[test]
+fn identical_regions_across_dimensions_are_grouped() {
+    let world = common::temp_dir("rt-duplicates");
+    let overworld = common::write_dimension(&world, 100);
+    let nether = common::write_dimension(&world.join("DIM-1"), 100);
+    common::write_dimension(&world.join("DIM1"), 200);
+    // header-only files are trivially equal and not reported
+    for dim in ["DIM-1", "DIM1"] {
+        fs::write(
+            world.join(dim).join("region").join("r.5.5.mca"),
+            [0u8; 8192],
+        )
+        .unwrap();
+    }
+    let groups = find_duplicate_regions(&world, 3, &RegionNaming::default()).unwrap();
+    assert_eq!(groups, vec![vec![nether, overworld]]);
+}
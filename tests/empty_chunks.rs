@@ -0,0 +1,56 @@
+mod common;
+
+use rust_thanos::mca::entry::{compress_zlib, serialize_chunk, CompressionMethod};
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+
+fn kept(remove_empty_chunks: bool) -> Vec<u32> {
+    let world = common::temp_dir("rt-empty");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            // valid RAW header over zero bytes of data
+            (1, common::chunk_bytes(3, &[])),
+            // zlib stream of nothing
+            (
+                2,
+                serialize_chunk(CompressionMethod::Zlib, &compress_zlib(&[], 6).unwrap()),
+            ),
+            // custom compression cannot be decoded: unknown, not empty
+            (
+                3,
+                common::chunk_bytes(127, &[0, 3, b'f', b'o', b'o', 1, 2, 3]),
+            ),
+        ],
+    );
+    let out = common::temp_dir("rt-empty-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        remove_empty_chunks,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut reader = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect()
+}
+
+#[test]
+fn empty_payloads_are_unknown_by_default() {
+    assert_eq!(kept(false), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn remove_empty_chunks_culls_only_decoded_empty_payloads() {
+    assert_eq!(kept(true), vec![0, 3]);
+}
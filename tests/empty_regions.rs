@@ -0,0 +1,92 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// r.0.0 keeps one of its chunks; r.1.0 and DIM-1's r.-1.0 keep none.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-empty-regions");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, played), (1, fresh.clone())],
+    );
+    common::write_region(
+        &world.join("region").join("r.1.0.mca"),
+        &[(0, fresh.clone()), (5, fresh.clone())],
+    );
+    common::write_region(
+        &world.join("DIM-1").join("region").join("r.-1.0.mca"),
+        &[(7, fresh)],
+    );
+    world
+}
+
+#[test]
+fn lists_regions_that_would_keep_nothing_without_writing() {
+    let world = world();
+    let before = fs::read(world.join("region").join("r.1.0.mca")).unwrap();
+    let out = common::temp_dir("rt-empty-regions-out").join("never-created");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(&out)
+        .args([
+            "--report-empty-regions",
+            "--progress-mode",
+            "off",
+            "-t",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert!(
+        lines.contains(&"[.] 将不再保留任何区块的区域文件 1 个:"),
+        "{stdout}"
+    );
+    assert!(
+        lines.contains(&"  region/r.1.0.mca（2 个区块全部删除）"),
+        "{stdout}"
+    );
+    assert!(
+        lines.contains(&"[DIM-1] 将不再保留任何区块的区域文件 1 个:"),
+        "{stdout}"
+    );
+    assert!(
+        lines.contains(&"  DIM-1/region/r.-1.0.mca（1 个区块全部删除）"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("r.0.0.mca"), "{stdout}");
+    assert!(stdout.contains("共 2 个区域文件将变为空"), "{stdout}");
+
+    assert!(!out.exists());
+    assert_eq!(
+        fs::read(world.join("region").join("r.1.0.mca")).unwrap(),
+        before
+    );
+}
+
+#[test]
+fn forced_chunks_spare_their_region() {
+    let world = world();
+    common::write_tickets(&world, &[("minecraft:forced", 32, 0)]);
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args([
+            "--report-empty-regions",
+            "--progress-mode",
+            "off",
+            "-t",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("r.1.0.mca"), "{stdout}");
+    assert!(stdout.contains("共 1 个区域文件将变为空"), "{stdout}");
+}
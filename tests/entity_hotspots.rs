@@ -0,0 +1,123 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::entities::entity_count;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+fn mob(id: &str, passengers: Vec<Value>) -> Value {
+    let mut map = HashMap::from([("id".to_string(), Value::String(id.to_string()))]);
+    if !passengers.is_empty() {
+        map.insert("Passengers".to_string(), Value::List(passengers));
+    }
+    Value::Compound(map)
+}
+
+fn entities_chunk(entities: Vec<Value>) -> Vec<u8> {
+    common::nbt_compound(vec![("Entities", Value::List(entities))])
+}
+
+fn indices(path: &Path) -> Vec<u32> {
+    let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    r.entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect()
+}
+
+/// A world whose chunk 0 holds 30 items, chunk 1 a cow and chunk 2 (unplayed) 50 items.
+fn farm_world(name: &str) -> std::path::PathBuf {
+    let world = common::temp_dir(name);
+    let played = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, played.clone()),
+            (1, played),
+            (2, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    let items = |n| entities_chunk(vec![mob("minecraft:item", vec![]); n]);
+    common::write_region(
+        &world.join("entities").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &items(30))),
+            (
+                1,
+                common::chunk_bytes(3, &entities_chunk(vec![mob("minecraft:cow", vec![])])),
+            ),
+            (2, common::chunk_bytes(3, &items(50))),
+        ],
+    );
+    world
+}
+
+#[test]
+fn passengers_are_counted() {
+    let jockey = mob(
+        "minecraft:spider",
+        vec![mob(
+            "minecraft:skeleton",
+            vec![mob("minecraft:bat", vec![])],
+        )],
+    );
+    let chunk = entities_chunk(vec![jockey, mob("minecraft:cow", vec![])]);
+    assert_eq!(entity_count(&chunk), Some(4));
+    assert_eq!(entity_count(&entities_chunk(vec![])), Some(0));
+}
+
+#[test]
+fn hotspots_are_reported_and_left_alone() {
+    let world = farm_world("rt-hotspots");
+    let out = common::temp_dir("rt-hotspots-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(&out)
+        .args(["--report-entity-hotspots", "10", "-t", "5"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("实体超过 10 个的区块: 1 个"), "{}", stdout);
+    assert!(
+        stdout.contains("[.] 区块 (0, 0) · 方块 (0, 0) · 30 个实体"),
+        "{}",
+        stdout
+    );
+    // the unplayed chunk is culled, so it is not assessed
+    assert!(!stdout.contains("50 个实体"), "{}", stdout);
+    assert_eq!(indices(&out.join("entities").join("r.0.0.mca")), vec![0, 1]);
+}
+
+#[test]
+fn cleared_hotspots_keep_their_region_chunk() {
+    let world = farm_world("rt-hotspots-clear");
+    let out = common::temp_dir("rt-hotspots-clear-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(&out)
+        .args(["--report-entity-hotspots", "10", "--clear-entity-hotspots"])
+        .args(["-t", "5"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", stdout);
+    assert!(stdout.contains("（实体已清除）"), "{}", stdout);
+    assert_eq!(indices(&out.join("region").join("r.0.0.mca")), vec![0, 1]);
+    assert_eq!(indices(&out.join("entities").join("r.0.0.mca")), vec![1]);
+}
+
+#[test]
+fn clearing_needs_the_report() {
+    let world = farm_world("rt-hotspots-alone");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(common::temp_dir("rt-hotspots-alone-out"))
+        .arg("--clear-entity-hotspots")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+}
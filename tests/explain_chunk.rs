@@ -0,0 +1,101 @@
+mod common;
+
+use std::fs;
+use std::process::Command;
+
+/// Chunks (0, 0) inhabited 100000 ticks and (1, 0) never visited, (1, 0) also
+/// force-loaded; the nether has no region file.
+fn world() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-explain-chunk");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    common::write_tickets(&world, &[("minecraft:forced", 1, 0)]);
+    fs::create_dir_all(world.join("DIM-1").join("region")).unwrap();
+    world
+}
+
+fn explain(world: &std::path::Path, chunk: &str, extra: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(world)
+        .args(["--explain-chunk", chunk, "-t", "60"])
+        .args(extra)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn traces_every_rule_for_one_chunk() {
+    let world = world();
+    let before = fs::read(world.join("region").join("r.0.0.mca")).unwrap();
+
+    let out = explain(&world, "1,0", &["--remove-empty-chunks"]);
+    assert!(
+        out.contains("[.] 区块 (1, 0) · r.0.0.mca 槽位 1"),
+        "{}",
+        out
+    );
+    assert!(out.contains("InhabitedTime: 0 刻（0.0 秒）"), "{}", out);
+    assert!(out.contains("[始终保留] force-loaded → 符合"), "{}", out);
+    assert!(
+        out.contains("[删除] --remove-empty-chunks → 不符合"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("[保留] InhabitedTime >= 1200 → 不符合"),
+        "{}",
+        out
+    );
+    assert!(out.contains("结果: 保留"), "{}", out);
+    assert!(out.contains("[DIM-1] 区块 (1, 0) 不存在"), "{}", out);
+
+    let out = explain(&world, "0,0", &[]);
+    assert!(out.contains("[始终保留] force-loaded → 不符合"), "{}", out);
+    assert!(
+        out.contains("[保留] InhabitedTime >= 1200 → 符合"),
+        "{}",
+        out
+    );
+    assert!(out.contains("结果: 保留"), "{}", out);
+
+    let out = explain(&world, "1,0", &["--keep-ticket-types", "portal"]);
+    assert!(out.contains("结果: 删除"), "{}", out);
+
+    // read-only: even without OUTPUT_DIR nothing is replaced
+    assert_eq!(
+        fs::read(world.join("region").join("r.0.0.mca")).unwrap(),
+        before
+    );
+}
+
+#[test]
+fn missing_chunks_and_verbatim_regions_are_reported() {
+    let world = world();
+    let out = explain(&world, "-40,7", &[]);
+    assert!(
+        out.contains("[.] 区块 (-40, 7) 不存在（r.-2.0.mca 槽位 248）"),
+        "{}",
+        out
+    );
+
+    let out = explain(&world, "0,0", &["--region-bounds", "1", "1", "2", "2"]);
+    assert!(
+        out.contains("结果: 保留（整个区域原样复制: region is outside --region-bounds）"),
+        "{}",
+        out
+    );
+}
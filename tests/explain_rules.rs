@@ -0,0 +1,120 @@
+mod common;
+
+use rust_thanos::world::explain::{overlaps, KeepSource, Overlap, RuleSummary};
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn overlaps_pair_boxes_of_different_sources() {
+    let sources = [
+        KeepSource::chunks("forced", &[(1, 0), (9, 9)]),
+        KeepSource::new("--keep-range", vec![(0, 0, 3, 3), (2, 2, 5, 5)]),
+        KeepSource::chunks("--keep-chunk", &[(3, 3)]),
+    ];
+    let found = overlaps(&sources);
+    assert_eq!(
+        found,
+        vec![
+            Overlap {
+                first: "forced",
+                second: "--keep-range",
+                area: (1, 0, 1, 0)
+            },
+            Overlap {
+                first: "--keep-range",
+                second: "--keep-chunk",
+                area: (3, 3, 3, 3)
+            },
+            Overlap {
+                first: "--keep-range",
+                second: "--keep-chunk",
+                area: (3, 3, 3, 3)
+            },
+        ]
+    );
+    // boxes of one source overlapping each other are not reported
+    assert_eq!(sources[1].chunk_count(), 32);
+}
+
+#[test]
+fn summary_lists_precedence_and_conflicts() {
+    let summary = RuleSummary {
+        label: "DIM-1".to_string(),
+        sources: vec![
+            KeepSource::chunks("强加载区块", &[(0, 0)]),
+            KeepSource::chunks("--keep-chunk", &[(0, 0)]),
+            KeepSource::new("--keep-range", Vec::new()),
+        ],
+        polygons: 0,
+        vetoes: vec!["--remove-below-status full".to_string()],
+        keeps: Vec::new(),
+    };
+    let text = summary.lines().join("\n");
+    assert!(
+        text.contains("1. 始终保留: 强加载区块 1 个区块 · --keep-chunk 1 个区块\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("3. 保留: 无"), "{}", text);
+    assert!(text.contains("冲突时第 1 级优先"), "{}", text);
+    assert!(
+        text.contains("强加载区块 与 --keep-chunk: (0, 0)"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("--keep-range"), "{}", text);
+}
+
+#[test]
+fn explain_rules_reports_before_the_run() {
+    let world = common::temp_dir("rt-explain");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(0))),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    common::write_tickets(&world, &[("minecraft:forced", 1, 0)]);
+    let list = common::temp_dir("rt-explain-list").join("keep.txt");
+    fs::write(&list, "# spawn area\n0,0,31,31\n").unwrap();
+    let out = common::temp_dir("rt-explain-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(&out)
+        .args(["--explain-rules", "--keep-chunk", "1,0", "--keep-list"])
+        .arg(&list)
+        .args(["--keep-modified-only", "-t", "60"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("[.] 保留规则（按优先级从高到低）:"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("强加载区块 1 个区块 · --keep-chunk 1 个区块 · --keep-list 1024 个区块"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("2. 删除: --keep-modified-only"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("InhabitedTime ≥ 1200 刻"), "{}", stdout);
+    assert!(stdout.contains("重叠 3 处（共 3 个区块"), "{}", stdout);
+    assert!(
+        stdout.contains("--keep-chunk 与 --keep-list: (1, 0)"),
+        "{}",
+        stdout
+    );
+    // the run itself goes ahead
+    assert!(out.join("region").join("r.0.0.mca").is_file());
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn heatmap_lists_kept_and_removed_chunks() {
+    let world = common::temp_dir("rt-heatmap");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(100))),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    common::write_dimension(&world.join("DIM-1"), 40);
+    let csv = world.join("heat.csv");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        export_inhabited: Some(csv.clone()),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(
+        world.clone(),
+        Some(common::temp_dir("rt-heatmap-out")),
+        &opts,
+    )
+    .unwrap();
+
+    let text = fs::read_to_string(&csv).unwrap();
+    let mut lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines.remove(0),
+        "dimension,chunk_x,chunk_z,inhabited_seconds"
+    );
+    lines.sort();
+    assert_eq!(lines, vec![".,0,0,5.00", ".,1,0,0.00", "DIM-1,0,0,2.00"]);
+}
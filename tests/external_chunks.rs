@@ -0,0 +1,125 @@
+mod common;
+
+use byteorder::{ByteOrder, LittleEndian};
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use rust_thanos::mca::entry::lz4_checksum;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+fn lz4_block(payload: &[u8]) -> Vec<u8> {
+    let compressed = lz4_flex::block::compress(payload);
+    let mut out = b"LZ4Block".to_vec();
+    out.push(0x20);
+    let mut buf = [0u8; 4];
+    for v in [
+        compressed.len() as u32,
+        payload.len() as u32,
+        lz4_checksum(payload),
+    ] {
+        LittleEndian::write_u32(&mut buf, v);
+        out.extend_from_slice(&buf);
+    }
+    out.extend_from_slice(&compressed);
+    out
+}
+
+/// `(stub method byte, .mcc contents)` for every external compression method.
+fn external(payload: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(payload).unwrap();
+    let mut zl = ZlibEncoder::new(Vec::new(), Compression::default());
+    zl.write_all(payload).unwrap();
+    vec![
+        (0x81, gz.finish().unwrap()),
+        (0x82, zl.finish().unwrap()),
+        (0x83, payload.to_vec()),
+        (0x84, lz4_block(payload)),
+    ]
+}
+
+/// Writes one `.mcc` file per method for chunks `first..` and returns the region stubs
+/// together with the `.mcc` names.
+fn write_external(region_dir: &Path, first: u32, inhabited: i64) -> Vec<(u32, Vec<u8>, String)> {
+    fs::create_dir_all(region_dir).unwrap();
+    external(&common::inhabited_payload(inhabited))
+        .into_iter()
+        .zip(first..)
+        .map(|((method, mcc), index)| {
+            let name = format!("c.{}.{}.mcc", index % 32, index / 32);
+            fs::write(region_dir.join(&name), mcc).unwrap();
+            (index, common::chunk_bytes(method, &[]), name)
+        })
+        .collect()
+}
+
+fn stubs(chunks: &[(u32, Vec<u8>, String)]) -> Vec<(u32, Vec<u8>)> {
+    chunks
+        .iter()
+        .map(|(i, stub, _)| (*i, stub.clone()))
+        .collect()
+}
+
+#[test]
+fn every_external_method_decodes_from_its_mcc_file() {
+    let world = common::temp_dir("rt-external-read");
+    let region_dir = world.join("region");
+    let chunks = write_external(&region_dir, 0, 4242);
+    let path = region_dir.join("r.0.0.mca");
+    common::write_region(&path, &stubs(&chunks));
+
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let mut entries = reader.entries().unwrap();
+    assert_eq!(entries.len(), 4);
+    for entry in entries.iter_mut() {
+        assert!(entry.is_external().unwrap());
+        let data = entry.all_data_uncompressed().unwrap();
+        assert_eq!(data, common::inhabited_payload(4242));
+        let mut streamed = Vec::new();
+        std::io::copy(&mut entry.uncompressed_reader().unwrap(), &mut streamed).unwrap();
+        assert_eq!(streamed, data);
+    }
+}
+
+#[test]
+fn kept_external_chunks_keep_their_mcc_files() {
+    let world = common::temp_dir("rt-external-run");
+    let region_dir = world.join("region");
+    let played = write_external(&region_dir, 0, 100_000);
+    let unused = write_external(&region_dir, 4, 0);
+    let mut all = stubs(&played);
+    all.extend(stubs(&unused));
+    common::write_region(&region_dir.join("r.0.0.mca"), &all);
+
+    let out = common::temp_dir("rt-external-run-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        remove_unknown: true,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+
+    let out_region = out.join("region");
+    let path = out_region.join("r.0.0.mca");
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0, 1, 2, 3]);
+    for (_, _, name) in &played {
+        assert_eq!(
+            fs::read(out_region.join(name)).unwrap(),
+            fs::read(region_dir.join(name)).unwrap()
+        );
+    }
+    for (_, _, name) in &unused {
+        assert!(!out_region.join(name).exists());
+    }
+}
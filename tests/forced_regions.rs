@@ -0,0 +1,39 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn regions_with_forced_chunks_are_copied_whole() {
+    let world = common::temp_dir("rt-forced-whole");
+    let unused = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for x in 0..=1 {
+        let name = format!("r.{}.0.mca", x);
+        common::write_region(
+            &world.join("region").join(&name),
+            &[(0, unused.clone()), (1, unused.clone())],
+        );
+    }
+    // Chunk (33, 0) lives in r.1.0.mca at index 1
+    common::write_tickets(&world, &[("minecraft:forced", 33, 0)]);
+    let out = common::temp_dir("rt-forced-whole-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        keep_forced_regions_whole: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+
+    let forced = Path::new("region").join("r.1.0.mca");
+    assert_eq!(
+        fs::read(out.join(&forced)).unwrap(),
+        fs::read(world.join(&forced)).unwrap()
+    );
+    assert_eq!(common::chunk_count(&out.join(&forced)), 2);
+    assert_eq!(
+        common::chunk_count(&out.join("region").join("r.0.0.mca")),
+        0
+    );
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::get_chunk;
+
+#[test]
+fn chunks_are_found_by_global_coordinate() {
+    let naming = RegionNaming::default();
+    let world = common::temp_dir("rt-get-chunk");
+    // chunk (-3, 40) lives in r.-1.1.mca at local (29, 8)
+    common::write_region(
+        &world.join("DIM-1").join("region").join("r.-1.1.mca"),
+        &[(
+            29 + 8 * 32,
+            common::chunk_bytes(3, &common::inhabited_payload(77)),
+        )],
+    );
+    common::write_dimension(&world, 5);
+
+    assert_eq!(
+        get_chunk(&world, "DIM-1", -3, 40, &naming).unwrap(),
+        Some(common::inhabited_payload(77))
+    );
+    assert_eq!(
+        get_chunk(&world, ".", 0, 0, &naming).unwrap(),
+        Some(common::inhabited_payload(5))
+    );
+    // empty slot, absent region, absent dimension
+    assert_eq!(get_chunk(&world, "DIM-1", -4, 40, &naming).unwrap(), None);
+    assert_eq!(get_chunk(&world, ".", 100, 100, &naming).unwrap(), None);
+    assert_eq!(get_chunk(&world, "DIM1", 0, 0, &naming).unwrap(), None);
+}
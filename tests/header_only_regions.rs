@@ -0,0 +1,80 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// r.0.0 with one played chunk, r.1.0 header-only but with an entities region
+/// holding one chunk.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-header-only");
+    common::write_dimension(&world, 100_000);
+    common::write_header_only_region(&world.join("region").join("r.1.0.mca"));
+    common::write_region(
+        &world.join("entities").join("r.1.0.mca"),
+        &[(0, common::chunk_bytes(3, &common::inhabited_payload(0)))],
+    );
+    world
+}
+
+fn opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn header_only_region_is_copied_and_its_entities_emptied() {
+    let events = common::temp_dir("rt-header-only-events").join("events.jsonl");
+    let out = common::temp_dir("rt-header-only-out");
+    let opts = RunOptions {
+        progress_events: Some(events.clone()),
+        ..opts()
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.clone()), &opts).unwrap();
+
+    assert_eq!(
+        fs::read(out.join("region").join("r.1.0.mca")).unwrap(),
+        vec![0u8; 8192]
+    );
+    assert_eq!(
+        common::chunk_count(&out.join("entities").join("r.1.0.mca")),
+        0
+    );
+    assert_eq!(
+        common::chunk_count(&out.join("region").join("r.0.0.mca")),
+        1
+    );
+
+    let events: Vec<Value> = fs::read_to_string(&events)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    let header_only = events
+        .iter()
+        .find(|e| e["event"] == "region" && e["region"] == "r.1.0.mca")
+        .unwrap();
+    assert_eq!(
+        (&header_only["chunks"], &header_only["verbatim"]),
+        (&0.into(), &true.into())
+    );
+    let summary = events.last().unwrap();
+    assert_eq!(
+        (&summary["kept"], &summary["removed"]),
+        (&1.into(), &0.into())
+    );
+}
+
+#[test]
+fn header_only_region_survives_in_place() {
+    let world = world();
+    rust_thanos::world::run_with_options(world.clone(), None, &opts()).unwrap();
+    assert_eq!(
+        fs::read(world.join("region").join("r.1.0.mca")).unwrap(),
+        vec![0u8; 8192]
+    );
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{discover_dimensions, output_divergence, ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn a_skipped_dimension_is_detected() {
+    let world = common::temp_dir("rt-diverge");
+    common::write_dimension(&world, 100);
+    common::write_dimension(&world.join("DIM-1"), 100);
+    fs::create_dir_all(world.join("DIM-1").join("entities")).unwrap();
+    let tasks = discover_dimensions(&world, 4).unwrap();
+    assert_eq!(tasks.len(), 2);
+
+    // staged output as if the nether had been skipped
+    let out = common::temp_dir("rt-diverge-out");
+    common::write_dimension(&out, 100);
+    assert_eq!(
+        output_divergence(&world, &out, &tasks, 4).unwrap(),
+        vec!["DIM-1/entities".to_string(), "DIM-1/region".to_string()]
+    );
+
+    common::write_dimension(&out.join("DIM-1"), 100);
+    fs::create_dir_all(out.join("DIM-1").join("entities")).unwrap();
+    assert!(output_divergence(&world, &out, &tasks, 4)
+        .unwrap()
+        .is_empty());
+
+    // a dimension that was never processed
+    common::write_dimension(&out.join("DIM1"), 100);
+    assert_eq!(
+        output_divergence(&world, &out, &tasks, 4).unwrap(),
+        vec!["DIM1".to_string()]
+    );
+}
+
+#[test]
+fn complete_in_place_runs_are_copied_back() {
+    let world = common::temp_dir("rt-diverge-run");
+    common::write_dimension(&world, 100_000);
+    common::write_dimension(&world.join("DIM-1"), 0);
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        strict: true,
+        max_removed_percent: Some(100.0),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), None, &opts).unwrap();
+    assert!(world.join("region").join("r.0.0.mca").is_file());
+    // the nether's only chunk was removed
+    let nether = world.join("DIM-1").join("region").join("r.0.0.mca");
+    let chunks = McaReader::open(nether.to_string_lossy().as_ref())
+        .map(|mut r| r.chunk_count().unwrap())
+        .unwrap_or(0);
+    assert_eq!(chunks, 0);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::collections::HashMap;
+
+fn audit_opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        audit_inhabited_percent: Some(100.0),
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn audit_passes_when_scan_and_nbt_agree() {
+    let world = common::temp_dir("rt-audit-ok");
+    let chunk = common::nbt_compound(vec![
+        ("DataVersion", Value::Int(3953)),
+        ("InhabitedTime", Value::Long(1234)),
+    ]);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, common::chunk_bytes(3, &chunk))],
+    );
+    let out = common::temp_dir("rt-audit-out");
+    rust_thanos::world::run_with_options(world.clone(), Some(out), &audit_opts()).unwrap();
+}
+
+#[test]
+fn audit_fails_on_nested_false_match() {
+    let world = common::temp_dir("rt-audit-mismatch");
+    // only a nested compound carries InhabitedTime; the byte scan picks it up anyway
+    let nested = HashMap::from([("InhabitedTime".to_string(), Value::Long(999))]);
+    let chunk = common::nbt_compound(vec![
+        ("DataVersion", Value::Int(3953)),
+        ("block_entities", Value::List(vec![Value::Compound(nested)])),
+    ]);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, common::chunk_bytes(3, &chunk))],
+    );
+    let out = common::temp_dir("rt-audit-out");
+    assert!(rust_thanos::world::run_with_options(
+        world.clone(),
+        Some(out.clone()),
+        &RunOptions {
+            audit_inhabited_percent: None,
+            ..audit_opts()
+        }
+    )
+    .is_ok());
+    let out2 = common::temp_dir("rt-audit-out");
+    assert!(
+        rust_thanos::world::run_with_options(world.clone(), Some(out2), &audit_opts()).is_err()
+    );
+}
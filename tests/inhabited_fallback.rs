@@ -0,0 +1,40 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+
+#[test]
+fn inhabited_time_without_long_tag_is_read_through_nbt() {
+    let world = common::temp_dir("rt-inhabited-fallback");
+    // Int-typed InhabitedTime is invisible to the Long byte scan
+    let played = common::nbt_compound(vec![("InhabitedTime", Value::Int(100_000))]);
+    let unused = common::nbt_compound(vec![("InhabitedTime", Value::Int(0))]);
+    let missing = common::nbt_compound(vec![("xPos", Value::Int(2))]);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &played)),
+            (1, common::chunk_bytes(3, &unused)),
+            (2, common::chunk_bytes(3, &missing)),
+        ],
+    );
+    let out = common::temp_dir("rt-inhabited-fallback-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        remove_unknown: true,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+
+    let path = out.join("region").join("r.0.0.mca");
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0]);
+}
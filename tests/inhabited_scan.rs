@@ -1,18 +1,34 @@
-use rust_thanos::mca::entry::McaEntry;
-use rust_thanos::patterns::inhabited::InhabitedTimePattern;
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::evaluate_default;
 
 #[test]
 fn scan_inhabited_long() {
-    let mut data = Vec::new();
-    data.extend_from_slice(b"\x04\x00\x0eInhabitedTime\x00\x00\x00\x00\x00\x00\x00\x2a");
-    let temp_dir = std::env::temp_dir();
-    let temp_file = temp_dir.join("dummy");
-    let file = std::fs::File::create(temp_file).unwrap();
-    let _entry = McaEntry::new(file, 0, data.len(), 0, 0, 0, 0);
-    // hijack: overwrite serialized/all_data_uncompressed via temp file is complex; instead directly use pattern scanner on bytes through public API
-    // here we only assert the fast path indirectly by constructing the data layout
-    let _pattern = InhabitedTimePattern::new(10, false);
-    // emulate: we cannot call matches without entry file setup; so only ensure the scanner logic via private convention (omitted)
-    // this test acts as a placeholder to ensure module compiles; end-to-end covered in world test
-    assert!(!data.is_empty());
+    let dir = common::temp_dir("rt-scan");
+    let path = dir.join("region").join("r.0.0.mca");
+    common::write_region(
+        &path,
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(42))),
+            (1, common::chunk_bytes(2, b"not zlib")),
+            (2, common::chunk_bytes(3, b"no tag here")),
+        ],
+    );
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let mut entries = reader.entries().unwrap();
+
+    let mut played = entries.remove(0);
+    assert!(evaluate_default(&mut played, 42, false, &[]).unwrap());
+    assert!(!evaluate_default(&mut played, 43, false, &[]).unwrap());
+    // forced chunks are kept whatever their InhabitedTime
+    assert!(evaluate_default(&mut played, 43, false, &[(0, 0)]).unwrap());
+
+    // an undecodable zlib stream surfaces as an error
+    let mut broken = entries.remove(0);
+    assert!(evaluate_default(&mut broken, 0, false, &[]).is_err());
+
+    let mut unknown = entries.remove(0);
+    assert!(evaluate_default(&mut unknown, 10, false, &[]).unwrap());
+    assert!(!evaluate_default(&mut unknown, 10, true, &[]).unwrap());
 }
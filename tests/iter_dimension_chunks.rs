@@ -0,0 +1,55 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::iter_dimension_chunks;
+use std::fs;
+
+#[test]
+fn chunks_are_yielded_region_by_region() {
+    let world = common::temp_dir("rt-iter-dim");
+    let chunk = |t| common::chunk_bytes(3, &common::inhabited_payload(t));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, chunk(1)), (33, chunk(2))],
+    );
+    common::write_region(&world.join("region").join("r.-1.0.mca"), &[(5, chunk(3))]);
+    // neither a region name nor a full header
+    fs::write(world.join("region").join("notes.txt"), b"hi").unwrap();
+    fs::write(world.join("region").join("r.2.2.mca"), b"tiny").unwrap();
+
+    let chunks: Vec<_> = iter_dimension_chunks(&world, &RegionNaming::default())
+        .map(|c| {
+            let (path, entry) = c.unwrap();
+            (
+                path.file_name().unwrap().to_string_lossy().to_string(),
+                entry.global_x(),
+                entry.global_z(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        chunks,
+        vec![
+            ("r.-1.0.mca".to_string(), -27, 0),
+            ("r.0.0.mca".to_string(), 0, 0),
+            ("r.0.0.mca".to_string(), 1, 1),
+        ]
+    );
+}
+
+#[test]
+fn entries_can_be_read_and_missing_regions_yield_nothing() {
+    let world = common::temp_dir("rt-iter-dim-read");
+    common::write_dimension(&world, 4242);
+    let mut chunks = iter_dimension_chunks(&world, &RegionNaming::default());
+    let (_, mut entry) = chunks.next().unwrap().unwrap();
+    assert_eq!(
+        rust_thanos::patterns::inhabited::inhabited_time(&mut entry).unwrap(),
+        Some(4242)
+    );
+    assert!(chunks.next().is_none());
+    assert_eq!(
+        iter_dimension_chunks(&world.join("DIM-1"), &RegionNaming::default()).count(),
+        0
+    );
+}
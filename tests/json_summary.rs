@@ -0,0 +1,79 @@
+mod common;
+
+use std::process::Command;
+
+#[test]
+fn json_summary_is_the_only_stdout_line() {
+    let world = common::temp_dir("rt-json");
+    common::write_dimension(&world, 100_000);
+    let out = common::temp_dir("rt-json-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(&out)
+        .args(["--json-summary", "--progress-mode", "off", "-t", "1"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "{}", stdout);
+    let line = lines[0];
+    assert!(
+        line.starts_with("{\"kept\":1,\"removed\":0,\"before_bytes\":"),
+        "{}",
+        line
+    );
+    for key in ["after_bytes", "reduction_pct", "elapsed_secs"] {
+        assert!(line.contains(&format!("\"{}\":", key)), "{}", line);
+    }
+    assert!(line.ends_with('}'));
+}
+
+#[test]
+fn summary_names_the_largest_chunk_of_each_dimension() {
+    let world = common::temp_dir("rt-largest");
+    let small = common::chunk_bytes(3, &common::inhabited_payload(0));
+    let mut big_payload = common::inhabited_payload(0);
+    big_payload.extend(std::iter::repeat_n(0u8, 5000));
+    let big = common::chunk_bytes(3, &big_payload);
+    let big_len = big.len();
+    // slot 37 of r.-1.0 is chunk (-27, 1)
+    common::write_region(
+        &world.join("region").join("r.-1.0.mca"),
+        &[(0, small.clone()), (37, big)],
+    );
+    common::write_region(
+        &world.join("DIM-1").join("region").join("r.0.0.mca"),
+        &[(3, small.clone())],
+    );
+    let run = |extra: &[&str]| {
+        let out = common::temp_dir("rt-largest-out");
+        let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+            .arg(&world)
+            .arg(&out)
+            .args(["--progress-mode", "off", "-t", "1"])
+            .args(extra)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let json = run(&["--json-summary"]);
+    assert!(
+        json.contains(&format!(
+            "\"largest_chunks\":[{{\"dimension\":\".\",\"x\":-27,\"z\":1,\"bytes\":{}}},{{\"dimension\":\"DIM-1\",\"x\":3,\"z\":0,\"bytes\":{}}}]",
+            big_len,
+            small.len()
+        )),
+        "{}",
+        json
+    );
+    let text = run(&[]);
+    assert!(text.contains("[.] 最大区块: (-27, 1)"), "{}", text);
+    assert!(text.contains("[DIM-1] 最大区块: (3, 0)"), "{}", text);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use rust_thanos::world::regions::is_junk_file;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn recognizes_junk_names() {
+    for junk in [
+        ".DS_Store",
+        "Thumbs.db",
+        "thumbs.db",
+        "desktop.ini",
+        "._r.0.0.mca",
+    ] {
+        assert!(is_junk_file(&Path::new("region").join(junk)), "{}", junk);
+    }
+    for real in ["r.0.0.mca", "c.0.0.mcc", "level.dat", "_r.0.0.mca"] {
+        assert!(!is_junk_file(&Path::new("region").join(real)), "{}", real);
+    }
+}
+
+fn junky_world() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-junk");
+    let region = common::write_dimension(&world, 100_000);
+    let dir = region.parent().unwrap();
+    // an AppleDouble twin large enough to pass the tiny-region check
+    fs::write(dir.join("._r.0.0.mca"), vec![0xAB; 16 * 1024]).unwrap();
+    fs::write(dir.join(".DS_Store"), b"finder").unwrap();
+    fs::write(dir.join("Thumbs.db"), b"explorer").unwrap();
+    fs::write(world.join(".DS_Store"), b"finder").unwrap();
+    fs::write(world.join("level.dat"), b"level").unwrap();
+    world
+}
+
+#[test]
+fn junk_in_region_dirs_is_not_processed() {
+    let world = junky_world();
+    let out = common::temp_dir("rt-junk-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        strict: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let names: Vec<String> = fs::read_dir(out.join("region"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, vec!["r.0.0.mca"]);
+}
+
+#[test]
+fn zip_output_and_sizes_leave_junk_out() {
+    let world = junky_world();
+    let parent = common::temp_dir("rt-junk-zip");
+    let out = parent.join("out");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(&out)
+        .args([
+            "--copy-world-files",
+            "--zip-output",
+            "--json-summary",
+            "-t",
+            "1",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let real = fs::metadata(world.join("region").join("r.0.0.mca"))
+        .unwrap()
+        .len()
+        + 5;
+    assert!(
+        stdout.contains(&format!("\"before_bytes\":{},", real)),
+        "{}",
+        stdout
+    );
+
+    let zip_path = fs::read_dir(&parent)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "zip"))
+        .unwrap();
+    let archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+    let mut names: Vec<&str> = archive.file_names().filter(|n| !n.ends_with('/')).collect();
+    names.sort();
+    assert_eq!(names, vec!["level.dat", "region/r.0.0.mca"]);
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::coords::{chunk_ranges, parse_keep_list, CoordUnit, KeepList};
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn coordinates_convert_to_chunk_ranges() {
+    assert_eq!(
+        chunk_ranges(CoordUnit::Block, &[(-1, 17)], &[(0, 0, 31, -16)]).unwrap(),
+        vec![(-1, 1, -1, 1), (0, -1, 1, 0)]
+    );
+    assert_eq!(
+        chunk_ranges(CoordUnit::Region, &[(1, -1)], &[]).unwrap(),
+        vec![(32, -32, 63, -1)]
+    );
+    assert_eq!(
+        chunk_ranges(CoordUnit::Chunk, &[], &[(5, 5, -5, -5)]).unwrap(),
+        vec![(-5, -5, 5, 5)]
+    );
+    assert!(chunk_ranges(CoordUnit::Block, &[(30_000_001, 0)], &[]).is_err());
+    assert!(chunk_ranges(CoordUnit::Region, &[(0, 60_000)], &[]).is_err());
+}
+
+#[test]
+fn keep_list_accepts_points_ranges_and_comments() {
+    let dir = common::temp_dir("rt-keep-list");
+    let path = dir.join("keep.txt");
+    fs::write(&path, "# spawn\n0,0\n\n-3 4  # base\n10,10,20,20\n").unwrap();
+    assert_eq!(
+        parse_keep_list(&path).unwrap(),
+        KeepList {
+            points: vec![(0, 0), (-3, 4)],
+            ranges: vec![(10, 10, 20, 20)],
+            dimension_chunks: Vec::new(),
+        }
+    );
+    fs::write(&path, "1,2,3\n").unwrap();
+    let err = parse_keep_list(&path).unwrap_err().to_string();
+    assert!(err.contains(":1:"), "{}", err);
+}
+
+#[test]
+fn explicit_keeps_in_block_units_are_pinned() {
+    let world = common::temp_dir("rt-keep-coords");
+    let unused = common::chunk_bytes(3, &common::inhabited_payload(0));
+    // chunks (0,0), (1,0) and (8,1)
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, unused.clone()), (1, unused.clone()), (40, unused)],
+    );
+    let list = world.join("keep.txt");
+    fs::write(&list, "128,16,130,20\n").unwrap();
+    let out = common::temp_dir("rt-keep-coords-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        keep_chunks: vec![(20, 5)],
+        keep_list: Some(list),
+        coord_unit: CoordUnit::Block,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = r
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![1, 40]);
+}
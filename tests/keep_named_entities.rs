@@ -0,0 +1,131 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::entities::has_named_entities;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::path::{Path, PathBuf};
+
+fn compound(fields: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+fn entities_chunk(entities: Vec<Value>) -> Vec<u8> {
+    common::nbt_compound(vec![("Entities", Value::List(entities))])
+}
+
+fn mob(id: &str, name: Option<Value>) -> Value {
+    let mut fields = vec![("id", Value::String(id.into()))];
+    if let Some(name) = name {
+        fields.push(("CustomName", name));
+    }
+    compound(fields)
+}
+
+fn named(data: Vec<u8>) -> bool {
+    has_named_entities(&data).unwrap()
+}
+
+#[test]
+fn custom_name_formats_are_recognized() {
+    // JSON text component (1.13 to 1.21.4), also as a bare JSON string
+    let json = mob(
+        "minecraft:wolf",
+        Some(Value::String(r#"{"text":"Rex"}"#.into())),
+    );
+    assert!(named(entities_chunk(vec![json])));
+    let quoted = mob("minecraft:wolf", Some(Value::String(r#""Rex""#.into())));
+    assert!(named(entities_chunk(vec![quoted])));
+    let extra = r#"{"text":"","extra":[{"text":"Rex"}]}"#;
+    let extra = mob("minecraft:wolf", Some(Value::String(extra.into())));
+    assert!(named(entities_chunk(vec![extra])));
+    // plain string (before 1.13)
+    let legacy = mob("Wolf", Some(Value::String("Rex".into())));
+    assert!(named(entities_chunk(vec![legacy])));
+    // NBT component (since 1.21.5)
+    let nbt = compound(vec![("text", Value::String("Rex".into()))]);
+    assert!(named(entities_chunk(vec![mob(
+        "minecraft:wolf",
+        Some(nbt)
+    )])));
+    // a named passenger
+    let rider = mob("minecraft:villager", Some(Value::String("Bob".into())));
+    let boat = compound(vec![
+        ("id", Value::String("minecraft:boat".into())),
+        ("Passengers", Value::List(vec![rider])),
+    ]);
+    assert!(named(entities_chunk(vec![boat])));
+
+    // empty names and unnamed mobs do not count
+    let empty = mob(
+        "minecraft:cat",
+        Some(Value::String(r#"{"text":""}"#.into())),
+    );
+    let blank = mob("minecraft:cat", Some(Value::String("  ".into())));
+    let plain = mob("minecraft:cow", None);
+    assert!(!named(entities_chunk(vec![empty, blank, plain])));
+    assert!(!named(common::nbt_compound(vec![])));
+    assert_eq!(has_named_entities(b"not nbt"), None);
+}
+
+/// Four unplayed chunks; the entities chunks of slots 0 and 2 hold a named mob.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-named-entities");
+    let fresh = || common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, fresh()), (1, fresh()), (2, fresh()), (3, fresh())],
+    );
+    let name = |s: &str| Some(Value::String(s.into()));
+    let chunks = [
+        vec![mob("minecraft:wolf", name(r#"{"text":"Rex"}"#))],
+        vec![mob("minecraft:cow", None)],
+        vec![mob("Villager", name("Bob"))],
+        vec![mob("minecraft:cat", name(r#"{"text":""}"#))],
+    ];
+    common::write_region(
+        &world.join("entities").join("r.0.0.mca"),
+        &chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, e)| (i as u32, common::chunk_bytes(3, &entities_chunk(e))))
+            .collect::<Vec<_>>(),
+    );
+    world
+}
+
+fn slots(region: &Path) -> Vec<u32> {
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    r.entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect()
+}
+
+fn run(keep_named_entities: bool) -> PathBuf {
+    let out = common::temp_dir("rt-named-entities-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        keep_named_entities,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.clone()), &opts).unwrap();
+    out
+}
+
+#[test]
+fn chunks_with_named_entities_are_kept() {
+    let out = run(true);
+    assert_eq!(slots(&out.join("region").join("r.0.0.mca")), vec![0, 2]);
+    assert_eq!(slots(&out.join("entities").join("r.0.0.mca")), vec![0, 2]);
+
+    let out = run(false);
+    assert!(slots(&out.join("region").join("r.0.0.mca")).is_empty());
+}
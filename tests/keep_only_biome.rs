@@ -0,0 +1,139 @@
+mod common;
+
+use fastnbt::{LongArray, Value};
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::biome::{biome_counts, BiomePattern};
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::collections::HashMap;
+
+/// A section's `biomes` compound holding `cells` (64 palette indices into `palette`).
+fn section(palette: &[&str], cells: &[usize]) -> Value {
+    let mut biomes = HashMap::from([(
+        "palette".to_string(),
+        Value::List(
+            palette
+                .iter()
+                .map(|b| Value::String(b.to_string()))
+                .collect(),
+        ),
+    )]);
+    if palette.len() > 1 {
+        let bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as usize;
+        let per_long = 64 / bits;
+        let mut data = vec![0u64; 64usize.div_ceil(per_long)];
+        for (i, &cell) in cells.iter().enumerate() {
+            data[i / per_long] |= (cell as u64) << ((i % per_long) * bits);
+        }
+        biomes.insert(
+            "data".to_string(),
+            Value::LongArray(LongArray::new(data.into_iter().map(|l| l as i64).collect())),
+        );
+    }
+    Value::Compound(HashMap::from([(
+        "biomes".to_string(),
+        Value::Compound(biomes),
+    )]))
+}
+
+fn chunk(inhabited: i64, sections: Vec<Value>) -> Vec<u8> {
+    common::nbt_compound(vec![
+        ("InhabitedTime", Value::Long(inhabited)),
+        ("sections", Value::List(sections)),
+    ])
+}
+
+/// `n` cells of palette index 1, the rest index 0.
+fn split(n: usize) -> Vec<usize> {
+    (0..64).map(|i| usize::from(i < n)).collect()
+}
+
+#[test]
+fn palettes_are_counted_across_sections() {
+    let data = chunk(
+        0,
+        vec![
+            section(&["minecraft:plains"], &[]),
+            section(&["minecraft:plains", "minecraft:river"], &split(40)),
+            section(
+                &["a:one", "a:two", "a:three"],
+                &(0..64).map(|i| i % 3).collect::<Vec<_>>(),
+            ),
+        ],
+    );
+    let counts = biome_counts(&data).unwrap();
+    assert_eq!(counts["minecraft:plains"], 88);
+    assert_eq!(counts["minecraft:river"], 40);
+    assert_eq!(counts["a:one"], 22);
+    assert_eq!(counts["a:three"], 21);
+    assert_eq!(biome_counts(&common::inhabited_payload(5)), None);
+}
+
+#[test]
+fn the_dominant_biome_decides_at_transitions() {
+    let plains = BiomePattern::outside(&["plains".to_string()]).unwrap();
+    let desert = BiomePattern::outside(&["minecraft:desert".to_string()]).unwrap();
+    // a plains/desert border mostly on the desert side
+    let border = chunk(
+        0,
+        vec![section(
+            &["minecraft:plains", "minecraft:desert"],
+            &split(40),
+        )],
+    );
+    assert_eq!(plains.dominant_listed(&border), Some(false));
+    assert_eq!(desert.dominant_listed(&border), Some(true));
+    // an exact tie counts for either side
+    let tie = chunk(
+        0,
+        vec![section(
+            &["minecraft:plains", "minecraft:desert"],
+            &split(32),
+        )],
+    );
+    assert_eq!(plains.dominant_listed(&tie), Some(true));
+    assert_eq!(desert.dominant_listed(&tie), Some(true));
+    assert_eq!(plains.dominant_listed(&common::inhabited_payload(5)), None);
+    assert!(BiomePattern::outside(&[" ".to_string()]).is_err());
+}
+
+#[test]
+fn chunks_outside_the_biomes_are_vetoed() {
+    let world = common::temp_dir("rt-biome");
+    let plains = || vec![section(&["minecraft:plains"], &[])];
+    let desert = || vec![section(&["minecraft:desert"], &[])];
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &chunk(100_000, plains()))),
+            (1, common::chunk_bytes(3, &chunk(100_000, desert()))),
+            // still needs the InhabitedTime keep
+            (2, common::chunk_bytes(3, &chunk(0, plains()))),
+            // no palettes: left to the other rules
+            (
+                3,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            // force-loaded chunks are pinned, so the veto does not apply
+            (4, common::chunk_bytes(3, &chunk(0, desert()))),
+        ],
+    );
+    common::write_tickets(&world, &[("minecraft:forced", 4, 0)]);
+    let out = common::temp_dir("rt-biome-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        keep_only_biomes: vec!["plains".to_string()],
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+
+    let path = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = r
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0, 3, 4]);
+}
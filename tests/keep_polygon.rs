@@ -0,0 +1,87 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::polygon::PolygonPattern;
+use rust_thanos::world::coords::{parse_polygons, CoordUnit};
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn ray_casting_handles_concave_polygons() {
+    // L shape: the square (0..20)^2 minus its (10..20, 10..20) quarter
+    let l = PolygonPattern::new(vec![
+        (0.0, 0.0),
+        (20.0, 0.0),
+        (20.0, 10.0),
+        (10.0, 10.0),
+        (10.0, 20.0),
+        (0.0, 20.0),
+        (0.0, 0.0),
+    ])
+    .unwrap();
+    assert!(l.contains(5.0, 5.0));
+    assert!(l.contains(15.0, 5.0));
+    assert!(l.contains(5.0, 15.0));
+    assert!(!l.contains(15.0, 15.0));
+    assert!(!l.contains(-1.0, 5.0));
+    assert!(!l.contains(25.0, 5.0));
+}
+
+#[test]
+fn degenerate_polygons_are_rejected() {
+    let err = PolygonPattern::new(vec![(0.0, 0.0), (1.0, 1.0), (0.0, 0.0)]).unwrap_err();
+    assert!(err.to_string().contains("at least 3"), "{}", err);
+    let err = PolygonPattern::new(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)]).unwrap_err();
+    assert!(err.to_string().contains("collinear"), "{}", err);
+}
+
+#[test]
+fn polygon_files_accept_rings_and_geojson() {
+    let dir = common::temp_dir("rt-polygon-parse");
+    let path = dir.join("p.json");
+    fs::write(&path, "[[0, 0], [2, 0], [0, 2]]").unwrap();
+    assert_eq!(
+        parse_polygons(&path, CoordUnit::Chunk).unwrap(),
+        vec![vec![(0.0, 0.0), (32.0, 0.0), (0.0, 32.0)]]
+    );
+    let features = r#"{"type": "FeatureCollection", "features": [
+        {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [0, 1]]]}},
+        {"type": "Feature", "geometry": {"type": "MultiPolygon", "coordinates": [[[[5, 5], [6, 5], [5, 6]]]]}}
+    ]}"#;
+    fs::write(&path, features).unwrap();
+    assert_eq!(parse_polygons(&path, CoordUnit::Block).unwrap().len(), 2);
+    fs::write(&path, r#"{"type": "Point", "coordinates": [0, 0]}"#).unwrap();
+    assert!(parse_polygons(&path, CoordUnit::Block).is_err());
+}
+
+#[test]
+fn chunks_centered_in_polygon_are_pinned() {
+    let world = common::temp_dir("rt-polygon");
+    let unused = common::chunk_bytes(3, &common::inhabited_payload(0));
+    // chunks (0,0), (1,0), (0,1) and (1,1)
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[0, 1, 32, 33].map(|i| (i, unused.clone())),
+    );
+    // triangle over blocks (0,0)-(40,0)-(0,40): holds the centers of all but chunk (1,1)
+    let path = world.join("claim.json");
+    fs::write(&path, "[[0, 0], [40, 0], [0, 40]]").unwrap();
+    let out = common::temp_dir("rt-polygon-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        keep_polygon: Some(path),
+        coord_unit: CoordUnit::Block,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut reader = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0, 1, 32]);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::inhabited::InhabitedTimePattern;
+use rust_thanos::world::{ProgressMode, RunOptions};
+
+#[test]
+fn keep_zero_only_changes_exactly_zero() {
+    let plain = InhabitedTimePattern::new(100, true);
+    let zero = InhabitedTimePattern::new(100, true).with_keep_zero(true);
+    assert!(!plain.decide(Some(0)));
+    assert!(zero.decide(Some(0)));
+    assert!(!zero.decide(Some(1)));
+    assert!(zero.decide(Some(100)));
+    assert!(!zero.decide(None));
+}
+
+#[test]
+fn zero_inhabited_chunks_survive_the_threshold() {
+    let run = |keep_zero_inhabited| {
+        let world = common::temp_dir("rt-keep-zero");
+        common::write_region(
+            &world.join("region").join("r.0.0.mca"),
+            &[0, 5, 50, 500]
+                .iter()
+                .enumerate()
+                .map(|(i, t)| {
+                    (
+                        i as u32,
+                        common::chunk_bytes(3, &common::inhabited_payload(*t)),
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+        let out = common::temp_dir("rt-keep-zero-out");
+        let opts = RunOptions {
+            inhabited_threshold: 100,
+            progress_mode: ProgressMode::Off,
+            keep_zero_inhabited,
+            ..RunOptions::default()
+        };
+        rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+        let mut r = McaReader::open(
+            out.join("region")
+                .join("r.0.0.mca")
+                .to_string_lossy()
+                .as_ref(),
+        )
+        .unwrap();
+        r.entries()
+            .unwrap()
+            .iter()
+            .map(|e| e.region_index())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(run(false), vec![3]);
+    assert_eq!(run(true), vec![0, 3]);
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::patterns::lastupdate::find_last_update;
+use rust_thanos::world::{level_time, ProgressMode, RunOptions};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn write_level_time(world: &Path, time: i64) {
+    let data = HashMap::from([("Time".to_string(), Value::Long(time))]);
+    let nbt = common::nbt_compound(vec![("Data", Value::Compound(data))]);
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(&nbt).unwrap();
+    fs::create_dir_all(world).unwrap();
+    fs::write(world.join("level.dat"), enc.finish().unwrap()).unwrap();
+}
+
+/// A never-visited chunk, with `LastUpdate` when given.
+fn chunk(last_update: Option<i64>) -> Vec<u8> {
+    let mut fields = vec![("InhabitedTime", Value::Long(0))];
+    if let Some(t) = last_update {
+        fields.push(("LastUpdate", Value::Long(t)));
+    }
+    common::chunk_bytes(3, &common::nbt_compound(fields))
+}
+
+/// World Time 100000; chunks updated at 99000, 50000, never (no field), and one in
+/// the legacy `Level` layout updated at 99500.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-last-update");
+    write_level_time(&world, 100_000);
+    let level = HashMap::from([
+        ("LastUpdate".to_string(), Value::Long(99_500)),
+        ("InhabitedTime".to_string(), Value::Long(0)),
+    ]);
+    let legacy = common::nbt_compound(vec![("Level", Value::Compound(level))]);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, chunk(Some(99_000))),
+            (1, chunk(Some(50_000))),
+            (2, chunk(None)),
+            (3, common::chunk_bytes(3, &legacy)),
+        ],
+    );
+    world
+}
+
+fn run(world: PathBuf, within: Option<i64>) -> PathBuf {
+    let out = common::temp_dir("rt-last-update-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        last_update_within: within,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    out.join("region").join("r.0.0.mca")
+}
+
+#[test]
+fn reads_time_and_last_update() {
+    let world = world();
+    assert_eq!(level_time(&world).unwrap(), 100_000);
+    assert!(level_time(&common::temp_dir("rt-last-update-none")).is_err());
+    let nbt = common::nbt_compound(vec![("LastUpdate", Value::Long(7))]);
+    assert_eq!(find_last_update(&nbt), Some(7));
+    assert_eq!(find_last_update(&common::nbt_compound(vec![])), None);
+}
+
+#[test]
+fn keeps_recently_updated_chunks() {
+    assert_eq!(common::kept_indices(&run(world(), Some(2_000))), vec![0, 3]);
+    assert_eq!(
+        common::kept_indices(&run(world(), Some(60_000))),
+        vec![0, 1, 3]
+    );
+    // without the option nothing here is inhabited enough
+    assert!(common::kept_indices(&run(world(), None)).is_empty());
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::path::Path;
+
+/// Indices 0..3 with InhabitedTime given per dimension.
+fn write_dim(dim: &Path, inhabited: [i64; 3]) {
+    let chunks: Vec<(u32, Vec<u8>)> = inhabited
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            (
+                i as u32,
+                common::chunk_bytes(3, &common::inhabited_payload(*t)),
+            )
+        })
+        .collect();
+    common::write_region(&dim.join("region").join("r.0.0.mca"), &chunks);
+}
+
+fn kept(out: &Path, dim: &str) -> Vec<u32> {
+    let region = out.join(dim).join("region").join("r.0.0.mca");
+    let mut reader = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect()
+}
+
+fn run(links: Vec<Vec<&str>>) -> std::path::PathBuf {
+    let world = common::temp_dir("rt-link");
+    write_dim(&world, [100_000, 0, 0]);
+    write_dim(&world.join("DIM-1"), [0, 100_000, 0]);
+    write_dim(&world.join("DIM1"), [0, 0, 100_000]);
+    let out = common::temp_dir("rt-link-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        linked_dimensions: links
+            .into_iter()
+            .map(|g| g.into_iter().map(String::from).collect())
+            .collect(),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    out
+}
+
+#[test]
+fn dimensions_are_independent_without_links() {
+    let out = run(vec![]);
+    assert_eq!(kept(&out, "."), vec![0]);
+    assert_eq!(kept(&out, "DIM-1"), vec![1]);
+    assert_eq!(kept(&out, "DIM1"), vec![2]);
+}
+
+#[test]
+fn linked_dimensions_keep_each_others_chunks() {
+    let out = run(vec![vec!["overworld", "DIM-1"]]);
+    assert_eq!(kept(&out, "."), vec![0, 1]);
+    assert_eq!(kept(&out, "DIM-1"), vec![0, 1]);
+    // not in the group
+    assert_eq!(kept(&out, "DIM1"), vec![2]);
+}
+
+#[test]
+fn groups_sharing_a_dimension_do_not_chain() {
+    let out = run(vec![vec!["overworld", "DIM-1"], vec!["DIM-1", "the_end"]]);
+    assert_eq!(kept(&out, "DIM-1"), vec![0, 1, 2]);
+    assert_eq!(kept(&out, "."), vec![0, 1]);
+    assert_eq!(kept(&out, "DIM1"), vec![1, 2]);
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::summarize_dimensions;
+
+#[test]
+fn summarizes_discovered_dimensions() {
+    let world = common::temp_dir("rt-list-dims");
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, chunk.clone()), (1, chunk.clone()), (40, chunk.clone())],
+    );
+    common::write_region(&world.join("region").join("r.1.0.mca"), &[(0, chunk)]);
+    common::write_dimension(&world.join("DIM-1"), 0);
+    common::write_dimension(&world.join("dimensions").join("ns").join("mine"), 0);
+
+    let dims = summarize_dimensions(&world, 3, &RegionNaming::default()).unwrap();
+    let rows: Vec<(&str, u64, u64)> = dims
+        .iter()
+        .map(|d| (d.label.as_str(), d.regions, d.chunks))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![(".", 2, 4), ("DIM-1", 1, 1), ("dimensions/ns/mine", 1, 1)]
+    );
+
+    // too shallow for the datapack dimension
+    let labels: Vec<String> = summarize_dimensions(&world, 1, &RegionNaming::default())
+        .unwrap()
+        .into_iter()
+        .map(|d| d.label)
+        .collect();
+    assert_eq!(labels, vec![".", "DIM-1"]);
+}
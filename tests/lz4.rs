@@ -1,5 +1,6 @@
 use byteorder::{ByteOrder, LittleEndian};
-use rust_thanos::mca::entry::{decode_lz4_blocks, lz4_checksum};
+use rust_thanos::mca::entry::{decode_lz4_blocks, encode_lz4_blocks, lz4_checksum, Lz4BlockReader};
+use std::io::Read;
 
 #[test]
 fn lz4_roundtrip() {
@@ -21,3 +22,66 @@ fn lz4_roundtrip() {
     let decoded = decode_lz4_blocks(&stream).expect("decode");
     assert_eq!(decoded, payload);
 }
+
+fn blocks(stream: &[u8]) -> Vec<(u8, usize, usize)> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < stream.len() {
+        assert_eq!(&stream[i..i + 8], b"LZ4Block");
+        let comp = LittleEndian::read_u32(&stream[i + 9..]) as usize;
+        let decomp = LittleEndian::read_u32(&stream[i + 13..]) as usize;
+        out.push((stream[i + 8], comp, decomp));
+        i += 21 + comp;
+    }
+    out
+}
+
+#[test]
+fn encode_roundtrips_across_block_sizes() {
+    let mut payload: Vec<u8> = (0..200_000u32).map(|i| (i / 7 % 13) as u8).collect();
+    // an incompressible stretch forces raw blocks
+    let mut x = 12345u32;
+    payload.extend((0..5000).map(|_| {
+        x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (x >> 16) as u8
+    }));
+    for block_size in [64, 1000, 4096, 65536, 1 << 20] {
+        let stream = encode_lz4_blocks(&payload, block_size);
+        assert_eq!(
+            decode_lz4_blocks(&stream).unwrap(),
+            payload,
+            "{}",
+            block_size
+        );
+        let mut streamed = Vec::new();
+        Lz4BlockReader::new(&stream[..])
+            .read_to_end(&mut streamed)
+            .unwrap();
+        assert_eq!(streamed, payload, "{}", block_size);
+        let b = blocks(&stream);
+        assert_eq!(b.len(), payload.len().div_ceil(block_size) + 1);
+        assert!(b.iter().all(|(_, _, d)| *d <= block_size));
+    }
+}
+
+#[test]
+fn encode_matches_lz4_java_framing() {
+    let compressible = vec![7u8; 100_000];
+    let b = blocks(&encode_lz4_blocks(&compressible, 65536));
+    // token low nibble: log2(block size) - 10
+    assert_eq!(b[0].0, 0x26);
+    assert_eq!((b[0].2, b[1].2), (65536, 100_000 - 65536));
+    assert!(b[0].1 < b[0].2);
+    // closing empty raw block
+    assert_eq!(*b.last().unwrap(), (0x16, 0, 0));
+
+    let noise: Vec<u8> = (0..64u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+        .collect();
+    let b = blocks(&encode_lz4_blocks(&noise, 64));
+    assert_eq!(b[0], (0x10, 64, 64));
+    assert_eq!(
+        decode_lz4_blocks(&encode_lz4_blocks(&[], 64)).unwrap(),
+        Vec::<u8>::new()
+    );
+}
@@ -1,5 +1,7 @@
 use byteorder::{ByteOrder, LittleEndian};
-use rust_thanos::mca::entry::decode_lz4_blocks;
+use rust_thanos::mca::entry::{decode_lz4_blocks, Lz4BlockReader};
+use rust_thanos::mca::error::McaError;
+use std::io::Read;
 
 #[test]
 fn lz4_invalid_checksum() {
@@ -20,3 +22,30 @@ fn lz4_invalid_checksum() {
     stream.extend_from_slice(&compressed[4..]);
     assert!(decode_lz4_blocks(&stream).is_err());
 }
+
+#[test]
+fn lz4_oversized_block_is_rejected_before_decoding() {
+    let mut stream = Vec::new();
+    stream.extend_from_slice(b"LZ4Block");
+    stream.push(0x20);
+    let mut buf = [0u8; 4];
+    LittleEndian::write_u32(&mut buf, 4);
+    stream.extend_from_slice(&buf);
+    // claims 4 GiB decoded
+    LittleEndian::write_u32(&mut buf, u32::MAX);
+    stream.extend_from_slice(&buf);
+    LittleEndian::write_u32(&mut buf, 0);
+    stream.extend_from_slice(&buf);
+    stream.extend_from_slice(&[0u8; 4]);
+
+    let err = decode_lz4_blocks(&stream).unwrap_err();
+    assert!(
+        matches!(err, McaError::Lz4BlockTooLarge { decompressed, .. } if decompressed == u32::MAX as usize),
+        "{}",
+        err
+    );
+    let err = Lz4BlockReader::new(&stream[..])
+        .read_to_end(&mut Vec::new())
+        .unwrap_err();
+    assert!(err.to_string().contains("block limit"), "{}", err);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::budget::MemoryBudget;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn permits_wait_for_room_below_the_cap() {
+    let budget = Arc::new(MemoryBudget::new(100));
+    let peak = Arc::new(AtomicU64::new(0));
+    let workers: Vec<_> = (0..8)
+        .map(|_| {
+            let (budget, peak) = (budget.clone(), peak.clone());
+            thread::spawn(move || {
+                for _ in 0..20 {
+                    let _permit = budget.acquire(30);
+                    peak.fetch_max(budget.used(), Ordering::SeqCst);
+                    thread::sleep(Duration::from_micros(100));
+                }
+            })
+        })
+        .collect();
+    for w in workers {
+        w.join().unwrap();
+    }
+    assert!(peak.load(Ordering::SeqCst) <= 90);
+    assert_eq!(budget.used(), 0);
+}
+
+#[test]
+fn oversized_permit_is_granted_alone() {
+    let budget = MemoryBudget::new(10);
+    let big = budget.acquire(1000);
+    assert_eq!(budget.used(), 1000);
+    drop(big);
+    let _small = budget.acquire(5);
+    assert_eq!(budget.used(), 5);
+}
+
+#[test]
+fn capped_run_keeps_the_same_chunks() {
+    let world = common::temp_dir("rt-max-memory");
+    common::write_dimension(&world, 100_000);
+    common::write_dimension(&world.join("DIM-1"), 0);
+    let out = common::temp_dir("rt-max-memory-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        max_memory_mb: Some(1),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let count = |p: std::path::PathBuf| {
+        McaReader::open(p.to_string_lossy().as_ref())
+            .unwrap()
+            .chunk_count()
+            .unwrap()
+    };
+    assert_eq!(count(out.join("region").join("r.0.0.mca")), 1);
+    assert_eq!(count(out.join("DIM-1").join("region").join("r.0.0.mca")), 0);
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+fn world() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-max-removed");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    let unused = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, played),
+            (1, unused.clone()),
+            (2, unused.clone()),
+            (3, unused),
+        ],
+    );
+    world
+}
+
+/// Every file below `out`, relative to it.
+fn output_files(out: &Path) -> Vec<String> {
+    WalkDir::new(out)
+        .into_iter()
+        .map(|e| e.unwrap())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().strip_prefix(out).unwrap().display().to_string())
+        .collect()
+}
+
+fn opts(limit: f64) -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        max_removed_percent: Some(limit),
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn in_place_run_above_the_cap_leaves_the_world_untouched() {
+    let world = world();
+    let region = world.join("region").join("r.0.0.mca");
+    let before = fs::read(&region).unwrap();
+    let err = rust_thanos::world::run_with_options(world.clone(), None, &opts(50.0)).unwrap_err();
+    assert!(err.to_string().contains("75.00%"), "{}", err);
+    assert_eq!(fs::read(&region).unwrap(), before);
+}
+
+#[test]
+fn output_run_above_the_cap_leaves_no_regions_in_the_output() {
+    let world = world();
+    let out = common::temp_dir("rt-max-removed-abort-out");
+    let err =
+        rust_thanos::world::run_with_options(world, Some(out.clone()), &opts(50.0)).unwrap_err();
+    assert!(err.to_string().contains("75.00%"), "{}", err);
+    assert_eq!(output_files(&out), Vec::<String>::new());
+    assert_eq!(fs::read_dir(&out).unwrap().count(), 0);
+}
+
+#[test]
+fn run_within_the_cap_succeeds() {
+    let world = world();
+    let out = common::temp_dir("rt-max-removed-out");
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts(75.0)).unwrap();
+    // moved out of the staging folder
+    assert_eq!(output_files(&out), vec!["region/r.0.0.mca"]);
+}
+
+#[test]
+fn in_place_run_below_the_kept_floor_leaves_the_world_untouched() {
+    let world = world();
+    let region = world.join("region").join("r.0.0.mca");
+    let before = fs::read(&region).unwrap();
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        min_kept_chunks: 2,
+        ..RunOptions::default()
+    };
+    let err = rust_thanos::world::run_with_options(world.clone(), None, &opts).unwrap_err();
+    assert!(err.to_string().contains("--min-kept-chunks 2"), "{}", err);
+    assert_eq!(fs::read(&region).unwrap(), before);
+}
+
+#[test]
+fn output_run_below_the_kept_floor_leaves_no_regions_in_the_output() {
+    let world = world();
+    let out = common::temp_dir("rt-min-kept-abort-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        min_kept_chunks: 2,
+        copy_world_files: true,
+        ..RunOptions::default()
+    };
+    let err = rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap_err();
+    assert!(err.to_string().contains("--min-kept-chunks 2"), "{}", err);
+    assert_eq!(fs::read_dir(&out).unwrap().count(), 0);
+}
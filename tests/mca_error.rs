@@ -0,0 +1,138 @@
+mod common;
+
+use rust_thanos::mca::entry::McaEntry;
+use rust_thanos::mca::error::McaError;
+use rust_thanos::mca::reader::McaReader;
+use std::fs;
+
+#[test]
+fn invalid_filename_is_reported() {
+    let err = McaReader::open("region/not-a-region.mca").err().unwrap();
+    assert!(matches!(err, McaError::InvalidFilename(_)));
+}
+
+#[test]
+fn unknown_compression_is_reported() {
+    let path = std::env::temp_dir().join(format!("rt-unknown-cm-{}", uuid::Uuid::new_v4()));
+    // length=1, method=9 (not a known compression id)
+    fs::write(&path, [0u8, 0, 0, 1, 9]).unwrap();
+    let file = fs::File::open(&path).unwrap();
+    let mut entry = McaEntry::new(file, 0, 4096, 0, 0, 0, 0);
+    let err = entry.read_header().err().unwrap();
+    assert!(matches!(err, McaError::UnknownCompression(9)));
+}
+
+#[test]
+fn truncated_chunk_is_reported() {
+    let path = std::env::temp_dir().join(format!("rt-truncated-{}", uuid::Uuid::new_v4()));
+    // header declares 100 bytes but only 3 follow
+    fs::write(&path, [0u8, 0, 0, 100, 3, 1, 2, 3]).unwrap();
+    let file = fs::File::open(&path).unwrap();
+    let mut entry = McaEntry::new(file, 0, 4096, 0, 0, 0, 0);
+    let err = entry.serialized_bytes().err().unwrap();
+    assert!(matches!(err, McaError::TruncatedChunk { .. }));
+}
+
+#[test]
+fn zero_chunk_length_is_reported() {
+    let path = std::env::temp_dir().join(format!("rt-zero-len-{}", uuid::Uuid::new_v4()));
+    // length=0 leaves no room for the method byte that follows it
+    fs::write(&path, [0u8, 0, 0, 0, 2, 0x78, 0x9c]).unwrap();
+    let file = fs::File::open(&path).unwrap();
+    let mut entry = McaEntry::new(file, 0, 4096, 0, 0, 0, 0);
+    let err = entry.data_bytes().err().unwrap();
+    assert!(
+        matches!(
+            err,
+            McaError::InvalidChunkLength {
+                declared: 0,
+                min: 1
+            }
+        ),
+        "{}",
+        err
+    );
+    assert!(matches!(
+        entry.all_data_uncompressed(),
+        Err(McaError::InvalidChunkLength { .. })
+    ));
+    assert!(entry.uncompressed_reader().is_err());
+
+    // a custom method whose length does not even cover its name
+    let path = std::env::temp_dir().join(format!("rt-short-custom-{}", uuid::Uuid::new_v4()));
+    let mut data = vec![0u8, 0, 0, 2, 127, 0, 4];
+    data.extend_from_slice(b"test");
+    fs::write(&path, data).unwrap();
+    let file = fs::File::open(&path).unwrap();
+    let mut entry = McaEntry::new(file, 0, 4096, 0, 0, 0, 0);
+    let err = entry.data_bytes().err().unwrap();
+    assert!(
+        matches!(
+            err,
+            McaError::InvalidChunkLength {
+                declared: 2,
+                min: 7
+            }
+        ),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn offset_past_the_end_of_the_file_is_reported() {
+    let path = common::temp_dir("rt-huge-offset").join("r.0.0.mca");
+    common::write_region(
+        &path,
+        &[(0, common::chunk_bytes(3, &common::inhabited_payload(7)))],
+    );
+    let mut data = fs::read(&path).unwrap();
+    let sectors = (data.len() / 4096) as u32;
+    // 0xFFFFFF sectors is 64 GiB in: too large for u32 byte arithmetic
+    for (slot, offset) in [(1usize, 0xFF_FFFFu32), (2, sectors)] {
+        data[slot * 4..slot * 4 + 4].copy_from_slice(&((offset << 8) | 1).to_be_bytes());
+    }
+    fs::write(&path, &data).unwrap();
+
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    for (slot, offset) in [(1usize, 0xFF_FFFFu64 * 4096), (2, sectors as u64 * 4096)] {
+        match reader.get(slot).err().unwrap() {
+            McaError::OffsetOutOfRange {
+                index,
+                offset: at,
+                file_len,
+            } => {
+                assert_eq!((index, at, file_len), (slot, offset, data.len() as u64));
+            }
+            other => panic!("slot {}: {}", slot, other),
+        }
+    }
+    let indices: Vec<u32> = reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(indices, vec![0]);
+    assert!(reader.get(0).unwrap().is_some());
+}
+
+#[test]
+fn truncated_header_names_the_file_and_table() {
+    let dir = common::temp_dir("rt-truncated-header");
+    for (len, table, got) in [(100usize, "location", 100usize), (5000, "timestamp", 904)] {
+        let path = dir.join(format!("r.{}.0.mca", len));
+        fs::write(&path, vec![0u8; len]).unwrap();
+        let name = path.to_string_lossy().to_string();
+        let err = McaReader::open(&name).unwrap().chunk_count().err().unwrap();
+        assert!(err.to_string().contains(&name), "{}", err);
+        match err {
+            McaError::TruncatedHeader {
+                path,
+                table: t,
+                got: g,
+            } => assert_eq!((path, t, g), (name, table, got)),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::modified::looks_modified;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::collections::HashMap;
+
+fn compound(fields: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+fn section(blocks: &[&str]) -> Value {
+    let palette = blocks
+        .iter()
+        .map(|b| compound(vec![("Name", Value::String(b.to_string()))]))
+        .collect();
+    compound(vec![(
+        "block_states",
+        compound(vec![("palette", Value::List(palette))]),
+    )])
+}
+
+fn chunk(extra: Vec<(&str, Value)>) -> Vec<u8> {
+    let mut fields = vec![
+        ("InhabitedTime", Value::Long(100_000)),
+        (
+            "sections",
+            Value::List(vec![section(&["minecraft:stone", "minecraft:air"])]),
+        ),
+        ("block_entities", Value::List(vec![])),
+    ];
+    fields.extend(extra);
+    common::nbt_compound(fields)
+}
+
+#[test]
+fn activity_markers_are_detected_in_both_layouts() {
+    assert_eq!(looks_modified(&chunk(vec![])), Some(false));
+    let chest = compound(vec![("id", Value::String("minecraft:chest".into()))]);
+    assert_eq!(
+        looks_modified(&chunk(vec![("block_entities", Value::List(vec![chest]))])),
+        Some(true)
+    );
+    let hopper = section(&["minecraft:stone", "minecraft:hopper"]);
+    assert_eq!(
+        looks_modified(&chunk(vec![("sections", Value::List(vec![hopper]))])),
+        Some(true)
+    );
+    let tick = compound(vec![("i", Value::String("minecraft:repeater".into()))]);
+    let level = HashMap::from([("TileTicks".to_string(), Value::List(vec![tick]))]);
+    let legacy = common::nbt_compound(vec![("Level", Value::Compound(level))]);
+    assert_eq!(looks_modified(&legacy), Some(true));
+    assert_eq!(looks_modified(b"not nbt"), None);
+}
+
+#[test]
+fn pristine_chunks_are_removed_unless_forced() {
+    let world = common::temp_dir("rt-modified");
+    let hopper = section(&["minecraft:hopper"]);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &chunk(vec![]))),
+            (
+                1,
+                common::chunk_bytes(3, &chunk(vec![("sections", Value::List(vec![hopper]))])),
+            ),
+            (2, common::chunk_bytes(3, &chunk(vec![]))),
+        ],
+    );
+    common::write_tickets(&world, &[("minecraft:forced", 2, 0)]);
+    let out = common::temp_dir("rt-modified-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        keep_modified_only: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+
+    let path = out.join("region").join("r.0.0.mca");
+    let mut reader = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = reader
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![1, 2]);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+
+fn run(world: &Path, abort_if_no_dimensions: bool, strict: bool) -> anyhow::Result<()> {
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        abort_if_no_dimensions,
+        strict,
+        ..RunOptions::default()
+    };
+    let out = common::temp_dir("rt-nodims-parent").join("out");
+    let result =
+        rust_thanos::world::run_with_options(world.to_path_buf(), Some(out.clone()), &opts);
+    if result.is_err() {
+        assert!(!out.exists(), "aborting must not create the output");
+    }
+    result
+}
+
+#[test]
+fn server_folder_points_at_the_world_inside() {
+    let server = common::temp_dir("rt-nodims");
+    common::write_level_dat(&server.join("survival"), 3465);
+    fs::write(server.join("server.properties"), "").unwrap();
+    // deeper than the default --max-dimension-depth of 3
+    common::write_dimension(&server.join("archive").join("a").join("b").join("c"), 0);
+
+    let err = run(&server, true, false).unwrap_err().to_string();
+    assert!(err.starts_with("no dimension found in"), "{}", err);
+    assert!(
+        err.contains("archive/, server.properties, survival/"),
+        "{}",
+        err
+    );
+    assert!(err.contains("survival holds a level.dat"), "{}", err);
+    assert!(err.contains("raise --max-dimension-depth"), "{}", err);
+
+    assert!(run(&server, false, true).is_err());
+    // without either flag it stays a warning
+    run(&server, false, false).unwrap();
+}
+
+#[test]
+fn explains_a_world_without_regions() {
+    let world = common::temp_dir("rt-nodims-fresh");
+    common::write_level_dat(&world, 3465);
+    let err = run(&world, true, false).unwrap_err().to_string();
+    assert!(err.contains("level.dat is here but no region/"), "{}", err);
+
+    let bedrock = common::temp_dir("rt-nodims-bedrock");
+    fs::create_dir_all(bedrock.join("db")).unwrap();
+    let err = run(&bedrock, true, false).unwrap_err().to_string();
+    assert!(err.contains("Bedrock"), "{}", err);
+}
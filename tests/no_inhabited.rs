@@ -0,0 +1,67 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::path::{Path, PathBuf};
+
+/// Chunks 0 and 1 unvisited, 2 and 3 heavily played.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-no-inhabited");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[0, 0, 1_000_000, 1_000_000]
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                (
+                    i as u32,
+                    common::chunk_bytes(3, &common::inhabited_payload(*t)),
+                )
+            })
+            .collect::<Vec<_>>(),
+    );
+    world
+}
+
+fn run(world: &Path, opts: RunOptions) -> anyhow::Result<Vec<u32>> {
+    let out = common::temp_dir("rt-no-inhabited-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        no_inhabited: true,
+        ..opts
+    };
+    rust_thanos::world::run_with_options(world.to_path_buf(), Some(out.clone()), &opts)?;
+    Ok(common::kept_indices(&out.join("region").join("r.0.0.mca")))
+}
+
+#[test]
+fn only_the_keep_options_decide() {
+    let world = world();
+    let kept = run(
+        &world,
+        RunOptions {
+            keep_ranges: vec![(1, 0, 2, 0)],
+            ..RunOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(kept, vec![1, 2]);
+}
+
+#[test]
+fn no_inhabited_alone_is_refused_unless_confirmed() {
+    let world = world();
+    let err = run(&world, RunOptions::default()).unwrap_err();
+    assert!(err.to_string().contains("--no-inhabited"), "{err}");
+
+    common::write_tickets(&world, &[("minecraft:forced", 3, 0)]);
+    let kept = run(
+        &world,
+        RunOptions {
+            max_removed_percent: Some(100.0),
+            ..RunOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(kept, vec![3]);
+}
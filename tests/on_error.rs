@@ -0,0 +1,71 @@
+mod common;
+
+use rust_thanos::world::{OnError, ProgressMode, RunOptions};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Chunk 0 is played, chunk 1 fresh, chunks 2 and 3 fail to decompress; chunk 3 is
+/// force-loaded.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-on-error");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+            (2, common::chunk_bytes(2, b"not zlib")),
+            (3, common::chunk_bytes(2, b"not zlib either")),
+        ],
+    );
+    common::write_tickets(&world, &[("forced", 3, 0)]);
+    world
+}
+
+fn run(on_error: OnError, remove_unknown: bool) -> Vec<u32> {
+    let out = common::temp_dir("rt-on-error-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        on_error,
+        remove_unknown,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.clone()), &opts).unwrap();
+    common::kept_indices(&out.join("region").join("r.0.0.mca"))
+}
+
+#[test]
+fn undecodable_chunks_are_kept_by_default() {
+    assert_eq!(RunOptions::default().on_error, OnError::Keep);
+    assert_eq!(run(OnError::Keep, false), vec![0, 2, 3]);
+    // not an unknown InhabitedTime: remove_unknown leaves them alone
+    assert_eq!(run(OnError::Keep, true), vec![0, 2, 3]);
+}
+
+#[test]
+fn on_error_remove_drops_them_unless_pinned() {
+    assert_eq!(run(OnError::Remove, false), vec![0, 3]);
+}
+
+#[test]
+fn cli_flag() {
+    let out = common::temp_dir("rt-on-error-cli");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(world())
+        .arg(&out)
+        .args(["-t", "5", "--progress-mode", "off", "--on-error", "remove"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        vec![0, 3]
+    );
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use rust_thanos::mca::writer::morton_rank;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn morton_rank_orders_aligned_blocks_first() {
+    // (x, z) = (0,0) (1,0) (0,1) (1,1) (2,0)
+    let order: Vec<u32> = [0, 1, 32, 33, 2].iter().map(|&i| morton_rank(i)).collect();
+    assert_eq!(order, vec![0, 1, 2, 3, 4]);
+    let mut all: Vec<u32> = (0..1024).map(morton_rank).collect();
+    all.sort_unstable();
+    assert_eq!(all, (0..1024).collect::<Vec<_>>());
+}
+
+fn sector_offsets(path: &std::path::Path, indices: &[usize]) -> Vec<u32> {
+    let data = fs::read(path).unwrap();
+    indices
+        .iter()
+        .map(|&i| u32::from_be_bytes(data[i * 4..i * 4 + 4].try_into().unwrap()) >> 8)
+        .collect()
+}
+
+#[test]
+fn optimize_layout_writes_chunks_in_z_order() {
+    let world = common::temp_dir("rt-layout");
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    let indices = [0usize, 1, 2, 32, 33];
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &indices.map(|i| (i as u32, chunk.clone())),
+    );
+    let run = |optimize_layout: bool| {
+        let out = common::temp_dir("rt-layout-out");
+        let opts = RunOptions {
+            inhabited_threshold: 10,
+            progress_mode: ProgressMode::Off,
+            optimize_layout,
+            ..RunOptions::default()
+        };
+        rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+        sector_offsets(&out.join("region").join("r.0.0.mca"), &indices)
+    };
+    assert_eq!(run(false), vec![2, 3, 4, 5, 6]);
+    // Z-order: 0, 1, 32, 33, 2
+    assert_eq!(run(true), vec![2, 3, 6, 4, 5]);
+}
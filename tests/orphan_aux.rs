@@ -0,0 +1,55 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+fn opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    }
+}
+
+fn entity_region(path: &std::path::Path) -> Vec<u8> {
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(path, &[(0, chunk.clone()), (7, chunk)]);
+    fs::read(path).unwrap()
+}
+
+#[test]
+fn entities_only_dimension_is_copied_as_is() {
+    let world = common::temp_dir("rt-orphan-dim");
+    common::write_dimension(&world, 100_000);
+    let entities = entity_region(&world.join("DIM1").join("entities").join("r.0.0.mca"));
+    let poi = entity_region(&world.join("DIM1").join("poi").join("r.-1.0.mca"));
+
+    let dims = rust_thanos::world::discover_dimensions(&world, 3).unwrap();
+    assert!(dims.contains(&world.join("DIM1")), "{:?}", dims);
+
+    let out = common::temp_dir("rt-orphan-dim-out");
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts()).unwrap();
+    let dim = out.join("DIM1");
+    assert_eq!(
+        fs::read(dim.join("entities").join("r.0.0.mca")).unwrap(),
+        entities
+    );
+    assert_eq!(fs::read(dim.join("poi").join("r.-1.0.mca")).unwrap(), poi);
+    assert!(!dim.join("region").exists());
+}
+
+#[test]
+fn orphaned_entities_region_survives_in_place() {
+    let world = common::temp_dir("rt-orphan-inplace");
+    common::write_dimension(&world, 0);
+    entity_region(&world.join("entities").join("r.0.0.mca"));
+    let orphan = entity_region(&world.join("entities").join("r.5.5.mca"));
+
+    rust_thanos::world::run_with_options(world.clone(), None, &opts()).unwrap();
+    assert_eq!(
+        fs::read(world.join("entities").join("r.5.5.mca")).unwrap(),
+        orphan
+    );
+    // the matching one is still filtered with its region
+    assert!(world.join("entities").join("r.0.0.mca").is_file());
+}
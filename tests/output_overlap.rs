@@ -0,0 +1,65 @@
+mod common;
+
+use rust_thanos::world::{OutputPolicy, ProgressMode, RunOptions};
+
+fn opts() -> RunOptions {
+    RunOptions {
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn output_equal_to_or_nested_in_input_is_rejected() {
+    let world = common::temp_dir("rt-overlap");
+    common::write_dimension(&world, 100);
+    for out in [
+        world.clone(),
+        world.join("out"),
+        world.join("region").join(".."),
+    ] {
+        let err = rust_thanos::world::run_with_options(world.clone(), Some(out), &opts())
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("output must not be"), "{}", err);
+    }
+    // Nothing was written into the world
+    assert!(!world.join("out").exists());
+}
+
+#[test]
+fn input_nested_in_output_is_rejected() {
+    let parent = common::temp_dir("rt-overlap-parent");
+    let world = parent.join("world");
+    common::write_dimension(&world, 100);
+    let err = rust_thanos::world::run_with_options(world, Some(parent), &opts())
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.starts_with("input must not be inside output"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn must_create_rejects_any_existing_output() {
+    let world = common::temp_dir("rt-must-create");
+    common::write_dimension(&world, 100);
+    let must_create = RunOptions {
+        output_policy: OutputPolicy::MustCreate,
+        ..opts()
+    };
+    let existing = common::temp_dir("rt-must-create-out");
+    let err = rust_thanos::world::run_with_options(world.clone(), Some(existing), &must_create)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("already exists"), "{}", err);
+
+    let fresh = common::temp_dir("rt-must-create-fresh").join("out");
+    rust_thanos::world::run_with_options(world.clone(), Some(fresh.clone()), &must_create).unwrap();
+    assert!(fresh.join("region").join("r.0.0.mca").is_file());
+    // the default still accepts an existing empty directory
+    let empty = common::temp_dir("rt-allow-empty-out");
+    rust_thanos::world::run_with_options(world, Some(empty), &opts()).unwrap();
+}
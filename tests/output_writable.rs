@@ -0,0 +1,50 @@
+mod common;
+
+use rust_thanos::world::{probe_writable, ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn probing_leaves_nothing_behind() {
+    let dir = common::temp_dir("rt-probe");
+    probe_writable(&dir, "output").unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    // a directory yet to be created is probed through its parent, not created
+    let nested = dir.join("a").join("b");
+    probe_writable(&nested, "output").unwrap();
+    assert!(!dir.join("a").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_directories_are_refused() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = common::temp_dir("rt-probe-ro");
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+    // root ignores permission bits; nothing to check then
+    let writable = fs::write(dir.join("x"), b"").is_ok();
+    if !writable {
+        let err = probe_writable(&dir.join("out"), "output").unwrap_err();
+        assert!(err.to_string().contains("is not writable"), "{}", err);
+    }
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn an_unwritable_output_fails_before_processing() {
+    let world = common::temp_dir("rt-probe-world");
+    common::write_dimension(&world, 100);
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    // procfs refuses new files even to root
+    let out = std::path::PathBuf::from("/proc/self/rt-thanos-out");
+    let err = rust_thanos::world::run_with_options(world, Some(out), &opts).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("output /proc/self is not writable"),
+        "{}",
+        err
+    );
+}
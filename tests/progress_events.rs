@@ -0,0 +1,129 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// r.0.0 filled with 1024 chunks, every other one played; r.1.0 with a single played
+/// chunk, copied as-is through `--region-bounds`.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-progress-events");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(1000));
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    let chunks: Vec<_> = (0..1024)
+        .map(|i| {
+            (
+                i,
+                if i % 2 == 0 {
+                    played.clone()
+                } else {
+                    fresh.clone()
+                },
+            )
+        })
+        .collect();
+    common::write_region(&world.join("region").join("r.0.0.mca"), &chunks);
+    common::write_region(&world.join("region").join("r.1.0.mca"), &[(0, played)]);
+    world
+}
+
+fn events(path: &Path) -> Vec<Value> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+fn check(events: &[Value]) {
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds.first(), Some(&"start"));
+    assert_eq!(kinds.last(), Some(&"summary"));
+    assert_eq!(events[0]["chunks"], 1025);
+    assert_eq!(events[0]["regions"], 2);
+    let chunks: Vec<&Value> = events.iter().filter(|e| e["event"] == "chunks").collect();
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(
+        (&chunks[0]["done"], &chunks[0]["total"]),
+        (&1024.into(), &1025.into())
+    );
+
+    let mut regions: Vec<&Value> = events.iter().filter(|e| e["event"] == "region").collect();
+    regions.sort_by_key(|e| e["region"].as_str().unwrap().to_string());
+    assert_eq!(regions.len(), 2);
+    assert_eq!(regions[0]["dimension"], ".");
+    assert_eq!(regions[0]["region"], "r.0.0.mca");
+    assert_eq!(
+        (
+            &regions[0]["chunks"],
+            &regions[0]["removed"],
+            &regions[0]["verbatim"]
+        ),
+        (&1024.into(), &512.into(), &false.into())
+    );
+    assert_eq!(
+        (
+            &regions[1]["chunks"],
+            &regions[1]["removed"],
+            &regions[1]["verbatim"]
+        ),
+        (&1.into(), &0.into(), &true.into())
+    );
+    let last_region = events.iter().rfind(|e| e["event"] == "region").unwrap();
+    assert_eq!(last_region["regions_done"], 2);
+    assert_eq!(last_region["chunks_done"], 1025);
+
+    let summary = events.last().unwrap();
+    assert_eq!(
+        (&summary["kept"], &summary["removed"]),
+        (&513.into(), &512.into())
+    );
+    assert!(summary["after_bytes"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn events_cover_start_chunks_regions_and_summary() {
+    let file = common::temp_dir("rt-progress-events-file").join("events.jsonl");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        region_bounds: Some((0, 0, 0, 0)),
+        progress_events: Some(file.clone()),
+        ..RunOptions::default()
+    };
+    let out = common::temp_dir("rt-progress-events-out");
+    rust_thanos::world::run_with_options(world(), Some(out), &opts).unwrap();
+    check(&events(&file));
+}
+
+#[cfg(unix)]
+#[test]
+fn progress_fd_writes_to_the_inherited_descriptor() {
+    let file = common::temp_dir("rt-progress-fd").join("events.jsonl");
+    let out = common::temp_dir("rt-progress-fd-out");
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(r#"exec "$0" "$@" 3>"$EVENTS""#)
+        .arg(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(world())
+        .arg(&out)
+        .args(["-t", "5", "--region-bounds", "0", "0", "0", "0"])
+        .args(["--progress-mode", "off", "--progress-fd", "3"])
+        .env("EVENTS", &file)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // the human-facing stdout carries no events
+    assert!(!String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("\"event\""));
+    check(&events(&file));
+}
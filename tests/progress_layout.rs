@@ -0,0 +1,48 @@
+use rust_thanos::world::progress::{BarLayout, MAX_BAR_WIDTH, MIN_COMPACT_COLUMNS};
+
+#[test]
+fn wide_terminals_get_a_clamped_bar() {
+    assert_eq!(BarLayout::for_columns(80, 40), BarLayout::Bar(40));
+    assert_eq!(
+        BarLayout::for_columns(200, 40),
+        BarLayout::Bar(MAX_BAR_WIDTH as usize)
+    );
+    assert_eq!(BarLayout::for_columns(40, 30), BarLayout::Bar(10));
+}
+
+#[test]
+fn narrow_terminals_drop_the_bar_then_the_numbers() {
+    // too little room for a bar next to the numbers
+    assert_eq!(BarLayout::for_columns(45, 40), BarLayout::Compact);
+    assert_eq!(
+        BarLayout::for_columns(MIN_COMPACT_COLUMNS, 40),
+        BarLayout::Compact
+    );
+    assert_eq!(
+        BarLayout::for_columns(MIN_COMPACT_COLUMNS - 1, 40),
+        BarLayout::Spinner
+    );
+    assert_eq!(BarLayout::for_columns(0, 40), BarLayout::Spinner);
+}
+
+#[test]
+fn templates_leave_out_what_does_not_fit() {
+    let parts = (
+        "{spinner:.green}",
+        "cyan/blue",
+        "{pos}/{len} 区块 {percent}%",
+    );
+    let template = |layout: BarLayout| layout.template(parts.0, parts.1, parts.2);
+    assert_eq!(
+        template(BarLayout::Bar(12)),
+        "{spinner:.green} {bar:12.cyan/blue} {pos}/{len} 区块 {percent}% {msg}"
+    );
+    assert_eq!(
+        template(BarLayout::Compact),
+        "{spinner:.green} {pos}/{len} 区块 {percent}%"
+    );
+    assert_eq!(template(BarLayout::Spinner), "{spinner:.green}");
+    for layout in [BarLayout::Bar(12), BarLayout::Compact, BarLayout::Spinner] {
+        assert!(indicatif::ProgressStyle::with_template(&template(layout)).is_ok());
+    }
+}
@@ -1,42 +1,34 @@
-use rust_thanos::world::ProgressMode;
+use rust_thanos::world::{progress_bars_created, ProgressMode};
 use std::path::PathBuf;
 
-#[test]
-fn run_with_all_progress_modes() {
-    let input = PathBuf::from("tests/Fixtures/world");
-    let out_off = PathBuf::from("./rust-out-off");
-    let _ = std::fs::remove_dir_all(&out_off);
-    rust_thanos::world::run(
-        input.clone(),
-        Some(out_off.clone()),
-        0,
-        false,
-        ProgressMode::Off,
-    )
-    .unwrap();
-    assert!(out_off.join("region").exists());
-
-    let out_global = PathBuf::from("./rust-out-global");
-    let _ = std::fs::remove_dir_all(&out_global);
+/// Runs the fixture world (three dimensions) and returns the progress bars it created.
+fn run_counting_bars(out: &str, mode: ProgressMode) -> u64 {
+    let out = PathBuf::from(out);
+    let _ = std::fs::remove_dir_all(&out);
+    let before = progress_bars_created();
     rust_thanos::world::run(
-        input.clone(),
-        Some(out_global.clone()),
+        PathBuf::from("tests/Fixtures/world"),
+        Some(out.clone()),
         0,
         false,
-        ProgressMode::Global,
+        mode,
     )
     .unwrap();
-    assert!(out_global.join("region").exists());
+    assert!(out.join("region").exists());
+    progress_bars_created() - before
+}
 
-    let out_region = PathBuf::from("./rust-out-region");
-    let _ = std::fs::remove_dir_all(&out_region);
-    rust_thanos::world::run(
-        input.clone(),
-        Some(out_region.clone()),
-        0,
-        false,
-        ProgressMode::Region,
-    )
-    .unwrap();
-    assert!(out_region.join("region").exists());
+#[test]
+fn run_with_all_progress_modes() {
+    assert_eq!(run_counting_bars("./rust-out-off", ProgressMode::Off), 0);
+    // only the aggregate bar
+    assert_eq!(
+        run_counting_bars("./rust-out-global", ProgressMode::Global),
+        1
+    );
+    // the aggregate bar plus one per dimension
+    assert_eq!(
+        run_counting_bars("./rust-out-region", ProgressMode::Region),
+        4
+    );
 }
@@ -0,0 +1,54 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::mca::repair::rebuild_header;
+use std::fs;
+use std::io::{Seek, SeekFrom, Write};
+
+fn chunk(x: i32, z: i32, last_update: i64) -> Vec<u8> {
+    common::chunk_bytes(
+        3,
+        &common::nbt_compound(vec![
+            ("xPos", Value::Int(x)),
+            ("zPos", Value::Int(z)),
+            ("LastUpdate", Value::Long(last_update)),
+        ]),
+    )
+}
+
+#[test]
+fn rebuilds_location_table_from_data_sectors() {
+    let dir = common::temp_dir("rt-rebuild");
+    let damaged = dir.join("r.0.0.mca");
+    let newest = chunk(5, 0, 10);
+    common::write_region(
+        &damaged,
+        &[
+            (0, chunk(0, 0, 1)),
+            (33, chunk(1, 1, 1)),
+            (5, newest.clone()),
+            // stale copy of chunk 5 that the old header pointed at
+            (5, chunk(5, 0, 3)),
+            // belongs to region (1, 0)
+            (7, chunk(40, 0, 1)),
+        ],
+    );
+    let mut f = fs::OpenOptions::new().write(true).open(&damaged).unwrap();
+    f.seek(SeekFrom::Start(0)).unwrap();
+    f.write_all(&[0xAB; 4096]).unwrap();
+    drop(f);
+
+    let fixed = dir.join("fixed").join("r.0.0.mca");
+    fs::create_dir_all(fixed.parent().unwrap()).unwrap();
+    let report = rebuild_header(&damaged, &fixed).unwrap();
+    assert_eq!(report.recovered, 3);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.unplaced, 1);
+
+    let mut r = McaReader::open(fixed.to_string_lossy().as_ref()).unwrap();
+    let mut entries = r.entries().unwrap();
+    let indices: Vec<u32> = entries.iter().map(|e| e.region_index()).collect();
+    assert_eq!(indices, vec![0, 5, 33]);
+    assert_eq!(entries[1].serialized_bytes().unwrap(), newest);
+}
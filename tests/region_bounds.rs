@@ -0,0 +1,43 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn regions_outside_bounds_are_copied_unchanged() {
+    let world = common::temp_dir("rt-bounds");
+    let unused = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for x in -1..=2 {
+        let name = format!("r.{}.0.mca", x);
+        common::write_region(&world.join("region").join(&name), &[(0, unused.clone())]);
+        common::write_region(&world.join("entities").join(&name), &[(0, unused.clone())]);
+    }
+    let out = common::temp_dir("rt-bounds-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        region_bounds: Some((0, 0, 1, 0)),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+
+    for x in -1..=2 {
+        let name = format!("r.{}.0.mca", x);
+        let region = out.join("region").join(&name);
+        let inside = (0..=1).contains(&x);
+        assert_eq!(
+            common::chunk_count(&region),
+            if inside { 0 } else { 1 },
+            "{}",
+            name
+        );
+        if !inside {
+            for dir in ["region", "entities"] {
+                assert_eq!(
+                    fs::read(out.join(dir).join(&name)).unwrap(),
+                    fs::read(world.join(dir).join(&name)).unwrap()
+                );
+            }
+        }
+    }
+}
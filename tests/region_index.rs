@@ -0,0 +1,163 @@
+mod common;
+
+use rust_thanos::mca::index::{IndexEntry, RegionIndex};
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+fn run(world: &Path, build_index: bool, use_index: bool) -> Vec<u32> {
+    let out = common::temp_dir("rt-index-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        build_index,
+        use_index,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.to_path_buf(), Some(out.clone()), &opts).unwrap();
+    common::kept_indices(&out.join("region").join("r.0.0.mca"))
+}
+
+#[test]
+fn build_index_records_every_chunk() {
+    let world = common::temp_dir("rt-index-build");
+    let region = world.join("region").join("r.0.0.mca");
+    common::write_region(
+        &region,
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(500))),
+            (7, common::chunk_bytes(3, &common::nbt_compound(vec![]))),
+        ],
+    );
+    assert_eq!(run(&world, true, false), vec![0, 7]);
+    let index = RegionIndex::load_fresh(&region).expect("fresh index");
+    let size = (1 + common::inhabited_payload(500).len() + 4) as u32;
+    assert_eq!(
+        index.entries[0],
+        IndexEntry {
+            index: 0,
+            compression: 3,
+            size,
+            inhabited: Some(500),
+        }
+    );
+    assert_eq!(index.entries[1].index, 7);
+    assert_eq!(index.entries[1].inhabited, None);
+}
+
+#[test]
+fn use_index_trusts_only_fresh_sidecars() {
+    let world = common::temp_dir("rt-index-use");
+    let region = world.join("region").join("r.0.0.mca");
+    common::write_region(
+        &region,
+        &[(0, common::chunk_bytes(3, &common::inhabited_payload(0)))],
+    );
+    // an index claiming the unused chunk was played on proves the chunk is not decoded
+    let entry = IndexEntry {
+        index: 0,
+        compression: 3,
+        size: 0,
+        inhabited: Some(1_000_000),
+    };
+    RegionIndex::new(&region, vec![entry])
+        .unwrap()
+        .write(&RegionIndex::sidecar_path(&region))
+        .unwrap();
+    assert_eq!(run(&world, false, true), vec![0]);
+    assert_eq!(run(&world, false, false), Vec::<u32>::new());
+
+    // touching the region makes the sidecar stale
+    let later = SystemTime::now() + Duration::from_secs(60);
+    fs::File::options()
+        .write(true)
+        .open(&region)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    assert!(RegionIndex::load_fresh(&region).is_none());
+    assert_eq!(run(&world, false, true), Vec::<u32>::new());
+}
+
+#[test]
+fn build_index_is_rejected_in_place() {
+    let world = common::temp_dir("rt-index-inplace");
+    common::write_dimension(&world, 100);
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        build_index: true,
+        ..RunOptions::default()
+    };
+    assert!(rust_thanos::world::run_with_options(world, None, &opts).is_err());
+}
+
+#[test]
+fn use_index_trusts_a_missing_inhabited_time() {
+    let world = common::temp_dir("rt-index-none");
+    let region = world.join("region").join("r.0.0.mca");
+    common::write_region(
+        &region,
+        &[(
+            0,
+            common::chunk_bytes(3, &common::inhabited_payload(1_000_000)),
+        )],
+    );
+    // indexed without InhabitedTime: with --remove-unknown the played chunk goes
+    let entry = IndexEntry {
+        index: 0,
+        compression: 3,
+        size: 0,
+        inhabited: None,
+    };
+    RegionIndex::new(&region, vec![entry])
+        .unwrap()
+        .write(&RegionIndex::sidecar_path(&region))
+        .unwrap();
+    let out = common::temp_dir("rt-index-none-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        remove_unknown: true,
+        progress_mode: ProgressMode::Off,
+        use_index: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        Vec::<u32>::new()
+    );
+}
+
+#[test]
+fn build_index_skips_undecodable_chunks() {
+    let world = common::temp_dir("rt-index-corrupt");
+    let region = world.join("region").join("r.0.0.mca");
+    common::write_region(
+        &region,
+        &[
+            (0, common::chunk_bytes(3, &common::inhabited_payload(500))),
+            // zlib header promising data that is not there
+            (1, common::chunk_bytes(2, &[0x78, 0x9c, 0xff, 0xff])),
+        ],
+    );
+    run(&world, true, false);
+    let index = RegionIndex::load_fresh(&region).expect("fresh index");
+    let indexed: Vec<u16> = index.entries.iter().map(|e| e.index).collect();
+    assert_eq!(indexed, vec![0]);
+
+    // decoded again under --use-index: the read error keeps the chunk (--on-error keep)
+    let out = common::temp_dir("rt-index-corrupt-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        remove_unknown: true,
+        progress_mode: ProgressMode::Off,
+        use_index: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        vec![0, 1]
+    );
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn templates_parse_and_name_regions() {
+    let vanilla = RegionNaming::default();
+    assert_eq!(vanilla.coords("world/region/r.-3.12.mca"), Some((-3, 12)));
+    assert_eq!(vanilla.coords("r.0.0.mcr"), None);
+    assert_eq!(vanilla.file_name(-1, 2), "r.-1.2.mca");
+
+    let custom = RegionNaming::parse("c_{z}_{x}.region").unwrap();
+    assert_eq!(custom.coords("c_-1_4.region"), Some((4, -1)));
+    assert!(custom.matches(Path::new("dim/region/c_0_0.region")));
+    assert!(!custom.matches(Path::new("dim/region/r.0.0.mca")));
+    assert_eq!(custom.file_name(4, -1), "c_-1_4.region");
+    // regex metacharacters in the template are literal
+    let dotted = RegionNaming::parse("r.{x}.{z}.mca").unwrap();
+    assert_eq!(dotted.coords("rx0y0zmca"), None);
+
+    for bad in [
+        "r.{x}.mca",
+        "r.{x}.{x}.{z}.mca",
+        "r.{x}{z}.mca",
+        "region/{x}.{z}.mca",
+    ] {
+        assert!(RegionNaming::parse(bad).is_err(), "{}", bad);
+    }
+}
+
+/// `c_{z}_{x}.region` files for region (1, -1): one played and one fresh chunk, with
+/// an entities region of the same name.
+fn world() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-region-naming");
+    for dir in ["region", "entities"] {
+        common::write_region(
+            &world.join(dir).join("c_-1_1.region"),
+            &[
+                (
+                    0,
+                    common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+                ),
+                (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+            ],
+        );
+    }
+    world
+}
+
+#[test]
+fn custom_named_regions_are_processed() {
+    let naming = RegionNaming::parse("c_{z}_{x}.region").unwrap();
+    let out = common::temp_dir("rt-region-naming-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        region_naming: naming.clone(),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("c_-1_1.region");
+    assert_eq!(common::chunk_count_named(&region, &naming), 1);
+    assert_eq!(
+        common::chunk_count_named(&out.join("entities").join("c_-1_1.region"), &naming),
+        1
+    );
+    // global coordinates come from the name: region (1, -1), slot 0
+    let mut reader = McaReader::open_named(region.to_string_lossy().as_ref(), &naming).unwrap();
+    let entry = &reader.entries().unwrap()[0];
+    assert_eq!((entry.global_x(), entry.global_z()), (32, -32));
+}
+
+#[test]
+fn region_naming_round_trips_in_place() {
+    let world = world();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args([
+            "--in-place",
+            "-t",
+            "5",
+            "--region-naming",
+            "c_{z}_{x}.region",
+        ])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let naming = RegionNaming::parse("c_{z}_{x}.region").unwrap();
+    assert_eq!(
+        common::chunk_count_named(&world.join("region").join("c_-1_1.region"), &naming),
+        1
+    );
+
+    let bad = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args(["--in-place", "--region-naming", "r.{x}.mca"])
+        .output()
+        .unwrap();
+    assert!(!bad.status.success());
+    assert!(fs::read_dir(world.join("region")).unwrap().count() == 1);
+}
+
+#[test]
+fn lookups_and_listings_follow_the_naming() {
+    let world = world();
+    let naming = RegionNaming::parse("c_{z}_{x}.region").unwrap();
+    let vanilla = RegionNaming::default();
+    assert_eq!(
+        rust_thanos::world::get_chunk(&world, ".", 32, -32, &naming).unwrap(),
+        Some(common::inhabited_payload(100_000))
+    );
+    assert_eq!(
+        rust_thanos::world::get_chunk(&world, ".", 32, -32, &vanilla).unwrap(),
+        None
+    );
+    let dims = rust_thanos::world::summarize_dimensions(&world, 1, &naming).unwrap();
+    assert_eq!((dims[0].regions, dims[0].chunks), (1, 2));
+    assert_eq!(
+        rust_thanos::world::iter_dimension_chunks(&world, &naming).count(),
+        2
+    );
+    assert_eq!(
+        rust_thanos::world::iter_dimension_chunks(&world, &vanilla).count(),
+        0
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg("dump")
+        .arg(&world)
+        .args(["32", "-32", "--region-naming", "c_{z}_{x}.region"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(output.stdout, common::inhabited_payload(100_000));
+}
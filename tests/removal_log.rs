@@ -0,0 +1,63 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::Path;
+
+/// 64 chunks in `dim`, the odd ones never visited.
+fn write_dim(dim: &Path) {
+    let played = common::chunk_bytes(3, &common::inhabited_payload(1000));
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    let chunks: Vec<_> = (0..64)
+        .map(|i| {
+            (
+                i,
+                if i % 2 == 0 {
+                    played.clone()
+                } else {
+                    fresh.clone()
+                },
+            )
+        })
+        .collect();
+    common::write_region(&dim.join("region").join("r.-1.0.mca"), &chunks);
+}
+
+#[test]
+fn every_removed_chunk_gets_a_whole_line() {
+    let world = common::temp_dir("rt-removal-log");
+    write_dim(&world);
+    write_dim(&world.join("DIM-1"));
+    let log = common::temp_dir("rt-removal-log-file").join("removed.log");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        removal_log: Some(log.clone()),
+        ..RunOptions::default()
+    };
+    let out = common::temp_dir("rt-removal-log-out");
+    rust_thanos::world::run_with_options(world.clone(), Some(out), &opts).unwrap();
+
+    let text = fs::read_to_string(&log).unwrap();
+    let mut lines: Vec<Vec<&str>> = text.lines().map(|l| l.split('\t').collect()).collect();
+    assert_eq!(lines.len(), 64);
+    assert!(lines.iter().all(|l| l.len() == 5 && l[2] == "r.-1.0.mca"));
+    assert!(lines
+        .iter()
+        .all(|l| chrono::DateTime::parse_from_rfc3339(l[0]).is_ok()));
+    lines.sort_by_key(|l| {
+        (
+            l[1].to_string(),
+            l[4].parse::<i32>().unwrap(),
+            l[3].parse::<i32>().unwrap(),
+        )
+    });
+    assert_eq!(lines.iter().filter(|l| l[1] == "DIM-1").count(), 32);
+    // index 1 of r.-1.0 is chunk (-31, 0)
+    assert_eq!(lines[0][1..], [".", "r.-1.0.mca", "-31", "0"]);
+
+    // a second run appends
+    let out = common::temp_dir("rt-removal-log-out2");
+    rust_thanos::world::run_with_options(world, Some(out), &opts).unwrap();
+    assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 128);
+}
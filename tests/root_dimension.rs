@@ -0,0 +1,81 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::PathBuf;
+
+/// A bare export: `region/`, `entities/` and `poi/` at the root, no `level.dat`.
+/// r.0.0 keeps chunk 0 only, every chunk of r.1.0 is removed.
+fn bare_export() -> PathBuf {
+    let root = common::temp_dir("rt-root-dim");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(1000));
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for folder in ["region", "entities", "poi"] {
+        common::write_region(
+            &root.join(folder).join("r.0.0.mca"),
+            &[(0, played.clone()), (1, fresh.clone())],
+        );
+    }
+    common::write_region(
+        &root.join("region").join("r.1.0.mca"),
+        &[(0, fresh.clone())],
+    );
+    root
+}
+
+fn opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn root_dimension_is_mirrored_at_the_output_root() {
+    let root = bare_export();
+    let out = common::temp_dir("rt-root-dim-out");
+    rust_thanos::world::run_with_options(root.clone(), Some(out.clone()), &opts()).unwrap();
+
+    for folder in ["region", "entities", "poi"] {
+        assert_eq!(
+            common::kept_indices(&out.join(folder).join("r.0.0.mca")),
+            vec![0],
+            "{folder}"
+        );
+    }
+    assert!(common::kept_indices(&out.join("region").join("r.1.0.mca")).is_empty());
+    let mut top: Vec<String> = fs::read_dir(&out)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    top.sort();
+    assert_eq!(top, vec!["entities", "poi", "region"]);
+    // the input is left alone
+    assert_eq!(
+        common::kept_indices(&root.join("region").join("r.0.0.mca")),
+        vec![0, 1]
+    );
+}
+
+#[test]
+fn root_dimension_is_rewritten_in_place() {
+    let root = bare_export();
+    rust_thanos::world::run_with_options(root.clone(), None, &opts()).unwrap();
+
+    for folder in ["region", "entities", "poi"] {
+        assert_eq!(
+            common::kept_indices(&root.join(folder).join("r.0.0.mca")),
+            vec![0],
+            "{folder}"
+        );
+    }
+    assert!(common::kept_indices(&root.join("region").join("r.1.0.mca")).is_empty());
+    assert!(!root.join("level.dat").exists());
+    let mut top: Vec<String> = fs::read_dir(&root)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    top.sort();
+    assert_eq!(top, vec!["entities", "poi", "region"]);
+}
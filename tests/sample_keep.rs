@@ -0,0 +1,61 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::sample::SamplePattern;
+use rust_thanos::world::{ProgressMode, RunOptions};
+
+fn sampled(p: &SamplePattern) -> Vec<(i32, i32)> {
+    (-50..50)
+        .flat_map(|x| (-50..50).map(move |z| (x, z)))
+        .filter(|&(x, z)| p.sampled(x, z))
+        .collect()
+}
+
+#[test]
+fn samples_are_seeded_and_close_to_the_percentage() {
+    let ten = sampled(&SamplePattern::new(10.0, 7).unwrap());
+    assert!((850..1150).contains(&ten.len()), "{}", ten.len());
+    assert_eq!(ten, sampled(&SamplePattern::new(10.0, 7).unwrap()));
+    assert_ne!(ten, sampled(&SamplePattern::new(10.0, 8).unwrap()));
+    assert!(sampled(&SamplePattern::new(0.0, 7).unwrap()).is_empty());
+    assert_eq!(
+        sampled(&SamplePattern::new(100.0, 7).unwrap()).len(),
+        10_000
+    );
+    assert!(SamplePattern::new(100.5, 7).is_err());
+    assert!(SamplePattern::new(-1.0, 7).is_err());
+}
+
+#[test]
+fn removable_chunks_are_kept_by_sample() {
+    let world = common::temp_dir("rt-sample");
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    let chunks: Vec<_> = (0..1024).map(|i| (i, fresh.clone())).collect();
+    common::write_region(&world.join("region").join("r.0.0.mca"), &chunks);
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        sample_keep: Some(25.0),
+        sample_seed: 3,
+        ..RunOptions::default()
+    };
+    let kept = |name: &str| -> Vec<u32> {
+        let out = common::temp_dir(name);
+        rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+        let path = out.join("region").join("r.0.0.mca");
+        let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+        r.entries()
+            .unwrap()
+            .iter()
+            .map(|e| e.region_index())
+            .collect()
+    };
+    let first = kept("rt-sample-out");
+    let pattern = SamplePattern::new(25.0, 3).unwrap();
+    let expected: Vec<u32> = (0..1024)
+        .filter(|i| pattern.sampled((i % 32) as i32, (i / 32) as i32))
+        .collect();
+    assert_eq!(first, expected);
+    assert!((150..360).contains(&first.len()), "{}", first.len());
+    assert_eq!(kept("rt-sample-out2"), first);
+}
@@ -0,0 +1,207 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::entry::{compress_zlib, CompressionMethod};
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::world::scrub::{scrub_nbt, DEFAULT_SCRUB_FIELDS};
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+fn compound(fields: Vec<(&str, Value)>) -> Value {
+    Value::Compound(
+        fields
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect(),
+    )
+}
+
+fn default_fields() -> Vec<String> {
+    DEFAULT_SCRUB_FIELDS.iter().map(|s| s.to_string()).collect()
+}
+
+fn root(data: &[u8]) -> HashMap<String, Value> {
+    match fastnbt::from_bytes(data).unwrap() {
+        Value::Compound(m) => m,
+        other => panic!("not a compound: {:?}", other),
+    }
+}
+
+/// A played chunk with a sign and a command block, nothing else identifying.
+fn region_payload() -> Vec<u8> {
+    common::nbt_compound(vec![
+        ("InhabitedTime", Value::Long(100_000)),
+        (
+            "block_entities",
+            Value::List(vec![
+                compound(vec![
+                    ("id", Value::String("minecraft:sign".into())),
+                    ("front_text", compound(vec![])),
+                    ("is_waxed", Value::Byte(0)),
+                ]),
+                compound(vec![
+                    ("id", Value::String("minecraft:command_block".into())),
+                    ("LastOutput", Value::String("steve joined".into())),
+                    ("Command", Value::String("say hi".into())),
+                ]),
+            ]),
+        ),
+    ])
+}
+
+fn entities_payload() -> Vec<u8> {
+    common::nbt_compound(vec![(
+        "Entities",
+        Value::List(vec![compound(vec![
+            ("id", Value::String("minecraft:wolf".into())),
+            (
+                "Owner",
+                Value::IntArray(fastnbt::IntArray::new(vec![1, 2, 3, 4])),
+            ),
+            ("Health", Value::Float(8.0)),
+        ])]),
+    )])
+}
+
+/// Slot 0 carries identifying tags, slot 1 is played but clean, slot 2 is fresh.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-scrub");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(2, &compress_zlib(&region_payload(), 6).unwrap()),
+            ),
+            (
+                1,
+                common::chunk_bytes(
+                    3,
+                    &common::nbt_compound(vec![("InhabitedTime", Value::Long(100_000))]),
+                ),
+            ),
+            (2, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    common::write_region(
+        &world.join("entities").join("r.0.0.mca"),
+        &[(0, common::chunk_bytes(3, &entities_payload()))],
+    );
+    world
+}
+
+fn run(fields: Vec<String>) -> PathBuf {
+    let out = common::temp_dir("rt-scrub-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        scrub_fields: Some(fields),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world(), Some(out.clone()), &opts).unwrap();
+    out
+}
+
+/// Decoded payload and compression method id of every chunk, by slot.
+fn chunks(region: &Path) -> Vec<(u32, i8, Vec<u8>)> {
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    r.entries()
+        .unwrap()
+        .iter_mut()
+        .map(|e| {
+            let (_, cm, _) = e.read_header().unwrap();
+            (
+                e.region_index(),
+                cm.id(),
+                e.all_data_uncompressed().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn fields_are_removed_at_any_depth() {
+    let data = common::nbt_compound(vec![(
+        "Level",
+        compound(vec![(
+            "Entities",
+            Value::List(vec![compound(vec![
+                ("Owner", Value::String("x".into())),
+                ("id", Value::String("minecraft:cat".into())),
+            ])]),
+        )]),
+    )]);
+    let scrubbed = scrub_nbt(&data, &default_fields()).unwrap().unwrap();
+    let Some(Value::Compound(level)) = root(&scrubbed).remove("Level") else {
+        panic!("Level lost");
+    };
+    let Some(Value::List(entities)) = level.get("Entities") else {
+        panic!("Entities lost");
+    };
+    let Value::Compound(cat) = &entities[0] else {
+        panic!("not a compound");
+    };
+    assert!(!cat.contains_key("Owner"));
+    assert!(cat.contains_key("id"));
+
+    let clean = common::nbt_compound(vec![("InhabitedTime", Value::Long(5))]);
+    assert!(scrub_nbt(&clean, &default_fields()).unwrap().is_none());
+    assert!(scrub_nbt(b"not nbt", &default_fields()).is_err());
+}
+
+#[test]
+fn kept_chunks_are_scrubbed_and_the_rest_untouched() {
+    let source = world();
+    let out = run(default_fields());
+
+    let region = chunks(&out.join("region").join("r.0.0.mca"));
+    assert_eq!(
+        region.iter().map(|(i, ..)| *i).collect::<Vec<_>>(),
+        vec![0, 1]
+    );
+    assert_eq!(region[0].1, CompressionMethod::Zlib.id());
+    let scrubbed = root(&region[0].2);
+    assert_eq!(scrubbed["InhabitedTime"], Value::Long(100_000));
+    let Value::List(block_entities) = &scrubbed["block_entities"] else {
+        panic!("block_entities lost");
+    };
+    for be in block_entities {
+        let Value::Compound(be) = be else {
+            panic!("not a compound");
+        };
+        assert!(!be.contains_key("front_text") && !be.contains_key("LastOutput"));
+        assert!(be.contains_key("id"));
+    }
+    // nothing to scrub: still the raw chunk it was
+    assert_eq!(region[1].1, CompressionMethod::Raw.id());
+    assert_eq!(
+        region[1].2,
+        chunks(&source.join("region").join("r.0.0.mca"))[1].2
+    );
+
+    let entities = chunks(&out.join("entities").join("r.0.0.mca"));
+    let Value::List(list) = &root(&entities[0].2)["Entities"] else {
+        panic!("Entities lost");
+    };
+    let Value::Compound(wolf) = &list[0] else {
+        panic!("not a compound");
+    };
+    assert!(!wolf.contains_key("Owner"));
+    assert_eq!(wolf["Health"], Value::Float(8.0));
+}
+
+#[test]
+fn scrub_fields_replaces_the_default_list() {
+    let out = run(vec!["LastOutput".to_string()]);
+    let region = chunks(&out.join("region").join("r.0.0.mca"));
+    let Value::List(block_entities) = &root(&region[0].2)["block_entities"] else {
+        panic!("block_entities lost");
+    };
+    let Value::Compound(sign) = &block_entities[0] else {
+        panic!("not a compound");
+    };
+    assert!(sign.contains_key("front_text"));
+    let entities = chunks(&out.join("entities").join("r.0.0.mca"));
+    assert_eq!(entities[0].1, CompressionMethod::Raw.id());
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use rust_thanos::mca::selfcheck::run_selfcheck;
+use std::process::Command;
+
+#[test]
+fn every_selfcheck_case_passes() {
+    let reports = run_selfcheck(&common::temp_dir("rt-selfcheck"));
+    let names: Vec<&str> = reports.iter().map(|r| r.name).collect();
+    assert!(names.contains(&"lz4") && names.contains(&"max in-region size"));
+    for r in &reports {
+        assert!(r.failure.is_none(), "{}: {:?}", r.name, r.failure);
+    }
+}
+
+#[test]
+fn selfcheck_subcommand_reports_each_case() {
+    let out = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg("selfcheck")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout
+        .lines()
+        .any(|l| l.starts_with("通过") && l.ends_with("gzip")));
+    assert!(stdout.contains("自检通过"), "{stdout}");
+}
@@ -0,0 +1,131 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A Bukkit server root: three world roots, each with its own level.dat and one
+/// played plus one fresh chunk, next to folders that are not worlds.
+fn server() -> PathBuf {
+    let root = common::temp_dir("rt-server");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(1000));
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for (world, dim) in [
+        ("world", ""),
+        ("world_nether", "DIM-1"),
+        ("world_the_end", "DIM1"),
+    ] {
+        let world = root.join(world);
+        common::write_level_dat(&world, 3700);
+        common::write_region(
+            &world.join(dim).join("region").join("r.0.0.mca"),
+            &[(0, played.clone()), (1, fresh.clone())],
+        );
+    }
+    fs::create_dir_all(root.join("plugins")).unwrap();
+    fs::write(root.join("server.properties"), "level-name=world\n").unwrap();
+    root
+}
+
+fn opts() -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn detects_only_folders_with_level_dat() {
+    let root = server();
+    let worlds = rust_thanos::world::server_worlds(&root).unwrap();
+    assert_eq!(
+        worlds,
+        vec![
+            root.join("world"),
+            root.join("world_nether"),
+            root.join("world_the_end")
+        ]
+    );
+    let err = rust_thanos::world::server_worlds(&root.join("world")).unwrap_err();
+    assert!(err.to_string().contains("no world"), "{}", err);
+}
+
+#[test]
+fn each_world_is_processed_into_its_own_folder() {
+    let root = server();
+    let out = common::temp_dir("rt-server-out");
+    rust_thanos::world::run_server_with_options(root, Some(out.clone()), &opts()).unwrap();
+    for region in [
+        "world/region/r.0.0.mca",
+        "world_nether/DIM-1/region/r.0.0.mca",
+        "world_the_end/DIM1/region/r.0.0.mca",
+    ] {
+        assert_eq!(common::chunk_count(&out.join(region)), 1, "{}", region);
+    }
+    assert!(!out.join("plugins").exists());
+}
+
+#[test]
+fn in_place_processes_every_world() {
+    let root = server();
+    rust_thanos::world::run_server_with_options(root.clone(), None, &opts()).unwrap();
+    assert_eq!(
+        common::chunk_count(&root.join("world_nether/DIM-1/region/r.0.0.mca")),
+        1
+    );
+    assert_eq!(common::chunk_count(&root.join("world/region/r.0.0.mca")), 1);
+    assert!(root.join("server.properties").is_file());
+}
+
+#[test]
+fn json_summary_aggregates_the_worlds() {
+    let root = server();
+    let out = common::temp_dir("rt-server-json");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&root)
+        .arg(out.join("out"))
+        .args([
+            "--server-layout",
+            "--json-summary",
+            "--progress-mode",
+            "off",
+        ])
+        .args(["-t", "5"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1, "{}", stdout);
+    let summary: Value = serde_json::from_str(stdout.trim()).unwrap();
+    assert_eq!(
+        (&summary["kept"], &summary["removed"]),
+        (&3.into(), &3.into())
+    );
+    let worlds: Vec<&str> = summary["worlds"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|w| {
+            assert_eq!((&w["kept"], &w["removed"]), (&1.into(), &1.into()));
+            w["world"].as_str().unwrap()
+        })
+        .collect();
+    assert_eq!(worlds, vec!["world", "world_nether", "world_the_end"]);
+    let largest: Vec<&str> = summary["largest_chunks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["dimension"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        largest,
+        vec!["world", "world_nether/DIM-1", "world_the_end/DIM1"]
+    );
+}
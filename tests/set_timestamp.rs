@@ -0,0 +1,75 @@
+mod common;
+
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::mca::writer::McaWriter;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::path::{Path, PathBuf};
+
+fn world_with_timestamps() -> PathBuf {
+    let world = common::temp_dir("rt-timestamp");
+    std::fs::create_dir_all(world.join("region")).unwrap();
+    let path = world.join("region").join("r.0.0.mca");
+    let mut w = McaWriter::open(path.to_string_lossy().as_ref()).unwrap();
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(100));
+    w.write_serialized(0, 1_700_000_000, &chunk).unwrap();
+    w.write_serialized(1, 1_700_000_500, &chunk).unwrap();
+    w.finalize().unwrap();
+    world
+}
+
+fn timestamps(region: &Path) -> Vec<u32> {
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    r.entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.modified_time())
+        .collect()
+}
+
+fn run(set_timestamp: Option<u32>) -> Vec<u32> {
+    let out = common::temp_dir("rt-timestamp-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        set_timestamp,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world_with_timestamps(), Some(out.clone()), &opts)
+        .unwrap();
+    timestamps(&out.join("region").join("r.0.0.mca"))
+}
+
+#[test]
+fn timestamps_are_preserved_by_default() {
+    assert_eq!(run(None), vec![1_700_000_000, 1_700_000_500]);
+}
+
+#[test]
+fn set_timestamp_overrides_every_chunk() {
+    assert_eq!(run(Some(0)), vec![0, 0]);
+    assert_eq!(run(Some(42)), vec![42, 42]);
+}
+
+#[test]
+fn set_timestamp_rewrites_regions_copied_as_is() {
+    let out = common::temp_dir("rt-timestamp-bounds-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        set_timestamp: Some(42),
+        // r.0.0 lies outside and is copied unchanged
+        region_bounds: Some((1, 1, 2, 2)),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world_with_timestamps(), Some(out.clone()), &opts)
+        .unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    assert_eq!(timestamps(&region), vec![42, 42]);
+    // only the timestamp table differs from the input
+    let copied = std::fs::read(&region).unwrap();
+    let input = std::fs::read(world_with_timestamps().join("region").join("r.0.0.mca")).unwrap();
+    assert_eq!(copied.len(), input.len());
+    assert_eq!(copied[..4096], input[..4096]);
+    assert_eq!(copied[8192..], input[8192..]);
+    assert!(copied[4096 + 8..8192].iter().all(|&b| b == 0));
+}
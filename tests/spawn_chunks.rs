@@ -0,0 +1,138 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::world::{level_spawn, ProgressMode, RunOptions, LEGACY_SPAWN_CHUNK_RADIUS};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn write_level_data(world: &Path, data: Vec<(&str, Value)>) {
+    let data = data.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    let nbt = common::nbt_compound(vec![("Data", Value::Compound(data))]);
+    let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(&nbt).unwrap();
+    fs::create_dir_all(world).unwrap();
+    fs::write(world.join("level.dat"), enc.finish().unwrap()).unwrap();
+}
+
+fn radius_rule(radius: &str) -> Value {
+    Value::Compound(HashMap::from([(
+        "spawnChunkRadius".to_string(),
+        Value::String(radius.to_string()),
+    )]))
+}
+
+/// Spawn at block (40, 40), chunk (2, 2), radius 1; the overworld and the nether
+/// both hold chunks (0, 0), (2, 2) and (4, 4), none of them inhabited.
+fn world_with_spawn() -> PathBuf {
+    let world = common::temp_dir("rt-spawn");
+    write_level_data(
+        &world,
+        vec![
+            ("SpawnX", Value::Int(40)),
+            ("SpawnZ", Value::Int(40)),
+            ("GameRules", radius_rule("1")),
+        ],
+    );
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for dim in [world.clone(), world.join("DIM-1")] {
+        common::write_region(
+            &dim.join("region").join("r.0.0.mca"),
+            &[
+                (0, chunk.clone()),
+                (66, chunk.clone()),
+                (132, chunk.clone()),
+            ],
+        );
+    }
+    world
+}
+
+fn run(world: &Path, opts: RunOptions) -> PathBuf {
+    let out = common::temp_dir("rt-spawn-out");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        ..opts
+    };
+    rust_thanos::world::run_with_options(world.to_path_buf(), Some(out.clone()), &opts).unwrap();
+    out
+}
+
+#[test]
+fn spawn_is_read_from_legacy_and_modern_level_dat() {
+    let world = world_with_spawn();
+    let spawn = level_spawn(&world).unwrap();
+    assert_eq!(spawn.chunk, (2, 2));
+    assert_eq!(spawn.radius, 1);
+    assert_eq!(spawn.dimension, "minecraft:overworld");
+    assert_eq!(spawn.area(), (1, 1, 3, 3));
+
+    let modern = common::temp_dir("rt-spawn-modern");
+    write_level_data(
+        &modern,
+        vec![(
+            "spawn",
+            Value::Compound(HashMap::from([
+                (
+                    "pos".to_string(),
+                    Value::IntArray(fastnbt::IntArray::new(vec![-17, 64, 300])),
+                ),
+                (
+                    "dimension".to_string(),
+                    Value::String("minecraft:the_nether".to_string()),
+                ),
+            ])),
+        )],
+    );
+    let spawn = level_spawn(&modern).unwrap();
+    assert_eq!(spawn.chunk, (-2, 18));
+    assert_eq!(spawn.radius, LEGACY_SPAWN_CHUNK_RADIUS);
+    assert_eq!(spawn.dimension, "minecraft:the_nether");
+
+    let none = common::temp_dir("rt-spawn-none");
+    write_level_data(&none, vec![("DataVersion", Value::Int(3700))]);
+    assert!(level_spawn(&none).is_err());
+}
+
+#[test]
+fn spawn_chunks_are_kept_in_the_spawn_dimension_only() {
+    let world = world_with_spawn();
+    let out = run(&world, RunOptions::default());
+    assert!(common::kept_indices(&out.join("region").join("r.0.0.mca")).is_empty());
+
+    let out = run(
+        &world,
+        RunOptions {
+            keep_spawn_chunks: true,
+            ..RunOptions::default()
+        },
+    );
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        vec![66]
+    );
+    assert!(common::kept_indices(&out.join("DIM-1").join("region").join("r.0.0.mca")).is_empty());
+}
+
+#[test]
+fn safe_defaults_combine_spawn_and_ticket_keeps() {
+    let opts = RunOptions::default().with_safe_defaults();
+    assert!(opts.keep_spawn_chunks && opts.keep_active_tickets);
+    assert_eq!(opts.keep_ticket_types, vec!["forced", "start"]);
+
+    let world = world_with_spawn();
+    common::write_ticket_entries(
+        &world,
+        &[
+            ("minecraft:start", 22, 0, 0, 0),
+            ("minecraft:portal", 30, 100, 4, 4),
+        ],
+    );
+    let out = run(&world, RunOptions::default().with_safe_defaults());
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        vec![0, 66, 132]
+    );
+}
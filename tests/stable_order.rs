@@ -0,0 +1,72 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// Overworld and nether with several regions each, written out of name order.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-stable-order");
+    for dim in [world.clone(), world.join("DIM-1")] {
+        for name in ["r.2.0.mca", "r.-1.0.mca", "r.0.1.mca", "r.0.0.mca"] {
+            common::write_region(
+                &dim.join("region").join(name),
+                &[(
+                    0,
+                    common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+                )],
+            );
+        }
+    }
+    world
+}
+
+/// `(dimension, region)` of every region event of one stable-order run.
+fn region_events() -> Vec<(String, String)> {
+    let events = common::temp_dir("rt-stable-order-events").join("events.jsonl");
+    let opts = RunOptions {
+        inhabited_threshold: 100,
+        progress_mode: ProgressMode::Off,
+        progress_events: Some(events.clone()),
+        stable_order: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(
+        world(),
+        Some(common::temp_dir("rt-stable-order-out")),
+        &opts,
+    )
+    .unwrap();
+    fs::read_to_string(&events)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<Value>(l).unwrap())
+        .filter(|e| e["event"] == "region")
+        .map(|e| {
+            (
+                e["dimension"].as_str().unwrap().to_string(),
+                e["region"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn stable_order_repeats_the_same_event_sequence() {
+    let first = region_events();
+    assert_eq!(first.len(), 8);
+    for _ in 0..3 {
+        assert_eq!(region_events(), first);
+    }
+
+    // one dimension after the other, each in file name order
+    let (a, b) = first.split_at(4);
+    assert!(a.iter().all(|(d, _)| *d == a[0].0));
+    assert!(b.iter().all(|(d, _)| *d == b[0].0));
+    assert_ne!(a[0].0, b[0].0);
+    for half in [a, b] {
+        let names: Vec<_> = half.iter().map(|(_, r)| r.as_str()).collect();
+        assert_eq!(names, ["r.-1.0.mca", "r.0.0.mca", "r.0.1.mca", "r.2.0.mca"]);
+    }
+}
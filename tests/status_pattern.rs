@@ -0,0 +1,87 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::status::status_rank;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::collections::HashMap;
+
+fn chunk(status: Option<&str>) -> Vec<u8> {
+    let mut fields = vec![("InhabitedTime", Value::Long(100_000))];
+    if let Some(s) = status {
+        fields.push(("Status", Value::String(s.to_string())));
+    }
+    common::chunk_bytes(3, &common::nbt_compound(fields))
+}
+
+fn legacy_chunk(status: &str) -> Vec<u8> {
+    let level = HashMap::from([
+        ("InhabitedTime".to_string(), Value::Long(100_000)),
+        ("Status".to_string(), Value::String(status.to_string())),
+    ]);
+    common::chunk_bytes(
+        3,
+        &common::nbt_compound(vec![("Level", Value::Compound(level))]),
+    )
+}
+
+#[test]
+fn status_names_rank_across_versions() {
+    assert_eq!(status_rank("minecraft:full"), status_rank("full"));
+    assert_eq!(status_rank("fullchunk"), status_rank("full"));
+    assert!(status_rank("decorated") < status_rank("light"));
+    assert!(status_rank("noise") < status_rank("features"));
+    assert_eq!(status_rank("mymod:custom"), None);
+}
+
+#[test]
+fn proto_chunks_are_vetoed_unless_forced() {
+    let world = common::temp_dir("rt-status");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, chunk(Some("minecraft:full"))),
+            (1, chunk(Some("minecraft:noise"))),
+            (2, legacy_chunk("decorated")),
+            (3, chunk(None)),
+            (4, chunk(Some("minecraft:features"))),
+            (5, chunk(Some("mymod:custom"))),
+        ],
+    );
+    common::write_tickets(&world, &[("minecraft:forced", 4, 0)]);
+    let out = common::temp_dir("rt-status-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        remove_below_status: Some("full".to_string()),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    let kept: Vec<u32> = r
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|e| e.region_index())
+        .collect();
+    assert_eq!(kept, vec![0, 3, 4, 5]);
+}
+
+#[test]
+fn unknown_minimum_status_is_rejected() {
+    let world = common::temp_dir("rt-status-bad");
+    common::write_dimension(&world, 100);
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        remove_below_status: Some("finished".to_string()),
+        ..RunOptions::default()
+    };
+    let err = rust_thanos::world::run_with_options(
+        world,
+        Some(common::temp_dir("rt-status-bad-out")),
+        &opts,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown chunk status"));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use byteorder::{ByteOrder, LittleEndian};
+use rust_thanos::mca::entry::{decode_lz4_blocks, lz4_checksum, Lz4BlockReader};
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::inhabited::find_inhabited_stream;
+use rust_thanos::world::{ProgressMode, Recompression, RunOptions};
+use std::io::Read;
+
+fn lz4_block(payload: &[u8]) -> Vec<u8> {
+    let compressed = lz4_flex::block::compress(payload);
+    let mut out = b"LZ4Block".to_vec();
+    out.push(0x20);
+    let mut buf = [0u8; 4];
+    LittleEndian::write_u32(&mut buf, compressed.len() as u32);
+    out.extend_from_slice(&buf);
+    LittleEndian::write_u32(&mut buf, payload.len() as u32);
+    out.extend_from_slice(&buf);
+    LittleEndian::write_u32(&mut buf, lz4_checksum(payload));
+    out.extend_from_slice(&buf);
+    out.extend_from_slice(&compressed);
+    out
+}
+
+/// Hands out at most three bytes per read to exercise window boundaries.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(3).min(self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn lz4_reader_matches_block_decoder() {
+    let mut stream = lz4_block(b"first block ");
+    stream.extend(lz4_block(&[7u8; 5000]));
+    let mut streamed = Vec::new();
+    Lz4BlockReader::new(Trickle(&stream))
+        .read_to_end(&mut streamed)
+        .unwrap();
+    assert_eq!(streamed, decode_lz4_blocks(&stream).unwrap());
+
+    let mut bad = stream.clone();
+    let last = bad.len() - 1;
+    bad[last] ^= 0xFF;
+    assert!(Lz4BlockReader::new(&bad[..])
+        .read_to_end(&mut Vec::new())
+        .is_err());
+}
+
+#[test]
+fn stream_scan_finds_tag_across_reads() {
+    let mut data = vec![0u8; 70_000];
+    data.extend(common::inhabited_payload(4242));
+    assert_eq!(find_inhabited_stream(Trickle(&data)).unwrap(), Some(4242));
+    assert_eq!(find_inhabited_stream(&data[..]).unwrap(), Some(4242));
+    assert_eq!(find_inhabited_stream(&[0u8; 100][..]).unwrap(), None);
+}
+
+#[test]
+fn chunks_above_memory_limit_are_streamed_verbatim() {
+    let world = common::temp_dir("rt-stream");
+    let kept = common::chunk_bytes(3, &common::inhabited_payload(100));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, kept.clone()),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    let out = common::temp_dir("rt-stream-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        recompress: Some(Recompression::Zlib),
+        chunk_memory_limit: Some(8),
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    let region = out.join("region").join("r.0.0.mca");
+    let mut r = McaReader::open(region.to_string_lossy().as_ref()).unwrap();
+    let mut entries = r.entries().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].region_index(), 0);
+    // not recompressed: still the original RAW bytes
+    assert_eq!(entries[0].serialized_bytes().unwrap(), kept);
+}
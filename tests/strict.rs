@@ -0,0 +1,54 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::path::PathBuf;
+
+fn corrupt_world(prefix: &str) -> PathBuf {
+    let world = common::temp_dir(prefix);
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(2, b"not zlib")),
+        ],
+    );
+    world
+}
+
+fn opts(strict: bool) -> RunOptions {
+    RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        strict,
+        ..RunOptions::default()
+    }
+}
+
+#[test]
+fn corrupt_chunk_is_skipped_without_strict() {
+    let world = corrupt_world("rt-strict-off");
+    let out = common::temp_dir("rt-strict-off-out");
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts(false)).unwrap();
+    assert!(out.join("region").join("r.0.0.mca").is_file());
+}
+
+#[test]
+fn corrupt_chunk_fails_strict_run() {
+    let world = corrupt_world("rt-strict-on");
+    let out = common::temp_dir("rt-strict-on-out");
+    let err = rust_thanos::world::run_with_options(world, Some(out), &opts(true)).unwrap_err();
+    assert!(err.to_string().contains("chunk 1"), "{}", err);
+}
+
+#[test]
+fn strict_in_place_failure_leaves_world_untouched() {
+    let world = corrupt_world("rt-strict-inplace");
+    let region = world.join("region").join("r.0.0.mca");
+    let before = fs::read(&region).unwrap();
+    assert!(rust_thanos::world::run_with_options(world, None, &opts(true)).is_err());
+    assert_eq!(fs::read(&region).unwrap(), before);
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn syncing_does_not_change_the_output() {
+    let world = common::temp_dir("rt-sync");
+    for x in 0..3 {
+        common::write_region(
+            &world.join("region").join(format!("r.{}.0.mca", x)),
+            &[
+                (
+                    0,
+                    common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+                ),
+                (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+            ],
+        );
+    }
+    let run = |sync_interval| {
+        let out = common::temp_dir("rt-sync-out");
+        let opts = RunOptions {
+            inhabited_threshold: 10,
+            progress_mode: ProgressMode::Off,
+            sync_interval,
+            ..RunOptions::default()
+        };
+        rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+        out
+    };
+    let (plain, synced) = (run(0), run(2));
+    for x in 0..3 {
+        let name = format!("r.{}.0.mca", x);
+        assert_eq!(
+            fs::read(plain.join("region").join(&name)).unwrap(),
+            fs::read(synced.join("region").join(&name)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Extracts `archive` with the system tar into a fresh directory.
+fn extract(archive: &[u8]) -> std::path::PathBuf {
+    let dir = common::temp_dir("rt-tar-extract");
+    let mut tar = Command::new("tar")
+        .arg("-xf")
+        .arg("-")
+        .arg("-C")
+        .arg(&dir)
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+    tar.stdin.take().unwrap().write_all(archive).unwrap();
+    assert!(tar.wait().unwrap().success());
+    dir
+}
+
+#[test]
+fn tar_stdout_streams_the_optimized_world() {
+    let world = common::temp_dir("rt-tar-world");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    common::write_level_dat(&world, 3700);
+    let before = fs::read(world.join("region").join("r.0.0.mca")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args(["--tar-stdout", "--copy-world-files", "--json-summary"])
+        .args(["-t", "5"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    // the report moved to stderr; stdout is nothing but the archive
+    assert!(stderr.contains("\"kept\":1"), "{}", stderr);
+    assert_eq!(output.stdout.len() % 512, 0);
+
+    let dir = extract(&output.stdout);
+    assert_eq!(
+        common::chunk_count(&dir.join("region").join("r.0.0.mca")),
+        1
+    );
+    assert!(dir.join("level.dat").is_file());
+    // the input is left alone
+    assert_eq!(
+        fs::read(world.join("region").join("r.0.0.mca")).unwrap(),
+        before
+    );
+}
+
+#[test]
+fn tar_stdout_streams_nested_dimensions_and_server_worlds() {
+    let root = common::temp_dir("rt-tar-server");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    for (world, dim) in [("world", ""), ("world_nether", "DIM-1")] {
+        let world = root.join(world);
+        common::write_level_dat(&world, 3700);
+        common::write_region(
+            &world.join(dim).join("region").join("r.0.0.mca"),
+            &[(0, played.clone())],
+        );
+    }
+    // a dimension below the root dimension, processed in parallel with it
+    common::write_region(
+        &root
+            .join("world")
+            .join("DIM1")
+            .join("region")
+            .join("r.1.0.mca"),
+        &[(0, played)],
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&root)
+        .args(["--tar-stdout", "--server-layout", "--progress-mode", "off"])
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("tar: 已写入 3 个文件到标准输出"),
+        "{}",
+        stderr
+    );
+
+    let dir = extract(&output.stdout);
+    for region in [
+        "world/region/r.0.0.mca",
+        "world/DIM1/region/r.1.0.mca",
+        "world_nether/DIM-1/region/r.0.0.mca",
+    ] {
+        assert_eq!(common::chunk_count(&dir.join(region)), 1, "{}", region);
+    }
+}
+
+#[test]
+fn tar_stdout_rejects_output_checks() {
+    let world = common::temp_dir("rt-tar-guard");
+    common::write_dimension(&world, 100);
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args(["--tar-stdout", "--max-removed-percent", "50"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn tar_stdout_rejects_an_output_dir() {
+    let world = common::temp_dir("rt-tar-conflict");
+    common::write_dimension(&world, 100);
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .arg(common::temp_dir("rt-tar-conflict-out"))
+        .arg("--tar-stdout")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
@@ -48,7 +48,7 @@ fn threshold_keeps_and_removes() {
     let input_mca = create_input_region_with_one_chunk(&base, 42);
 
     // run with threshold below 42 (e.g., 10 ticks): expect chunk kept
-    let out1 = base.join("out1");
+    let out1 = std::env::temp_dir().join(format!("rt-threshold-out1-{}", uuid::Uuid::new_v4()));
     rust_thanos::world::run(
         base.clone(),
         Some(out1.clone()),
@@ -71,7 +71,7 @@ fn threshold_keeps_and_removes() {
     let base2 = std::env::temp_dir().join(format!("rt-threshold-{}", uuid::Uuid::new_v4()));
     fs::create_dir_all(&base2).unwrap();
     let _ = create_input_region_with_one_chunk(&base2, 42);
-    let out2 = base2.join("out2");
+    let out2 = std::env::temp_dir().join(format!("rt-threshold-out2-{}", uuid::Uuid::new_v4()));
     rust_thanos::world::run(
         base2.clone(),
         Some(out2.clone()),
@@ -96,7 +96,7 @@ fn threshold_equal_is_kept() {
     let base = std::env::temp_dir().join(format!("rt-threshold-eq-{}", uuid::Uuid::new_v4()));
     fs::create_dir_all(&base).unwrap();
     let _ = create_input_region_with_one_chunk(&base, 42);
-    let out = base.join("out");
+    let out = std::env::temp_dir().join(format!("rt-threshold-out-{}", uuid::Uuid::new_v4()));
     rust_thanos::world::run(
         base.clone(),
         Some(out.clone()),
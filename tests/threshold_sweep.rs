@@ -0,0 +1,138 @@
+mod common;
+
+use rust_thanos::mca::naming::RegionNaming;
+use rust_thanos::world::analyze::threshold_sweep;
+use rust_thanos::world::DEFAULT_TICKET_TYPES;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn default_types() -> Vec<String> {
+    DEFAULT_TICKET_TYPES.iter().map(|s| s.to_string()).collect()
+}
+
+/// Chunks inhabited for 0s, 30s, 120s and 900s (slots 0..4), slot 4 without an
+/// InhabitedTime and slot 5 (0s) force-loaded; the nether holds one 120s chunk.
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-sweep");
+    let chunk = |secs: i64| common::chunk_bytes(3, &common::inhabited_payload(secs * 20));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (0, chunk(0)),
+            (1, chunk(30)),
+            (2, chunk(120)),
+            (3, chunk(900)),
+            (4, common::chunk_bytes(3, &common::nbt_compound(vec![]))),
+            (5, chunk(0)),
+        ],
+    );
+    common::write_tickets(&world, &[("minecraft:forced", 5, 0)]);
+    common::write_region(
+        &world.join("DIM-1").join("region").join("r.0.0.mca"),
+        &[(0, chunk(120))],
+    );
+    world
+}
+
+#[test]
+fn every_threshold_is_evaluated_in_one_pass() {
+    let sweep = threshold_sweep(
+        &world(),
+        3,
+        &RegionNaming::default(),
+        &[60, 300, 0, 1200],
+        &default_types(),
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!((sweep.chunks, sweep.forced, sweep.errors), (7, 1, 0));
+    assert_eq!(sweep.bytes, 7 * 4096);
+    let rows: Vec<(i64, u64, u64)> = sweep
+        .rows
+        .iter()
+        .map(|r| (r.threshold_seconds, r.removed_chunks, r.removed_bytes))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (60, 2, 2 * 4096),
+            (300, 4, 4 * 4096),
+            (0, 0, 0),
+            (1200, 5, 5 * 4096),
+        ]
+    );
+}
+
+#[test]
+fn overflowing_thresholds_are_rejected() {
+    let naming = RegionNaming::default();
+    let result = threshold_sweep(
+        &world(),
+        3,
+        &naming,
+        &[60, i64::MAX / 10],
+        &[],
+        false,
+        false,
+    );
+    let err = result.err().expect("an overflowing threshold was accepted");
+    assert!(err.to_string().contains("overflow"), "{}", err);
+}
+
+#[test]
+fn regions_follow_the_naming_template() {
+    let world = world();
+    let region = world.join("region");
+    std::fs::rename(region.join("r.0.0.mca"), region.join("region_0_0.mca")).unwrap();
+    let naming = RegionNaming::parse("region_{x}_{z}.mca").unwrap();
+    let sweep = threshold_sweep(&world, 3, &naming, &[60], &default_types(), false, false).unwrap();
+    // the overworld region under its custom name; the nether's vanilla name no longer matches
+    assert_eq!((sweep.chunks, sweep.forced), (6, 1));
+}
+
+#[test]
+fn unknown_chunks_follow_remove_unknown() {
+    let sweep = threshold_sweep(
+        &world(),
+        3,
+        &RegionNaming::default(),
+        &[60],
+        &default_types(),
+        false,
+        true,
+    )
+    .unwrap();
+    assert_eq!(sweep.rows[0].removed_chunks, 3);
+}
+
+#[test]
+fn sweep_prints_a_table_without_touching_the_world() {
+    let world = world();
+    let before = std::fs::read(world.join("region").join("r.0.0.mca")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(&world)
+        .args(["--threshold-sweep", "60,300"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("扫描了 7 个区块"), "{}", stdout);
+    let rows: Vec<Vec<&str>> = stdout
+        .lines()
+        .skip(2)
+        .map(|l| l.split_whitespace().take(3).collect())
+        .collect();
+    assert_eq!(
+        rows,
+        vec![vec!["60", "2", "28.6%"], vec!["300", "4", "57.1%"]]
+    );
+    assert_eq!(
+        std::fs::read(world.join("region").join("r.0.0.mca")).unwrap(),
+        before
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+
+fn world_with_tickets() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-tickets");
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, chunk.clone()), (1, chunk.clone()), (2, chunk)],
+    );
+    common::write_tickets(
+        &world,
+        &[("minecraft:forced", 0, 0), ("minecraft:start", 1, 0)],
+    );
+    world
+}
+
+#[test]
+fn only_forced_tickets_are_kept_by_default() {
+    let world = world_with_tickets();
+    let out = common::temp_dir("rt-tickets-out");
+    rust_thanos::world::run(
+        world.clone(),
+        Some(out.clone()),
+        10,
+        true,
+        ProgressMode::Off,
+    )
+    .unwrap();
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        vec![0]
+    );
+}
+
+#[test]
+fn extra_ticket_types_are_kept_when_requested() {
+    let world = world_with_tickets();
+    let out = common::temp_dir("rt-tickets-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        remove_unknown: true,
+        progress_mode: ProgressMode::Off,
+        keep_ticket_types: vec!["forced".into(), "start".into(), "portal".into()],
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world.clone(), Some(out.clone()), &opts).unwrap();
+    assert_eq!(
+        common::kept_indices(&out.join("region").join("r.0.0.mca")),
+        vec![0, 1]
+    );
+}
+
+fn world_with_mixed_tickets() -> std::path::PathBuf {
+    let world = common::temp_dir("rt-tickets-mixed");
+    let chunk = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &(0..5).map(|i| (i, chunk.clone())).collect::<Vec<_>>(),
+    );
+    common::write_ticket_entries(
+        &world,
+        &[
+            ("minecraft:forced", 31, 0, 0, 0),
+            // a portal ticket still counting down
+            ("minecraft:portal", 30, 280, 1, 0),
+            ("minecraft:dragon", 24, 0, 2, 0),
+            // ran out, not yet purged
+            ("minecraft:portal", 30, 0, 3, 0),
+        ],
+    );
+    world
+}
+
+#[test]
+fn chunk_tickets_keep_their_metadata() {
+    let world = world_with_mixed_tickets();
+    let tickets = rust_thanos::world::parse_chunk_tickets(&world);
+    assert_eq!(tickets.len(), 4);
+    assert_eq!(tickets[1].ticket_type, "minecraft:portal");
+    assert_eq!(tickets[1].level, Some(30));
+    assert_eq!(tickets[1].ticks_left, Some(280));
+    assert_eq!((tickets[1].x, tickets[1].z), (1, 0));
+    let active: Vec<bool> = tickets.iter().map(|t| t.is_active()).collect();
+    assert_eq!(active, vec![false, true, false, false]);
+}
+
+#[test]
+fn active_tickets_are_kept_only_when_requested() {
+    let run = |keep_active_tickets| {
+        let world = world_with_mixed_tickets();
+        let out = common::temp_dir("rt-tickets-out");
+        let opts = RunOptions {
+            inhabited_threshold: 10,
+            remove_unknown: true,
+            progress_mode: ProgressMode::Off,
+            keep_active_tickets,
+            ..RunOptions::default()
+        };
+        rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+        common::kept_indices(&out.join("region").join("r.0.0.mca"))
+    };
+    assert_eq!(run(false), vec![0]);
+    assert_eq!(run(true), vec![0, 1]);
+}
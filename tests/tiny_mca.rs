@@ -8,7 +8,7 @@ fn tiny_mca_is_skipped_without_error() {
     fs::create_dir_all(base.join("region")).unwrap();
     // create a tiny file (<8192 bytes)
     fs::write(base.join("region").join("r.0.0.mca"), vec![0u8; 100]).unwrap();
-    let out = base.join("out");
+    let out = std::env::temp_dir().join(format!("rt-tiny-out-{}", uuid::Uuid::new_v4()));
     rust_thanos::world::run(base.clone(), Some(out.clone()), 0, false, ProgressMode::Off).unwrap();
     // Should complete and create output structure
     assert!(out.join("region").exists());
@@ -0,0 +1,84 @@
+mod common;
+
+use fastnbt::Value;
+use rust_thanos::mca::reader::McaReader;
+use rust_thanos::patterns::entities::only_trivial_entities;
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::collections::HashMap;
+
+fn entity(id: &str, extra: Vec<(&str, Value)>) -> Value {
+    let mut map = HashMap::from([("id".to_string(), Value::String(id.to_string()))]);
+    map.extend(extra.into_iter().map(|(k, v)| (k.to_string(), v)));
+    Value::Compound(map)
+}
+
+fn entities_chunk(entities: Vec<Value>) -> Vec<u8> {
+    common::nbt_compound(vec![("Entities", Value::List(entities))])
+}
+
+#[test]
+fn only_unnamed_ambient_mobs_are_trivial() {
+    assert_eq!(only_trivial_entities(&entities_chunk(vec![])), Some(true));
+    let bats = vec![
+        entity("minecraft:bat", vec![]),
+        entity("minecraft:cod", vec![]),
+    ];
+    assert_eq!(only_trivial_entities(&entities_chunk(bats)), Some(true));
+    let named = entity(
+        "minecraft:bat",
+        vec![("CustomName", Value::String("Bob".into()))],
+    );
+    assert_eq!(
+        only_trivial_entities(&entities_chunk(vec![named])),
+        Some(false)
+    );
+    let pet = entity("minecraft:salmon", vec![("FromBucket", Value::Byte(1))]);
+    assert_eq!(
+        only_trivial_entities(&entities_chunk(vec![pet])),
+        Some(false)
+    );
+    let cow = vec![
+        entity("minecraft:bat", vec![]),
+        entity("minecraft:cow", vec![]),
+    ];
+    assert_eq!(only_trivial_entities(&entities_chunk(cow)), Some(false));
+}
+
+#[test]
+fn trivial_entities_chunks_are_pruned_from_kept_chunks() {
+    let world = common::temp_dir("rt-trivial");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, played.clone()), (1, played)],
+    );
+    let bats = entities_chunk(vec![entity("minecraft:bat", vec![])]);
+    let cows = entities_chunk(vec![entity("minecraft:cow", vec![])]);
+    common::write_region(
+        &world.join("entities").join("r.0.0.mca"),
+        &[
+            (0, common::chunk_bytes(3, &bats)),
+            (1, common::chunk_bytes(3, &cows)),
+        ],
+    );
+    let out = common::temp_dir("rt-trivial-out");
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        prune_trivial_entities: true,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+
+    let indices = |dir: &str| -> Vec<u32> {
+        let path = out.join(dir).join("r.0.0.mca");
+        let mut r = McaReader::open(path.to_string_lossy().as_ref()).unwrap();
+        r.entries()
+            .unwrap()
+            .iter()
+            .map(|e| e.region_index())
+            .collect()
+    };
+    assert_eq!(indices("region"), vec![0, 1]);
+    assert_eq!(indices("entities"), vec![1]);
+}
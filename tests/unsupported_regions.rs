@@ -0,0 +1,20 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+
+#[test]
+fn linear_regions_are_skipped_without_failing() {
+    let world = common::temp_dir("rt-linear");
+    common::write_dimension(&world, 100_000);
+    fs::write(world.join("region").join("r.1.0.linear"), vec![0u8; 9000]).unwrap();
+    fs::write(world.join("region").join("r.2.0.mcr"), vec![0u8; 9000]).unwrap();
+    let out = common::temp_dir("rt-linear-out");
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    rust_thanos::world::run_with_options(world, Some(out.clone()), &opts).unwrap();
+    assert!(out.join("region").join("r.0.0.mca").is_file());
+    assert!(!out.join("region").join("r.1.0.linear").exists());
+}
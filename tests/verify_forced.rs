@@ -0,0 +1,67 @@
+mod common;
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Region (0, 0) with fresh chunks in slots 0..3 and force-loaded chunks 1 and 2;
+/// chunk 40 is force-loaded too but was never generated.
+fn world(forced: Vec<u8>) -> PathBuf {
+    let world = common::temp_dir("rt-verify-forced");
+    let fresh = common::chunk_bytes(3, &common::inhabited_payload(0));
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[(0, fresh.clone()), (1, fresh), (2, forced)],
+    );
+    common::write_tickets(
+        &world,
+        &[
+            ("minecraft:forced", 1, 0),
+            ("minecraft:forced", 2, 0),
+            ("minecraft:forced", 40, 0),
+        ],
+    );
+    world
+}
+
+fn run(world: &PathBuf, extra: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(world)
+        .arg(common::temp_dir("rt-verify-forced-out"))
+        .args(["--verify-forced", "-t", "5"])
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn kept_forced_chunks_pass_the_check() {
+    let world = world(common::chunk_bytes(3, &common::inhabited_payload(0)));
+    let output = run(&world, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("强加载区块校验: 缺失 0 个"), "{}", stdout);
+}
+
+#[test]
+fn dropped_forced_chunks_fail_the_check() {
+    // an unknown compression method: the chunk cannot be read and is not written
+    let world = world(common::chunk_bytes(99, b"garbage"));
+    let output = run(&world, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    // chunk 40 was never generated and is not missed
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("强加载区块校验: 缺失 1 个"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("1 force-loaded chunks were dropped"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,22 @@
+mod common;
+
+use std::fs;
+
+#[test]
+fn finalized_region_has_no_trailing_sectors() {
+    let dir = common::temp_dir("rt-writer-len");
+    let path = dir.join("region").join("r.0.0.mca");
+    let small = common::chunk_bytes(3, &[1u8; 100]);
+    let large = common::chunk_bytes(3, &[2u8; 5000]);
+    common::write_region(&path, &[(0, small), (1, large)]);
+    // header (2 sectors) + 1 sector + 2 sectors
+    assert_eq!(fs::metadata(&path).unwrap().len(), 8192 + 3 * 4096);
+}
+
+#[test]
+fn empty_region_is_header_only() {
+    let dir = common::temp_dir("rt-writer-empty");
+    let path = dir.join("region").join("r.0.0.mca");
+    common::write_region(&path, &[]);
+    assert_eq!(fs::metadata(&path).unwrap().len(), 8192);
+}
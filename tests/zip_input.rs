@@ -0,0 +1,169 @@
+mod common;
+
+use rust_thanos::world::{ProgressMode, RunOptions};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+
+/// Zips `world` under a top-level `name/` folder, as archives of worlds usually are.
+fn zip_world(world: &Path, name: &str, dst: &Path) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(dst).unwrap());
+    for entry in walkdir::WalkDir::new(world).sort_by_file_name() {
+        let entry = entry.unwrap();
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(world).unwrap();
+        let path = format!("{}/{}", name, rel.to_string_lossy().replace('\\', "/"));
+        zip.start_file(path, FileOptions::default()).unwrap();
+        zip.write_all(&fs::read(entry.path()).unwrap()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn zipped_world_matches_the_unzipped_run() {
+    let world = common::temp_dir("rt-zip-world");
+    let played = common::chunk_bytes(3, &common::inhabited_payload(100_000));
+    let unused = common::chunk_bytes(3, &common::inhabited_payload(0));
+    for dim in [world.clone(), world.join("DIM-1")] {
+        let chunks = [
+            (0, played.clone()),
+            (1, unused.clone()),
+            (2, unused.clone()),
+        ];
+        common::write_region(&dim.join("region").join("r.0.0.mca"), &chunks);
+        common::write_region(&dim.join("entities").join("r.0.0.mca"), &chunks);
+    }
+    // pins chunk 2 through the staged chunks.dat
+    common::write_tickets(&world, &[("minecraft:forced", 2, 0)]);
+    common::write_level_dat(&world, 3700);
+    let archive = common::temp_dir("rt-zip-archive").join("world.zip");
+    zip_world(&world, "myworld", &archive);
+
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        copy_world_files: true,
+        ..RunOptions::default()
+    };
+    let from_dir = common::temp_dir("rt-zip-dir-out");
+    rust_thanos::world::run_with_options(world.clone(), Some(from_dir.clone()), &opts).unwrap();
+    let from_zip = common::temp_dir("rt-zip-out");
+    rust_thanos::world::run_with_options(archive.clone(), Some(from_zip.clone()), &opts).unwrap();
+
+    for rel in [
+        "region/r.0.0.mca",
+        "entities/r.0.0.mca",
+        "DIM-1/region/r.0.0.mca",
+        "DIM-1/entities/r.0.0.mca",
+        "level.dat",
+    ] {
+        assert_eq!(
+            fs::read(from_zip.join(rel)).unwrap(),
+            fs::read(from_dir.join(rel)).unwrap(),
+            "{}",
+            rel
+        );
+    }
+}
+
+#[test]
+fn zipped_world_cannot_be_processed_in_place() {
+    let world = common::temp_dir("rt-zip-inplace");
+    common::write_dimension(&world, 0);
+    let archive = common::temp_dir("rt-zip-inplace-archive").join("world.zip");
+    zip_world(&world, "w", &archive);
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    assert!(rust_thanos::world::run_with_options(archive, None, &opts).is_err());
+}
+
+/// Writes a zip at `dst` holding `files` of `(name, bytes)`.
+fn zip_files(dst: &Path, files: &[(&str, Vec<u8>)]) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(dst).unwrap());
+    for (name, data) in files {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+fn kept_chunks(out: &Path) -> usize {
+    rust_thanos::mca::reader::McaReader::open(
+        out.join("region/r.0.0.mca").to_string_lossy().as_ref(),
+    )
+    .unwrap()
+    .chunk_count()
+    .unwrap()
+}
+
+#[test]
+fn mcworld_and_nested_archives_are_unwrapped() {
+    let world = common::temp_dir("rt-zip-nested-world");
+    common::write_region(
+        &world.join("region").join("r.0.0.mca"),
+        &[
+            (
+                0,
+                common::chunk_bytes(3, &common::inhabited_payload(100_000)),
+            ),
+            (1, common::chunk_bytes(3, &common::inhabited_payload(0))),
+        ],
+    );
+    common::write_level_dat(&world, 3700);
+    let dir = common::temp_dir("rt-zip-nested");
+    let inner = dir.join("world.mcworld");
+    zip_world(&world, "saves/world", &inner);
+    // a backup archive wrapping the world archive, with some junk next to it
+    let outer = dir.join("backup.zip");
+    zip_files(
+        &outer,
+        &[
+            (
+                "backups/2024-01-01/world.mcworld",
+                fs::read(&inner).unwrap(),
+            ),
+            ("__MACOSX/._world.mcworld", b"junk".to_vec()),
+        ],
+    );
+    let opts = RunOptions {
+        inhabited_threshold: 10,
+        progress_mode: ProgressMode::Off,
+        copy_world_files: true,
+        ..RunOptions::default()
+    };
+    for archive in [inner, outer] {
+        let out = common::temp_dir("rt-zip-nested-out");
+        rust_thanos::world::run_with_options(archive, Some(out.clone()), &opts).unwrap();
+        assert_eq!(kept_chunks(&out), 1);
+        assert!(out.join("level.dat").is_file());
+    }
+}
+
+#[test]
+fn bedrock_worlds_are_rejected() {
+    let archive = common::temp_dir("rt-zip-bedrock").join("bedrock.mcworld");
+    zip_files(
+        &archive,
+        &[
+            ("level.dat", vec![0u8; 8]),
+            ("db/CURRENT", b"MANIFEST-000001\n".to_vec()),
+            ("db/000005.ldb", vec![0u8; 16]),
+        ],
+    );
+    let opts = RunOptions {
+        progress_mode: ProgressMode::Off,
+        ..RunOptions::default()
+    };
+    let err = rust_thanos::world::run_with_options(
+        archive,
+        Some(common::temp_dir("rt-zip-bedrock-out")),
+        &opts,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("Bedrock"), "{}", err);
+}
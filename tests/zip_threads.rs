@@ -0,0 +1,82 @@
+mod common;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn world() -> PathBuf {
+    let world = common::temp_dir("rt-zip-threads");
+    common::write_dimension(&world, 100_000);
+    common::write_dimension(&world.join("DIM-1"), 100_000);
+    common::write_dimension(&world.join("DIM1"), 100_000);
+    common::write_level_dat(&world, 3700);
+    world
+}
+
+/// Zips `world` with `--zip-threads threads`; returns the archive's entries in order.
+fn zipped(world: &Path, threads: &str) -> Vec<(String, Vec<u8>)> {
+    let parent = common::temp_dir("rt-zip-threads-out");
+    let output = Command::new(env!("CARGO_BIN_EXE_rust-thanos"))
+        .arg(world)
+        .arg(parent.join("out"))
+        .args([
+            "--copy-world-files",
+            "--zip-output",
+            "--zip-threads",
+            threads,
+        ])
+        .args(["--progress-mode", "off", "-t", "1"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!parent.join("out").exists());
+    let zip_path = fs::read_dir(&parent)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|e| e == "zip"))
+        .unwrap();
+    let mut archive = zip::ZipArchive::new(fs::File::open(zip_path).unwrap()).unwrap();
+    (0..archive.len())
+        .map(|i| {
+            let mut entry = archive.by_index(i).unwrap();
+            assert!(entry.is_dir() || entry.compression() == zip::CompressionMethod::Deflated);
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data).unwrap();
+            (entry.name().to_string(), data)
+        })
+        .collect()
+}
+
+#[test]
+fn thread_count_does_not_change_the_archive() {
+    let world = world();
+    let single = zipped(&world, "1");
+    assert_eq!(zipped(&world, "3"), single);
+    assert_eq!(zipped(&world, "0"), single);
+
+    let files: BTreeMap<&str, &[u8]> = single
+        .iter()
+        .filter(|(name, _)| !name.ends_with('/'))
+        .map(|(name, data)| (name.as_str(), data.as_slice()))
+        .collect();
+    assert_eq!(
+        files.keys().copied().collect::<Vec<_>>(),
+        vec![
+            "DIM-1/region/r.0.0.mca",
+            "DIM1/region/r.0.0.mca",
+            "level.dat",
+            "region/r.0.0.mca",
+        ]
+    );
+    assert_eq!(
+        files["level.dat"],
+        fs::read(world.join("level.dat")).unwrap()
+    );
+    assert!(single.iter().any(|(name, _)| name == "DIM-1/region/"));
+}